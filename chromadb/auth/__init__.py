@@ -160,6 +160,7 @@ class AuthzAction(str, Enum):
     """
 
     RESET = "system:reset"
+    GET_LOG_RETENTION = "system:get_log_retention"
     CREATE_TENANT = "tenant:create_tenant"
     GET_TENANT = "tenant:get_tenant"
     CREATE_DATABASE = "db:create_database"
@@ -172,6 +173,7 @@ class AuthzAction(str, Enum):
     GET_OR_CREATE_COLLECTION = "db:get_or_create_collection"
     GET_COLLECTION = "collection:get_collection"
     DELETE_COLLECTION = "collection:delete_collection"
+    RESTORE_COLLECTION = "collection:restore_collection"
     UPDATE_COLLECTION = "collection:update_collection"
     ADD = "collection:add"
     DELETE = "collection:delete"
@@ -0,0 +1,3 @@
+-- Tombstones deleted collections so that they can be restored until they are purged.
+-- Unset for live collections, otherwise the unix timestamp in seconds of the deletion.
+ALTER TABLE collections ADD COLUMN deleted_at INTEGER;
@@ -126,6 +126,25 @@ func (r *LogRepository) PullRecords(ctx context.Context, collectionId string, of
 	return
 }
 
+// GetFirstOffsetSince returns the offset of the first record of the collection written at or
+// after the timestamp, or nil if there is none. Records purged after compaction are not considered.
+func (r *LogRepository) GetFirstOffsetSince(ctx context.Context, collectionId string, timestamp int64) (offset *int64, err error) {
+	var firstOffset int64
+	firstOffset, err = r.queries.GetFirstOffsetSince(ctx, log.GetFirstOffsetSinceParams{
+		CollectionID: collectionId,
+		Timestamp:    timestamp,
+	})
+	if errors.Is(err, pgx.ErrNoRows) {
+		return nil, nil
+	}
+	if err != nil {
+		trace_log.Error("Error in getting first offset since timestamp from record_log table", zap.Error(err), zap.String("collectionId", collectionId))
+		return
+	}
+	offset = &firstOffset
+	return
+}
+
 func (r *LogRepository) GetAllCollectionInfoToCompact(ctx context.Context, minCompactionSize uint64) (collectionToCompact []log.GetAllCollectionsToCompactRow, err error) {
 	collectionToCompact, err = r.queries.GetAllCollectionsToCompact(ctx, int64(minCompactionSize))
 	if collectionToCompact == nil {
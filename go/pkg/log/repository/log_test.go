@@ -79,6 +79,29 @@ func (suite *LogTestSuite) TestGarbageCollection() {
 	assert.Equal(suite.t, int64(1), records[0].Offset, "Failed to run garbage collection")
 }
 
+func (suite *LogTestSuite) TestGetFirstOffsetSince() {
+	ctx := context.Background()
+	collectionID := types.NewUniqueID()
+
+	_, err := suite.lr.InsertRecords(ctx, collectionID.String(), [][]byte{{1}, {2}})
+	assert.NoError(suite.t, err, "Failed to insert records")
+	between := time.Now().UnixNano()
+	_, err = suite.lr.InsertRecords(ctx, collectionID.String(), [][]byte{{3}})
+	assert.NoError(suite.t, err, "Failed to insert records")
+
+	offset, err := suite.lr.GetFirstOffsetSince(ctx, collectionID.String(), 0)
+	assert.NoError(suite.t, err, "Failed to get first offset")
+	assert.Equal(suite.t, int64(1), *offset)
+
+	offset, err = suite.lr.GetFirstOffsetSince(ctx, collectionID.String(), between)
+	assert.NoError(suite.t, err, "Failed to get first offset")
+	assert.Equal(suite.t, int64(3), *offset)
+
+	offset, err = suite.lr.GetFirstOffsetSince(ctx, collectionID.String(), time.Now().UnixNano())
+	assert.NoError(suite.t, err, "Failed to get first offset")
+	assert.Nil(suite.t, offset)
+}
+
 func TestLogTestSuite(t *testing.T) {
 	testSuite := new(LogTestSuite)
 	testSuite.t = t
@@ -86,6 +86,7 @@ func (s *logServer) GetAllCollectionInfoToCompact(ctx context.Context, req *logs
 			CollectionId:   collectionToCompact[index].CollectionID,
 			FirstLogOffset: collectionToCompact[index].Offset,
 			FirstLogTs:     int64(collectionToCompact[index].Timestamp),
+			LastLogOffset:  collectionToCompact[index].RecordEnumerationOffsetPosition,
 		}
 	}
 	return
@@ -105,6 +106,23 @@ func (s *logServer) UpdateCollectionLogOffset(ctx context.Context, req *logservi
 	return
 }
 
+func (s *logServer) GetFirstOffsetSince(ctx context.Context, req *logservicepb.GetFirstOffsetSinceRequest) (res *logservicepb.GetFirstOffsetSinceResponse, err error) {
+	var collectionID types.UniqueID
+	collectionID, err = types.ToUniqueID(&req.CollectionId)
+	if err != nil {
+		return
+	}
+	var offset *int64
+	offset, err = s.lr.GetFirstOffsetSince(ctx, collectionID.String(), req.Timestamp)
+	if err != nil {
+		return
+	}
+	res = &logservicepb.GetFirstOffsetSinceResponse{
+		LogOffset: offset,
+	}
+	return
+}
+
 func NewLogServer(lr *repository.LogRepository) logservicepb.LogServiceServer {
 	return &logServer{
 		lr: lr,
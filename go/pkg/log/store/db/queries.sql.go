@@ -53,22 +53,23 @@ func (q *Queries) GetAllCollections(ctx context.Context) ([]string, error) {
 
 const getAllCollectionsToCompact = `-- name: GetAllCollectionsToCompact :many
 with summary as (
-    select r.collection_id, r.offset, r.timestamp, row_number() over(partition by r.collection_id order by r.offset) as rank
+    select r.collection_id, r.offset, r.timestamp, c.record_enumeration_offset_position, row_number() over(partition by r.collection_id order by r.offset) as rank
     from record_log r, collection c
     where r.collection_id = c.id
     and (c.record_enumeration_offset_position - c.record_compaction_offset_position) >= $1
     and r.offset > c.record_compaction_offset_position
 )
-select collection_id, "offset", timestamp, rank from summary
+select collection_id, "offset", timestamp, record_enumeration_offset_position, rank from summary
 where rank=1
 order by timestamp
 `
 
 type GetAllCollectionsToCompactRow struct {
-	CollectionID string
-	Offset       int64
-	Timestamp    int64
-	Rank         int64
+	CollectionID                    string
+	Offset                          int64
+	Timestamp                       int64
+	RecordEnumerationOffsetPosition int64
+	Rank                            int64
 }
 
 func (q *Queries) GetAllCollectionsToCompact(ctx context.Context, minCompactionSize int64) ([]GetAllCollectionsToCompactRow, error) {
@@ -84,6 +85,7 @@ func (q *Queries) GetAllCollectionsToCompact(ctx context.Context, minCompactionS
 			&i.CollectionID,
 			&i.Offset,
 			&i.Timestamp,
+			&i.RecordEnumerationOffsetPosition,
 			&i.Rank,
 		); err != nil {
 			return nil, err
@@ -110,6 +112,22 @@ func (q *Queries) GetCollectionForUpdate(ctx context.Context, id string) (Collec
 	return i, err
 }
 
+const getFirstOffsetSince = `-- name: GetFirstOffsetSince :one
+SELECT r.offset FROM record_log r WHERE r.collection_id = $1 AND r.timestamp >= $2 ORDER BY r.offset ASC limit 1
+`
+
+type GetFirstOffsetSinceParams struct {
+	CollectionID string
+	Timestamp    int64
+}
+
+func (q *Queries) GetFirstOffsetSince(ctx context.Context, arg GetFirstOffsetSinceParams) (int64, error) {
+	row := q.db.QueryRow(ctx, getFirstOffsetSince, arg.CollectionID, arg.Timestamp)
+	var offset int64
+	err := row.Scan(&offset)
+	return offset, err
+}
+
 const getLastCompactedOffset = `-- name: GetLastCompactedOffset :one
 SELECT record_compaction_offset_position FROM collection c WHERE c.id = $1
 `
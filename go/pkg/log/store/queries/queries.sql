@@ -10,9 +10,12 @@ INSERT INTO record_log (collection_id, "offset", record, timestamp) values($1, $
 -- name: GetRecordsForCollection :many
 SELECT * FROM record_log r WHERE r.collection_id = $1 AND r.offset >= $2 and r.timestamp <= $4  ORDER BY r.offset ASC limit $3 ;
 
+-- name: GetFirstOffsetSince :one
+SELECT r.offset FROM record_log r WHERE r.collection_id = $1 AND r.timestamp >= $2 ORDER BY r.offset ASC limit 1;
+
 -- name: GetAllCollectionsToCompact :many
 with summary as (
-    select r.collection_id, r.offset, r.timestamp, row_number() over(partition by r.collection_id order by r.offset) as rank
+    select r.collection_id, r.offset, r.timestamp, c.record_enumeration_offset_position, row_number() over(partition by r.collection_id order by r.offset) as rank
     from record_log r, collection c
     where r.collection_id = c.id
     and (c.record_enumeration_offset_position - c.record_compaction_offset_position) >= sqlc.arg(min_compaction_size)
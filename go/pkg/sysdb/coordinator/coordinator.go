@@ -145,6 +145,10 @@ func (s *Coordinator) DeleteCollection(ctx context.Context, deleteCollection *mo
 	return s.catalog.DeleteCollection(ctx, deleteCollection, false)
 }
 
+func (s *Coordinator) RestoreSoftDeletedCollection(ctx context.Context, restoreCollection *model.RestoreCollection) (*model.Collection, error) {
+	return s.catalog.RestoreSoftDeletedCollection(ctx, restoreCollection)
+}
+
 func (s *Coordinator) CleanupSoftDeletedCollection(ctx context.Context, deleteCollection *model.DeleteCollection) error {
 	return s.catalog.DeleteCollection(ctx, deleteCollection, false)
 }
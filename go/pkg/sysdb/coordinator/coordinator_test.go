@@ -1285,6 +1285,54 @@ func (suite *APIsTestSuite) TestCollectionVersioningWithMinio() {
 	// suite.True(exists, "Version file should exist in S3")
 }
 
+func (suite *APIsTestSuite) TestFlushCollectionCompactionReplacesSegmentMetadata() {
+	ctx := context.Background()
+
+	collectionID := types.NewUniqueID()
+	segmentID := types.NewUniqueID()
+	oldMetadata := model.NewSegmentMetadata[model.SegmentMetadataValueType]()
+	oldMetadata.Set("hnsw:space", &model.SegmentMetadataValueStringType{Value: "l2"})
+	oldMetadata.Set("hnsw:M", &model.SegmentMetadataValueInt64Type{Value: 16})
+	_, _, err := suite.coordinator.CreateCollectionAndSegments(ctx, &model.CreateCollection{
+		ID:           collectionID,
+		Name:         "test_flush_segment_metadata",
+		TenantID:     suite.tenantName,
+		DatabaseName: suite.databaseName,
+	}, []*model.CreateSegment{
+		{
+			ID:           segmentID,
+			Type:         "test_type_a",
+			Scope:        "VECTOR",
+			CollectionID: collectionID,
+			Metadata:     oldMetadata,
+		},
+	})
+	suite.NoError(err)
+
+	newMetadata := model.NewSegmentMetadata[model.SegmentMetadataValueType]()
+	newMetadata.Set("hnsw:space", &model.SegmentMetadataValueStringType{Value: "cosine"})
+	_, err = suite.coordinator.FlushCollectionCompaction(ctx, &model.FlushCollectionCompaction{
+		ID:                       collectionID,
+		TenantID:                 suite.tenantName,
+		LogPosition:              0,
+		CurrentCollectionVersion: 0,
+		FlushSegmentCompactions: []*model.FlushSegmentCompaction{
+			{
+				ID:        segmentID,
+				FilePaths: map[string][]string{"hnsw_index": {"index_1"}},
+				Metadata:  newMetadata,
+			},
+		},
+	})
+	suite.NoError(err)
+
+	segments, err := suite.coordinator.GetSegments(ctx, segmentID, nil, nil, collectionID)
+	suite.NoError(err)
+	suite.Len(segments, 1)
+	suite.Equal(map[string][]string{"hnsw_index": {"index_1"}}, segments[0].FilePaths)
+	suite.Equal(newMetadata, segments[0].Metadata)
+}
+
 func TestAPIsTestSuite(t *testing.T) {
 	testSuite := new(APIsTestSuite)
 	suite.Run(t, testSuite)
@@ -44,6 +44,12 @@ type DeleteCollection struct {
 	Ts           types.Timestamp
 }
 
+type RestoreCollection struct {
+	ID           types.UniqueID
+	TenantID     string
+	DatabaseName string
+}
+
 type UpdateCollection struct {
 	ID            types.UniqueID
 	Name          *string
@@ -42,6 +42,8 @@ type GetSegments struct {
 type FlushSegmentCompaction struct {
 	ID        types.UniqueID
 	FilePaths map[string][]string
+	// Replaces the metadata of the segment when set
+	Metadata *SegmentMetadata[SegmentMetadataValueType]
 }
 
 func FilterSegments(segment *Segment, segmentID types.UniqueID, segmentType *string, scope *string, topic *string, collectionID types.UniqueID) bool {
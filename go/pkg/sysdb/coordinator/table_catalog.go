@@ -5,6 +5,7 @@ import (
 	"errors"
 	"fmt"
 	"math/rand"
+	"strings"
 	"time"
 
 	"github.com/chroma-core/chroma/go/pkg/common"
@@ -25,6 +26,9 @@ const (
 	maxAttemptsToDeleteVersionEntries   = 5
 )
 
+// Soft deleted collections are renamed with this prefix, which frees their name
+const deletedCollectionNamePrefix = "_deleted_"
+
 // The catalog backed by databases using GORM.
 type Catalog struct {
 	metaDomain         dbmodel.IMetaDomain
@@ -540,7 +544,7 @@ func (tc *Catalog) softDeleteCollection(ctx context.Context, deleteCollection *m
 
 		// Generate new name with timestamp and random number
 		oldName := *collections[0].Collection.Name
-		newName := fmt.Sprintf("_deleted_%s_%d_%d", oldName, time.Now().Unix(), rand.Intn(1000))
+		newName := fmt.Sprintf("%s%s_%d_%d", deletedCollectionNamePrefix, oldName, time.Now().Unix(), rand.Intn(1000))
 
 		dbCollection := &dbmodel.Collection{
 			ID:        deleteCollection.ID.String(),
@@ -558,6 +562,58 @@ func (tc *Catalog) softDeleteCollection(ctx context.Context, deleteCollection *m
 	})
 }
 
+// originalCollectionName recovers the name that softDeleteCollection replaced, by dropping the
+// prefix and the timestamp and random number suffixes.
+func originalCollectionName(deletedName string) string {
+	name := strings.TrimPrefix(deletedName, deletedCollectionNamePrefix)
+	for i := 0; i < 2; i++ {
+		if idx := strings.LastIndex(name, "_"); idx >= 0 {
+			name = name[:idx]
+		}
+	}
+	return name
+}
+
+func (tc *Catalog) RestoreSoftDeletedCollection(ctx context.Context, restoreCollection *model.RestoreCollection) (*model.Collection, error) {
+	log.Info("Restoring soft deleted collection", zap.Any("restoreCollection", restoreCollection))
+	var result *model.Collection
+	err := tc.txImpl.Transaction(ctx, func(txCtx context.Context) error {
+		collectionID := restoreCollection.ID.String()
+		collections, err := tc.metaDomain.CollectionDb(txCtx).GetSoftDeletedCollections(&collectionID, restoreCollection.TenantID, restoreCollection.DatabaseName, 1)
+		if err != nil {
+			return err
+		}
+		if len(collections) == 0 {
+			return common.ErrCollectionNotFound
+		}
+
+		name := originalCollectionName(*collections[0].Collection.Name)
+		restoredCount, err := tc.metaDomain.CollectionDb(txCtx).Restore(collectionID, name)
+		if err != nil {
+			log.Error("restore collection failed", zap.Error(err))
+			return err
+		}
+		if restoredCount == 0 {
+			return common.ErrCollectionNotFound
+		}
+
+		restored, err := tc.metaDomain.CollectionDb(txCtx).GetCollections(&collectionID, nil, restoreCollection.TenantID, restoreCollection.DatabaseName, nil, nil)
+		if err != nil {
+			return err
+		}
+		if len(restored) == 0 {
+			return common.ErrCollectionNotFound
+		}
+		result = convertCollectionToModel(restored)[0]
+		return nil
+	})
+	if err != nil {
+		return nil, err
+	}
+	log.Info("collection restored", zap.Any("collection", result))
+	return result, nil
+}
+
 func (tc *Catalog) GetSoftDeletedCollections(ctx context.Context, collectionID *string, tenantID string, databaseName string, limit int32) ([]*model.Collection, error) {
 	collections, err := tc.metaDomain.CollectionDb(ctx).GetSoftDeletedCollections(collectionID, tenantID, databaseName, limit)
 	if err != nil {
@@ -1073,6 +1129,10 @@ func (tc *Catalog) FlushCollectionCompaction(ctx context.Context, flushCollectio
 		if err != nil {
 			return err
 		}
+		err = tc.replaceFlushedSegmentMetadata(txCtx, flushCollectionCompaction.FlushSegmentCompactions)
+		if err != nil {
+			return err
+		}
 
 		// update collection log position and version
 		collectionVersion, err := tc.metaDomain.CollectionDb(txCtx).UpdateLogPositionVersionAndTotalRecords(flushCollectionCompaction.ID.String(), flushCollectionCompaction.LogPosition, flushCollectionCompaction.CurrentCollectionVersion, flushCollectionCompaction.TotalRecordsPostCompaction)
@@ -1100,6 +1160,29 @@ func (tc *Catalog) FlushCollectionCompaction(ctx context.Context, flushCollectio
 	return flushCollectionInfo, nil
 }
 
+// replaceFlushedSegmentMetadata replaces the metadata of the flushed segments that carry new
+// metadata, so that a reindex swaps the index files and their parameters together.
+func (tc *Catalog) replaceFlushedSegmentMetadata(txCtx context.Context, flushSegmentCompactions []*model.FlushSegmentCompaction) error {
+	for _, flushSegmentCompaction := range flushSegmentCompactions {
+		if flushSegmentCompaction.Metadata == nil {
+			continue
+		}
+		segmentID := flushSegmentCompaction.ID.String()
+		err := tc.metaDomain.SegmentMetadataDb(txCtx).DeleteBySegmentID(segmentID)
+		if err != nil {
+			return err
+		}
+		dbSegmentMetadataList := convertSegmentMetadataToDB(segmentID, flushSegmentCompaction.Metadata)
+		if len(dbSegmentMetadataList) != 0 {
+			err = tc.metaDomain.SegmentMetadataDb(txCtx).Insert(dbSegmentMetadataList)
+			if err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
 func (tc *Catalog) validateVersionFile(versionFile *coordinatorpb.CollectionVersionFile, collectionID string, version int64) error {
 	if versionFile.GetCollectionInfoImmutable().GetCollectionId() != collectionID {
 		log.Error("collection id mismatch", zap.String("collection_id", collectionID), zap.String("version_file_collection_id", versionFile.GetCollectionInfoImmutable().GetCollectionId()))
@@ -1224,6 +1307,10 @@ func (tc *Catalog) FlushCollectionCompactionForVersionedCollection(ctx context.C
 			if err != nil {
 				return err
 			}
+			err = tc.replaceFlushedSegmentMetadata(txCtx, flushCollectionCompaction.FlushSegmentCompactions)
+			if err != nil {
+				return err
+			}
 			// update tenant last compaction time
 			// TODO: add a system configuration to disable
 			// since this might cause resource contention if one tenant has a lot of collection compactions at the same time
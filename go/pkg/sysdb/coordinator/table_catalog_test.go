@@ -618,3 +618,8 @@ func TestCatalog_MarkVersionForDeletion_VersionNotFound(t *testing.T) {
 	mockMetaDomain.AssertExpectations(t)
 	mockCollectionDb.AssertExpectations(t)
 }
+
+func TestOriginalCollectionName(t *testing.T) {
+	assert.Equal(t, "test_collection", originalCollectionName("_deleted_test_collection_1700000000_42"))
+	assert.Equal(t, "collection", originalCollectionName("_deleted_collection_1700000000_7"))
+}
@@ -273,6 +273,35 @@ func (s *Server) DeleteCollection(ctx context.Context, req *coordinatorpb.Delete
 	return res, nil
 }
 
+func (s *Server) RestoreDeletedCollection(ctx context.Context, req *coordinatorpb.RestoreDeletedCollectionRequest) (*coordinatorpb.RestoreDeletedCollectionResponse, error) {
+	collectionID := req.GetId()
+	res := &coordinatorpb.RestoreDeletedCollectionResponse{}
+	parsedCollectionID, err := types.Parse(collectionID)
+	if err != nil {
+		log.Error("RestoreDeletedCollection failed", zap.Error(err), zap.String("collection_id", collectionID))
+		return res, grpcutils.BuildInternalGrpcError(err.Error())
+	}
+	restoreCollection := &model.RestoreCollection{
+		ID:           parsedCollectionID,
+		TenantID:     req.GetTenant(),
+		DatabaseName: req.GetDatabase(),
+	}
+	collection, err := s.coordinator.RestoreSoftDeletedCollection(ctx, restoreCollection)
+	if err != nil {
+		log.Error("RestoreDeletedCollection failed", zap.Error(err), zap.String("collection_id", collectionID))
+		if err == common.ErrCollectionNotFound {
+			return res, grpcutils.BuildNotFoundGrpcError(err.Error())
+		}
+		if err == common.ErrCollectionUniqueConstraintViolation {
+			return res, grpcutils.BuildAlreadyExistsGrpcError(err.Error())
+		}
+		return res, grpcutils.BuildInternalGrpcError(err.Error())
+	}
+	res.Collection = convertCollectionToProto(collection)
+	log.Info("RestoreDeletedCollection succeeded", zap.String("collection_id", collectionID))
+	return res, nil
+}
+
 func (s *Server) UpdateCollection(ctx context.Context, req *coordinatorpb.UpdateCollectionRequest) (*coordinatorpb.UpdateCollectionResponse, error) {
 	res := &coordinatorpb.UpdateCollectionResponse{}
 
@@ -368,9 +397,18 @@ func (s *Server) FlushCollectionCompaction(ctx context.Context, req *coordinator
 		for key, filePath := range flushSegmentCompaction.FilePaths {
 			filePaths[key] = filePath.Paths
 		}
+		var metadata *model.SegmentMetadata[model.SegmentMetadataValueType]
+		if flushSegmentCompaction.Metadata != nil {
+			metadata, err = convertSegmentMetadataToModel(flushSegmentCompaction.Metadata)
+			if err != nil {
+				log.Error("FlushCollectionCompaction failed. error parsing segment metadata", zap.Error(err), zap.String("collection_id", req.CollectionId), zap.Int32("collection_version", req.CollectionVersion), zap.Int64("log_position", req.LogPosition))
+				return nil, grpcutils.BuildInternalGrpcError(err.Error())
+			}
+		}
 		segmentCompactionInfo = append(segmentCompactionInfo, &model.FlushSegmentCompaction{
 			ID:        segmentID,
 			FilePaths: filePaths,
+			Metadata:  metadata,
 		})
 	}
 	FlushCollectionCompaction := &model.FlushCollectionCompaction{
@@ -275,6 +275,27 @@ func (s *collectionDb) GetSoftDeletedCollections(collectionID *string, tenantID
 	return s.getCollections(collectionID, nil, tenantID, databaseName, &limit, nil, true)
 }
 
+// Restore brings back a soft deleted collection under the given name. Returns the number of
+// restored collections.
+func (s *collectionDb) Restore(collectionID string, name string) (int64, error) {
+	log.Info("restore collection", zap.String("collectionID", collectionID), zap.String("name", name))
+	result := s.db.Model(&dbmodel.Collection{}).Where("id = ? AND is_deleted = ?", collectionID, true).Updates(map[string]interface{}{
+		"name":       name,
+		"is_deleted": false,
+		"updated_at": time.Now(),
+	})
+	if result.Error != nil {
+		log.Error("restore collection failed", zap.Error(result.Error))
+		var pgErr *pgconn.PgError
+		if errors.As(result.Error, &pgErr) && pgErr.Code == "23505" {
+			log.Error("collection already exists")
+			return 0, common.ErrCollectionUniqueConstraintViolation
+		}
+		return 0, result.Error
+	}
+	return result.RowsAffected, nil
+}
+
 // NOTE: This is the only method to do a hard delete of a single collection.
 func (s *collectionDb) DeleteCollectionByID(collectionID string) (int, error) {
 	var collections []dbmodel.Collection
@@ -46,6 +46,7 @@ type ICollectionDb interface {
 	CountCollections(tenantID string, databaseName *string) (uint64, error)
 	DeleteCollectionByID(collectionID string) (int, error)
 	GetSoftDeletedCollections(collectionID *string, tenantID string, databaseName string, limit int32) ([]*CollectionAndMetadata, error)
+	Restore(collectionID string, name string) (int64, error)
 	Insert(in *Collection) error
 	Update(in *Collection) error
 	DeleteAll() error
@@ -252,6 +252,34 @@ func (_m *ICollectionDb) ListCollectionsToGc() ([]*dbmodel.CollectionToGc, error
 	return r0, r1
 }
 
+// Restore provides a mock function with given fields: collectionID, name
+func (_m *ICollectionDb) Restore(collectionID string, name string) (int64, error) {
+	ret := _m.Called(collectionID, name)
+
+	if len(ret) == 0 {
+		panic("no return value specified for Restore")
+	}
+
+	var r0 int64
+	var r1 error
+	if rf, ok := ret.Get(0).(func(string, string) (int64, error)); ok {
+		return rf(collectionID, name)
+	}
+	if rf, ok := ret.Get(0).(func(string, string) int64); ok {
+		r0 = rf(collectionID, name)
+	} else {
+		r0 = ret.Get(0).(int64)
+	}
+
+	if rf, ok := ret.Get(1).(func(string, string) error); ok {
+		r1 = rf(collectionID, name)
+	} else {
+		r1 = ret.Error(1)
+	}
+
+	return r0, r1
+}
+
 // Update provides a mock function with given fields: in
 func (_m *ICollectionDb) Update(in *dbmodel.Collection) error {
 	ret := _m.Called(in)
@@ -0,0 +1,245 @@
+//! Measure the recall@k of the vector index of collections on a running Chroma server.
+//!
+//! For every collection, a sample of query embeddings is searched twice: once through the
+//! vector index, and once with `brute_force` enabled to obtain the exact nearest neighbours.
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, ValueEnum};
+use futures::StreamExt;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+use serde_json::json;
+
+use chroma_benchmark::{
+    datasets::{
+        gist::GistDataset,
+        sift::Sift1MData,
+        types::{Record, RecordDataset},
+    },
+    recall::{recall_at_k, RecallSummary},
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum QuerySource {
+    /// Sample the embeddings of the records in the collection
+    Collection,
+    /// Sample the query embeddings of the Sift1M dataset
+    Sift,
+    /// Sample the embeddings of the Gist dataset
+    Gist,
+}
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(long)]
+    host: String,
+    #[arg(long, default_value = "default_tenant")]
+    tenant: String,
+    #[arg(long, default_value = "default_database")]
+    database: String,
+    #[arg(long = "collection-id", required = true)]
+    collection_ids: Vec<String>,
+    #[arg(long, value_enum, default_value_t = QuerySource::Collection)]
+    queries: QuerySource,
+    #[arg(long, default_value_t = 100)]
+    num_queries: usize,
+    #[arg(long, default_value_t = 10)]
+    k: u32,
+    #[arg(long, default_value_t = 32)]
+    batch_size: usize,
+}
+
+#[derive(Deserialize)]
+struct GetResponse {
+    embeddings: Option<Vec<Vec<f32>>>,
+}
+
+#[derive(Deserialize)]
+struct QueryResponse {
+    ids: Vec<Vec<String>>,
+}
+
+struct CollectionClient<'a> {
+    client: &'a reqwest::Client,
+    base_url: String,
+}
+
+impl CollectionClient<'_> {
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let response = request
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!(
+                "Request to {} failed with status {}: {}",
+                self.base_url,
+                status,
+                response.text().await.unwrap_or_default().trim()
+            ));
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn count(&self) -> Result<u32> {
+        self.send(self.client.get(format!("{}/count", self.base_url)))
+            .await
+    }
+
+    async fn embedding_at(&self, offset: u32) -> Result<Vec<f32>> {
+        let response: GetResponse = self
+            .send(
+                self.client
+                    .post(format!("{}/get", self.base_url))
+                    .json(&json!({ "limit": 1, "offset": offset, "include": ["embeddings"] })),
+            )
+            .await?;
+        response
+            .embeddings
+            .and_then(|embeddings| embeddings.into_iter().next())
+            .ok_or(anyhow!("No embedding found at offset {offset}"))
+    }
+
+    async fn query(
+        &self,
+        embeddings: &[Vec<f32>],
+        k: u32,
+        brute_force: bool,
+    ) -> Result<Vec<Vec<String>>> {
+        let response: QueryResponse = self
+            .send(
+                self.client
+                    .post(format!("{}/query", self.base_url))
+                    .json(&json!({
+                        "query_embeddings": embeddings,
+                        "n_results": k,
+                        "include": [],
+                        "brute_force": brute_force,
+                    })),
+            )
+            .await?;
+        Ok(response.ids)
+    }
+}
+
+async fn sample_dataset_queries(source: QuerySource, num_queries: usize) -> Result<Vec<Vec<f32>>> {
+    let mut embeddings = match source {
+        QuerySource::Collection => unreachable!("Collection queries are sampled per collection"),
+        QuerySource::Sift => Sift1MData::init()
+            .await?
+            .query()
+            .await?
+            .into_iter()
+            .map(|(embedding, _)| embedding)
+            .collect::<Vec<_>>(),
+        QuerySource::Gist => {
+            GistDataset::init()
+                .await?
+                .create_records_stream()
+                .await?
+                .filter_map(|record| async move { record.ok().and_then(|r: Record| r.embedding) })
+                .collect::<Vec<_>>()
+                .await
+        }
+    };
+    embeddings.shuffle(&mut rand::thread_rng());
+    embeddings.truncate(num_queries);
+    Ok(embeddings)
+}
+
+async fn sample_collection_queries(
+    collection: &CollectionClient<'_>,
+    num_queries: usize,
+) -> Result<Vec<Vec<f32>>> {
+    let count = collection.count().await?;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let mut embeddings = Vec::with_capacity(num_queries);
+    for _ in 0..num_queries {
+        let offset = rand::thread_rng().gen_range(0..count);
+        embeddings.push(collection.embedding_at(offset).await?);
+    }
+    Ok(embeddings)
+}
+
+async fn measure_recall(
+    collection: &CollectionClient<'_>,
+    queries: &[Vec<f32>],
+    k: u32,
+    batch_size: usize,
+) -> Result<RecallSummary> {
+    let mut recalls = Vec::with_capacity(queries.len());
+    for batch in queries.chunks(batch_size.max(1)) {
+        let approximate = collection.query(batch, k, false).await?;
+        let exact = collection.query(batch, k, true).await?;
+        if approximate.len() != batch.len() || exact.len() != batch.len() {
+            return Err(anyhow!(
+                "Expected {} results, got {} approximate and {} exact results",
+                batch.len(),
+                approximate.len(),
+                exact.len()
+            ));
+        }
+        recalls.extend(
+            approximate
+                .iter()
+                .zip(exact.iter())
+                .map(|(approximate, exact)| recall_at_k(approximate, exact, k as usize)),
+        );
+    }
+    Ok(RecallSummary::from_recalls(k as usize, &recalls))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let client = reqwest::Client::new();
+
+    let dataset_queries = match args.queries {
+        QuerySource::Collection => None,
+        source => match sample_dataset_queries(source, args.num_queries).await {
+            Ok(queries) => Some(queries),
+            Err(err) => {
+                eprintln!("Failed to load {:?} queries: {}", source, err);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let mut failed = false;
+    for collection_id in &args.collection_ids {
+        let collection = CollectionClient {
+            client: &client,
+            base_url: format!(
+                "{}/api/v2/tenants/{}/databases/{}/collections/{}",
+                args.host, args.tenant, args.database, collection_id
+            ),
+        };
+        let queries = match dataset_queries.as_ref() {
+            Some(queries) => queries.clone(),
+            None => match sample_collection_queries(&collection, args.num_queries).await {
+                Ok(queries) => queries,
+                Err(err) => {
+                    eprintln!("Failed to sample queries from {}: {}", collection_id, err);
+                    failed = true;
+                    continue;
+                }
+            },
+        };
+        match measure_recall(&collection, &queries, args.k, args.batch_size).await {
+            Ok(summary) => println!("{}: {}", collection_id, summary),
+            Err(err) => {
+                eprintln!("Failed to measure recall of {}: {}", collection_id, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
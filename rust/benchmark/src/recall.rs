@@ -0,0 +1,95 @@
+use std::{collections::HashSet, fmt, hash::Hash};
+
+/// Returns the fraction of the exact top `k` neighbours that are present in the approximate
+/// top `k` neighbours. If the exact result is empty there is nothing to recall and the recall is 1.
+pub fn recall_at_k<T: Eq + Hash>(approximate: &[T], exact: &[T], k: usize) -> f64 {
+    let exact = &exact[..exact.len().min(k)];
+    if exact.is_empty() {
+        return 1.0;
+    }
+    let approximate = approximate[..approximate.len().min(k)]
+        .iter()
+        .collect::<HashSet<_>>();
+    let hits = exact.iter().filter(|id| approximate.contains(id)).count();
+    hits as f64 / exact.len() as f64
+}
+
+/// The distribution of recall@k over a set of queries
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecallSummary {
+    pub k: usize,
+    pub num_queries: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub p50: f64,
+    pub p99: f64,
+}
+
+impl RecallSummary {
+    pub fn from_recalls(k: usize, recalls: &[f64]) -> Self {
+        if recalls.is_empty() {
+            return Self {
+                k,
+                ..Default::default()
+            };
+        }
+        let mut sorted = recalls.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Low percentiles are the interesting ones for recall, so p99 is the recall that 99% of the queries reach
+        let percentile = |p: usize| sorted[(sorted.len() - 1) * (100 - p) / 100];
+        Self {
+            k,
+            num_queries: sorted.len(),
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            min: sorted[0],
+            p50: percentile(50),
+            p99: percentile(99),
+        }
+    }
+}
+
+impl fmt::Display for RecallSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "recall@{} over {} queries: mean {:.4}, min {:.4}, p50 {:.4}, p99 {:.4}",
+            self.k, self.num_queries, self.mean, self.min, self.p50, self.p99
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recall_at_k() {
+        assert_eq!(recall_at_k(&[1, 2, 3, 4], &[1, 2, 3, 4], 4), 1.0);
+        assert_eq!(recall_at_k(&[1, 2, 5, 6], &[1, 2, 3, 4], 4), 0.5);
+        // Only the top k of each result are compared
+        assert_eq!(recall_at_k(&[1, 2, 4, 3], &[1, 2, 3, 4], 2), 1.0);
+        assert_eq!(recall_at_k(&[1, 4, 2, 3], &[1, 2, 3, 4], 2), 0.5);
+        // Fewer than k records in the collection
+        assert_eq!(recall_at_k(&[1, 2], &[2, 1], 10), 1.0);
+        assert_eq!(recall_at_k::<u32>(&[], &[], 10), 1.0);
+    }
+
+    #[test]
+    fn test_recall_summary() {
+        let recalls = (0..=100).map(|i| i as f64 / 100.0).collect::<Vec<_>>();
+        let summary = RecallSummary::from_recalls(10, &recalls);
+        assert_eq!(summary.num_queries, 101);
+        assert_eq!(summary.min, 0.0);
+        assert_eq!(summary.p50, 0.5);
+        assert_eq!(summary.p99, 0.01);
+        assert!((summary.mean - 0.5).abs() < 1e-9);
+
+        assert_eq!(
+            RecallSummary::from_recalls(10, &[]),
+            RecallSummary {
+                k: 10,
+                ..Default::default()
+            }
+        );
+    }
+}
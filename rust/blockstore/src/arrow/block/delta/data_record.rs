@@ -117,6 +117,11 @@ impl DataRecordStorage {
         inner.storage.keys().next().cloned()
     }
 
+    pub(super) fn get_keys(&self) -> Vec<CompositeKey> {
+        let inner = self.inner.read();
+        inner.storage.keys().cloned().collect()
+    }
+
     pub(super) fn get_size<K: ArrowWriteableKey>(&self) -> usize {
         let inner = self.inner.read();
         let prefix_size = bit_util::round_upto_multiple_of_64(inner.size_tracker.get_prefix_size());
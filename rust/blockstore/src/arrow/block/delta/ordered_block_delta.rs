@@ -6,7 +6,7 @@ use crate::{
         block::Block,
         types::{ArrowReadableKey, ArrowReadableValue, ArrowWriteableKey, ArrowWriteableValue},
     },
-    key::{CompositeKey, KeyWrapper},
+    key::{CompositeKey, KeyWrapper, PrefixKeyRange},
 };
 use arrow::array::{RecordBatch, StringArray};
 use uuid::Uuid;
@@ -94,6 +94,14 @@ impl OrderedBlockDelta {
         self.copy_up_to::<K::ReadableKey<'_>, V::ReadableValue<'_>>(prefix, &key.into());
     }
 
+    /// Copies the rows of the old block that sort before the range and skips the rows in the range.
+    pub(crate) fn skip_range<K: ArrowWriteableKey, V: ArrowWriteableValue>(
+        &mut self,
+        range: &PrefixKeyRange,
+    ) {
+        self.skip_range_of_old_block::<K::ReadableKey<'_>, V::ReadableValue<'_>>(range);
+    }
+
     pub fn copy_to_end<K: ArrowWriteableKey, V: ArrowWriteableValue>(&mut self) {
         // Copy remaining rows
         if let Some(old_block) = self.old_block.as_ref() {
@@ -127,6 +135,18 @@ impl OrderedBlockDelta {
         }
     }
 
+    /// Append every row of `block` to this delta. The rows must all sort after the rows already in the delta.
+    pub(crate) fn extend_from_block<K: ArrowWriteableKey, V: ArrowWriteableValue>(
+        &mut self,
+        block: &Block,
+    ) {
+        self.old_block = Some(block.clone());
+        self.copied_up_to_row_of_old_block = 0;
+        self.copy_to_end::<K, V>();
+        self.old_block = None;
+        self.copied_up_to_row_of_old_block = 0;
+    }
+
     pub fn len(&self) -> usize {
         self.builder.len()
     }
@@ -185,6 +205,39 @@ impl OrderedBlockDelta {
         }
     }
 
+    fn skip_range_of_old_block<'me, K: ArrowReadableKey<'me>, V: ArrowReadableValue<'me>>(
+        &'me mut self,
+        range: &PrefixKeyRange,
+    ) {
+        if let Some(old_block) = self.old_block.as_ref() {
+            let prefix_arr = old_block
+                .data
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            let key_arr = old_block.data.column(1);
+
+            for i in self.copied_up_to_row_of_old_block..old_block.data.num_rows() {
+                let old_prefix = prefix_arr.value(i);
+                let old_key = K::get(key_arr, i);
+                let old_key_wrapper: KeyWrapper = old_key.clone().into();
+
+                if range.contains(old_prefix, &old_key_wrapper) {
+                    self.copied_up_to_row_of_old_block += 1;
+                    continue;
+                }
+                if range.ends_before(old_prefix, &old_key_wrapper) {
+                    break;
+                }
+
+                let old_value = V::get(old_block.data.column(2), i);
+                K::add_to_delta(old_prefix, old_key, old_value, &mut self.builder);
+                self.copied_up_to_row_of_old_block += 1;
+            }
+        }
+    }
+
     ///  Gets the size of the block delta as it would be in a block. This includes
     ///  the size of the prefix, key, and value data and the size of the offsets
     ///  where applicable. The size is rounded up to the nearest 64 bytes as per
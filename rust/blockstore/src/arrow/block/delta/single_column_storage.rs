@@ -71,6 +71,11 @@ impl<V: ArrowWriteableValue<SizeTracker = SingleColumnSizeTracker>> SingleColumn
         inner.storage.min_key().cloned()
     }
 
+    pub(super) fn get_keys(&self) -> Vec<CompositeKey> {
+        let inner = self.inner.read();
+        inner.storage.iter().map(|(key, _)| key.clone()).collect()
+    }
+
     pub(super) fn get_size<K: ArrowWriteableKey>(&self) -> usize {
         let inner = self.inner.read();
 
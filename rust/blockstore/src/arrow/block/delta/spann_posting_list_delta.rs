@@ -225,6 +225,10 @@ impl SpannPostingListDelta {
         self.inner.read().storage.keys().next().cloned()
     }
 
+    pub(super) fn get_keys(&self) -> Vec<CompositeKey> {
+        self.inner.read().storage.keys().cloned().collect()
+    }
+
     pub(super) fn len(&self) -> usize {
         self.inner.read().storage.len()
     }
@@ -184,6 +184,18 @@ impl BlockStorage {
         }
     }
 
+    /// Returns every key in the delta.
+    pub fn get_keys(&self) -> Vec<CompositeKey> {
+        match self {
+            BlockStorage::String(builder) => builder.get_keys(),
+            BlockStorage::UInt32(builder) => builder.get_keys(),
+            BlockStorage::DataRecord(builder) => builder.get_keys(),
+            BlockStorage::VecUInt32(builder) => builder.get_keys(),
+            BlockStorage::RoaringBitmap(builder) => builder.get_keys(),
+            BlockStorage::SpannPostingListDelta(builder) => builder.get_keys(),
+        }
+    }
+
     /// Returns the arrow-padded (rounded to 64 bytes) size for the delta.
     pub fn get_size<K: ArrowWriteableKey>(&self) -> usize {
         match self {
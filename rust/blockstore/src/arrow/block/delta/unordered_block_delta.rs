@@ -6,7 +6,7 @@ use crate::{
         block::Block,
         types::{ArrowWriteableKey, ArrowWriteableValue},
     },
-    key::CompositeKey,
+    key::{CompositeKey, PrefixKeyRange},
 };
 use arrow::array::RecordBatch;
 use uuid::Uuid;
@@ -70,6 +70,15 @@ impl UnorderedBlockDelta {
         V::delete(prefix, key.into(), self)
     }
 
+    /// Deletes every key in the range from the block delta.
+    pub(crate) fn delete_range<V: ArrowWriteableValue>(&self, range: &PrefixKeyRange) {
+        for key in self.builder.get_keys() {
+            if range.contains(&key.prefix, &key.key) {
+                V::delete(&key.prefix, key.key, self);
+            }
+        }
+    }
+
     ///  Gets the size of the block delta as it would be in a block. This includes
     ///  the size of the prefix, key, and value data and the size of the offsets
     ///  where applicable. The size is rounded up to the nearest 64 bytes as per
@@ -137,6 +146,7 @@ impl UnorderedBlockDelta {
 mod test {
     use crate::arrow::{
         block::{delta::UnorderedBlockDelta, Block},
+        compression::BlockCompression,
         config::TEST_MAX_BLOCK_SIZE_BYTES,
         provider::BlockManager,
     };
@@ -193,7 +203,10 @@ mod test {
             let read = block.get::<&str, &[u32]>("prefix", &key).unwrap();
             values_before_flush.push(read.to_vec());
         }
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let block = block_manager.get(&block.clone().id).await.unwrap().unwrap();
         #[allow(clippy::needless_range_loop)]
         for i in 0..n {
@@ -232,7 +245,10 @@ mod test {
             let read = block.get::<&str, &str>("prefix", &key);
             values_before_flush.push(read.unwrap().to_string());
         }
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
 
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
 
@@ -260,7 +276,10 @@ mod test {
             .unwrap();
         let new_id = forked_block.id;
         let block = block_manager.commit::<&str, String>(forked_block).await;
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let forked_block = block_manager.get(&new_id).await.unwrap().unwrap();
         for i in 0..n {
             let key = format!("key{}", i);
@@ -295,7 +314,10 @@ mod test {
             let read = block.get::<f32, &str>("prefix", key).unwrap();
             values_before_flush.push(read);
         }
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
         assert_eq!(size, block.get_size());
         #[allow(clippy::needless_range_loop)]
@@ -328,7 +350,10 @@ mod test {
         let size = delta.get_size::<&str, RoaringBitmap>();
         let delta_id = delta.id;
         let block = block_manager.commit::<&str, RoaringBitmap>(delta).await;
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
 
         assert_eq!(size, block.get_size());
@@ -393,7 +418,10 @@ mod test {
         let size = delta.get_size::<&str, &DataRecord>();
         let delta_id = delta.id;
         let block = block_manager.commit::<&str, &DataRecord>(delta).await;
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
         for i in 0..3 {
             let read = block.get::<&str, DataRecord>("", ids[i]).unwrap();
@@ -428,7 +456,10 @@ mod test {
         let size = delta.get_size::<u32, String>();
         let delta_id = delta.id;
         let block = block_manager.commit::<u32, String>(delta).await;
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
         assert_eq!(size, block.get_size());
 
@@ -463,7 +494,10 @@ mod test {
             let read = block.get::<u32, u32>("prefix", key);
             values_before_flush.push(read.unwrap().to_string());
         }
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
 
         let block = block_manager.get(&delta_id).await.unwrap().unwrap();
 
@@ -491,7 +525,10 @@ mod test {
             .unwrap();
         let new_id = forked_block.id;
         let block = block_manager.commit::<u32, u32>(forked_block).await;
-        block_manager.flush(&block).await.unwrap();
+        block_manager
+            .flush(&block, BlockCompression::None)
+            .await
+            .unwrap();
         let forked_block = block_manager.get(&new_id).await.unwrap().unwrap();
         #[allow(clippy::needless_range_loop)]
         for i in 0..n {
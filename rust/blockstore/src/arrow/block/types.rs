@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::io::SeekFrom;
 use std::ops::{Bound, RangeBounds};
 
+use crate::arrow::compression::{BlockCompression, CompressionError};
 use crate::arrow::types::{ArrowReadableKey, ArrowReadableValue};
 use arrow::array::ArrayData;
 use arrow::buffer::Buffer;
@@ -526,21 +527,49 @@ impl Block {
         Ok(bytes)
     }
 
+    /// Convert the block to bytes in Arrow IPC format, compressed with the given compression
+    pub fn to_bytes_with_compression(
+        &self,
+        compression: BlockCompression,
+    ) -> Result<Vec<u8>, BlockToBytesError> {
+        let bytes = self.to_bytes()?;
+        Ok(compression.compress(bytes)?)
+    }
+
     /// Load a block from bytes in Arrow IPC format with the given id
+    /// ### Notes
+    /// - The bytes may be compressed with any `BlockCompression`, it is detected from the bytes
     pub fn from_bytes(bytes: &[u8], id: Uuid) -> Result<Self, BlockLoadError> {
         Self::from_bytes_internal(bytes, id, false)
     }
 
-    /// Load a block from bytes in Arrow IPC format with the given id and validate the layout
+    /// Load a block from bytes in Arrow IPC format with the given id and validate the layout,
+    /// as well as the checksum of the bytes if one is given
     /// ### Notes
-    /// - This method should be used in tests to ensure that the layout of the IPC file is as expected
-    /// - The validation is not performant and should not be used in production code
-    pub fn from_bytes_with_validation(bytes: &[u8], id: Uuid) -> Result<Self, BlockLoadError> {
+    /// - This method should be used in tests and by the scrubber to ensure that the layout of the IPC file is as expected
+    /// - The validation is not performant and should not be used in the query path
+    pub fn from_bytes_with_validation(
+        bytes: &[u8],
+        id: Uuid,
+        checksum: Option<u32>,
+    ) -> Result<Self, BlockLoadError> {
+        if let Some(expected) = checksum {
+            let actual = Self::checksum(bytes);
+            if actual != expected {
+                return Err(BlockLoadError::ChecksumMismatch { expected, actual });
+            }
+        }
         Self::from_bytes_internal(bytes, id, true)
     }
 
+    /// The checksum of the stored bytes of a block, as recorded in the sparse index
+    pub fn checksum(bytes: &[u8]) -> u32 {
+        crc32fast::hash(bytes)
+    }
+
     fn from_bytes_internal(bytes: &[u8], id: Uuid, validate: bool) -> Result<Self, BlockLoadError> {
-        let cursor = std::io::Cursor::new(bytes);
+        let bytes = BlockCompression::decompress(bytes)?;
+        let cursor = std::io::Cursor::new(bytes.as_ref());
         Self::load_with_reader(cursor, id, validate)
     }
 
@@ -674,12 +703,15 @@ impl ChromaError for BlockSaveError {
 pub enum BlockToBytesError {
     #[error(transparent)]
     ArrowError(#[from] arrow::error::ArrowError),
+    #[error(transparent)]
+    CompressionError(#[from] CompressionError),
 }
 
 impl ChromaError for BlockToBytesError {
     fn code(&self) -> ErrorCodes {
         match self {
             BlockToBytesError::ArrowError(_) => ErrorCodes::Internal,
+            BlockToBytesError::CompressionError(e) => e.code(),
         }
     }
 }
@@ -698,6 +730,10 @@ pub enum BlockLoadError {
     BlockToBytesError(#[from] crate::arrow::block::types::BlockToBytesError),
     #[error(transparent)]
     CacheError(#[from] chroma_cache::CacheError),
+    #[error(transparent)]
+    CompressionError(#[from] CompressionError),
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
 }
 
 impl ChromaError for BlockLoadError {
@@ -709,6 +745,8 @@ impl ChromaError for BlockLoadError {
             BlockLoadError::NoRecordBatches => ErrorCodes::Internal,
             BlockLoadError::BlockToBytesError(_) => ErrorCodes::Internal,
             BlockLoadError::CacheError(_) => ErrorCodes::Internal,
+            BlockLoadError::CompressionError(e) => e.code(),
+            BlockLoadError::ChecksumMismatch { .. } => ErrorCodes::DataLoss,
         }
     }
 }
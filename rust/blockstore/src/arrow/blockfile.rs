@@ -1,3 +1,4 @@
+use super::merge::{merge_underfilled_blocks, MergeError};
 use super::migrations::{apply_migrations_to_blockfile, MigrationError};
 use super::provider::{GetError, RootManager};
 use super::root::{RootReader, RootWriter, Version};
@@ -7,10 +8,12 @@ use super::{
     flusher::ArrowBlockfileFlusher,
     types::{ArrowReadableKey, ArrowReadableValue, ArrowWriteableKey, ArrowWriteableValue},
 };
+use crate::arrow::compression::BlockCompression;
 use crate::arrow::root::CURRENT_VERSION;
 use crate::arrow::sparse_index::SparseIndexWriter;
 use crate::key::CompositeKey;
 use crate::key::KeyWrapper;
+use crate::key::PrefixKeyRange;
 use chroma_error::ChromaError;
 use chroma_error::ErrorCodes;
 use futures::future::join_all;
@@ -42,6 +45,8 @@ pub enum ArrowBlockfileError {
     BlockFetchError(#[from] GetError),
     #[error("Could not migrate blockfile to new version")]
     MigrationError(#[from] MigrationError),
+    #[error("Could not merge underfilled blocks")]
+    MergeError(#[from] MergeError),
 }
 
 impl ChromaError for ArrowBlockfileError {
@@ -50,6 +55,7 @@ impl ChromaError for ArrowBlockfileError {
             ArrowBlockfileError::BlockNotFound => ErrorCodes::Internal,
             ArrowBlockfileError::BlockFetchError(_) => ErrorCodes::Internal,
             ArrowBlockfileError::MigrationError(e) => e.code(),
+            ArrowBlockfileError::MergeError(e) => e.code(),
         }
     }
 }
@@ -59,10 +65,12 @@ impl ArrowUnorderedBlockfileWriter {
         id: Uuid,
         block_manager: BlockManager,
         root_manager: RootManager,
+        compression: BlockCompression,
     ) -> Self {
         let initial_block = block_manager.create::<K, V, UnorderedBlockDelta>();
         let sparse_index = SparseIndexWriter::new(initial_block.id);
-        let root_writer = RootWriter::new(CURRENT_VERSION, id, sparse_index);
+        let root_writer =
+            RootWriter::new(CURRENT_VERSION, id, sparse_index).with_compression(compression);
 
         let block_deltas = Arc::new(Mutex::new(HashMap::new()));
         {
@@ -127,6 +135,15 @@ impl ArrowUnorderedBlockfileWriter {
             blocks.push(block);
         }
 
+        let blocks = merge_underfilled_blocks::<K, V>(
+            &self.root.sparse_index,
+            &self.block_manager,
+            blocks,
+            &mut new_block_ids,
+        )
+        .await
+        .map_err(|e| Box::new(ArrowBlockfileError::MergeError(e)) as Box<dyn ChromaError>)?;
+
         apply_migrations_to_blockfile(&mut self.root, &self.block_manager, &new_block_ids)
             .await
             .map_err(|e| {
@@ -349,6 +366,55 @@ impl ArrowUnorderedBlockfileWriter {
         Ok(())
     }
 
+    /// Delete every key in `key_range` under `prefix`. Blocks that only hold keys in the range
+    /// are dropped from the sparse index without being loaded.
+    pub(crate) async fn delete_range<K: ArrowWriteableKey, V: ArrowWriteableValue, KeyRange>(
+        &self,
+        prefix: &str,
+        key_range: KeyRange,
+    ) -> Result<(), Box<dyn ChromaError>>
+    where
+        KeyRange: RangeBounds<K>,
+    {
+        let _guard = self.write_mutex.lock().await;
+        let range = PrefixKeyRange::new(prefix, key_range);
+        let (covered_block_ids, mut overlapping_block_ids) =
+            self.root.sparse_index.get_block_ids_for_range(&range);
+
+        for block_id in covered_block_ids {
+            if self.root.sparse_index.remove_block(&block_id) {
+                self.block_deltas.lock().remove(&block_id);
+            } else {
+                // The last block of a blockfile is never removed, so empty it instead
+                overlapping_block_ids.push(block_id);
+            }
+        }
+
+        for block_id in overlapping_block_ids {
+            let delta = {
+                let deltas = self.block_deltas.lock();
+                deltas.get(&block_id).cloned()
+            };
+            let delta = match delta {
+                Some(delta) => delta,
+                None => {
+                    let new_delta = self
+                        .block_manager
+                        .fork::<K, V, UnorderedBlockDelta>(&block_id)
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn ChromaError>)?;
+                    self.root.sparse_index.replace_block(block_id, new_delta.id);
+                    self.block_deltas
+                        .lock()
+                        .insert(new_delta.id, new_delta.clone());
+                    new_delta
+                }
+            };
+            delta.delete_range::<V>(&range);
+        }
+        Ok(())
+    }
+
     pub(crate) fn id(&self) -> Uuid {
         self.id
     }
@@ -378,6 +444,34 @@ impl<'me, K: ArrowReadableKey<'me> + Into<KeyWrapper>, V: ArrowReadableValue<'me
         }
     }
 
+    /// Stream every block of the blockfile in key order. Unlike `get_block`, the blocks are not
+    /// kept by this reader, so a blockfile larger than memory can be streamed.
+    pub(crate) fn get_block_stream(
+        &self,
+    ) -> impl Stream<Item = Result<Block, Box<dyn ChromaError>>> {
+        let block_manager = self.block_manager.clone();
+        futures::stream::iter(
+            self.root
+                .sparse_index
+                .get_block_ids_range::<K, _, _>(.., ..),
+        )
+        .then(move |block_id| {
+            let block_manager = block_manager.clone();
+            async move {
+                match block_manager.get(&block_id).await {
+                    Ok(Some(block)) => Ok(block),
+                    Ok(None) => {
+                        Err(Box::new(ArrowBlockfileError::BlockNotFound) as Box<dyn ChromaError>)
+                    }
+                    Err(e) => {
+                        Err(Box::new(ArrowBlockfileError::BlockFetchError(e))
+                            as Box<dyn ChromaError>)
+                    }
+                }
+            }
+        })
+    }
+
     pub(super) async fn get_block(&self, block_id: Uuid) -> Result<Option<&Block>, GetError> {
         // NOTE(rescrv):  This will complain with clippy, but we don't want to hold a reference to
         // the loaded_blocks map across a call to the block manager.
@@ -693,6 +787,7 @@ mod tests {
     use crate::arrow::block::delta::UnorderedBlockDelta;
     use crate::arrow::block::Block;
     use crate::arrow::blockfile::ArrowUnorderedBlockfileWriter;
+    use crate::arrow::compression::BlockCompression;
     use crate::arrow::provider::{BlockManager, RootManager};
     use crate::arrow::root::{RootWriter, Version};
     use crate::arrow::sparse_index::SparseIndexWriter;
@@ -709,7 +804,7 @@ mod tests {
     use proptest::prelude::*;
     use proptest::test_runner::Config;
     use rand::seq::IteratorRandom;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::ops::Bound;
     use std::sync::Arc;
     use tokio::runtime::Runtime;
@@ -755,6 +850,133 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_compressed_blocks() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage.clone(),
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            new_cache_for_test(),
+            new_cache_for_test(),
+        );
+
+        // Write an uncompressed blockfile, then fork it with zstd compression
+        let writer = blockfile_provider
+            .write::<&str, Vec<u32>>(BlockfileWriterOptions::default())
+            .await
+            .unwrap();
+        let id_1 = writer.id();
+        for i in 0..1000 {
+            let key = format!("{:04}", i);
+            writer.set("key", key.as_str(), vec![i]).await.unwrap();
+        }
+        let flusher = writer.commit::<&str, Vec<u32>>().await.unwrap();
+        flusher.flush::<&str, Vec<u32>>().await.unwrap();
+
+        let writer = blockfile_provider
+            .write::<&str, Vec<u32>>(
+                BlockfileWriterOptions::new()
+                    .fork(id_1)
+                    .compression(BlockCompression::Zstd),
+            )
+            .await
+            .unwrap();
+        let id_2 = writer.id();
+        // Only the last block is rewritten, the others are kept uncompressed
+        for i in 0..10 {
+            let key = format!("{:04}", i);
+            writer.set("other", key.as_str(), vec![i]).await.unwrap();
+        }
+        let flusher = writer.commit::<&str, Vec<u32>>().await.unwrap();
+        flusher.flush::<&str, Vec<u32>>().await.unwrap();
+
+        // The forked blockfile holds both compressed and uncompressed blocks
+        let root_manager = RootManager::new(storage.clone(), new_cache_for_test());
+        let mut compressions = HashSet::new();
+        for block_id in root_manager.get_all_block_ids(&id_2).await.unwrap() {
+            let bytes = storage.get(&format!("block/{}", block_id)).await.unwrap();
+            compressions.insert(BlockCompression::detect(&bytes).unwrap());
+        }
+        assert_eq!(
+            compressions,
+            HashSet::from([BlockCompression::None, BlockCompression::Zstd])
+        );
+
+        // A reader without cached blocks decodes both transparently
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage,
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            new_cache_for_test(),
+            new_cache_for_test(),
+        );
+        let reader = blockfile_provider
+            .read::<&str, &[u32]>(&id_2)
+            .await
+            .unwrap();
+        match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => {
+                assert_eq!(reader.root.compression, BlockCompression::Zstd);
+            }
+            _ => panic!("Unexpected reader type"),
+        }
+        for i in 0..1000 {
+            let key = format!("{:04}", i);
+            assert_eq!(reader.get("key", key.as_str()).await.unwrap().unwrap(), [i]);
+        }
+        for i in 0..10 {
+            let key = format!("{:04}", i);
+            assert_eq!(
+                reader.get("other", key.as_str()).await.unwrap().unwrap(),
+                [i]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scrub() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage.clone(),
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            new_cache_for_test(),
+            new_cache_for_test(),
+        );
+        let writer = blockfile_provider
+            .write::<&str, Vec<u32>>(BlockfileWriterOptions::default())
+            .await
+            .unwrap();
+        let id = writer.id();
+        for i in 0..1000 {
+            let key = format!("{:04}", i);
+            writer.set("key", key.as_str(), vec![i]).await.unwrap();
+        }
+        let flusher = writer.commit::<&str, Vec<u32>>().await.unwrap();
+        flusher.flush::<&str, Vec<u32>>().await.unwrap();
+
+        let root_manager = RootManager::new(storage.clone(), new_cache_for_test());
+        let blocks = root_manager.get_all_block_checksums(&id).await.unwrap();
+        assert!(blocks.len() > 1);
+        assert!(blocks.iter().all(|(_, checksum)| checksum.is_some()));
+
+        let report = blockfile_provider.scrub(&id).await;
+        assert!(report.is_healthy());
+        assert_eq!(report.files_checked, blocks.len() as u64 + 1);
+        assert_eq!(report.files_without_checksum, 0);
+
+        // Flip a byte of a block, it still decodes but no longer matches its checksum
+        let block_key = format!("block/{}", blocks[0].0);
+        let mut bytes = storage.get(&block_key).await.unwrap().to_vec();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        storage.put_bytes(&block_key, bytes).await.unwrap();
+
+        let report = blockfile_provider.scrub(&id).await;
+        assert_eq!(report.corruptions.len(), 1);
+        assert_eq!(report.corruptions[0].path, block_key);
+    }
+
     #[tokio::test]
     async fn test_writer_count() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -1584,6 +1806,159 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_merge_underfilled_blocks() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let block_cache = new_cache_for_test();
+        let sparse_index_cache = new_cache_for_test();
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage,
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            block_cache,
+            sparse_index_cache,
+        )
+        .with_min_block_size_bytes(TEST_MAX_BLOCK_SIZE_BYTES / 4);
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::default())
+            .await
+            .unwrap();
+        let id = writer.id();
+
+        let n = 2000;
+        for i in 0..n {
+            let key = format!("{:04}", i);
+            writer
+                .set("key", key.as_str(), format!("{:04}", i))
+                .await
+                .unwrap();
+        }
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        let blocks_before = match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => reader.root.sparse_index.len(),
+            _ => panic!("Unexpected reader type"),
+        };
+        assert!(blocks_before > 2);
+
+        // Keep every tenth key so that every block becomes underfilled
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::new().fork(id))
+            .await
+            .unwrap();
+        let id = writer.id();
+        let sparse_index = match &writer {
+            BlockfileWriter::ArrowUnorderedBlockfileWriter(writer) => {
+                writer.root.sparse_index.clone()
+            }
+            _ => panic!("Unexpected writer type"),
+        };
+        for i in (0..n).filter(|i| i % 10 != 0) {
+            let key = format!("{:04}", i);
+            writer
+                .delete::<&str, String>("key", key.as_str())
+                .await
+                .unwrap();
+        }
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+        assert!(sparse_index.stats().blocks_merged > 0);
+
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => {
+                assert!(reader.root.sparse_index.len() < blocks_before);
+                assert!(reader.root.sparse_index.is_valid());
+            }
+            _ => panic!("Unexpected reader type"),
+        }
+        assert_eq!(reader.count().await.unwrap(), n / 10);
+        for i in 0..n {
+            let key = format!("{:04}", i);
+            if i % 10 == 0 {
+                let value = reader.get("key", &key).await.unwrap().unwrap();
+                assert_eq!(value, format!("{:04}", i));
+            } else {
+                assert!(matches!(reader.get("key", &key).await, Ok(None)));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_range() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let block_cache = new_cache_for_test();
+        let sparse_index_cache = new_cache_for_test();
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage,
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            block_cache,
+            sparse_index_cache,
+        );
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::default())
+            .await
+            .unwrap();
+        let id = writer.id();
+
+        let n = 2000;
+        for prefix in ["a", "b"] {
+            for i in 0..n {
+                let key = format!("{:04}", i);
+                writer
+                    .set(prefix, key.as_str(), format!("{}{:04}", prefix, i))
+                    .await
+                    .unwrap();
+            }
+        }
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        let blocks_before = match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => reader.root.sparse_index.len(),
+            _ => panic!("Unexpected reader type"),
+        };
+
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::new().fork(id))
+            .await
+            .unwrap();
+        let id = writer.id();
+        writer
+            .delete_range::<&str, String, _>("a", "0100".."1900")
+            .await
+            .unwrap();
+        writer
+            .delete_range::<&str, String, _>("b", "1500"..)
+            .await
+            .unwrap();
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => {
+                assert!(reader.root.sparse_index.len() < blocks_before);
+                assert!(reader.root.sparse_index.is_valid());
+            }
+            _ => panic!("Unexpected reader type"),
+        }
+        assert_eq!(reader.count().await.unwrap(), 200 + 1500);
+        for i in 0..n {
+            let key = format!("{:04}", i);
+            match reader.get("a", &key).await.unwrap() {
+                Some(value) => {
+                    assert!(!(100..1900).contains(&i));
+                    assert_eq!(value, format!("a{:04}", i));
+                }
+                None => assert!((100..1900).contains(&i)),
+            }
+            assert_eq!(reader.get("b", &key).await.unwrap().is_some(), i < 1500);
+        }
+    }
+
     #[tokio::test]
     async fn test_rank() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -1852,8 +2227,15 @@ mod tests {
         let old_block_1 = Block::from_record_batch(old_block_id_1, old_block_1_record_batch);
         let old_block_2_record_batch = old_block_delta_2.finish::<&str, String>(None);
         let old_block_2 = Block::from_record_batch(old_block_id_2, old_block_2_record_batch);
-        block_manager.flush(&old_block_1).await.unwrap();
-        block_manager.flush(&old_block_2).await.unwrap();
+        block_manager
+            .flush(&old_block_1, BlockCompression::None)
+            .await
+            .unwrap();
+        // Blocks of an existing blockfile may be compressed or not
+        block_manager
+            .flush(&old_block_2, BlockCompression::Zstd)
+            .await
+            .unwrap();
         root_manager.flush::<&str>(&old_root_writer).await.unwrap();
 
         // We now have a v1 blockfile with 2 blocks and no counts in the root
@@ -0,0 +1,141 @@
+use chroma_error::{ChromaError, ErrorCodes};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt::Display, io::Read};
+use thiserror::Error;
+
+// Arrow IPC files start with this magic, compressed blocks start with the frame magic of the codec
+const ARROW_MAGIC: &[u8] = b"ARROW1";
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4D, 0x18];
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// The compression applied to the blocks of a blockfile when they are written to storage.
+/// The compression of a blockfile is recorded in its root, while each block is self-describing,
+/// so a blockfile may hold a mix of compressed and uncompressed blocks (e.g. after a fork).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockCompression {
+    #[default]
+    None,
+    Zstd,
+    Lz4,
+}
+
+impl Display for BlockCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockCompression::None => write!(f, "none"),
+            BlockCompression::Zstd => write!(f, "zstd"),
+            BlockCompression::Lz4 => write!(f, "lz4"),
+        }
+    }
+}
+
+impl TryFrom<&str> for BlockCompression {
+    type Error = CompressionError;
+    fn try_from(s: &str) -> Result<Self, CompressionError> {
+        match s {
+            "none" => Ok(BlockCompression::None),
+            "zstd" => Ok(BlockCompression::Zstd),
+            "lz4" => Ok(BlockCompression::Lz4),
+            _ => Err(CompressionError::UnknownCompression(s.to_string())),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CompressionError {
+    #[error("Unknown compression: {0}")]
+    UnknownCompression(String),
+    #[error("Unrecognized block encoding")]
+    UnrecognizedEncoding,
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+}
+
+impl ChromaError for CompressionError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            CompressionError::UnknownCompression(_) => ErrorCodes::InvalidArgument,
+            CompressionError::UnrecognizedEncoding => ErrorCodes::DataLoss,
+            CompressionError::IOError(_) => ErrorCodes::Internal,
+        }
+    }
+}
+
+impl BlockCompression {
+    /// Compress the serialized bytes of a block
+    pub(crate) fn compress(&self, bytes: Vec<u8>) -> Result<Vec<u8>, CompressionError> {
+        match self {
+            BlockCompression::None => Ok(bytes),
+            BlockCompression::Zstd => Ok(zstd::stream::encode_all(bytes.as_slice(), ZSTD_LEVEL)?),
+            BlockCompression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                std::io::copy(&mut bytes.as_slice(), &mut encoder)?;
+                encoder
+                    .finish()
+                    .map_err(|e| CompressionError::IOError(e.into()))
+            }
+        }
+    }
+
+    /// Detect the compression of stored block bytes from their leading magic
+    pub(crate) fn detect(bytes: &[u8]) -> Result<Self, CompressionError> {
+        if bytes.starts_with(ARROW_MAGIC) {
+            Ok(BlockCompression::None)
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Ok(BlockCompression::Zstd)
+        } else if bytes.starts_with(LZ4_MAGIC) {
+            Ok(BlockCompression::Lz4)
+        } else {
+            Err(CompressionError::UnrecognizedEncoding)
+        }
+    }
+
+    /// Decompress stored block bytes into Arrow IPC bytes, whatever compression they were written with
+    pub(crate) fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>, CompressionError> {
+        match Self::detect(bytes)? {
+            BlockCompression::None => Ok(Cow::Borrowed(bytes)),
+            BlockCompression::Zstd => Ok(Cow::Owned(zstd::stream::decode_all(bytes)?)),
+            BlockCompression::Lz4 => {
+                let mut decoded = Vec::new();
+                lz4_flex::frame::FrameDecoder::new(bytes).read_to_end(&mut decoded)?;
+                Ok(Cow::Owned(decoded))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let mut bytes = ARROW_MAGIC.to_vec();
+        bytes.extend(std::iter::repeat_n(42u8, 4096));
+
+        for compression in [
+            BlockCompression::None,
+            BlockCompression::Zstd,
+            BlockCompression::Lz4,
+        ] {
+            let compressed = compression
+                .compress(bytes.clone())
+                .expect("Compression should succeed");
+            assert_eq!(
+                BlockCompression::detect(&compressed).expect("Encoding should be detected"),
+                compression
+            );
+            if compression != BlockCompression::None {
+                assert!(compressed.len() < bytes.len());
+            }
+            let decompressed =
+                BlockCompression::decompress(&compressed).expect("Decompression should succeed");
+            assert_eq!(decompressed.as_ref(), bytes.as_slice());
+        }
+
+        assert!(BlockCompression::decompress(b"garbage").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use super::compression::BlockCompression;
 use chroma_cache::{CacheConfig, FoyerCacheConfig};
 use serde::{Deserialize, Serialize};
 
@@ -19,12 +20,21 @@ pub struct BlockManagerConfig {
     pub max_block_size_bytes: usize,
     #[serde(default)]
     pub block_cache_config: CacheConfig,
+    #[serde(default)]
+    pub compression: BlockCompression,
+    /// Blocks smaller than this are merged with their neighbors on commit. Zero disables merging.
+    #[serde(default = "BlockManagerConfig::default_min_block_size_bytes")]
+    pub min_block_size_bytes: usize,
 }
 
 impl BlockManagerConfig {
     fn default_max_block_size_bytes() -> usize {
         16384
     }
+
+    fn default_min_block_size_bytes() -> usize {
+        4096
+    }
 }
 
 impl Default for BlockManagerConfig {
@@ -35,6 +45,8 @@ impl Default for BlockManagerConfig {
                 capacity: 1000,
                 ..Default::default()
             }),
+            compression: BlockCompression::default(),
+            min_block_size_bytes: BlockManagerConfig::default_min_block_size_bytes(),
         }
     }
 }
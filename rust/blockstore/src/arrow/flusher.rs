@@ -50,7 +50,9 @@ impl ArrowBlockfileFlusher {
         // number of futures is high.
         let mut futures = Vec::new();
         for block in &self.blocks {
-            futures.push(self.block_manager.flush(block));
+            let block_id = block.id;
+            let flush = self.block_manager.flush(block, self.root.compression);
+            futures.push(async move { flush.await.map(|checksum| (block_id, checksum)) });
         }
         let num_futures = futures.len();
         // buffer_unordered hangs with 0 futures.
@@ -59,10 +61,17 @@ impl ArrowBlockfileFlusher {
             return Ok(());
         }
         tracing::debug!("Flushing {} blocks", num_futures);
-        futures::stream::iter(futures)
+        let checksums = futures::stream::iter(futures)
             .buffer_unordered(num_futures)
             .try_collect::<Vec<_>>()
             .await?;
+        // The root is written after the blocks so it can record their checksums
+        for (block_id, checksum) in checksums {
+            self.root
+                .sparse_index
+                .set_checksum(block_id, checksum)
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>)?;
+        }
 
         self.root_manager.flush::<K>(&self.root).await?;
         Ok(())
@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    block::{delta::OrderedBlockDelta, Block},
+    provider::{BlockManager, GetError},
+    sparse_index::{SetCountError, SparseIndexWriter},
+    types::{ArrowWriteableKey, ArrowWriteableValue},
+};
+use chroma_error::{ChromaError, ErrorCodes};
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum MergeError {
+    #[error("Block not found")]
+    BlockNotFound,
+    #[error("Block could not be fetched")]
+    BlockFetchError(#[from] GetError),
+    #[error("Error updating sparse index")]
+    SetCountError(#[from] SetCountError),
+}
+
+impl ChromaError for MergeError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            MergeError::BlockNotFound => ErrorCodes::Internal,
+            MergeError::BlockFetchError(e) => e.code(),
+            MergeError::SetCountError(e) => e.code(),
+        }
+    }
+}
+
+/// Merge runs of adjacent blocks when a block written in this commit is smaller than the
+/// minimum block size of the `BlockManager`. A run grows with the blocks that follow it (and, if
+/// nothing follows, the block before it) while it is underfilled and the merged block stays
+/// within the maximum block size. Blocks that were not written in this commit are only read
+/// when they neighbor an underfilled new block.
+/// # Arguments
+/// * `sparse_index` - The sparse index of the blockfile being committed
+/// * `block_manager` - The block manager used to read untouched blocks and commit merged ones
+/// * `blocks` - The blocks written in this commit
+/// * `new_block_ids` - The ids of the blocks written in this commit, updated with the merged blocks
+/// # Returns
+/// The blocks to flush for this commit
+pub(super) async fn merge_underfilled_blocks<K: ArrowWriteableKey, V: ArrowWriteableValue>(
+    sparse_index: &SparseIndexWriter,
+    block_manager: &BlockManager,
+    blocks: Vec<Block>,
+    new_block_ids: &mut HashSet<Uuid>,
+) -> Result<Vec<Block>, MergeError> {
+    let min_block_size_bytes = block_manager.min_block_size_bytes();
+    let max_block_size_bytes = block_manager.max_block_size_bytes();
+    if min_block_size_bytes == 0 || blocks.is_empty() {
+        return Ok(blocks);
+    }
+
+    let mut committed = blocks
+        .into_iter()
+        .map(|block| (block.id, block))
+        .collect::<HashMap<_, _>>();
+    let block_ids = sparse_index.block_ids();
+    let mut merged_blocks = Vec::new();
+    // The block that currently precedes the block being visited, after any merges
+    let mut previous: Option<Block> = None;
+    let mut i = 0;
+    while i < block_ids.len() {
+        let block_id = block_ids[i];
+        let block = match committed.get(&block_id) {
+            Some(block) if block.get_size() < min_block_size_bytes => block.clone(),
+            _ => {
+                previous = None;
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut run_size = block.get_size();
+        let mut run = vec![block];
+        let mut next = i + 1;
+        while run_size < min_block_size_bytes && next < block_ids.len() {
+            let next_block = get_block(block_manager, &committed, &block_ids[next]).await?;
+            if run_size + next_block.get_size() > max_block_size_bytes {
+                break;
+            }
+            run_size += next_block.get_size();
+            run.push(next_block);
+            next += 1;
+        }
+        if run.len() == 1 && next == block_ids.len() && i > 0 {
+            // The last block has nothing after it to absorb, so fold it into the block before it
+            let previous_block = match previous.take() {
+                Some(previous_block) => previous_block,
+                None => get_block(block_manager, &committed, &block_ids[i - 1]).await?,
+            };
+            if run_size + previous_block.get_size() <= max_block_size_bytes {
+                run.insert(0, previous_block);
+            }
+        }
+
+        if run.len() == 1 {
+            previous = run.pop();
+            i = next;
+            continue;
+        }
+
+        let mut delta = block_manager.create::<K, V, OrderedBlockDelta>();
+        for block in run.iter() {
+            delta.extend_from_block::<K, V>(block);
+        }
+        let run_ids = run.iter().map(|block| block.id).collect::<Vec<_>>();
+        sparse_index.merge_blocks(&run_ids, delta.id)?;
+        sparse_index.set_count(delta.id, delta.len() as u32)?;
+        for block_id in run_ids.iter() {
+            committed.remove(block_id);
+            merged_blocks.retain(|block: &Block| block.id != *block_id);
+        }
+        new_block_ids.insert(delta.id);
+        let merged = block_manager.commit::<K, V>(delta).await;
+        merged_blocks.push(merged.clone());
+        previous = Some(merged);
+        i = next;
+    }
+
+    if !merged_blocks.is_empty() {
+        let stats = sparse_index.stats();
+        tracing::debug!(
+            "Merged underfilled blocks into {} blocks: {} merged, {} split, {} removed",
+            merged_blocks.len(),
+            stats.blocks_merged,
+            stats.blocks_split,
+            stats.blocks_removed
+        );
+    }
+
+    Ok(committed.into_values().chain(merged_blocks).collect())
+}
+
+async fn get_block(
+    block_manager: &BlockManager,
+    committed: &HashMap<Uuid, Block>,
+    block_id: &Uuid,
+) -> Result<Block, MergeError> {
+    if let Some(block) = committed.get(block_id) {
+        return Ok(block.clone());
+    }
+    block_manager
+        .get(block_id)
+        .await?
+        .ok_or(MergeError::BlockNotFound)
+}
@@ -73,6 +73,9 @@ async fn migrate_v1_to_v1_1(
     Ok(())
 }
 
+/// Migrate the root of a blockfile being written to the current version.
+/// Blocks are only read through the `BlockManager`, which detects the compression of each block,
+/// so the blocks of an existing blockfile may be any mix of compressed and uncompressed blocks.
 pub async fn apply_migrations_to_blockfile(
     root: &mut RootWriter,
     block_manager: &BlockManager,
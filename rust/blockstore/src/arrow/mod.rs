@@ -1,9 +1,11 @@
 pub(crate) mod block;
 pub(crate) mod blockfile;
+pub mod compression;
 #[cfg(test)]
 mod concurrency_test;
 pub mod config;
 pub(crate) mod flusher;
+mod merge;
 mod migrations;
 pub(crate) mod ordered_blockfile_writer;
 pub mod provider;
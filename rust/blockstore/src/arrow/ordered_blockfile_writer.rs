@@ -1,5 +1,7 @@
 use super::block::delta::types::Delta;
 use super::block::delta::OrderedBlockDelta;
+use super::merge::merge_underfilled_blocks;
+use super::merge::MergeError;
 use super::migrations::apply_migrations_to_blockfile;
 use super::migrations::MigrationError;
 use super::provider::BlockManager;
@@ -10,14 +12,17 @@ use super::{
     flusher::ArrowBlockfileFlusher,
     types::{ArrowWriteableKey, ArrowWriteableValue},
 };
+use crate::arrow::compression::BlockCompression;
 use crate::arrow::root::CURRENT_VERSION;
 use crate::arrow::sparse_index::SparseIndexWriter;
 use crate::key::CompositeKey;
+use crate::key::PrefixKeyRange;
 use chroma_error::ChromaError;
 use chroma_error::ErrorCodes;
 use itertools::Itertools;
 use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::ops::RangeBounds;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::Mutex;
@@ -52,12 +57,15 @@ pub struct ArrowOrderedBlockfileWriter {
 pub enum ArrowBlockfileError {
     #[error("Could not migrate blockfile to new version")]
     MigrationError(#[from] MigrationError),
+    #[error("Could not merge underfilled blocks")]
+    MergeError(#[from] MergeError),
 }
 
 impl ChromaError for ArrowBlockfileError {
     fn code(&self) -> ErrorCodes {
         match self {
             ArrowBlockfileError::MigrationError(e) => e.code(),
+            ArrowBlockfileError::MergeError(e) => e.code(),
         }
     }
 }
@@ -67,10 +75,12 @@ impl ArrowOrderedBlockfileWriter {
         id: Uuid,
         block_manager: BlockManager,
         root_manager: RootManager,
+        compression: BlockCompression,
     ) -> Self {
         let initial_block = block_manager.create::<K, V, OrderedBlockDelta>();
         let sparse_index = SparseIndexWriter::new(initial_block.id);
-        let root_writer = RootWriter::new(CURRENT_VERSION, id, sparse_index);
+        let root_writer =
+            RootWriter::new(CURRENT_VERSION, id, sparse_index).with_compression(compression);
 
         Self {
             block_manager,
@@ -188,6 +198,15 @@ impl ArrowOrderedBlockfileWriter {
             }
         }
 
+        let blocks = merge_underfilled_blocks::<K, V>(
+            &self.root.sparse_index,
+            &self.block_manager,
+            blocks,
+            &mut new_block_ids,
+        )
+        .await
+        .map_err(|e| Box::new(ArrowBlockfileError::MergeError(e)) as Box<dyn ChromaError>)?;
+
         apply_migrations_to_blockfile(&mut self.root, &self.block_manager, &new_block_ids)
             .await
             .map_err(|e| {
@@ -348,6 +367,69 @@ impl ArrowOrderedBlockfileWriter {
         Ok(())
     }
 
+    /// Delete every key in `key_range` under `prefix`. Blocks that only hold keys in the range
+    /// are dropped from the sparse index without being loaded.
+    /// Like every other mutation of this writer, the range must sort after all previous mutations
+    /// and before all subsequent ones.
+    pub(crate) async fn delete_range<K: ArrowWriteableKey, V: ArrowWriteableValue, KeyRange>(
+        &self,
+        prefix: &str,
+        key_range: KeyRange,
+    ) -> Result<(), Box<dyn ChromaError>>
+    where
+        KeyRange: RangeBounds<K>,
+    {
+        let range = PrefixKeyRange::new(prefix, key_range);
+        let mut inner = self.inner.lock().await;
+        // The start key of the block at the front of the remaining block stack, None if it is the first block
+        let mut next_start_key = inner
+            .current_block_delta
+            .as_ref()
+            .and_then(|(_, end_key)| end_key.clone());
+
+        loop {
+            if let Some((delta, end_key)) = inner.current_block_delta.as_mut() {
+                delta.skip_range::<K, V>(&range);
+                match end_key {
+                    Some(end_key) if !range.ends_before(&end_key.prefix, &end_key.key) => {}
+                    _ => return Ok(()),
+                }
+            }
+
+            let Some((block_id, end_key)) = inner.remaining_block_stack.front().cloned() else {
+                return Ok(());
+            };
+            if let Some(start_key) = next_start_key.as_ref() {
+                if range.ends_before(&start_key.prefix, &start_key.key) {
+                    return Ok(());
+                }
+            }
+            inner.remaining_block_stack.pop_front();
+            let start_key = std::mem::replace(&mut next_start_key, end_key.clone());
+
+            let Some(block_end_key) = end_key.as_ref() else {
+                self.swap_current_delta::<K, V>(&mut inner, &block_id, end_key)
+                    .await?;
+                continue;
+            };
+            if range.starts_at_or_after(&block_end_key.prefix, &block_end_key.key) {
+                // The block sorts before the range and is left untouched
+                continue;
+            }
+            let covered = match start_key.as_ref() {
+                Some(start_key) => {
+                    range.contains(&start_key.prefix, &start_key.key)
+                        && range.covers_up_to(&block_end_key.prefix, &block_end_key.key)
+                }
+                None => false,
+            };
+            if !(covered && self.root.sparse_index.remove_block(&block_id)) {
+                self.swap_current_delta::<K, V>(&mut inner, &block_id, end_key)
+                    .await?;
+            }
+        }
+    }
+
     pub(crate) fn id(&self) -> Uuid {
         self.id
     }
@@ -361,6 +443,7 @@ mod tests {
     use crate::arrow::block::delta::types::Delta;
     use crate::arrow::block::delta::OrderedBlockDelta;
     use crate::arrow::block::Block;
+    use crate::arrow::compression::BlockCompression;
     use crate::arrow::ordered_blockfile_writer::{ArrowOrderedBlockfileWriter, Inner};
     use crate::arrow::provider::{BlockManager, RootManager};
     use crate::arrow::root::{RootWriter, Version};
@@ -944,8 +1027,15 @@ mod tests {
         let old_block_1 = Block::from_record_batch(old_block_id_1, old_block_1_record_batch);
         let old_block_2_record_batch = old_block_delta_2.finish::<&str, String>(None);
         let old_block_2 = Block::from_record_batch(old_block_id_2, old_block_2_record_batch);
-        block_manager.flush(&old_block_1).await.unwrap();
-        block_manager.flush(&old_block_2).await.unwrap();
+        block_manager
+            .flush(&old_block_1, BlockCompression::None)
+            .await
+            .unwrap();
+        // Blocks of an existing blockfile may be compressed or not
+        block_manager
+            .flush(&old_block_2, BlockCompression::Zstd)
+            .await
+            .unwrap();
         root_manager.flush::<&str>(&old_root_writer).await.unwrap();
 
         // We now have a v1 blockfile with 2 blocks and no counts in the root
@@ -1048,4 +1138,78 @@ mod tests {
         assert_eq!(count_in_index, 3);
         assert_eq!(reader.count().await.unwrap(), 3);
     }
+
+    #[tokio::test]
+    async fn test_delete_range() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let block_cache = new_cache_for_test();
+        let sparse_index_cache = new_cache_for_test();
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage,
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            block_cache,
+            sparse_index_cache,
+        );
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::new().ordered_mutations())
+            .await
+            .unwrap();
+        let id = writer.id();
+
+        let n = 2000;
+        for prefix in ["a", "b"] {
+            for i in 0..n {
+                let key = format!("{:04}", i);
+                writer
+                    .set(prefix, key.as_str(), format!("{}{:04}", prefix, i))
+                    .await
+                    .unwrap();
+            }
+        }
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        let blocks_before = match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => reader.root.sparse_index.len(),
+            _ => panic!("Unexpected reader type"),
+        };
+
+        let writer = blockfile_provider
+            .write::<&str, String>(BlockfileWriterOptions::new().ordered_mutations().fork(id))
+            .await
+            .unwrap();
+        let id = writer.id();
+        writer
+            .delete_range::<&str, String, _>("a", "0100".."1900")
+            .await
+            .unwrap();
+        writer
+            .delete_range::<&str, String, _>("b", "1500"..)
+            .await
+            .unwrap();
+        let flusher = writer.commit::<&str, String>().await.unwrap();
+        flusher.flush::<&str, String>().await.unwrap();
+
+        let reader = blockfile_provider.read::<&str, &str>(&id).await.unwrap();
+        match &reader {
+            crate::BlockfileReader::ArrowBlockfileReader(reader) => {
+                assert!(reader.root.sparse_index.len() < blocks_before);
+                assert!(reader.root.sparse_index.is_valid());
+            }
+            _ => panic!("Unexpected reader type"),
+        }
+        assert_eq!(reader.count().await.unwrap(), 200 + 1500);
+        for i in 0..n {
+            let key = format!("{:04}", i);
+            match reader.get("a", &key).await.unwrap() {
+                Some(value) => {
+                    assert!(!(100..1900).contains(&i));
+                    assert_eq!(value, format!("a{:04}", i));
+                }
+                None => assert!((100..1900).contains(&i)),
+            }
+            assert_eq!(reader.get("b", &key).await.unwrap().is_some(), i < 1500);
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use super::{
     block::{delta::types::Delta, Block, BlockLoadError},
     blockfile::{ArrowBlockfileReader, ArrowUnorderedBlockfileWriter},
+    compression::BlockCompression,
     config::ArrowBlockfileProviderConfig,
     ordered_blockfile_writer::ArrowOrderedBlockfileWriter,
     root::{FromBytesError, RootReader, RootWriter},
@@ -18,6 +19,7 @@ use chroma_cache::{CacheError, PersistentCache};
 use chroma_config::{registry::Registry, Configurable};
 use chroma_error::{ChromaError, ErrorCodes};
 use chroma_storage::Storage;
+use chroma_types::{BlockfileStats, ScrubReport};
 use futures::{stream::FuturesUnordered, StreamExt};
 use std::sync::Arc;
 use thiserror::Error;
@@ -47,6 +49,7 @@ impl ChromaError for ArrowBlockfileProviderPrefetchError {
 pub struct ArrowBlockfileProvider {
     block_manager: BlockManager,
     root_manager: RootManager,
+    compression: BlockCompression,
 }
 
 impl ArrowBlockfileProvider {
@@ -59,6 +62,32 @@ impl ArrowBlockfileProvider {
         Self {
             block_manager: BlockManager::new(storage.clone(), max_block_size_bytes, block_cache),
             root_manager: RootManager::new(storage, root_cache),
+            compression: BlockCompression::None,
+        }
+    }
+
+    /// Set the compression of the blocks of blockfiles created without an explicit compression.
+    /// Forked blockfiles keep the compression of the blockfile they are forked from.
+    pub fn with_compression(mut self, compression: BlockCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Merge adjacent blocks smaller than `min_block_size_bytes` when a blockfile is committed,
+    /// as long as the merged block stays within the maximum block size. Zero disables merging.
+    pub fn with_min_block_size_bytes(mut self, min_block_size_bytes: usize) -> Self {
+        self.block_manager = self
+            .block_manager
+            .with_min_block_size_bytes(min_block_size_bytes);
+        self
+    }
+
+    /// A provider sharing this provider's caches whose writes go through `tenant`'s storage handle.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            block_manager: self.block_manager.for_tenant(tenant),
+            root_manager: self.root_manager.for_tenant(tenant),
+            compression: self.compression,
         }
     }
 
@@ -104,6 +133,69 @@ impl ArrowBlockfileProvider {
         Ok(count)
     }
 
+    /// Loads every block of the blockfile to report its layout
+    pub async fn stats(
+        &self,
+        id: &Uuid,
+    ) -> Result<BlockfileStats, ArrowBlockfileProviderPrefetchError> {
+        let block_ids = self
+            .root_manager
+            .get_all_block_ids(id)
+            .await
+            .map_err(|e| ArrowBlockfileProviderPrefetchError::RootManager(Box::new(e)))?;
+
+        let mut futures = block_ids
+            .iter()
+            .map(|block_id| self.block_manager.get(block_id))
+            .collect::<FuturesUnordered<_>>();
+
+        let mut stats = BlockfileStats::default();
+        while let Some(result) = futures.next().await {
+            if let Some(block) = result? {
+                stats.add_block(block.get_size() as u64);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Verifies the root and every block of the blockfile in storage, bypassing the caches.
+    /// Missing or corrupt files are recorded in the report rather than returned as errors.
+    pub async fn scrub(&self, id: &Uuid) -> ScrubReport {
+        let mut report = ScrubReport {
+            files_checked: 1,
+            ..Default::default()
+        };
+        let blocks = match self.root_manager.get_all_block_checksums(id).await {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                report.add_corruption(RootManager::get_storage_key(id), e);
+                return report;
+            }
+        };
+
+        let mut futures = blocks
+            .iter()
+            .map(|(block_id, checksum)| async move {
+                let result = self.block_manager.verify(block_id, *checksum).await;
+                (block_id, checksum.is_some(), result)
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some((block_id, has_checksum, result)) = futures.next().await {
+            report.files_checked += 1;
+            if !has_checksum {
+                report.files_without_checksum += 1;
+            }
+            if let Err(e) = result {
+                tracing::error!("Block {} of blockfile {} is corrupt: {}", block_id, id, e);
+                report.add_corruption(format!("block/{}", block_id), e);
+            }
+        }
+
+        report
+    }
+
     pub async fn write<
         'new,
         K: Key + Into<KeyWrapper> + ArrowWriteableKey + 'new,
@@ -115,7 +207,7 @@ impl ArrowBlockfileProvider {
         if let Some(fork_from) = options.fork_from {
             tracing::info!("Forking blockfile from {:?}", fork_from);
             let new_id = Uuid::new_v4();
-            let new_root = self
+            let mut new_root = self
                 .root_manager
                 .fork::<K>(&fork_from, new_id)
                 .await
@@ -123,6 +215,9 @@ impl ArrowBlockfileProvider {
                     tracing::error!("Error forking root: {:?}", e);
                     Box::new(CreateError::Other(Box::new(e)))
                 })?;
+            if let Some(compression) = options.compression {
+                new_root.compression = compression;
+            }
 
             match options.mutation_ordering {
                 BlockfileWriterMutationOrdering::Ordered => {
@@ -147,6 +242,7 @@ impl ArrowBlockfileProvider {
             }
         } else {
             let new_id = Uuid::new_v4();
+            let compression = options.compression.unwrap_or(self.compression);
 
             match options.mutation_ordering {
                 BlockfileWriterMutationOrdering::Ordered => {
@@ -154,6 +250,7 @@ impl ArrowBlockfileProvider {
                         new_id,
                         self.block_manager.clone(),
                         self.root_manager.clone(),
+                        compression,
                     );
 
                     Ok(BlockfileWriter::ArrowOrderedBlockfileWriter(file))
@@ -163,6 +260,7 @@ impl ArrowBlockfileProvider {
                         new_id,
                         self.block_manager.clone(),
                         self.root_manager.clone(),
+                        compression,
                     );
                     Ok(BlockfileWriter::ArrowUnorderedBlockfileWriter(file))
                 }
@@ -210,7 +308,9 @@ impl Configurable<(ArrowBlockfileProviderConfig, Storage)> for ArrowBlockfilePro
             blockfile_config.block_manager_config.max_block_size_bytes,
             block_cache,
             sparse_index_cache,
-        ))
+        )
+        .with_compression(blockfile_config.block_manager_config.compression)
+        .with_min_block_size_bytes(blockfile_config.block_manager_config.min_block_size_bytes))
     }
 }
 
@@ -259,6 +359,7 @@ pub(super) struct BlockManager {
     block_cache: Arc<dyn PersistentCache<Uuid, Block>>,
     storage: Storage,
     max_block_size_bytes: usize,
+    min_block_size_bytes: usize,
     write_mutex: Arc<tokio::sync::Mutex<()>>,
 }
 
@@ -273,10 +374,23 @@ impl BlockManager {
             block_cache,
             storage,
             max_block_size_bytes,
+            min_block_size_bytes: 0,
             write_mutex: Arc::new(tokio::sync::Mutex::new(())),
         }
     }
 
+    pub(super) fn with_min_block_size_bytes(mut self, min_block_size_bytes: usize) -> Self {
+        self.min_block_size_bytes = min_block_size_bytes;
+        self
+    }
+
+    pub(super) fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            storage: self.storage.for_tenant(tenant),
+            ..self.clone()
+        }
+    }
+
     pub(super) fn create<K: ArrowWriteableKey, V: ArrowWriteableValue, D: Delta>(&self) -> D {
         let new_block_id = Uuid::new_v4();
         D::new::<K, V>(new_block_id)
@@ -373,8 +487,21 @@ impl BlockManager {
         }
     }
 
-    pub(super) async fn flush(&self, block: &Block) -> Result<(), Box<dyn ChromaError>> {
-        let bytes = match block.to_bytes() {
+    /// Fetch the block from storage, bypassing the cache, and verify its checksum and layout
+    pub(super) async fn verify(&self, id: &Uuid, checksum: Option<u32>) -> Result<(), GetError> {
+        let key = format!("block/{}", id);
+        let bytes = self.storage.get(&key).await?;
+        Block::from_bytes_with_validation(&bytes, *id, checksum)?;
+        Ok(())
+    }
+
+    /// Write the block to storage and return the checksum of the stored bytes
+    pub(super) async fn flush(
+        &self,
+        block: &Block,
+        compression: BlockCompression,
+    ) -> Result<u32, Box<dyn ChromaError>> {
+        let bytes = match block.to_bytes_with_compression(compression) {
             Ok(bytes) => bytes,
             Err(e) => {
                 tracing::error!("Failed to convert block to bytes");
@@ -383,6 +510,7 @@ impl BlockManager {
         };
         let key = format!("block/{}", block.id);
         let block_bytes_len = bytes.len();
+        let checksum = Block::checksum(&bytes);
         let res = self.storage.put_bytes(&key, bytes).await;
         match res {
             Ok(_) => {
@@ -397,12 +525,16 @@ impl BlockManager {
                 return Err(Box::new(e));
             }
         }
-        Ok(())
+        Ok(checksum)
     }
 
     pub(super) fn max_block_size_bytes(&self) -> usize {
         self.max_block_size_bytes
     }
+
+    pub(super) fn min_block_size_bytes(&self) -> usize {
+        self.min_block_size_bytes
+    }
 }
 
 #[derive(Error, Debug)]
@@ -461,6 +593,13 @@ impl RootManager {
         Self { cache, storage }
     }
 
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            storage: self.storage.for_tenant(tenant),
+        }
+    }
+
     pub async fn get<'new, K: ArrowReadableKey<'new> + 'new>(
         &self,
         id: &Uuid,
@@ -505,6 +644,22 @@ impl RootManager {
         }
     }
 
+    /// Returns the id of every block of the blockfile with the checksum of its stored bytes, if known
+    pub(super) async fn get_all_block_checksums(
+        &self,
+        id: &Uuid,
+    ) -> Result<Vec<(Uuid, Option<u32>)>, RootManagerError> {
+        let key = Self::get_storage_key(id);
+        match self.storage.get(&key).await {
+            Ok(bytes) => RootReader::get_all_block_checksums_from_bytes(&bytes, *id)
+                .map_err(RootManagerError::FromBytesError),
+            Err(e) => {
+                tracing::error!("Error reading root from storage: {}", e);
+                Err(RootManagerError::StorageGetError(e))
+            }
+        }
+    }
+
     pub async fn flush<'read, K: ArrowWriteableKey + 'read>(
         &self,
         root: &RootWriter,
@@ -1,5 +1,6 @@
 use super::{
     block::{Block, BlockToBytesError},
+    compression::{BlockCompression, CompressionError},
     sparse_index::{SparseIndexReader, SparseIndexValue, SparseIndexWriter, SparseIndexWriterData},
     types::{ArrowReadableKey, ArrowWriteableKey},
 };
@@ -75,6 +76,7 @@ pub struct RootWriter {
     // Metadata
     pub(super) id: Uuid,
     pub(super) version: Version,
+    pub(super) compression: BlockCompression,
 }
 
 impl RootWriter {
@@ -83,9 +85,16 @@ impl RootWriter {
             version,
             sparse_index,
             id,
+            compression: BlockCompression::None,
         }
     }
 
+    /// Set the compression of the blocks written by this blockfile
+    pub(super) fn with_compression(mut self, compression: BlockCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     fn ids_as_arrow(&self, sparse_index_data: &SparseIndexWriterData) -> (Arc<dyn Array>, Field) {
         if self.version == Version::V1 {
             let mut ids_builder = StringBuilder::new();
@@ -124,6 +133,21 @@ impl RootWriter {
         )
     }
 
+    fn checksums_as_arrow(
+        &self,
+        sparse_index_data: &SparseIndexWriterData,
+    ) -> (Arc<dyn Array>, Field) {
+        let mut checksum_builder = UInt32Builder::new();
+        // Blocks inherited from blockfiles written before checksums have no checksum
+        for (_, block_id) in sparse_index_data.forward.iter() {
+            checksum_builder.append_option(sparse_index_data.checksums.get(block_id).copied());
+        }
+        (
+            Arc::new(checksum_builder.finish()),
+            Field::new("checksum", DataType::UInt32, true),
+        )
+    }
+
     pub(super) fn to_bytes<K: ArrowWriteableKey>(&self) -> Result<Vec<u8>, Box<dyn ChromaError>> {
         // Serialize the sparse index as an arrow record batch
         // TODO(hammadb): Note that this should ideally use the Block API to serialize the sparse
@@ -176,12 +200,20 @@ impl RootWriter {
             let (built_counts, count_field) = self.counts_as_arrow(&sparse_index_data);
             schema_fields.push(count_field);
             data_arrays.push(built_counts);
+            // The checksum column is optional, roots written before it was introduced lack it
+            let (built_checksums, checksum_field) = self.checksums_as_arrow(&sparse_index_data);
+            schema_fields.push(checksum_field);
+            data_arrays.push(built_checksums);
         }
 
-        let metadata = HashMap::from_iter(vec![
+        let mut metadata = HashMap::from_iter(vec![
             ("version".to_string(), self.version.to_string()),
             ("id".to_string(), self.id.to_string()),
         ]);
+        // Roots written before block compression have no compression field, which means none
+        if self.compression != BlockCompression::None {
+            metadata.insert("compression".to_string(), self.compression.to_string());
+        }
 
         let schema = Arc::new(Schema::new_with_metadata(schema_fields, metadata));
 
@@ -228,6 +260,8 @@ pub struct RootReader {
     // Metadata
     pub(super) id: Uuid,
     pub(super) version: Version,
+    #[serde(default)]
+    pub(super) compression: BlockCompression,
 }
 
 impl chroma_cache::Weighted for RootReader {
@@ -252,6 +286,8 @@ pub enum FromBytesError {
     IdMismatch,
     #[error(transparent)]
     VersionError(#[from] VersionError),
+    #[error(transparent)]
+    CompressionError(#[from] CompressionError),
 }
 
 impl ChromaError for FromBytesError {
@@ -264,6 +300,7 @@ impl ChromaError for FromBytesError {
             FromBytesError::NoDataError => chroma_error::ErrorCodes::Internal,
             FromBytesError::IdMismatch => chroma_error::ErrorCodes::InvalidArgument,
             FromBytesError::VersionError(e) => e.code(),
+            FromBytesError::CompressionError(e) => e.code(),
         }
     }
 }
@@ -273,6 +310,17 @@ impl RootReader {
         bytes: &[u8],
         id: Uuid,
     ) -> Result<Vec<Uuid>, FromBytesError> {
+        Ok(Self::get_all_block_checksums_from_bytes(bytes, id)?
+            .into_iter()
+            .map(|(block_id, _)| block_id)
+            .collect())
+    }
+
+    /// Returns the id of every block of the blockfile along with the checksum of its stored bytes, if known
+    pub(super) fn get_all_block_checksums_from_bytes(
+        bytes: &[u8],
+        id: Uuid,
+    ) -> Result<Vec<(Uuid, Option<u32>)>, FromBytesError> {
         let mut cursor = std::io::Cursor::new(bytes);
         let arrow_reader = arrow::ipc::reader::FileReader::try_new(&mut cursor, None);
 
@@ -292,7 +340,13 @@ impl RootReader {
             return Err(FromBytesError::IdMismatch);
         }
 
-        Self::block_ids_from_record_batch(&record_batch, version)
+        let ids = Self::block_ids_from_record_batch(&record_batch, version)?;
+        let checksums = Self::checksums_from_record_batch(&record_batch);
+        Ok(ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, block_id)| (block_id, checksums.and_then(|arr| checksum_at(arr, i))))
+            .collect())
     }
 
     pub(super) fn from_bytes<'data, K: ArrowReadableKey<'data>>(
@@ -342,6 +396,7 @@ impl RootReader {
         }
 
         let ids = Self::block_ids_from_record_batch(record_batch, version)?;
+        let checksums = Self::checksums_from_record_batch(record_batch);
 
         let mut forward = BTreeMap::new();
         for (i, block_id) in ids.iter().enumerate() {
@@ -352,28 +407,33 @@ impl RootReader {
                 Some(count_arr) => count_arr.value(i),
                 None => 0,
             };
+            let mut value = SparseIndexValue::new(*block_id, count);
+            value.checksum = checksums.and_then(|arr| checksum_at(arr, i));
 
             match prefix {
                 "START" => {
-                    forward.insert(
-                        SparseIndexDelimiter::Start,
-                        SparseIndexValue::new(*block_id, count),
-                    );
+                    forward.insert(SparseIndexDelimiter::Start, value);
                 }
                 _ => {
                     forward.insert(
                         SparseIndexDelimiter::Key(CompositeKey::new(prefix.to_string(), key)),
-                        SparseIndexValue::new(*block_id, count),
+                        value,
                     );
                 }
             }
         }
 
+        let compression = match record_batch.schema_ref().metadata.get("compression") {
+            Some(compression) => BlockCompression::try_from(compression.as_str())?,
+            None => BlockCompression::None,
+        };
+
         let sparse_index_reader = SparseIndexReader::new(forward);
         Ok(Self {
             version,
             sparse_index: sparse_index_reader,
             id,
+            compression,
         })
     }
 
@@ -383,6 +443,7 @@ impl RootReader {
             version: self.version,
             sparse_index: new_sparse_index,
             id: new_id,
+            compression: self.compression,
         }
     }
 
@@ -404,6 +465,12 @@ impl RootReader {
         }
     }
 
+    fn checksums_from_record_batch(record_batch: &RecordBatch) -> Option<&UInt32Array> {
+        record_batch
+            .column_by_name("checksum")
+            .and_then(|arr| arr.as_any().downcast_ref::<UInt32Array>())
+    }
+
     fn block_ids_from_record_batch(
         record_batch: &RecordBatch,
         version: Version,
@@ -438,6 +505,14 @@ impl RootReader {
     }
 }
 
+fn checksum_at(checksums: &UInt32Array, index: usize) -> Option<u32> {
+    if checksums.is_null(index) {
+        None
+    } else {
+        Some(checksums.value(index))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -484,6 +559,10 @@ mod test {
             .sparse_index
             .set_count(block_ids[3], 4)
             .expect("Set count should succeed");
+        root_writer
+            .sparse_index
+            .set_checksum(block_ids[1], 42)
+            .expect("Set checksum should succeed");
 
         let bytes = root_writer
             .to_bytes::<&str>()
@@ -522,6 +601,40 @@ mod test {
 
         assert_eq!(root_writer.version, root_reader.version);
         assert_eq!(root_writer.id, root_reader.id);
+        assert_eq!(root_reader.compression, BlockCompression::None);
+
+        // Check that checksums are kept, and that blocks without one have none
+        for value in root_reader.sparse_index.data.forward.values() {
+            let expected = if value.id == block_ids[1] {
+                Some(42)
+            } else {
+                None
+            };
+            assert_eq!(value.checksum, expected);
+        }
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let block_id = Uuid::new_v4();
+        let sparse_index = SparseIndexWriter::new(block_id);
+        sparse_index
+            .set_count(block_id, 1)
+            .expect("Set count should succeed");
+        let bf_id = Uuid::new_v4();
+        let root_writer = RootWriter::new(CURRENT_VERSION, bf_id, sparse_index)
+            .with_compression(BlockCompression::Zstd);
+
+        let bytes = root_writer
+            .to_bytes::<&str>()
+            .expect("To be able to serialize");
+        let root_reader =
+            RootReader::from_bytes::<&str>(&bytes, bf_id).expect("To be able to deserialize");
+        assert_eq!(root_reader.compression, BlockCompression::Zstd);
+
+        // Forks keep writing blocks with the compression of the original blockfile
+        let forked = root_reader.fork(Uuid::new_v4());
+        assert_eq!(forked.compression, BlockCompression::Zstd);
     }
 
     #[test]
@@ -1,5 +1,5 @@
 use super::types::ArrowReadableKey;
-use crate::key::CompositeKey;
+use crate::key::{CompositeKey, PrefixKeyRange};
 use chroma_error::ChromaError;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -72,6 +72,22 @@ pub(super) struct SparseIndexWriterData {
     // This is not intended updated incrementally, and is only populated
     // at commit time of the blockfile.
     pub(super) counts: BTreeMap<SparseIndexDelimiter, u32>,
+    // The checksum of the stored bytes of each block, keyed by block id.
+    // Only populated when the block is flushed, blocks of blockfiles written
+    // before checksums were introduced have none.
+    pub(super) checksums: HashMap<Uuid, u32>,
+    stats: SparseIndexWriterStats,
+}
+
+/// How the layout of the blocks changed while writing a blockfile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SparseIndexWriterStats {
+    /// The number of blocks added by splitting oversized blocks
+    pub(crate) blocks_split: usize,
+    /// The number of blocks that were folded into a neighbor because they were underfilled
+    pub(crate) blocks_merged: usize,
+    /// The number of empty blocks removed
+    pub(crate) blocks_removed: usize,
 }
 
 impl SparseIndexWriterData {
@@ -113,6 +129,7 @@ impl SparseIndexWriter {
         let mut forward = BTreeMap::new();
         let mut reverse = HashMap::new();
         let counts = BTreeMap::new();
+        let checksums = HashMap::new();
 
         forward.insert(SparseIndexDelimiter::Start, initial_block_id);
         reverse.insert(initial_block_id, SparseIndexDelimiter::Start);
@@ -121,6 +138,8 @@ impl SparseIndexWriter {
             forward,
             reverse,
             counts,
+            checksums,
+            stats: SparseIndexWriterStats::default(),
         };
 
         Self {
@@ -143,16 +162,111 @@ impl SparseIndexWriter {
             .insert(SparseIndexDelimiter::Key(start_key.clone()), block_id);
         data.reverse
             .insert(block_id, SparseIndexDelimiter::Key(start_key));
+        data.stats.blocks_split += 1;
+
+        Ok(())
+    }
 
+    /// Replace a run of adjacent blocks with a single block covering their key ranges.
+    /// The new block takes the start key of the first block in the run.
+    /// # Arguments
+    /// * `block_ids` - The ids of the blocks to merge, in key order
+    /// * `new_block_id` - The id of the merged block
+    pub(super) fn merge_blocks(
+        &self,
+        block_ids: &[Uuid],
+        new_block_id: Uuid,
+    ) -> Result<(), SetCountError> {
+        let mut data = self.data.lock();
+        let Some((first, rest)) = block_ids.split_first() else {
+            return Ok(());
+        };
+        if block_ids.iter().any(|id| !data.reverse.contains_key(id)) {
+            return Err(SetCountError::BlockIdDoesNotExist);
+        }
+        let mut count = 0;
+        for block_id in rest {
+            if let Some(start_key) = data.reverse.remove(block_id) {
+                data.forward.remove(&start_key);
+                data.checksums.remove(block_id);
+                count += data.counts.remove(&start_key).unwrap_or_default();
+            }
+        }
+        if let Some(start_key) = data.reverse.remove(first) {
+            data.checksums.remove(first);
+            count += data.counts.remove(&start_key).unwrap_or_default();
+            data.forward.insert(start_key.clone(), new_block_id);
+            data.reverse.insert(new_block_id, start_key.clone());
+            data.counts.insert(start_key, count);
+        }
+        data.stats.blocks_merged += rest.len();
         Ok(())
     }
 
+    /// Find the blocks holding keys in the range.
+    /// # Returns
+    /// The ids of the blocks that only hold keys in the range, followed by the ids of the blocks
+    /// that hold keys both in and out of the range.
+    pub(super) fn get_block_ids_for_range(&self, range: &PrefixKeyRange) -> (Vec<Uuid>, Vec<Uuid>) {
+        let data = self.data.lock();
+        let end_keys = data
+            .forward
+            .keys()
+            .skip(1)
+            .map(|delimiter| match delimiter {
+                SparseIndexDelimiter::Start => {
+                    panic!("Invariant violation. Sparse index is not valid.")
+                }
+                SparseIndexDelimiter::Key(k) => Some(k),
+            })
+            .chain(std::iter::once(None));
+
+        let mut covered = Vec::new();
+        let mut overlapping = Vec::new();
+        for ((start_key, block_id), end_key) in data.forward.iter().zip(end_keys) {
+            if let Some(end_key) = end_key {
+                if range.starts_at_or_after(&end_key.prefix, &end_key.key) {
+                    continue;
+                }
+            }
+            let start_key = match start_key {
+                SparseIndexDelimiter::Start => None,
+                SparseIndexDelimiter::Key(start_key) => Some(start_key),
+            };
+            if let Some(start_key) = start_key {
+                if range.ends_before(&start_key.prefix, &start_key.key) {
+                    break;
+                }
+            }
+            match (start_key, end_key) {
+                (Some(start_key), Some(end_key))
+                    if range.contains(&start_key.prefix, &start_key.key)
+                        && range.covers_up_to(&end_key.prefix, &end_key.key) =>
+                {
+                    covered.push(*block_id)
+                }
+                _ => overlapping.push(*block_id),
+            }
+        }
+        (covered, overlapping)
+    }
+
+    /// The ids of all blocks in the sparse index, in key order.
+    pub(super) fn block_ids(&self) -> Vec<Uuid> {
+        self.data.lock().forward.values().copied().collect()
+    }
+
+    pub(crate) fn stats(&self) -> SparseIndexWriterStats {
+        self.data.lock().stats
+    }
+
     pub(super) fn replace_block(&self, old_block_id: Uuid, new_block_id: Uuid) {
         let mut data = self.data.lock();
         if let Some(old_start_key) = data.reverse.remove(&old_block_id) {
             data.forward.remove(&old_start_key);
             data.forward.insert(old_start_key.clone(), new_block_id);
             data.reverse.insert(new_block_id, old_start_key.clone());
+            data.checksums.remove(&old_block_id);
             let old_count = data
                 .counts
                 .remove(&old_start_key)
@@ -179,6 +293,20 @@ impl SparseIndexWriter {
         }
     }
 
+    /// Set the checksum of the stored bytes of a block in the sparse index.
+    /// This is populated when the block is flushed to storage.
+    /// # Arguments
+    /// * `block_id` - The block id to set the checksum for
+    /// * `checksum` - The checksum of the bytes of the block in storage
+    pub(super) fn set_checksum(&self, block_id: Uuid, checksum: u32) -> Result<(), SetCountError> {
+        let mut data = self.data.lock();
+        if !data.reverse.contains_key(&block_id) {
+            return Err(SetCountError::BlockIdDoesNotExist);
+        }
+        data.checksums.insert(block_id, checksum);
+        Ok(())
+    }
+
     pub(super) fn get_target_block_id(&self, search_key: &CompositeKey) -> Uuid {
         let data = self.data.lock();
         let forward = &data.forward;
@@ -202,9 +330,11 @@ impl SparseIndexWriter {
         if data.len() > 1 {
             if let Some(start_key) = data.reverse.remove(block_id) {
                 data.forward.remove(&start_key);
+                data.checksums.remove(block_id);
                 // data.counts is not guaranteed to be in sync with forward, so ignore the result if the key doesn't exist
                 let _ = data.counts.remove(&start_key);
             }
+            data.stats.blocks_removed += 1;
             removed = true;
         }
         // It can happen that the sparse index does not contain
@@ -252,7 +382,9 @@ impl SparseIndexWriter {
 
         let zipped = data.forward.iter().zip(data.counts.iter());
         let new_forward = zipped.map(|((key, block_id), (_, count))| {
-            (key.clone(), SparseIndexValue::new(*block_id, *count))
+            let mut value = SparseIndexValue::new(*block_id, *count);
+            value.checksum = data.checksums.get(block_id).copied();
+            (key.clone(), value)
         });
         let new_forward = BTreeMap::from_iter(new_forward);
         Ok(SparseIndexReader::new(new_forward))
@@ -291,15 +423,22 @@ pub(super) struct SparseIndexReaderData {
 /// # Fields
 /// * `id` - The block id that contains the keys in the range
 /// * `count` - The number of keys in the block
+/// * `checksum` - The checksum of the stored bytes of the block, if known
 #[derive(Serialize, Deserialize)]
 pub(super) struct SparseIndexValue {
     pub(super) id: Uuid,
     pub(super) count: u32,
+    #[serde(default)]
+    pub(super) checksum: Option<u32>,
 }
 
 impl SparseIndexValue {
     pub(super) fn new(id: Uuid, count: u32) -> Self {
-        Self { id, count }
+        Self {
+            id,
+            count,
+            checksum: None,
+        }
     }
 }
 
@@ -464,12 +603,16 @@ impl SparseIndexReader {
         let mut new_forward = BTreeMap::new();
         let mut new_reverse = HashMap::new();
         let mut new_counts = BTreeMap::new();
+        let mut new_checksums = HashMap::new();
         let old_data = &self.data;
         let old_forward = &old_data.forward;
         for (key, curr_block_value) in old_forward.iter() {
             new_forward.insert(key.clone(), curr_block_value.id);
             new_reverse.insert(curr_block_value.id, key.clone());
             new_counts.insert(key.clone(), curr_block_value.count);
+            if let Some(checksum) = curr_block_value.checksum {
+                new_checksums.insert(curr_block_value.id, checksum);
+            }
         }
 
         SparseIndexWriter {
@@ -477,6 +620,8 @@ impl SparseIndexReader {
                 forward: new_forward,
                 reverse: new_reverse,
                 counts: new_counts,
+                checksums: new_checksums,
+                stats: SparseIndexWriterStats::default(),
             })),
         }
     }
@@ -737,6 +882,61 @@ mod tests {
         assert!(blocks.contains(&block_id_3));
     }
 
+    #[test]
+    fn test_merge_blocks() {
+        let ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let sparse_index = SparseIndexWriter::new(ids[0]);
+        sparse_index
+            .add_block(CompositeKey::new("prefix".to_string(), "c"), ids[1])
+            .expect("No error");
+        sparse_index
+            .add_block(CompositeKey::new("prefix".to_string(), "f"), ids[2])
+            .expect("No error");
+        for (block_id, count) in ids.iter().zip([10, 20, 30]) {
+            sparse_index
+                .set_count(*block_id, count)
+                .expect("Set count should succeed");
+        }
+
+        let merged_id = Uuid::new_v4();
+        sparse_index
+            .merge_blocks(&ids[1..], merged_id)
+            .expect("Merge should succeed");
+        assert_eq!(sparse_index.block_ids(), vec![ids[0], merged_id]);
+        assert_eq!(
+            sparse_index.get_target_block_id(&CompositeKey::new("prefix".to_string(), "g")),
+            merged_id
+        );
+        assert_eq!(
+            sparse_index.stats(),
+            SparseIndexWriterStats {
+                blocks_split: 2,
+                blocks_merged: 1,
+                blocks_removed: 0,
+            }
+        );
+
+        let reader = sparse_index.to_reader().expect("Conversion should succeed");
+        assert_eq!(reader.data.forward.len(), 2);
+        assert_eq!(
+            reader
+                .data
+                .forward
+                .get(&SparseIndexDelimiter::Key(CompositeKey::new(
+                    "prefix".to_string(),
+                    "c"
+                )))
+                .unwrap()
+                .count,
+            50
+        );
+
+        assert!(matches!(
+            sparse_index.merge_blocks(&[ids[0], ids[1]], Uuid::new_v4()),
+            Err(SetCountError::BlockIdDoesNotExist)
+        ));
+    }
+
     #[test]
     fn test_serde() {
         let ids = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
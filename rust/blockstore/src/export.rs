@@ -0,0 +1,295 @@
+use crate::arrow::types::{ArrowReadableKey, ArrowReadableValue};
+use crate::key::KeyWrapper;
+use crate::{BlockfileReader, Key, Value};
+use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_storage::{PutError, Storage};
+use futures::{Stream, StreamExt};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ParquetExportError {
+    #[error("Error reading data to export: {0}")]
+    Source(Box<dyn ChromaError>),
+    #[error("Nothing to export and no schema given")]
+    NoSchema,
+    #[error("Exporting memory blockfiles is not supported")]
+    Unsupported,
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Storage error: {0}")]
+    Storage(#[from] PutError),
+}
+
+impl ChromaError for ParquetExportError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            ParquetExportError::Source(e) => e.code(),
+            ParquetExportError::NoSchema => ErrorCodes::InvalidArgument,
+            ParquetExportError::Unsupported => ErrorCodes::Unimplemented,
+            ParquetExportError::Arrow(_) => ErrorCodes::Internal,
+            ParquetExportError::Parquet(_) => ErrorCodes::Internal,
+            ParquetExportError::Io(_) => ErrorCodes::Internal,
+            ParquetExportError::Storage(e) => e.code(),
+        }
+    }
+}
+
+/// Where an export writes its Parquet file.
+#[derive(Clone)]
+pub enum ParquetSink {
+    /// A file on local disk.
+    Local(PathBuf),
+    /// An object in storage. The file is staged on local disk and uploaded once it is complete.
+    Storage { storage: Storage, key: String },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParquetExportSummary {
+    pub num_rows: u64,
+    pub num_bytes: u64,
+}
+
+/// Writes record batches to a single Parquet file, one batch at a time, so that data larger than
+/// memory can be exported.
+#[derive(Clone)]
+pub struct ParquetExporter {
+    sink: ParquetSink,
+    properties: WriterProperties,
+}
+
+impl ParquetExporter {
+    pub fn new(sink: ParquetSink) -> Self {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        Self { sink, properties }
+    }
+
+    pub fn with_writer_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Export a blockfile with one row per key and the columns of its blocks (prefix, key, value).
+    /// Blocks are read one at a time and are not kept in the reader.
+    pub async fn export_blockfile<'me, K, V>(
+        &self,
+        reader: &BlockfileReader<'me, K, V>,
+    ) -> Result<ParquetExportSummary, ParquetExportError>
+    where
+        K: Key + Into<KeyWrapper> + ArrowReadableKey<'me>,
+        V: Value + ArrowReadableValue<'me>,
+    {
+        match reader {
+            BlockfileReader::MemoryBlockfileReader(_) => Err(ParquetExportError::Unsupported),
+            BlockfileReader::ArrowBlockfileReader(reader) => {
+                let batches = reader.get_block_stream().map(|block| {
+                    block.and_then(|block| {
+                        // Blocks carry their own schema metadata, which would otherwise make
+                        // the schemas of consecutive blocks differ
+                        let schema = block
+                            .data
+                            .schema()
+                            .as_ref()
+                            .clone()
+                            .with_metadata(Default::default());
+                        block.data.0.with_schema(Arc::new(schema)).map_err(|e| {
+                            Box::new(ParquetExportError::Arrow(e)) as Box<dyn ChromaError>
+                        })
+                    })
+                });
+                self.export_batches(None, batches).await
+            }
+        }
+    }
+
+    /// Export a stream of record batches. The schema of the file is `schema` if given, and the
+    /// schema of the first batch otherwise.
+    pub async fn export_batches<S>(
+        &self,
+        schema: Option<SchemaRef>,
+        batches: S,
+    ) -> Result<ParquetExportSummary, ParquetExportError>
+    where
+        S: Stream<Item = Result<RecordBatch, Box<dyn ChromaError>>>,
+    {
+        match &self.sink {
+            ParquetSink::Local(path) => {
+                let num_rows = self
+                    .write_batches(File::create(path)?, schema, batches)
+                    .await?;
+                let num_bytes = std::fs::metadata(path)?.len();
+                Ok(ParquetExportSummary {
+                    num_rows,
+                    num_bytes,
+                })
+            }
+            ParquetSink::Storage { storage, key } => {
+                let staging_path = std::env::temp_dir().join(format!(
+                    "chroma-parquet-export-{}.parquet",
+                    uuid::Uuid::new_v4()
+                ));
+                let result = async {
+                    let num_rows = self
+                        .write_batches(File::create(&staging_path)?, schema, batches)
+                        .await?;
+                    let num_bytes = std::fs::metadata(&staging_path)?.len();
+                    storage
+                        .put_file(key, &staging_path.to_string_lossy())
+                        .await?;
+                    Ok(ParquetExportSummary {
+                        num_rows,
+                        num_bytes,
+                    })
+                }
+                .await;
+                let _ = std::fs::remove_file(&staging_path);
+                result
+            }
+        }
+    }
+
+    async fn write_batches<W, S>(
+        &self,
+        sink: W,
+        schema: Option<SchemaRef>,
+        batches: S,
+    ) -> Result<u64, ParquetExportError>
+    where
+        W: Write + Send,
+        S: Stream<Item = Result<RecordBatch, Box<dyn ChromaError>>>,
+    {
+        let mut batches = std::pin::pin!(batches);
+        let mut sink = Some(sink);
+        let mut writer = None;
+        if let Some(schema) = schema {
+            writer = Some(ArrowWriter::try_new(
+                sink.take().expect("sink is only taken once"),
+                schema,
+                Some(self.properties.clone()),
+            )?);
+        }
+        let mut num_rows = 0;
+        while let Some(batch) = batches.next().await {
+            let batch = batch.map_err(ParquetExportError::Source)?;
+            if writer.is_none() {
+                writer = Some(ArrowWriter::try_new(
+                    sink.take().expect("sink is only taken once"),
+                    batch.schema(),
+                    Some(self.properties.clone()),
+                )?);
+            }
+            if let Some(writer) = writer.as_mut() {
+                writer.write(&batch)?;
+            }
+            num_rows += batch.num_rows() as u64;
+        }
+        match writer {
+            Some(writer) => {
+                writer.close()?;
+                Ok(num_rows)
+            }
+            None => Err(ParquetExportError::NoSchema),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::config::TEST_MAX_BLOCK_SIZE_BYTES;
+    use crate::arrow::provider::ArrowBlockfileProvider;
+    use crate::BlockfileWriterOptions;
+    use arrow::array::{Array, StringArray, UInt32Array};
+    use chroma_cache::new_cache_for_test;
+    use chroma_storage::local::LocalStorage;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[tokio::test]
+    async fn test_export_blockfile() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::Local(LocalStorage::new(tmp_dir.path().to_str().unwrap()));
+        let blockfile_provider = ArrowBlockfileProvider::new(
+            storage.clone(),
+            TEST_MAX_BLOCK_SIZE_BYTES,
+            new_cache_for_test(),
+            new_cache_for_test(),
+        );
+        let writer = blockfile_provider
+            .write::<u32, String>(BlockfileWriterOptions::default())
+            .await
+            .unwrap();
+        let id = writer.id();
+        let n = 5000;
+        for i in 0..n {
+            writer
+                .set("prefix", i, format!("value{}", i))
+                .await
+                .unwrap();
+        }
+        let flusher = writer.commit::<u32, String>().await.unwrap();
+        flusher.flush::<u32, String>().await.unwrap();
+
+        let reader = blockfile_provider.read::<u32, &str>(&id).await.unwrap();
+        let path = tmp_dir.path().join("export.parquet");
+        let summary = ParquetExporter::new(ParquetSink::Local(path.clone()))
+            .export_blockfile(&reader)
+            .await
+            .unwrap();
+        assert_eq!(summary.num_rows, n as u64);
+        assert!(summary.num_bytes > 0);
+
+        let file = File::open(&path).unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut expected = 0;
+        for batch in batches {
+            let keys = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .unwrap();
+            let values = batch
+                .column(2)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            for row in 0..batch.num_rows() {
+                assert_eq!(keys.value(row), expected);
+                assert_eq!(values.value(row), format!("value{}", expected));
+                expected += 1;
+            }
+        }
+        assert_eq!(expected, n);
+
+        let summary = ParquetExporter::new(ParquetSink::Storage {
+            storage: storage.clone(),
+            key: "exports/blockfile.parquet".to_string(),
+        })
+        .export_blockfile(&reader)
+        .await
+        .unwrap();
+        assert_eq!(summary.num_rows, n as u64);
+        let bytes = storage.get("exports/blockfile.parquet").await.unwrap();
+        assert_eq!(bytes.len() as u64, summary.num_bytes);
+    }
+}
@@ -2,6 +2,7 @@ use super::Key;
 use chroma_error::{ChromaError, ErrorCodes};
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
+use std::ops::{Bound, RangeBounds};
 
 // TODO(rescrv):  This used to be a panic/unwrap, but could be a nicer type.
 #[derive(thiserror::Error, Debug)]
@@ -166,3 +167,62 @@ impl Ord for CompositeKey {
         }
     }
 }
+
+/// A range of keys within a single prefix, used to delete ranges of keys from a blockfile.
+#[derive(Clone, Debug)]
+pub struct PrefixKeyRange {
+    prefix: String,
+    start: Bound<KeyWrapper>,
+    end: Bound<KeyWrapper>,
+}
+
+impl PrefixKeyRange {
+    pub(crate) fn new<K: Key, KeyRange: RangeBounds<K>>(prefix: &str, key_range: KeyRange) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            start: key_range.start_bound().map(|k| k.clone().into()),
+            end: key_range.end_bound().map(|k| k.clone().into()),
+        }
+    }
+
+    pub(crate) fn contains(&self, prefix: &str, key: &KeyWrapper) -> bool {
+        prefix == self.prefix && (self.start.as_ref(), self.end.as_ref()).contains(key)
+    }
+
+    /// Whether no key in the range sorts before `(prefix, key)`.
+    pub(crate) fn starts_at_or_after(&self, prefix: &str, key: &KeyWrapper) -> bool {
+        match prefix.cmp(self.prefix.as_str()) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => match &self.start {
+                Bound::Included(start) | Bound::Excluded(start) => key <= start,
+                Bound::Unbounded => false,
+            },
+        }
+    }
+
+    /// Whether no key in the range sorts at or after `(prefix, key)`.
+    pub(crate) fn ends_before(&self, prefix: &str, key: &KeyWrapper) -> bool {
+        match prefix.cmp(self.prefix.as_str()) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => match &self.end {
+                Bound::Included(end) => key > end,
+                Bound::Excluded(end) => key >= end,
+                Bound::Unbounded => false,
+            },
+        }
+    }
+
+    /// Whether every key that sorts after the start of the range and before `(prefix, key)` is in the range.
+    pub(crate) fn covers_up_to(&self, prefix: &str, key: &KeyWrapper) -> bool {
+        match prefix.cmp(self.prefix.as_str()) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => match &self.end {
+                Bound::Included(end) | Bound::Excluded(end) => key <= end,
+                Bound::Unbounded => true,
+            },
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub mod types;
 
 pub mod arrow;
 pub mod config;
+pub mod export;
 pub mod key;
 pub mod memory;
 pub mod provider;
@@ -4,7 +4,7 @@ use super::{
     super::{BlockfileError, Key, Value},
     storage::{Readable, Storage, StorageBuilder, StorageManager, Writeable},
 };
-use crate::key::{InvalidKeyConversion, KeyWrapper};
+use crate::key::{InvalidKeyConversion, KeyWrapper, PrefixKeyRange};
 use chroma_error::ChromaError;
 
 #[derive(Clone)]
@@ -61,6 +61,18 @@ impl MemoryBlockfileWriter {
         Ok(())
     }
 
+    pub(crate) fn delete_range<K: Key + Into<KeyWrapper>, V: Value + Writeable, KeyRange>(
+        &self,
+        prefix: &str,
+        key_range: KeyRange,
+    ) -> Result<(), Box<dyn ChromaError>>
+    where
+        KeyRange: RangeBounds<K>,
+    {
+        V::remove_range_from_storage(&PrefixKeyRange::new(prefix, key_range), &self.builder);
+        Ok(())
+    }
+
     pub(crate) fn id(&self) -> uuid::Uuid {
         self.id
     }
@@ -160,6 +172,27 @@ mod tests {
         assert_eq!(value, "value1");
     }
 
+    #[test]
+    fn test_delete_range() {
+        let storage_manager = StorageManager::new();
+        let writer = MemoryBlockfileWriter::new(storage_manager.clone());
+        for prefix in ["a", "b"] {
+            for i in 0..10u32 {
+                let _ = writer.set(prefix, i, format!("{}{}", prefix, i));
+            }
+        }
+        writer.delete_range::<u32, String, _>("a", 3..7).unwrap();
+        writer.delete_range::<u32, String, _>("b", 8..).unwrap();
+        let _ = writer.commit();
+
+        let reader: MemoryBlockfileReader<u32, &str> =
+            MemoryBlockfileReader::open(writer.id, storage_manager);
+        for i in 0..10u32 {
+            assert_eq!(reader.get("a", i).unwrap().is_some(), !(3..7).contains(&i));
+            assert_eq!(reader.get("b", i).unwrap().is_some(), i < 8);
+        }
+    }
+
     #[test]
     fn test_string_key_rbm_value() {
         let storage_manager = StorageManager::new();
@@ -1,4 +1,4 @@
-use crate::key::{CompositeKey, KeyWrapper};
+use crate::key::{CompositeKey, KeyWrapper, PrefixKeyRange};
 use chroma_error::ChromaError;
 use chroma_types::{DataRecord, SpannPostingList};
 use parking_lot::RwLock;
@@ -11,6 +11,7 @@ use std::{
 pub trait Writeable {
     fn write_to_storage(prefix: &str, key: KeyWrapper, value: Self, storage: &StorageBuilder);
     fn remove_from_storage(prefix: &str, key: KeyWrapper, storage: &StorageBuilder);
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder);
 }
 
 pub trait Readable<'referred_data>: Sized {
@@ -63,6 +64,15 @@ impl Writeable for String {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .string_value_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for &'referred_data str {
@@ -154,6 +164,15 @@ impl Writeable for Vec<u32> {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .uint32_array_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for &'referred_data [u32] {
@@ -244,6 +263,15 @@ impl Writeable for RoaringBitmap {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .roaring_bitmap_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for RoaringBitmap {
@@ -325,6 +353,15 @@ impl Writeable for f32 {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .f32_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for f32 {
@@ -406,6 +443,15 @@ impl Writeable for u32 {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .u32_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for u32 {
@@ -487,6 +533,15 @@ impl Writeable for bool {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .bool_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for bool {
@@ -598,6 +653,21 @@ impl Writeable for &DataRecord<'_> {
                 key,
             });
     }
+
+    fn remove_range_from_storage(range: &PrefixKeyRange, storage: &StorageBuilder) {
+        storage
+            .data_record_id_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+        storage
+            .data_record_embedding_storage
+            .write()
+            .as_mut()
+            .unwrap()
+            .retain(|k, _| !range.contains(&k.prefix, &k.key));
+    }
 }
 
 impl Writeable for &SpannPostingList<'_> {
@@ -608,6 +678,10 @@ impl Writeable for &SpannPostingList<'_> {
     fn remove_from_storage(_: &str, _: KeyWrapper, _: &StorageBuilder) {
         todo!()
     }
+
+    fn remove_range_from_storage(_: &PrefixKeyRange, _: &StorageBuilder) {
+        todo!()
+    }
 }
 
 impl<'referred_data> Readable<'referred_data> for DataRecord<'referred_data> {
@@ -18,6 +18,7 @@ use chroma_config::registry::Registry;
 use chroma_config::Configurable;
 use chroma_error::{ChromaError, ErrorCodes};
 use chroma_storage::Storage;
+use chroma_types::{BlockfileStats, ScrubReport};
 use core::fmt::{self, Debug};
 use std::fmt::Formatter;
 use thiserror::Error;
@@ -61,6 +62,17 @@ impl BlockfileProvider {
         ))
     }
 
+    /// A provider for the blockfiles of `tenant`. It shares this provider's caches, but its
+    /// writes are encrypted under the tenant's key when the storage is encrypted.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        match self {
+            BlockfileProvider::HashMapBlockfileProvider(_) => self.clone(),
+            BlockfileProvider::ArrowBlockfileProvider(provider) => {
+                BlockfileProvider::ArrowBlockfileProvider(provider.for_tenant(tenant))
+            }
+        }
+    }
+
     pub async fn read<
         'new,
         K: Key
@@ -110,6 +122,24 @@ impl BlockfileProvider {
             }
         }
     }
+
+    pub async fn scrub(&self, id: &uuid::Uuid) -> ScrubReport {
+        match self {
+            // The memory blockfile is never persisted
+            BlockfileProvider::HashMapBlockfileProvider(_) => ScrubReport::default(),
+            BlockfileProvider::ArrowBlockfileProvider(provider) => provider.scrub(id).await,
+        }
+    }
+
+    pub async fn stats(&self, id: &uuid::Uuid) -> Result<BlockfileStats, Box<dyn ChromaError>> {
+        match self {
+            // The memory blockfile is not organized in blocks
+            BlockfileProvider::HashMapBlockfileProvider(_) => Ok(BlockfileStats::default()),
+            BlockfileProvider::ArrowBlockfileProvider(provider) => {
+                provider.stats(id).await.map_err(|e| Box::new(e) as _)
+            }
+        }
+    }
 }
 
 // =================== Configurable ===================
@@ -6,6 +6,7 @@ use crate::key::KeyWrapper;
 use crate::memory::reader_writer::MemoryBlockfileWriter;
 use crate::memory::storage::Writeable;
 use chroma_error::ChromaError;
+use std::ops::RangeBounds;
 
 #[derive(Clone)]
 pub enum BlockfileWriter {
@@ -80,6 +81,33 @@ impl BlockfileWriter {
         }
     }
 
+    /// Delete every key in `key_range` under `prefix`.
+    /// Arrow blockfiles drop the blocks that only hold keys in the range without loading them.
+    pub async fn delete_range<
+        K: Key + Into<KeyWrapper> + ArrowWriteableKey,
+        V: Value + Writeable + ArrowWriteableValue,
+        KeyRange,
+    >(
+        &self,
+        prefix: &str,
+        key_range: KeyRange,
+    ) -> Result<(), Box<dyn ChromaError>>
+    where
+        KeyRange: RangeBounds<K>,
+    {
+        match self {
+            BlockfileWriter::MemoryBlockfileWriter(writer) => {
+                writer.delete_range::<K, V, _>(prefix, key_range)
+            }
+            BlockfileWriter::ArrowUnorderedBlockfileWriter(writer) => {
+                writer.delete_range::<K, V, _>(prefix, key_range).await
+            }
+            BlockfileWriter::ArrowOrderedBlockfileWriter(writer) => {
+                writer.delete_range::<K, V, _>(prefix, key_range).await
+            }
+        }
+    }
+
     pub async fn get_owned<
         K: Key + Into<KeyWrapper> + ArrowWriteableKey,
         V: Value + Writeable + ArrowWriteableValue,
@@ -1,3 +1,4 @@
+use crate::arrow::compression::BlockCompression;
 use uuid::Uuid;
 
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -11,6 +12,7 @@ pub enum BlockfileWriterMutationOrdering {
 pub struct BlockfileWriterOptions {
     pub(crate) mutation_ordering: BlockfileWriterMutationOrdering,
     pub(crate) fork_from: Option<Uuid>,
+    pub(crate) compression: Option<BlockCompression>,
 }
 
 impl BlockfileWriterOptions {
@@ -42,4 +44,11 @@ impl BlockfileWriterOptions {
         self.fork_from = Some(fork);
         self
     }
+
+    /// Compress the blocks written by this blockfile. Defaults to the compression of the forked
+    /// blockfile, or to the compression configured on the provider. Ignored by non-arrow blockfiles.
+    pub fn compression(mut self, compression: BlockCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
 }
@@ -10,6 +10,7 @@ use chroma_types::GetUserIdentityResponse;
 #[derive(Clone, Copy, Debug)]
 pub enum AuthzAction {
     Reset,
+    GetLogRetention,
     CreateTenant,
     GetTenant,
     CreateDatabase,
@@ -22,6 +23,7 @@ pub enum AuthzAction {
     GetOrCreateCollection,
     GetCollection,
     DeleteCollection,
+    RestoreCollection,
     UpdateCollection,
     Add,
     Delete,
@@ -36,6 +38,7 @@ impl Display for AuthzAction {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
             AuthzAction::Reset => write!(f, "system:reset"),
+            AuthzAction::GetLogRetention => write!(f, "system:get_log_retention"),
             AuthzAction::CreateTenant => write!(f, "tenant:create_tenant"),
             AuthzAction::GetTenant => write!(f, "tenant:get_tenant"),
             AuthzAction::CreateDatabase => write!(f, "db:create_database"),
@@ -48,6 +51,7 @@ impl Display for AuthzAction {
             AuthzAction::GetOrCreateCollection => write!(f, "db:get_or_create_collection"),
             AuthzAction::GetCollection => write!(f, "collection:get_collection"),
             AuthzAction::DeleteCollection => write!(f, "collection:delete_collection"),
+            AuthzAction::RestoreCollection => write!(f, "collection:restore_collection"),
             AuthzAction::UpdateCollection => write!(f, "collection:update_collection"),
             AuthzAction::Add => write!(f, "collection:add"),
             AuthzAction::Delete => write!(f, "collection:delete"),
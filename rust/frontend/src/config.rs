@@ -2,7 +2,7 @@ use crate::{
     executor::config::{ExecutorConfig, LocalExecutorConfig},
     CollectionsWithSegmentsProviderConfig,
 };
-use chroma_log::config::LogConfig;
+use chroma_log::{config::LogConfig, LocalCompactionManagerConfig};
 use chroma_segment::local_segment_manager::LocalSegmentManagerConfig;
 use chroma_sqlite::config::SqliteDBConfig;
 use chroma_sysdb::SysDbConfig;
@@ -59,6 +59,9 @@ pub struct FrontendConfig {
     pub collections_with_segments_provider: CollectionsWithSegmentsProviderConfig,
     #[serde(default = "default_log_config")]
     pub log: LogConfig,
+    /// Only used with single-node logs.
+    #[serde(default)]
+    pub compaction_manager: LocalCompactionManagerConfig,
     #[serde(default = "default_executor_config")]
     pub executor: ExecutorConfig,
 }
@@ -81,6 +84,34 @@ fn default_max_payload_size_bytes() -> usize {
     40 * 1024 * 1024 // 40 MB
 }
 
+fn default_changes_batch_size() -> u32 {
+    1000
+}
+
+fn default_changes_poll_interval_ms() -> u64 {
+    500
+}
+
+/// Configuration of the change streams that tail collection logs.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ChangesConfig {
+    /// Maximum number of log records read at once.
+    #[serde(default = "default_changes_batch_size")]
+    pub batch_size: u32,
+    /// How long a stream waits before reading the log again once it has caught up.
+    #[serde(default = "default_changes_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+impl Default for ChangesConfig {
+    fn default() -> Self {
+        ChangesConfig {
+            batch_size: default_changes_batch_size(),
+            poll_interval_ms: default_changes_poll_interval_ms(),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct FrontendServerConfig {
     #[serde(flatten)]
@@ -100,6 +131,8 @@ pub struct FrontendServerConfig {
     pub open_telemetry: Option<OpenTelemetryConfig>,
     #[serde(default)]
     pub persist_path: Option<String>,
+    #[serde(default)]
+    pub changes: ChangesConfig,
 }
 
 const DEFAULT_CONFIG_PATH: &str = "./frontend_config.yaml";
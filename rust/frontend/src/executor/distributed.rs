@@ -13,8 +13,11 @@ use chroma_memberlist::{
 use chroma_system::System;
 use chroma_types::{
     chroma_proto::query_executor_client::QueryExecutorClient,
-    operator::{from_proto_knn_batch_result, CountResult, GetResult, KnnBatchResult},
-    plan::{Count, Get, Knn},
+    operator::{
+        from_proto_knn_batch_result, from_proto_stats_result, CountResult, GetResult,
+        KnnBatchResult, StatsResult,
+    },
+    plan::{Count, Get, Knn, Stats},
     CollectionUuid, ExecutorError,
 };
 use rand::seq::SliceRandom;
@@ -122,6 +125,19 @@ impl DistributedExecutor {
         Ok(from_proto_knn_batch_result(res.into_inner())?)
     }
 
+    pub async fn segment_stats(&mut self, plan: Stats) -> Result<StatsResult, ExecutorError> {
+        let clients = self.clients(plan.scan.collection_and_segments.collection.collection_id)?;
+        let res = (|| async {
+            choose_client(clients.as_slice())?
+                .segment_stats(Request::new(plan.clone().into()))
+                .await
+        })
+        .retry(self.backoff)
+        .when(is_retryable_error)
+        .await?;
+        Ok(from_proto_stats_result(res.into_inner()))
+    }
+
     pub async fn is_ready(&self) -> bool {
         !self.node_name_to_client.read().is_empty()
     }
@@ -12,7 +12,7 @@ use chroma_system::ComponentHandle;
 use chroma_types::{
     operator::{
         CountResult, Filter, GetResult, KnnBatchResult, KnnProjectionOutput, KnnProjectionRecord,
-        Projection, ProjectionRecord, RecordDistance,
+        Projection, ProjectionRecord, RecordDistance, Scan,
     },
     plan::{Count, Get, Knn},
     CollectionAndSegments, CollectionUuid, ExecutorError, HnswSpace, SingleNodeHnswParameters,
@@ -22,6 +22,17 @@ use std::{
     sync::Arc,
 };
 
+/// The log is applied to the local segments as it is written, so there is no earlier state to
+/// read at.
+fn check_as_of(scan: &Scan) -> Result<(), ExecutorError> {
+    match scan.as_of {
+        Some(_) => Err(ExecutorError::Unsupported(
+            "point-in-time reads are only available for distributed collections".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LocalExecutor {
     hnsw_manager: LocalSegmentManager,
@@ -47,6 +58,7 @@ impl LocalExecutor {
 
 impl LocalExecutor {
     pub async fn count(&mut self, plan: Count) -> Result<CountResult, ExecutorError> {
+        check_as_of(&plan.scan)?;
         self.try_backfill_collection(&plan.scan.collection_and_segments)
             .await?;
         self.metadata_reader
@@ -88,6 +100,7 @@ impl LocalExecutor {
     }
 
     pub async fn get(&mut self, plan: Get) -> Result<GetResult, ExecutorError> {
+        check_as_of(&plan.scan)?;
         let collection_and_segments = plan.scan.collection_and_segments.clone();
         self.try_backfill_collection(&collection_and_segments)
             .await?;
@@ -121,6 +134,12 @@ impl LocalExecutor {
     }
 
     pub async fn knn(&mut self, plan: Knn) -> Result<KnnBatchResult, ExecutorError> {
+        check_as_of(&plan.scan)?;
+        if plan.knn.brute_force {
+            return Err(ExecutorError::Unsupported(
+                "brute force queries are only available for distributed collections".to_string(),
+            ));
+        }
         let collection_and_segments = plan.scan.collection_and_segments.clone();
         self.try_backfill_collection(&collection_and_segments)
             .await?;
@@ -1,6 +1,6 @@
 use chroma_types::{
-    operator::{CountResult, GetResult, KnnBatchResult},
-    plan::{Count, Get, Knn},
+    operator::{CountResult, GetResult, KnnBatchResult, StatsResult},
+    plan::{Count, Get, Knn, Stats},
     ExecutorError,
 };
 use distributed::DistributedExecutor;
@@ -39,6 +39,17 @@ impl Executor {
             Executor::Local(local_executor) => local_executor.knn(plan).await,
         }
     }
+    pub async fn segment_stats(&mut self, plan: Stats) -> Result<StatsResult, ExecutorError> {
+        match self {
+            Executor::Distributed(distributed_executor) => {
+                distributed_executor.segment_stats(plan).await
+            }
+            // Local segments are not stored in blockfiles
+            Executor::Local(_) => Err(ExecutorError::Unsupported(
+                "segment stats are only available for distributed collections".to_string(),
+            )),
+        }
+    }
     pub async fn is_ready(&self) -> bool {
         match self {
             Executor::Distributed(distributed_executor) => distributed_executor.is_ready().await,
@@ -4,30 +4,35 @@ use crate::{
 };
 use backon::Retryable;
 use chroma_config::{registry, Configurable};
+use chroma_distance::{normalize, DistanceFunction};
 use chroma_error::{ChromaError, ErrorCodes};
-use chroma_log::{LocalCompactionManager, LocalCompactionManagerConfig, Log};
+use chroma_log::{LocalCompactionManager, Log, LogRetentionStatusMessage};
 use chroma_segment::local_segment_manager::LocalSegmentManager;
 use chroma_sqlite::db::SqliteDb;
 use chroma_sysdb::SysDb;
-use chroma_system::System;
+use chroma_system::{ComponentHandle, System};
 use chroma_tracing::meter_event::{IoKind, MeterEvent};
 use chroma_types::{
-    operator::{Filter, KnnBatch, KnnProjection, Limit, Projection, Scan},
-    plan::{Count, Get, Knn},
+    operator::{Filter, KnnBatch, KnnBatchResult, KnnProjection, Limit, Projection, Scan},
+    plan::{Count, Get, Knn, Stats},
     AddCollectionRecordsError, AddCollectionRecordsRequest, AddCollectionRecordsResponse,
-    CollectionUuid, CountCollectionsError, CountCollectionsRequest, CountCollectionsResponse,
-    CountRequest, CountResponse, CreateCollectionError, CreateCollectionRequest,
-    CreateCollectionResponse, CreateDatabaseError, CreateDatabaseRequest, CreateDatabaseResponse,
-    CreateTenantError, CreateTenantRequest, CreateTenantResponse, DeleteCollectionError,
-    DeleteCollectionRecordsError, DeleteCollectionRecordsRequest, DeleteCollectionRecordsResponse,
-    DeleteCollectionRequest, DeleteDatabaseError, DeleteDatabaseRequest, DeleteDatabaseResponse,
-    DistributedHnswParameters, GetCollectionError, GetCollectionRequest, GetCollectionResponse,
-    GetCollectionsError, GetDatabaseError, GetDatabaseRequest, GetDatabaseResponse, GetRequest,
-    GetResponse, GetTenantError, GetTenantRequest, GetTenantResponse, HealthCheckResponse,
-    HeartbeatError, HeartbeatResponse, Include, ListCollectionsRequest, ListCollectionsResponse,
+    CollectionChange, CollectionUuid, CountCollectionsError, CountCollectionsRequest,
+    CountCollectionsResponse, CountRequest, CountResponse, CreateCollectionError,
+    CreateCollectionRequest, CreateCollectionResponse, CreateDatabaseError, CreateDatabaseRequest,
+    CreateDatabaseResponse, CreateTenantError, CreateTenantRequest, CreateTenantResponse,
+    DedupMode, DedupOptions, DeleteCollectionError, DeleteCollectionRecordsError,
+    DeleteCollectionRecordsRequest, DeleteCollectionRecordsResponse, DeleteCollectionRequest,
+    DeleteDatabaseError, DeleteDatabaseRequest, DeleteDatabaseResponse, DistributedHnswParameters,
+    DuplicateRecord, GetCollectionError, GetCollectionRequest, GetCollectionResponse,
+    GetCollectionsError, GetDatabaseError, GetDatabaseRequest, GetDatabaseResponse,
+    GetLogRetentionError, GetLogRetentionRequest, GetLogRetentionResponse, GetRequest, GetResponse,
+    GetTenantError, GetTenantRequest, GetTenantResponse, HealthCheckResponse, HeartbeatError,
+    HeartbeatResponse, Include, ListCollectionsRequest, ListCollectionsResponse,
     ListDatabasesError, ListDatabasesRequest, ListDatabasesResponse, Metadata, Operation,
-    OperationRecord, QueryError, QueryRequest, QueryResponse, ResetError, ResetResponse,
-    ScalarEncoding, Segment, SegmentScope, SegmentType, SegmentUuid, SingleNodeHnswParameters,
+    OperationRecord, QueryError, QueryRequest, QueryResponse, ReadChangesError, ReadChangesRequest,
+    ReadChangesResponse, ResetError, ResetResponse, RestoreCollectionError,
+    RestoreCollectionRequest, RestoreCollectionResponse, ScalarEncoding, Segment, SegmentScope,
+    SegmentStatsRequest, SegmentStatsResponse, SegmentType, SegmentUuid, SingleNodeHnswParameters,
     UpdateCollectionError, UpdateCollectionRecordsError, UpdateCollectionRecordsRequest,
     UpdateCollectionRecordsResponse, UpdateCollectionRequest, UpdateCollectionResponse,
     UpdateMetadata, UpdateMetadataValue, UpsertCollectionRecordsError,
@@ -36,6 +41,7 @@ use chroma_types::{
 };
 use opentelemetry::global;
 use opentelemetry::metrics::Counter;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -52,6 +58,18 @@ impl ChromaError for ToRecordsError {
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+enum DedupError {
+    #[error("Failed to compare the records of the batch: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl ChromaError for DedupError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::Internal
+    }
+}
+
 fn to_records<
     MetadataValue: Into<UpdateMetadataValue>,
     M: IntoIterator<Item = (String, MetadataValue)>,
@@ -124,6 +142,214 @@ fn to_records<
     Ok((records, total_bytes))
 }
 
+/// Splits a column of an ingest request into the values of the records that are kept and the
+/// values of the near-duplicates. A column whose length does not match is kept as is, so that
+/// `to_records` reports the inconsistency.
+#[allow(clippy::type_complexity)]
+fn partition_duplicates<T>(
+    values: Option<Vec<T>>,
+    duplicates: &[Option<DuplicateRecord>],
+) -> (Option<Vec<T>>, Option<Vec<T>>) {
+    match values {
+        Some(values) if values.len() == duplicates.len() => {
+            let (duplicated, kept): (Vec<_>, Vec<_>) = values
+                .into_iter()
+                .zip(duplicates)
+                .partition(|(_, duplicate)| duplicate.is_some());
+            (
+                Some(kept.into_iter().map(|(value, _)| value).collect()),
+                Some(duplicated.into_iter().map(|(value, _)| value).collect()),
+            )
+        }
+        values => (values, None),
+    }
+}
+
+/// Picks, for each record of a batch, the nearest existing record within `threshold`. The record
+/// itself is skipped, so that an upserted record is not a duplicate of its own previous version.
+fn existing_duplicates(
+    ids: &[String],
+    nearest: KnnBatchResult,
+    threshold: f32,
+) -> Vec<Option<DuplicateRecord>> {
+    ids.iter()
+        .zip(
+            nearest
+                .into_iter()
+                .chain(std::iter::repeat_with(Default::default)),
+        )
+        .map(|(id, output)| {
+            output.records.into_iter().find_map(|knn_record| {
+                let distance = knn_record.distance?;
+                (knn_record.record.id != *id && distance <= threshold).then(|| DuplicateRecord {
+                    id: id.clone(),
+                    duplicate_of: knn_record.record.id,
+                    distance,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Compares each record of a batch with the earlier records that are kept, and marks it as a
+/// duplicate of the nearest one within `threshold` unless it already duplicates a closer record.
+/// This is quadratic in the size of the batch.
+fn find_batch_duplicates(
+    ids: &[String],
+    embeddings: &[Vec<f32>],
+    distance_function: &DistanceFunction,
+    threshold: f32,
+    duplicates: &mut [Option<DuplicateRecord>],
+) {
+    let normalized_embeddings;
+    let embeddings = if let DistanceFunction::Cosine = distance_function {
+        normalized_embeddings = embeddings
+            .iter()
+            .map(|embedding| normalize(embedding))
+            .collect::<Vec<_>>();
+        &normalized_embeddings
+    } else {
+        embeddings
+    };
+    for (index, embedding) in embeddings.iter().enumerate() {
+        for (earlier_index, earlier_embedding) in embeddings[..index].iter().enumerate() {
+            if duplicates[earlier_index].is_some() {
+                continue;
+            }
+            let distance = distance_function.distance(embedding, earlier_embedding);
+            if distance <= threshold
+                && duplicates[index]
+                    .as_ref()
+                    .is_none_or(|duplicate| distance < duplicate.distance)
+            {
+                duplicates[index] = Some(DuplicateRecord {
+                    id: ids[index].clone(),
+                    duplicate_of: ids[earlier_index].clone(),
+                    distance,
+                });
+            }
+        }
+    }
+}
+
+/// Collapses the documents, uris and metadatas of the near-duplicates into one update per record
+/// they duplicate. Later duplicates take precedence, and their metadatas are merged key by key.
+#[allow(clippy::type_complexity)]
+fn merge_duplicates<
+    MetadataValue: Into<UpdateMetadataValue>,
+    M: IntoIterator<Item = (String, MetadataValue)>,
+>(
+    duplicates: &[DuplicateRecord],
+    documents: Option<Vec<Option<String>>>,
+    uris: Option<Vec<Option<String>>>,
+    metadatas: Option<Vec<Option<M>>>,
+) -> (
+    Vec<String>,
+    Option<Vec<Option<String>>>,
+    Option<Vec<Option<String>>>,
+    Option<Vec<Option<UpdateMetadata>>>,
+) {
+    let mut targets = Vec::new();
+    let mut target_indices = HashMap::new();
+    let slots = duplicates
+        .iter()
+        .map(|duplicate| {
+            *target_indices
+                .entry(duplicate.duplicate_of.clone())
+                .or_insert_with(|| {
+                    targets.push(duplicate.duplicate_of.clone());
+                    targets.len() - 1
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let merge_last = |values: Vec<Option<String>>| {
+        let mut merged = vec![None; targets.len()];
+        for (slot, value) in slots.iter().zip(values) {
+            if value.is_some() {
+                merged[*slot] = value;
+            }
+        }
+        merged
+    };
+    let documents = documents.map(merge_last);
+    let uris = uris.map(merge_last);
+    let metadatas = metadatas.map(|metadatas| {
+        let mut merged = (0..targets.len()).map(|_| None).collect::<Vec<_>>();
+        for (slot, metadata) in slots.iter().zip(metadatas) {
+            if let Some(metadata) = metadata {
+                merged[*slot]
+                    .get_or_insert_with(UpdateMetadata::new)
+                    .extend(metadata.into_iter().map(|(key, value)| (key, value.into())));
+            }
+        }
+        merged
+    });
+
+    (targets, documents, uris, metadatas)
+}
+
+/// Converts a batch into log records according to the dedup mode. Rejected and merged
+/// near-duplicates are left out, and in merge mode every record they duplicate gets one update.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn dedup_records<
+    MetadataValue: Into<UpdateMetadataValue>,
+    M: IntoIterator<Item = (String, MetadataValue)>,
+>(
+    mode: DedupMode,
+    duplicates: Vec<Option<DuplicateRecord>>,
+    ids: Vec<String>,
+    embeddings: Option<Vec<Vec<f32>>>,
+    documents: Option<Vec<Option<String>>>,
+    uris: Option<Vec<Option<String>>>,
+    metadatas: Option<Vec<Option<M>>>,
+    operation: Operation,
+) -> Result<(Vec<OperationRecord>, u64, Vec<DuplicateRecord>), ToRecordsError> {
+    if mode == DedupMode::Report || duplicates.iter().all(Option::is_none) {
+        let embeddings = embeddings.map(|embeddings| embeddings.into_iter().map(Some).collect());
+        let (records, log_bytes) =
+            to_records(ids, embeddings, documents, uris, metadatas, operation)?;
+        return Ok((
+            records,
+            log_bytes,
+            duplicates.into_iter().flatten().collect(),
+        ));
+    }
+
+    let (ids, _) = partition_duplicates(Some(ids), &duplicates);
+    let (embeddings, _) = partition_duplicates(embeddings, &duplicates);
+    let (documents, duplicated_documents) = partition_duplicates(documents, &duplicates);
+    let (uris, duplicated_uris) = partition_duplicates(uris, &duplicates);
+    let (metadatas, duplicated_metadatas) = partition_duplicates(metadatas, &duplicates);
+    let duplicates = duplicates.into_iter().flatten().collect::<Vec<_>>();
+
+    let embeddings = embeddings.map(|embeddings| embeddings.into_iter().map(Some).collect());
+    let (mut records, mut log_bytes) = to_records(
+        ids.unwrap_or_default(),
+        embeddings,
+        documents,
+        uris,
+        metadatas,
+        operation,
+    )?;
+
+    if mode == DedupMode::Merge {
+        // The near-duplicates keep the embedding of the record they duplicate
+        let (targets, documents, uris, metadatas) = merge_duplicates(
+            &duplicates,
+            duplicated_documents,
+            duplicated_uris,
+            duplicated_metadatas,
+        );
+        let (merge_records, merge_log_bytes) =
+            to_records(targets, None, documents, uris, metadatas, Operation::Update)?;
+        records.extend(merge_records);
+        log_bytes += merge_log_bytes;
+    }
+
+    Ok((records, log_bytes, duplicates))
+}
+
 #[derive(Debug)]
 struct Metrics {
     delete_retries_counter: Counter<u64>,
@@ -140,6 +366,7 @@ pub struct Frontend {
     sysdb_client: SysDb,
     collections_with_segments_provider: CollectionsWithSegmentsProvider,
     max_batch_size: u32,
+    compaction_manager: Option<ComponentHandle<LocalCompactionManager>>,
     metrics: Arc<Metrics>,
 }
 
@@ -151,6 +378,7 @@ impl Frontend {
         log_client: Log,
         executor: Executor,
         max_batch_size: u32,
+        compaction_manager: Option<ComponentHandle<LocalCompactionManager>>,
     ) -> Self {
         let meter = global::meter("chroma");
         let delete_retries_counter = meter.u64_counter("delete_retries").build();
@@ -170,6 +398,7 @@ impl Frontend {
             sysdb_client,
             collections_with_segments_provider,
             max_batch_size,
+            compaction_manager,
             metrics,
         }
     }
@@ -273,6 +502,7 @@ impl Frontend {
             .await
             .map_err(|err| ResetError::Cache(Box::new(err)))?;
         self.executor.reset().await.map_err(|err| err.boxed())?;
+        self.log_client.reset().await?;
         self.sysdb_client.reset().await
     }
 
@@ -554,6 +784,12 @@ impl Frontend {
             )
             .await
             .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        // Soft-deleted collections keep their logs until they are purged, so they can be restored
+        if !self.sysdb_client.soft_deletes_collections() {
+            self.log_client
+                .delete_collection(collection.collection_id)
+                .await?;
+        }
         // Invalidate the cache.
         self.collections_with_segments_provider
             .collections_with_segments_cache
@@ -563,6 +799,136 @@ impl Frontend {
         Ok(DeleteCollectionRecordsResponse {})
     }
 
+    pub async fn restore_collection(
+        &mut self,
+        RestoreCollectionRequest {
+            tenant_id,
+            database_name,
+            collection_id,
+            ..
+        }: RestoreCollectionRequest,
+    ) -> Result<RestoreCollectionResponse, RestoreCollectionError> {
+        let collection = self
+            .sysdb_client
+            .restore_collection(tenant_id, database_name, collection_id)
+            .await?;
+        // Invalidate the cache.
+        self.collections_with_segments_provider
+            .collections_with_segments_cache
+            .remove(&collection_id)
+            .await;
+
+        Ok(collection)
+    }
+
+    /// Finds, for each incoming embedding, an existing record or an earlier record of the same
+    /// batch that is within `threshold` of it
+    async fn find_duplicates(
+        &mut self,
+        collection_id: CollectionUuid,
+        ids: &[String],
+        embeddings: &[Vec<f32>],
+        threshold: f32,
+    ) -> Result<Vec<Option<DuplicateRecord>>, Box<dyn ChromaError>> {
+        let collection_and_segments = self
+            .collections_with_segments_provider
+            .get_collection_with_segments(collection_id)
+            .await
+            .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        let space = match self.executor {
+            Executor::Distributed(_) => {
+                DistributedHnswParameters::try_from(&collection_and_segments.vector_segment)
+                    .map(|params| params.space)
+            }
+            Executor::Local(_) => {
+                SingleNodeHnswParameters::try_from(&collection_and_segments.vector_segment)
+                    .map(|params| params.space)
+            }
+        }
+        .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        let distance_function = DistanceFunction::from(space);
+
+        // The existing records are searched through the query path, which covers both the vector
+        // segment and the log. Two neighbours are fetched so that an upserted record is not
+        // reported as a duplicate of its own previous version.
+        let nearest = self
+            .executor
+            .knn(Knn {
+                scan: Scan {
+                    collection_and_segments,
+                    as_of: None,
+                },
+                filter: Filter {
+                    query_ids: None,
+                    where_clause: None,
+                },
+                knn: KnnBatch {
+                    embeddings: embeddings.to_vec(),
+                    fetch: 2,
+                    brute_force: false,
+                },
+                proj: KnnProjection {
+                    projection: Projection {
+                        document: false,
+                        embedding: false,
+                        metadata: false,
+                    },
+                    distance: true,
+                },
+            })
+            .await
+            .map_err(|err| err.boxed())?;
+        let mut duplicates = existing_duplicates(ids, nearest, threshold);
+
+        // The batch is compared with itself off the async runtime, since this is quadratic
+        let ids = ids.to_vec();
+        let embeddings = embeddings.to_vec();
+        tokio::task::spawn_blocking(move || {
+            find_batch_duplicates(
+                &ids,
+                &embeddings,
+                &distance_function,
+                threshold,
+                &mut duplicates,
+            );
+            duplicates
+        })
+        .await
+        .map_err(|err| DedupError::Join(err).boxed())
+    }
+
+    /// Converts an `add` or `upsert` batch into log records, handling the near-duplicates
+    /// according to the dedup mode
+    #[allow(clippy::too_many_arguments)]
+    async fn build_records_with_dedup<
+        MetadataValue: Into<UpdateMetadataValue>,
+        M: IntoIterator<Item = (String, MetadataValue)>,
+    >(
+        &mut self,
+        collection_id: CollectionUuid,
+        dedup: Option<DedupOptions>,
+        ids: Vec<String>,
+        embeddings: Option<Vec<Vec<f32>>>,
+        documents: Option<Vec<Option<String>>>,
+        uris: Option<Vec<Option<String>>>,
+        metadatas: Option<Vec<Option<M>>>,
+        operation: Operation,
+    ) -> Result<(Vec<OperationRecord>, u64, Vec<DuplicateRecord>), Box<dyn ChromaError>> {
+        let duplicates = match (dedup.as_ref(), embeddings.as_ref()) {
+            (Some(dedup), Some(embeddings)) if embeddings.len() == ids.len() => {
+                self.find_duplicates(collection_id, &ids, embeddings, dedup.threshold)
+                    .await?
+            }
+            _ => Vec::new(),
+        };
+
+        let mode = dedup.map(|dedup| dedup.mode).unwrap_or(DedupMode::Report);
+        dedup_records(
+            mode, duplicates, ids, embeddings, documents, uris, metadatas, operation,
+        )
+        .map_err(|err| Box::new(err) as Box<dyn ChromaError>)
+    }
+
     pub async fn add(
         &mut self,
         AddCollectionRecordsRequest {
@@ -574,6 +940,7 @@ impl Frontend {
             documents,
             uris,
             metadatas,
+            dedup,
             ..
         }: AddCollectionRecordsRequest,
     ) -> Result<AddCollectionRecordsResponse, AddCollectionRecordsError> {
@@ -583,11 +950,18 @@ impl Frontend {
         .await
         .map_err(|err| err.boxed())?;
 
-        let embeddings = embeddings.map(|embeddings| embeddings.into_iter().map(Some).collect());
-
-        let (records, log_bytes) =
-            to_records(ids, embeddings, documents, uris, metadatas, Operation::Add)
-                .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        let (records, log_bytes, duplicates) = self
+            .build_records_with_dedup(
+                collection_id,
+                dedup,
+                ids,
+                embeddings,
+                documents,
+                uris,
+                metadatas,
+                Operation::Add,
+            )
+            .await?;
 
         self.log_client
             .push_logs(collection_id, records)
@@ -602,7 +976,7 @@ impl Frontend {
         .submit()
         .await;
 
-        Ok(AddCollectionRecordsResponse {})
+        Ok(AddCollectionRecordsResponse { duplicates })
     }
 
     pub async fn update(
@@ -662,6 +1036,7 @@ impl Frontend {
             documents,
             uris,
             metadatas,
+            dedup,
             ..
         }: UpsertCollectionRecordsRequest,
     ) -> Result<UpsertCollectionRecordsResponse, UpsertCollectionRecordsError> {
@@ -671,17 +1046,18 @@ impl Frontend {
         .await
         .map_err(|err| err.boxed())?;
 
-        let embeddings = embeddings.map(|embeddings| embeddings.into_iter().map(Some).collect());
-
-        let (records, log_bytes) = to_records(
-            ids,
-            embeddings,
-            documents,
-            uris,
-            metadatas,
-            Operation::Upsert,
-        )
-        .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        let (records, log_bytes, duplicates) = self
+            .build_records_with_dedup(
+                collection_id,
+                dedup,
+                ids,
+                embeddings,
+                documents,
+                uris,
+                metadatas,
+                Operation::Upsert,
+            )
+            .await?;
 
         self.log_client
             .push_logs(collection_id, records)
@@ -696,7 +1072,7 @@ impl Frontend {
         .submit()
         .await;
 
-        Ok(UpsertCollectionRecordsResponse {})
+        Ok(UpsertCollectionRecordsResponse { duplicates })
     }
 
     pub async fn retryable_delete(
@@ -729,6 +1105,7 @@ impl Frontend {
                 .get(Get {
                     scan: Scan {
                         collection_and_segments,
+                        as_of: None,
                     },
                     filter,
                     limit: Limit {
@@ -835,6 +1212,7 @@ impl Frontend {
             tenant_id,
             database_name,
             collection_id,
+            as_of,
             ..
         }: CountRequest,
     ) -> Result<CountResponse, QueryError> {
@@ -866,6 +1244,7 @@ impl Frontend {
             .count(Count {
                 scan: Scan {
                     collection_and_segments,
+                    as_of,
                 },
             })
             .await?;
@@ -912,6 +1291,94 @@ impl Frontend {
         res
     }
 
+    pub async fn segment_stats(
+        &mut self,
+        SegmentStatsRequest { collection_id, .. }: SegmentStatsRequest,
+    ) -> Result<SegmentStatsResponse, QueryError> {
+        let collection_and_segments = self
+            .collections_with_segments_provider
+            .get_collection_with_segments(collection_id)
+            .await
+            .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        let segments = self
+            .executor
+            .segment_stats(Stats {
+                scan: Scan {
+                    collection_and_segments,
+                    as_of: None,
+                },
+            })
+            .await?;
+        Ok(SegmentStatsResponse {
+            collection_id,
+            segments,
+        })
+    }
+
+    pub async fn read_changes(
+        &mut self,
+        ReadChangesRequest {
+            collection_id,
+            after_offset,
+            batch_size,
+            ..
+        }: ReadChangesRequest,
+    ) -> Result<ReadChangesResponse, ReadChangesError> {
+        // Fail early on a collection that does not exist instead of streaming nothing
+        let collection_and_segments = self
+            .collections_with_segments_provider
+            .get_collection_with_segments(collection_id)
+            .await
+            .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        // Fail instead of resuming past changes that were purged in the meantime
+        if let Some(after_offset) = after_offset {
+            if let Some(first_available_offset) = self
+                .log_client
+                .purged_after(
+                    collection_id,
+                    after_offset,
+                    collection_and_segments.collection.log_position,
+                )
+                .await
+                .map_err(ReadChangesError::Log)?
+            {
+                return Err(ReadChangesError::Purged {
+                    after_offset,
+                    first_available_offset,
+                });
+            }
+        }
+        let records = self
+            .log_client
+            .read_after(
+                collection_id,
+                after_offset.unwrap_or(-1),
+                batch_size.min(i32::MAX as u32) as i32,
+            )
+            .await
+            .map_err(ReadChangesError::Log)?;
+        Ok(records.into_iter().map(CollectionChange::from).collect())
+    }
+
+    pub async fn get_log_retention(
+        &mut self,
+        GetLogRetentionRequest { collection_id, .. }: GetLogRetentionRequest,
+    ) -> Result<GetLogRetentionResponse, GetLogRetentionError> {
+        let compaction_manager = self
+            .compaction_manager
+            .clone()
+            .ok_or(GetLogRetentionError::Unsupported)?;
+        self.collections_with_segments_provider
+            .get_collection_with_segments(collection_id)
+            .await
+            .map_err(|err| Box::new(err) as Box<dyn ChromaError>)?;
+        compaction_manager
+            .request(LogRetentionStatusMessage { collection_id }, None)
+            .await
+            .map_err(|err| err.boxed())?
+            .map_err(|err| err.boxed().into())
+    }
+
     async fn retryable_get(
         &mut self,
         GetRequest {
@@ -923,6 +1390,7 @@ impl Frontend {
             limit,
             offset,
             include,
+            as_of,
             ..
         }: GetRequest,
     ) -> Result<GetResponse, QueryError> {
@@ -954,6 +1422,7 @@ impl Frontend {
             .get(Get {
                 scan: Scan {
                     collection_and_segments,
+                    as_of,
                 },
                 filter: Filter {
                     query_ids: ids,
@@ -1026,6 +1495,8 @@ impl Frontend {
             embeddings,
             n_results,
             include,
+            brute_force,
+            as_of,
             ..
         }: QueryRequest,
     ) -> Result<QueryResponse, QueryError> {
@@ -1057,6 +1528,7 @@ impl Frontend {
             .knn(Knn {
                 scan: Scan {
                     collection_and_segments,
+                    as_of,
                 },
                 filter: Filter {
                     query_ids: ids,
@@ -1065,6 +1537,7 @@ impl Frontend {
                 knn: KnnBatch {
                     embeddings,
                     fetch: n_results,
+                    brute_force,
                 },
                 proj: KnnProjection {
                     projection: Projection {
@@ -1160,16 +1633,24 @@ impl Configurable<(FrontendConfig, System)> for Frontend {
         let max_batch_size = log.get_max_batch_size().await?;
 
         // Create compation manager and pass handle to log service if configured
-        if let Log::Sqlite(sqlite_log) = &log {
+        let mut compaction_manager_handle = None;
+        if let Log::Sqlite(_) | Log::File(_) = &log {
             let compaction_manager =
-                LocalCompactionManager::try_from_config(&LocalCompactionManagerConfig {}, registry)
+                LocalCompactionManager::try_from_config(&config.compaction_manager, registry)
                     .await?;
             // TODO: Move this inside LocalCompactionManager::try_from_config, when system is stored in registry
             let handle = system.start_component(compaction_manager);
-            sqlite_log
-                .init_compactor_handle(handle.clone())
-                .map_err(|e| e.boxed())?;
-            registry.register(handle);
+            match &log {
+                Log::Sqlite(sqlite_log) => sqlite_log
+                    .init_compactor_handle(handle.clone())
+                    .map_err(|e| e.boxed())?,
+                Log::File(file_log) => file_log
+                    .init_compactor_handle(handle.clone())
+                    .map_err(|e| e.boxed())?,
+                Log::Grpc(_) | Log::InMemory(_) => {}
+            }
+            registry.register(handle.clone());
+            compaction_manager_handle = Some(handle);
         }
 
         let collections_with_segments_provider = CollectionsWithSegmentsProvider::try_from_config(
@@ -1188,6 +1669,235 @@ impl Configurable<(FrontendConfig, System)> for Frontend {
             log,
             executor,
             max_batch_size,
+            compaction_manager_handle,
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chroma_types::operator::{KnnProjectionOutput, KnnProjectionRecord, ProjectionRecord};
+
+    fn knn_output(neighbours: &[(&str, f32)]) -> KnnProjectionOutput {
+        KnnProjectionOutput {
+            records: neighbours
+                .iter()
+                .map(|(id, distance)| KnnProjectionRecord {
+                    record: ProjectionRecord {
+                        id: id.to_string(),
+                        document: None,
+                        embedding: None,
+                        metadata: None,
+                    },
+                    distance: Some(*distance),
+                })
+                .collect(),
+        }
+    }
+
+    fn duplicate(id: &str, duplicate_of: &str, distance: f32) -> Option<DuplicateRecord> {
+        Some(DuplicateRecord {
+            id: id.to_string(),
+            duplicate_of: duplicate_of.to_string(),
+            distance,
+        })
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn documents(documents: &[Option<&str>]) -> Option<Vec<Option<String>>> {
+        Some(
+            documents
+                .iter()
+                .map(|document| document.map(str::to_string))
+                .collect(),
+        )
+    }
+
+    fn metadata(entries: &[(&str, i64)]) -> Option<UpdateMetadata> {
+        Some(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), UpdateMetadataValue::Int(*value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_existing_duplicates() {
+        let duplicates = existing_duplicates(
+            &ids(&["a", "b", "c"]),
+            vec![
+                knn_output(&[("x", 0.05), ("y", 0.07)]),
+                knn_output(&[("y", 0.1)]),
+                knn_output(&[("z", 0.1001)]),
+            ],
+            0.1,
+        );
+        assert_eq!(
+            duplicates,
+            vec![duplicate("a", "x", 0.05), duplicate("b", "y", 0.1), None]
+        );
+    }
+
+    #[test]
+    fn test_existing_duplicates_skip_previous_version() {
+        // An upserted record is its own nearest neighbour, but is not its own duplicate
+        let duplicates = existing_duplicates(
+            &ids(&["a", "b"]),
+            vec![
+                knn_output(&[("a", 0.0), ("x", 0.05)]),
+                knn_output(&[("b", 0.0), ("y", 0.5)]),
+            ],
+            0.1,
+        );
+        assert_eq!(duplicates, vec![duplicate("a", "x", 0.05), None]);
+    }
+
+    #[test]
+    fn test_find_batch_duplicates() {
+        let ids = ids(&["a", "b", "c", "d"]);
+        let embeddings = vec![
+            vec![0.0, 0.0],
+            vec![0.25, 0.0],
+            vec![0.5, 0.0],
+            vec![0.0, 0.2578125],
+        ];
+        let mut duplicates = vec![None; ids.len()];
+        find_batch_duplicates(
+            &ids,
+            &embeddings,
+            &DistanceFunction::Euclidean,
+            0.0625,
+            &mut duplicates,
+        );
+        // `c` is near `b`, but `b` is not kept, and `d` is just outside the threshold of `a`
+        assert_eq!(
+            duplicates,
+            vec![None, duplicate("b", "a", 0.0625), None, None]
+        );
+    }
+
+    #[test]
+    fn test_find_batch_duplicates_prefers_existing_duplicates() {
+        let ids = ids(&["a", "b"]);
+        let embeddings = vec![vec![0.0, 0.0], vec![0.1, 0.0]];
+        let mut duplicates = vec![None, duplicate("b", "x", 0.001)];
+        find_batch_duplicates(
+            &ids,
+            &embeddings,
+            &DistanceFunction::Euclidean,
+            0.1,
+            &mut duplicates,
+        );
+        assert_eq!(duplicates, vec![None, duplicate("b", "x", 0.001)]);
+    }
+
+    #[test]
+    fn test_dedup_records_report() {
+        let (records, _, duplicates) = dedup_records(
+            DedupMode::Report,
+            vec![None, duplicate("b", "a", 0.0)],
+            ids(&["a", "b"]),
+            Some(vec![vec![1.0], vec![1.0]]),
+            documents(&[Some("doc a"), Some("doc b")]),
+            None,
+            None::<Vec<Option<UpdateMetadata>>>,
+            Operation::Add,
+        )
+        .expect("Conversion should succeed");
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.id.as_str(), record.operation))
+                .collect::<Vec<_>>(),
+            vec![("a", Operation::Add), ("b", Operation::Add)]
+        );
+        assert_eq!(duplicates, vec![duplicate("b", "a", 0.0).unwrap()]);
+    }
+
+    #[test]
+    fn test_dedup_records_reject() {
+        let (records, _, duplicates) = dedup_records(
+            DedupMode::Reject,
+            vec![None, duplicate("b", "x", 0.0), None],
+            ids(&["a", "b", "c"]),
+            Some(vec![vec![1.0], vec![2.0], vec![3.0]]),
+            documents(&[Some("doc a"), Some("doc b"), Some("doc c")]),
+            None,
+            None::<Vec<Option<UpdateMetadata>>>,
+            Operation::Upsert,
+        )
+        .expect("Conversion should succeed");
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (
+                    record.id.as_str(),
+                    record.operation,
+                    record.document.as_deref(),
+                    record.embedding.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", Operation::Upsert, Some("doc a"), Some(vec![1.0])),
+                ("c", Operation::Upsert, Some("doc c"), Some(vec![3.0])),
+            ]
+        );
+        assert_eq!(duplicates, vec![duplicate("b", "x", 0.0).unwrap()]);
+    }
+
+    #[test]
+    fn test_dedup_records_merge() {
+        // `b` and `d` both duplicate `a` from the same batch, and `c` duplicates an existing record
+        let (records, _, duplicates) = dedup_records(
+            DedupMode::Merge,
+            vec![
+                None,
+                duplicate("b", "a", 0.01),
+                duplicate("c", "x", 0.02),
+                duplicate("d", "a", 0.03),
+            ],
+            ids(&["a", "b", "c", "d"]),
+            Some(vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0]]),
+            documents(&[Some("doc a"), Some("doc b"), Some("doc c"), None]),
+            None,
+            Some(vec![
+                metadata(&[("k", 0)]),
+                metadata(&[("k", 1), ("l", 1)]),
+                None,
+                metadata(&[("l", 3), ("m", 3)]),
+            ]),
+            Operation::Add,
+        )
+        .expect("Conversion should succeed");
+        assert_eq!(duplicates.len(), 3);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (
+                    record.id.as_str(),
+                    record.operation,
+                    record.document.as_deref(),
+                    record.embedding.is_some()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", Operation::Add, Some("doc a"), true),
+                ("a", Operation::Update, Some("doc b"), false),
+                ("x", Operation::Update, Some("doc c"), false),
+            ]
+        );
+        let merged_metadata = records[1].metadata.clone().expect("Metadata should be set");
+        assert_eq!(merged_metadata.get("k"), Some(&UpdateMetadataValue::Int(1)));
+        assert_eq!(merged_metadata.get("l"), Some(&UpdateMetadataValue::Int(3)));
+        assert_eq!(merged_metadata.get("m"), Some(&UpdateMetadataValue::Int(3)));
+        assert_eq!(
+            merged_metadata.get(CHROMA_DOCUMENT_KEY),
+            Some(&UpdateMetadataValue::Str("doc b".to_string()))
+        );
+    }
+}
@@ -1,24 +1,31 @@
 use axum::{
+    body::{Body, Bytes},
     extract::{DefaultBodyLimit, Path, Query, State},
-    http::header::HeaderMap,
+    http::header::{HeaderMap, HeaderValue, CONTENT_TYPE},
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router, ServiceExt,
 };
-use chroma_types::RawWhereFields;
+use chroma_types::{operator::AsOf, RawWhereFields};
 use chroma_types::{
-    AddCollectionRecordsResponse, ChecklistResponse, Collection, CollectionMetadataUpdate,
-    CollectionUuid, CountCollectionsRequest, CountCollectionsResponse, CountRequest, CountResponse,
-    CreateCollectionRequest, CreateDatabaseRequest, CreateDatabaseResponse, CreateTenantRequest,
-    CreateTenantResponse, DeleteCollectionRecordsResponse, DeleteDatabaseRequest,
-    DeleteDatabaseResponse, GetCollectionRequest, GetDatabaseRequest, GetDatabaseResponse,
-    GetRequest, GetResponse, GetTenantRequest, GetTenantResponse, GetUserIdentityResponse,
-    HeartbeatResponse, IncludeList, ListCollectionsRequest, ListCollectionsResponse,
-    ListDatabasesRequest, ListDatabasesResponse, Metadata, QueryRequest, QueryResponse,
+    AddCollectionRecordsResponse, ChecklistResponse, Collection, CollectionChange,
+    CollectionMetadataUpdate, CollectionUuid, CountCollectionsRequest, CountCollectionsResponse,
+    CountRequest, CountResponse, CreateCollectionRequest, CreateDatabaseRequest,
+    CreateDatabaseResponse, CreateTenantRequest, CreateTenantResponse, DedupOptions,
+    DeleteCollectionRecordsResponse, DeleteDatabaseRequest, DeleteDatabaseResponse,
+    GetCollectionRequest, GetDatabaseRequest, GetDatabaseResponse, GetLogRetentionRequest,
+    GetLogRetentionResponse, GetRequest, GetResponse, GetTenantRequest, GetTenantResponse,
+    GetUserIdentityResponse, HeartbeatResponse, IncludeList, ListCollectionsRequest,
+    ListCollectionsResponse, ListDatabasesRequest, ListDatabasesResponse, Metadata, QueryRequest,
+    QueryResponse, ReadChangesError, ReadChangesRequest, SegmentStatsRequest, SegmentStatsResponse,
     UpdateCollectionRecordsResponse, UpdateCollectionResponse, UpdateMetadata,
     UpsertCollectionRecordsResponse,
 };
+use futures::{stream, Stream, StreamExt};
 use mdac::{Rule, Scorecard, ScorecardTicket};
 use opentelemetry::global;
 use opentelemetry::metrics::{Counter, Meter};
@@ -29,6 +36,7 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::Duration;
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
 use utoipa::ToSchema;
 use utoipa::{Modify, OpenApi};
@@ -77,11 +85,15 @@ pub struct Metrics {
     get_collection: Counter<u64>,
     update_collection: Counter<u64>,
     delete_collection: Counter<u64>,
+    restore_collection: Counter<u64>,
     collection_add: Counter<u64>,
     collection_update: Counter<u64>,
     collection_upsert: Counter<u64>,
     collection_delete: Counter<u64>,
     collection_count: Counter<u64>,
+    collection_stats: Counter<u64>,
+    collection_changes: Counter<u64>,
+    log_retention: Counter<u64>,
     collection_get: Counter<u64>,
     collection_query: Counter<u64>,
 }
@@ -106,11 +118,15 @@ impl Metrics {
             get_collection: meter.u64_counter("get_collection").build(),
             update_collection: meter.u64_counter("update_collection").build(),
             delete_collection: meter.u64_counter("delete_collection").build(),
+            restore_collection: meter.u64_counter("restore_collection").build(),
             collection_add: meter.u64_counter("collection_add").build(),
             collection_update: meter.u64_counter("collection_update").build(),
             collection_upsert: meter.u64_counter("collection_upsert").build(),
             collection_delete: meter.u64_counter("collection_delete").build(),
             collection_count: meter.u64_counter("collection_count").build(),
+            collection_stats: meter.u64_counter("collection_stats").build(),
+            collection_changes: meter.u64_counter("collection_changes").build(),
+            log_retention: meter.u64_counter("log_retention").build(),
             collection_get: meter.u64_counter("collection_get").build(),
             collection_query: meter.u64_counter("collection_query").build(),
         }
@@ -208,6 +224,10 @@ impl FrontendServer {
                     .put(update_collection)
                     .delete(delete_collection),
             )
+            .route(
+                "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/restore",
+                post(restore_collection),
+            )
             .route(
                 "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/add",
                 post(collection_add),
@@ -228,6 +248,18 @@ impl FrontendServer {
                 "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/count",
                 get(collection_count),
             )
+            .route(
+                "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/stats",
+                get(collection_stats),
+            )
+            .route(
+                "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/changes",
+                get(collection_changes),
+            )
+            .route(
+                "/api/v2/admin/tenants/{tenant}/databases/{database}/collections/{collection_id}/log-retention",
+                get(log_retention),
+            )
             .route(
                 "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/get",
                 post(collection_get),
@@ -999,6 +1031,55 @@ async fn delete_collection(
     Ok(Json(UpdateCollectionResponse {}))
 }
 
+/// Restores a deleted collection that has not been purged yet.
+#[utoipa::path(
+    post,
+    path = "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/restore",
+    responses(
+        (status = 200, description = "Collection restored successfully", body = Collection),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Deleted collection not found", body = ErrorResponse),
+        (status = 409, description = "A collection with the same name already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    params(
+        ("tenant" = String, Path, description = "Tenant ID"),
+        ("database" = String, Path, description = "Database name"),
+        ("collection_id" = String, Path, description = "UUID of the deleted collection")
+    )
+)]
+async fn restore_collection(
+    headers: HeaderMap,
+    Path((tenant, database, collection_id)): Path<(String, String, String)>,
+    State(mut server): State<FrontendServer>,
+) -> Result<Json<Collection>, ServerError> {
+    server.metrics.restore_collection.add(1, &[]);
+    tracing::info!(
+        "Restoring collection [{collection_id}] in database [{database}] for tenant [{tenant}]"
+    );
+    server
+        .authenticate_and_authorize(
+            &headers,
+            AuthzAction::RestoreCollection,
+            AuthzResource {
+                tenant: Some(tenant.clone()),
+                database: Some(database.clone()),
+                collection: Some(collection_id.clone()),
+            },
+        )
+        .await?;
+    let _guard = server.scorecard_request(&[
+        "op:restore_collection",
+        format!("tenant:{}", tenant).as_str(),
+    ]);
+    let collection_id =
+        CollectionUuid::from_str(&collection_id).map_err(|_| ValidationError::CollectionId)?;
+    let request = chroma_types::RestoreCollectionRequest::try_new(tenant, database, collection_id)?;
+    let collection = server.frontend.restore_collection(request).await?;
+
+    Ok(Json(collection))
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct AddCollectionRecordsPayload {
     ids: Vec<String>,
@@ -1006,6 +1087,7 @@ pub struct AddCollectionRecordsPayload {
     documents: Option<Vec<Option<String>>>,
     uris: Option<Vec<Option<String>>>,
     metadatas: Option<Vec<Option<Metadata>>>,
+    dedup: Option<DedupOptions>,
 }
 
 /// Adds records to a collection.
@@ -1073,6 +1155,7 @@ async fn collection_add(
         payload.documents,
         payload.uris,
         payload.metadatas,
+        payload.dedup,
     )?;
 
     let res = server.frontend.add(request).await?;
@@ -1165,6 +1248,7 @@ pub struct UpsertCollectionRecordsPayload {
     documents: Option<Vec<Option<String>>>,
     uris: Option<Vec<Option<String>>>,
     metadatas: Option<Vec<Option<UpdateMetadata>>>,
+    dedup: Option<DedupOptions>,
 }
 
 /// Upserts records in a collection (create if not exists, otherwise update).
@@ -1239,6 +1323,7 @@ async fn collection_upsert(
         payload.documents,
         payload.uris,
         payload.metadatas,
+        payload.dedup,
     )?;
 
     Ok(Json(server.frontend.upsert(request).await?))
@@ -1320,6 +1405,23 @@ async fn collection_delete(
     Ok(Json(DeleteCollectionRecordsResponse {}))
 }
 
+#[derive(Deserialize, ToSchema, Debug)]
+struct CountParams {
+    as_of_log_offset: Option<i64>,
+    as_of_timestamp: Option<i64>,
+}
+
+impl CountParams {
+    fn as_of(&self) -> Result<Option<AsOf>, ValidationError> {
+        match (self.as_of_log_offset, self.as_of_timestamp) {
+            (Some(_), Some(_)) => Err(ValidationError::AsOf),
+            (Some(offset), None) => Ok(Some(AsOf::LogOffset(offset))),
+            (None, Some(timestamp)) => Ok(Some(AsOf::Timestamp(timestamp))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
 /// Retrieves the number of records in a collection.
 #[utoipa::path(
     get,
@@ -1333,12 +1435,15 @@ async fn collection_delete(
     params(
         ("tenant" = String, Path, description = "Tenant ID for the collection"),
         ("database" = String, Path, description = "Database containing this collection"),
-        ("collection_id" = String, Path, description = "Collection ID whose records are counted")
+        ("collection_id" = String, Path, description = "Collection ID whose records are counted"),
+        ("as_of_log_offset" = Option<i64>, Query, description = "Count the records as of this log offset"),
+        ("as_of_timestamp" = Option<i64>, Query, description = "Count the records as of this time, in nanoseconds since the epoch")
     )
 )]
 async fn collection_count(
     headers: HeaderMap,
     Path((tenant, database, collection_id)): Path<(String, String, String)>,
+    Query(params): Query<CountParams>,
     State(mut server): State<FrontendServer>,
 ) -> Result<Json<CountResponse>, ServerError> {
     server.metrics.collection_count.add(
@@ -1373,11 +1478,289 @@ async fn collection_count(
         tenant,
         database,
         CollectionUuid::from_str(&collection_id).map_err(|_| ValidationError::CollectionId)?,
+        params.as_of()?,
     )?;
 
     Ok(Json(server.frontend.count(request).await?))
 }
 
+/// Retrieves the physical layout and index statistics of the segments of a collection.
+#[utoipa::path(
+    get,
+    path = "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/stats",
+    responses(
+        (status = 200, description = "Statistics of the collection segments", body = SegmentStatsResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+        (status = 501, description = "Not supported by this deployment", body = ErrorResponse)
+    ),
+    params(
+        ("tenant" = String, Path, description = "Tenant ID for the collection"),
+        ("database" = String, Path, description = "Database containing this collection"),
+        ("collection_id" = String, Path, description = "Collection ID whose segments are inspected")
+    )
+)]
+async fn collection_stats(
+    headers: HeaderMap,
+    Path((tenant, database, collection_id)): Path<(String, String, String)>,
+    State(mut server): State<FrontendServer>,
+) -> Result<Json<SegmentStatsResponse>, ServerError> {
+    server.metrics.collection_stats.add(
+        1,
+        &[
+            KeyValue::new("tenant", tenant.clone()),
+            KeyValue::new("database", database.clone()),
+            KeyValue::new("collection_id", collection_id.clone()),
+        ],
+    );
+    tracing::info!(
+        "Inspecting segments of collection [{collection_id}] in database [{database}] for tenant [{tenant}]",
+    );
+    server
+        .authenticate_and_authorize(
+            &headers,
+            AuthzAction::GetCollection,
+            AuthzResource {
+                tenant: Some(tenant.clone()),
+                database: Some(database.clone()),
+                collection: Some(collection_id.clone()),
+            },
+        )
+        .await?;
+    let _guard = server.scorecard_request(&[
+        "op:read",
+        format!("tenant:{}", tenant).as_str(),
+        format!("collection:{}", collection_id).as_str(),
+    ]);
+
+    let request = SegmentStatsRequest::try_new(
+        tenant,
+        database,
+        CollectionUuid::from_str(&collection_id).map_err(|_| ValidationError::CollectionId)?,
+    )?;
+
+    Ok(Json(server.frontend.segment_stats(request).await?))
+}
+
+/// Reports how much of the log of a collection is retained and which records the configured
+/// retention policy allows to purge. Only available in single-node deployments.
+#[utoipa::path(
+    get,
+    path = "/api/v2/admin/tenants/{tenant}/databases/{database}/collections/{collection_id}/log-retention",
+    responses(
+        (status = 200, description = "Log retention status of the collection", body = GetLogRetentionResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+        (status = 501, description = "Not supported by this deployment", body = ErrorResponse)
+    ),
+    params(
+        ("tenant" = String, Path, description = "Tenant ID for the collection"),
+        ("database" = String, Path, description = "Database containing this collection"),
+        ("collection_id" = String, Path, description = "Collection ID whose log is inspected")
+    )
+)]
+async fn log_retention(
+    headers: HeaderMap,
+    Path((tenant, database, collection_id)): Path<(String, String, String)>,
+    State(mut server): State<FrontendServer>,
+) -> Result<Json<GetLogRetentionResponse>, ServerError> {
+    server.metrics.log_retention.add(
+        1,
+        &[
+            KeyValue::new("tenant", tenant.clone()),
+            KeyValue::new("database", database.clone()),
+            KeyValue::new("collection_id", collection_id.clone()),
+        ],
+    );
+    tracing::info!(
+        "Inspecting log retention of collection [{collection_id}] in database [{database}] for tenant [{tenant}]",
+    );
+    server
+        .authenticate_and_authorize(
+            &headers,
+            AuthzAction::GetLogRetention,
+            AuthzResource {
+                tenant: Some(tenant.clone()),
+                database: Some(database.clone()),
+                collection: Some(collection_id.clone()),
+            },
+        )
+        .await?;
+    let _guard = server.scorecard_request(&[
+        "op:read",
+        format!("tenant:{}", tenant).as_str(),
+        format!("collection:{}", collection_id).as_str(),
+    ]);
+
+    let request = GetLogRetentionRequest::try_new(
+        tenant,
+        database,
+        CollectionUuid::from_str(&collection_id).map_err(|_| ValidationError::CollectionId)?,
+    )?;
+
+    Ok(Json(server.frontend.get_log_retention(request).await?))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum ChangesFormat {
+    #[default]
+    Sse,
+    Ndjson,
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+struct CollectionChangesParams {
+    offset: Option<i64>,
+    #[serde(default)]
+    format: ChangesFormat,
+}
+
+/// Tails the log of a collection from the changes after `offset`, waiting for new changes
+/// once it has caught up. A failed read ends the stream with the error, including when the
+/// changes after the last streamed one are purged before they could be read.
+fn change_stream(
+    frontend: Frontend,
+    request: ReadChangesRequest,
+    poll_interval: Duration,
+) -> impl Stream<Item = Result<CollectionChange, ReadChangesError>> {
+    stream::unfold(Some((frontend, request)), move |state| async move {
+        let (mut frontend, mut request) = state?;
+        loop {
+            match frontend.read_changes(request.clone()).await {
+                Ok(changes) if changes.is_empty() => tokio::time::sleep(poll_interval).await,
+                Ok(changes) => {
+                    request.after_offset = changes.last().map(|change| change.offset);
+                    let changes = changes.into_iter().map(Ok).collect::<Vec<_>>();
+                    return Some((changes, Some((frontend, request))));
+                }
+                Err(err) => return Some((vec![Err(err)], None)),
+            }
+        }
+    })
+    .flat_map(stream::iter)
+}
+
+/// Streams the records pushed to a collection (add, update, upsert and delete) in log order,
+/// as server-sent events or newline-delimited JSON. Each change carries its log offset, which
+/// clients pass as `offset` (or, for server-sent events, which is sent back as `Last-Event-ID`)
+/// to resume after it. Resuming after changes that have since been purged from the log fails
+/// with the first offset that is still available.
+#[utoipa::path(
+    get,
+    path = "/api/v2/tenants/{tenant}/databases/{database}/collections/{collection_id}/changes",
+    responses(
+        (status = 200, description = "Stream of collection changes", body = CollectionChange),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 412, description = "Changes after the offset have been purged from the log", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    params(
+        ("tenant" = String, Path, description = "Tenant ID for the collection"),
+        ("database" = String, Path, description = "Database containing this collection"),
+        ("collection_id" = String, Path, description = "Collection ID whose changes are streamed"),
+        ("offset" = Option<i64>, Query, description = "Only stream changes after this log offset"),
+        ("format" = Option<String>, Query, description = "Either `sse` (default) or `ndjson`")
+    )
+)]
+async fn collection_changes(
+    headers: HeaderMap,
+    Path((tenant, database, collection_id)): Path<(String, String, String)>,
+    Query(CollectionChangesParams { offset, format }): Query<CollectionChangesParams>,
+    State(mut server): State<FrontendServer>,
+) -> Result<Response, ServerError> {
+    server.metrics.collection_changes.add(
+        1,
+        &[
+            KeyValue::new("tenant", tenant.clone()),
+            KeyValue::new("database", database.clone()),
+            KeyValue::new("collection_id", collection_id.clone()),
+        ],
+    );
+    tracing::info!(
+        "Streaming changes of collection [{collection_id}] in database [{database}] for tenant [{tenant}]",
+    );
+    server
+        .authenticate_and_authorize(
+            &headers,
+            AuthzAction::Get,
+            AuthzResource {
+                tenant: Some(tenant.clone()),
+                database: Some(database.clone()),
+                collection: Some(collection_id.clone()),
+            },
+        )
+        .await?;
+
+    // An event source that reconnects resumes after the last event it received
+    let after_offset = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .or(offset);
+    let mut request = ReadChangesRequest::try_new(
+        tenant.clone(),
+        database,
+        CollectionUuid::from_str(&collection_id).map_err(|_| ValidationError::CollectionId)?,
+        after_offset,
+        server.config.changes.batch_size,
+    )?;
+
+    // Read the first batch before responding so that a missing collection or an unreachable
+    // log fail the request instead of the stream
+    let first_batch = {
+        let _guard = server.scorecard_request(&[
+            "op:read",
+            format!("tenant:{}", tenant).as_str(),
+            format!("collection:{}", collection_id).as_str(),
+        ]);
+        server.frontend.read_changes(request.clone()).await?
+    };
+    if let Some(change) = first_batch.last() {
+        request.after_offset = Some(change.offset);
+    }
+    let changes = stream::iter(first_batch.into_iter().map(Ok)).chain(change_stream(
+        server.frontend.clone(),
+        request,
+        Duration::from_millis(server.config.changes.poll_interval_ms),
+    ));
+
+    match format {
+        ChangesFormat::Sse => {
+            let events = changes.map(|change| match change {
+                Ok(change) => Event::default()
+                    .id(change.offset.to_string())
+                    .json_data(&change),
+                Err(err) => Event::default()
+                    .event("error")
+                    .json_data(ServerError::from(err).error_response()),
+            });
+            Ok(Sse::new(events)
+                .keep_alive(KeepAlive::default())
+                .into_response())
+        }
+        ChangesFormat::Ndjson => {
+            let lines = changes.map(|change| -> Result<Bytes, serde_json::Error> {
+                let mut line = match change {
+                    Ok(change) => serde_json::to_vec(&change)?,
+                    Err(err) => serde_json::to_vec(&ServerError::from(err).error_response())?,
+                };
+                line.push(b'\n');
+                Ok(Bytes::from(line))
+            });
+            let mut response = Body::from_stream(lines).into_response();
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-ndjson"),
+            );
+            Ok(response)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct GetRequestPayload {
     ids: Option<Vec<String>>,
@@ -1387,6 +1770,8 @@ pub struct GetRequestPayload {
     offset: Option<u32>,
     #[serde(default = "IncludeList::default_get")]
     include: IncludeList,
+    /// Reads the collection as of a log offset or timestamp instead of its latest state
+    as_of: Option<AsOf>,
 }
 
 /// Retrieves records from a collection by ID or metadata filter.
@@ -1465,6 +1850,7 @@ async fn collection_get(
         payload.limit,
         payload.offset.unwrap_or(0),
         payload.include,
+        payload.as_of,
     )?;
     let res = server.frontend.get(request).await?;
     Ok(Json(res))
@@ -1479,6 +1865,11 @@ pub struct QueryRequestPayload {
     n_results: Option<u32>,
     #[serde(default = "IncludeList::default_query")]
     include: IncludeList,
+    /// Skips the vector index and computes the exact nearest neighbours
+    #[serde(default)]
+    brute_force: bool,
+    /// Reads the collection as of a log offset or timestamp instead of its latest state
+    as_of: Option<AsOf>,
 }
 
 /// Query a collection in a variety of ways, including vector search, metadata filtering, and full-text search
@@ -1562,6 +1953,8 @@ async fn collection_query(
         payload.query_embeddings,
         payload.n_results.unwrap_or(10),
         payload.include,
+        payload.brute_force,
+        payload.as_of,
     )?;
 
     let res = server.frontend.query(request).await?;
@@ -1616,11 +2009,15 @@ impl Modify for ChromaTokenSecurityAddon {
         get_collection,
         update_collection,
         delete_collection,
+        restore_collection,
         collection_add,
         collection_update,
         collection_upsert,
         collection_delete,
         collection_count,
+        collection_stats,
+        collection_changes,
+        log_retention,
         collection_get,
         collection_query
     ),
@@ -22,6 +22,8 @@ pub enum ValidationError {
     GetCollection(#[from] GetCollectionError),
     #[error("Error updating collection: {0}")]
     UpdateCollection(#[from] UpdateCollectionError),
+    #[error("Only one of as_of_log_offset and as_of_timestamp can be given")]
+    AsOf,
 }
 
 impl ChromaError for ValidationError {
@@ -32,6 +34,7 @@ impl ChromaError for ValidationError {
             ValidationError::DimensionMismatch(_, _) => ErrorCodes::InvalidArgument,
             ValidationError::GetCollection(err) => err.code(),
             ValidationError::UpdateCollection(err) => err.code(),
+            ValidationError::AsOf => ErrorCodes::InvalidArgument,
         }
     }
 }
@@ -87,6 +90,14 @@ impl IntoResponse for ServerError {
             ErrorCodes::VersionMismatch => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
+        (status_code, Json(self.error_response())).into_response()
+    }
+}
+
+impl ServerError {
+    /// The body sent to clients for this error. Streaming responses that fail after their status
+    /// was sent embed it in the stream instead.
+    pub fn error_response(&self) -> ErrorResponse {
         let error = match self.0.code() {
             ErrorCodes::InvalidArgument => "InvalidArgumentError",
             ErrorCodes::NotFound => "NotFoundError",
@@ -96,11 +107,9 @@ impl IntoResponse for ServerError {
         }
         .to_string();
 
-        let error = ErrorResponse {
+        ErrorResponse {
             error,
             message: self.0.to_string(),
-        };
-
-        (status_code, Json(error)).into_response()
+        }
     }
 }
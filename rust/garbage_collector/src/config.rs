@@ -17,9 +17,18 @@ pub(super) struct GarbageCollectorConfig {
     pub(super) sysdb_config: chroma_sysdb::GrpcSysDbConfig,
     pub(super) dispatcher_config: DispatcherConfig,
     pub(super) storage_config: StorageConfig,
+    /// Only report what would be deleted, without mutating storage or sysdb
+    #[serde(default)]
+    pub(super) dry_run: bool,
+    #[serde(default = "GarbageCollectorConfig::default_dry_run_report_dir")]
+    pub(super) dry_run_report_dir: String,
 }
 
 impl GarbageCollectorConfig {
+    fn default_dry_run_report_dir() -> String {
+        "./gc_reports".to_string()
+    }
+
     pub(super) fn load() -> Self {
         Self::load_from_path(DEFAULT_CONFIG_PATH)
     }
@@ -78,5 +87,7 @@ mod tests {
             }
             _ => panic!("Expected S3 storage config"),
         }
+        assert!(!config.dry_run);
+        assert_eq!(config.dry_run_report_dir, "./gc_reports");
     }
 }
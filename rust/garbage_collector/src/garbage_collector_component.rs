@@ -25,6 +25,7 @@ pub(crate) struct GarbageCollector {
     disabled_collections: HashSet<CollectionUuid>,
     sysdb_client: SysDb,
     storage: Storage,
+    dry_run_report_dir: Option<String>,
     dispatcher: Option<ComponentHandle<Dispatcher>>,
     system: Option<chroma_system::System>,
 }
@@ -43,6 +44,7 @@ impl GarbageCollector {
         disabled_collections: HashSet<CollectionUuid>,
         sysdb_client: SysDb,
         storage: Storage,
+        dry_run_report_dir: Option<String>,
     ) -> Self {
         Self {
             gc_interval_mins,
@@ -51,6 +53,7 @@ impl GarbageCollector {
             disabled_collections,
             sysdb_client,
             storage,
+            dry_run_report_dir,
             dispatcher: None,
             system: None,
         }
@@ -124,7 +127,7 @@ impl Handler<GarbageCollectMessage> for GarbageCollector {
             instrumented_span.follows_from(Span::current());
             match self.system {
                 Some(ref system) => {
-                    let orchestrator = GarbageCollectorOrchestrator::new(
+                    let mut orchestrator = GarbageCollectorOrchestrator::new(
                         collection.id,
                         collection.version_file_path,
                         self.cutoff_time_hours,
@@ -132,6 +135,9 @@ impl Handler<GarbageCollectMessage> for GarbageCollector {
                         dispatcher,
                         self.storage.clone(),
                     );
+                    if let Some(report_dir) = &self.dry_run_report_dir {
+                        orchestrator = orchestrator.with_dry_run(report_dir.clone());
+                    }
 
                     jobs.push(
                         orchestrator
@@ -197,6 +203,7 @@ impl Configurable<GarbageCollectorConfig> for GarbageCollector {
             disabled_collections,
             sysdb_client,
             storage,
+            config.dry_run.then(|| config.dry_run_report_dir.clone()),
         ))
     }
 }
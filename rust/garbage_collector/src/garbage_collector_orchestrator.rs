@@ -36,6 +36,14 @@
 //!    - Permanently deletes marked versions from the system database
 //!    - Input: Version file, versions to delete, unused S3 files
 //!    - Output: Deletion confirmation
+//!
+//! In dry-run mode, stage 3 is skipped so that sysdb is not touched, and stages 6 and 7 are
+//! replaced by a single stage:
+//!
+//! 6. Write Dry-Run Report (WriteDryRunReportOperator)
+//!    - Writes the versions and files that would be deleted, with the file sizes, to a report
+//!    - Input: Versions to delete, unused S3 files, HNSW prefixes
+//!    - Output: Report and its path
 
 use std::fmt::{Debug, Formatter};
 
@@ -49,7 +57,7 @@ use chroma_system::{
 };
 use chroma_types::chroma_proto::CollectionVersionFile;
 use chroma_types::CollectionUuid;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use thiserror::Error;
 use tokio::sync::oneshot::{error::RecvError, Sender};
 
@@ -80,6 +88,10 @@ use crate::operators::mark_versions_at_sysdb::{
     MarkVersionsAtSysDbError, MarkVersionsAtSysDbInput, MarkVersionsAtSysDbOperator,
     MarkVersionsAtSysDbOutput,
 };
+use crate::operators::write_dry_run_report::{
+    WriteDryRunReportError, WriteDryRunReportInput, WriteDryRunReportOperator,
+    WriteDryRunReportOutput,
+};
 
 use prost::Message;
 
@@ -96,6 +108,9 @@ pub struct GarbageCollectorOrchestrator {
     pending_epoch_id: Option<i64>,
     hnsw_prefixes_for_deletion: Vec<String>,
     num_versions_deleted: u32,
+    cutoff_time: Option<DateTime<Utc>>,
+    // Set in dry-run mode: the directory the report is written to instead of deleting anything
+    dry_run_report_dir: Option<String>,
 }
 
 impl Debug for GarbageCollectorOrchestrator {
@@ -110,6 +125,7 @@ pub struct GarbageCollectorResponse {
     collection_id: CollectionUuid,
     version_file_path: String,
     num_versions_deleted: u32,
+    dry_run_report_path: Option<String>,
 }
 
 impl GarbageCollectorOrchestrator {
@@ -134,8 +150,17 @@ impl GarbageCollectorOrchestrator {
             pending_epoch_id: None,
             hnsw_prefixes_for_deletion: Vec::new(),
             num_versions_deleted: 0,
+            cutoff_time: None,
+            dry_run_report_dir: None,
         }
     }
+
+    /// Computes what would be deleted and writes it to a report in `report_dir`, without
+    /// mutating storage or sysdb.
+    pub fn with_dry_run(mut self, report_dir: String) -> Self {
+        self.dry_run_report_dir = Some(report_dir);
+        self
+    }
 }
 
 #[derive(Error, Debug)]
@@ -164,6 +189,8 @@ pub enum GarbageCollectorError {
     Aborted,
     #[error("DeleteUnusedFiles error: {0}")]
     DeleteUnusedFiles(#[from] DeleteUnusedFilesError),
+    #[error("WriteDryRunReport error: {0}")]
+    WriteDryRunReport(#[from] WriteDryRunReportError),
 }
 
 impl ChromaError for GarbageCollectorError {
@@ -259,6 +286,7 @@ impl Handler<TaskResult<FetchVersionFileOutput, FetchVersionFileError>>
             cutoff_time = ?cutoff_time,
             "Computed cutoff time for version deletion"
         );
+        self.cutoff_time = Some(cutoff_time);
 
         let version_file = match CollectionVersionFile::decode(output.version_file_content()) {
             Ok(file) => {
@@ -321,6 +349,7 @@ impl Handler<TaskResult<ComputeVersionsToDeleteOutput, ComputeVersionsToDeleteEr
                 collection_id: self.collection_id,
                 version_file_path: self.version_file_path.clone(),
                 num_versions_deleted: 0,
+                dry_run_report_path: None,
             };
             tracing::info!(?response, "Garbage collection completed early");
             self.terminate_with_result(Ok(response), ctx);
@@ -328,6 +357,31 @@ impl Handler<TaskResult<ComputeVersionsToDeleteOutput, ComputeVersionsToDeleteEr
             return;
         }
 
+        if self.dry_run_report_dir.is_some() {
+            // Skip marking the versions, go straight to fetching their files
+            tracing::info!(
+                versions = ?output.versions_to_delete.versions,
+                "Dry run: not marking versions for deletion"
+            );
+            let fetch_task = wrap(
+                Box::new(FetchSparseIndexFilesOperator {
+                    storage: self.storage.clone(),
+                }),
+                FetchSparseIndexFilesInput {
+                    version_file: output.version_file,
+                    epoch_id: 0,
+                    sysdb_client: self.sysdb_client.clone(),
+                    versions_to_delete: output.versions_to_delete,
+                    oldest_version_to_keep: output.oldest_version_to_keep,
+                },
+                ctx.receiver(),
+            );
+            if let Err(e) = self.dispatcher().send(fetch_task, None).await {
+                self.terminate_with_result(Err(GarbageCollectorError::Channel(e)), ctx);
+            }
+            return;
+        }
+
         self.num_versions_deleted = output.versions_to_delete.versions.len() as u32;
         let mark_task = wrap(
             Box::new(MarkVersionsAtSysDbOperator {}),
@@ -447,6 +501,29 @@ impl Handler<TaskResult<ComputeUnusedBetweenVersionsOutput, ComputeUnusedBetween
             None => return,
         };
 
+        if let Some(report_dir) = &self.dry_run_report_dir {
+            let report_task = wrap(
+                Box::new(WriteDryRunReportOperator::new(
+                    self.storage.clone(),
+                    report_dir.clone(),
+                )),
+                WriteDryRunReportInput {
+                    collection_id: self.collection_id,
+                    version_file_path: self.version_file_path.clone(),
+                    cutoff_time: self.cutoff_time.unwrap_or_else(Utc::now),
+                    versions_to_delete: output.versions_to_delete.versions,
+                    oldest_version_to_keep: output.oldest_version_to_keep,
+                    unused_s3_files: output.unused_s3_files,
+                    hnsw_prefixes_for_deletion: self.hnsw_prefixes_for_deletion.clone(),
+                },
+                ctx.receiver(),
+            );
+            if let Err(e) = self.dispatcher().send(report_task, None).await {
+                self.terminate_with_result(Err(GarbageCollectorError::Channel(e)), ctx);
+            }
+            return;
+        }
+
         let delete_task = wrap(
             Box::new(DeleteUnusedFilesOperator::new(self.storage.clone(), true)), // Using soft delete mode
             DeleteUnusedFilesInput {
@@ -540,6 +617,35 @@ impl Handler<TaskResult<DeleteVersionsAtSysDbOutput, DeleteVersionsAtSysDbError>
             collection_id: self.collection_id,
             version_file_path: self.version_file_path.clone(),
             num_versions_deleted: self.num_versions_deleted,
+            dry_run_report_path: None,
+        };
+
+        self.terminate_with_result(Ok(response), ctx);
+    }
+}
+
+#[async_trait]
+impl Handler<TaskResult<WriteDryRunReportOutput, WriteDryRunReportError>>
+    for GarbageCollectorOrchestrator
+{
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        message: TaskResult<WriteDryRunReportOutput, WriteDryRunReportError>,
+        ctx: &ComponentContext<GarbageCollectorOrchestrator>,
+    ) {
+        // Dry run: the report is written, nothing was deleted
+        let output = match self.ok_or_terminate(message.into_inner(), ctx) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let response = GarbageCollectorResponse {
+            collection_id: self.collection_id,
+            version_file_path: self.version_file_path.clone(),
+            num_versions_deleted: 0,
+            dry_run_report_path: Some(output.report_path),
         };
 
         self.terminate_with_result(Ok(response), ctx);
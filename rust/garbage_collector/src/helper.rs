@@ -130,6 +130,7 @@ impl ChromaGrpcClients {
                     }),
                     operation: 0,
                     metadata: None,
+                    compressed_payload: None,
                 }
             })
             .collect();
@@ -178,6 +179,7 @@ impl ChromaGrpcClients {
                 knn: None,
                 metadata: None,
                 record: None,
+                as_of: None,
             }),
             filter: None,
             knn: Some(KnnOperator {
@@ -187,6 +189,7 @@ impl ChromaGrpcClients {
                     encoding: 0,
                 }],
                 fetch: 2,
+                brute_force: false,
             }),
             projection: Some(KnnProjectionOperator {
                 projection: Some(ProjectionOperator {
@@ -241,6 +244,7 @@ impl ChromaGrpcClients {
             knn: scope_to_segment.remove(&(SegmentScope::Vector as i32)),
             metadata: scope_to_segment.remove(&(SegmentScope::Metadata as i32)),
             record: scope_to_segment.remove(&(SegmentScope::Record as i32)),
+            as_of: None,
         };
 
         // Create the get plan
@@ -5,6 +5,21 @@ use chroma_system::{Operator, OperatorType};
 use std::collections::HashSet;
 use thiserror::Error;
 
+const HNSW_INDEX_FILES: [&str; 4] = [
+    "header.bin",
+    "data_level0.bin",
+    "length.bin",
+    "link_lists.bin",
+];
+
+/// The paths of the files of the HNSW index stored under `prefix`
+pub fn hnsw_index_file_paths(prefix: &str) -> Vec<String> {
+    HNSW_INDEX_FILES
+        .iter()
+        .map(|file| format!("hnsw/{}/{}", prefix, file))
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct DeleteUnusedFilesOperator {
     storage: Storage,
@@ -97,36 +112,16 @@ impl Operator<DeleteUnusedFilesInput, DeleteUnusedFilesOutput> for DeleteUnusedF
         let hnsw_files: Vec<String> = input
             .hnsw_prefixes_for_deletion
             .iter()
-            .flat_map(|prefix| {
-                [
-                    "header.bin",
-                    "data_level0.bin",
-                    "length.bin",
-                    "link_lists.bin",
-                ]
-                .iter()
-                .map(|file| format!("hnsw/{}/{}", prefix, file))
-                .collect::<Vec<String>>()
-            })
+            .flat_map(|prefix| hnsw_index_file_paths(prefix))
             .collect();
         println!("Deleting HNSW files: {:?}", hnsw_files);
 
-        for prefix in &input.hnsw_prefixes_for_deletion {
-            for file in [
-                "header.bin",
-                "data_level0.bin",
-                "length.bin",
-                "link_lists.bin",
-            ]
-            .iter()
+        for file_path in &hnsw_files {
+            if !self
+                .delete_file(file_path, input.epoch_id, &mut deleted_files)
+                .await?
             {
-                let file_path = format!("hnsw/{}/{}", prefix, file);
-                if !self
-                    .delete_file(&file_path, input.epoch_id, &mut deleted_files)
-                    .await?
-                {
-                    continue;
-                }
+                continue;
             }
         }
 
@@ -5,3 +5,4 @@ pub mod delete_versions_at_sysdb;
 pub mod fetch_sparse_index_files;
 pub mod fetch_version_file;
 pub mod mark_versions_at_sysdb;
+pub mod write_dry_run_report;
@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_storage::Storage;
+use chroma_system::{Operator, OperatorType};
+use chroma_types::CollectionUuid;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use super::delete_unused_files::hnsw_index_file_paths;
+
+/// Computes the sizes of the files a garbage collection run would delete and writes them,
+/// together with the versions it would delete, to a JSON report. Used instead of the
+/// deletion operators in dry-run mode, so nothing is mutated in storage or sysdb.
+#[derive(Clone)]
+pub struct WriteDryRunReportOperator {
+    storage: Storage,
+    report_dir: String,
+}
+
+impl std::fmt::Debug for WriteDryRunReportOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteDryRunReportOperator")
+            .field("report_dir", &self.report_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WriteDryRunReportOperator {
+    pub fn new(storage: Storage, report_dir: String) -> Self {
+        Self {
+            storage,
+            report_dir,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WriteDryRunReportInput {
+    pub collection_id: CollectionUuid,
+    pub version_file_path: String,
+    pub cutoff_time: DateTime<Utc>,
+    pub versions_to_delete: Vec<i64>,
+    pub oldest_version_to_keep: i64,
+    pub unused_s3_files: HashSet<String>,
+    pub hnsw_prefixes_for_deletion: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunFile {
+    pub path: String,
+    /// Unset if the size could not be read, e.g. because the file is already gone
+    pub size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    pub collection_id: String,
+    pub version_file_path: String,
+    pub generated_at: DateTime<Utc>,
+    pub cutoff_time: DateTime<Utc>,
+    pub versions_to_delete: Vec<i64>,
+    pub oldest_version_to_keep: i64,
+    pub files_to_delete: Vec<DryRunFile>,
+    pub total_size_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct WriteDryRunReportOutput {
+    pub report_path: String,
+    pub report: DryRunReport,
+}
+
+#[derive(Error, Debug)]
+pub enum WriteDryRunReportError {
+    #[error("Error serializing report: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("Error writing report {path}: {message}")]
+    Write { path: String, message: String },
+}
+
+impl ChromaError for WriteDryRunReportError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::Internal
+    }
+}
+
+#[async_trait]
+impl Operator<WriteDryRunReportInput, WriteDryRunReportOutput> for WriteDryRunReportOperator {
+    type Error = WriteDryRunReportError;
+
+    fn get_type(&self) -> OperatorType {
+        OperatorType::IO
+    }
+
+    async fn run(
+        &self,
+        input: &WriteDryRunReportInput,
+    ) -> Result<WriteDryRunReportOutput, WriteDryRunReportError> {
+        let mut paths = input.unused_s3_files.iter().cloned().collect::<Vec<_>>();
+        paths.sort();
+        paths.extend(
+            input
+                .hnsw_prefixes_for_deletion
+                .iter()
+                .flat_map(|prefix| hnsw_index_file_paths(prefix)),
+        );
+
+        let mut files_to_delete = Vec::with_capacity(paths.len());
+        for path in paths {
+            let size_bytes = match self.storage.size(&path).await {
+                Ok(size) => Some(size),
+                Err(e) => {
+                    tracing::warn!(error = %e, path = %path, "Failed to get file size");
+                    None
+                }
+            };
+            files_to_delete.push(DryRunFile { path, size_bytes });
+        }
+
+        let generated_at = Utc::now();
+        let report = DryRunReport {
+            collection_id: input.collection_id.to_string(),
+            version_file_path: input.version_file_path.clone(),
+            generated_at,
+            cutoff_time: input.cutoff_time,
+            versions_to_delete: input.versions_to_delete.clone(),
+            oldest_version_to_keep: input.oldest_version_to_keep,
+            total_size_bytes: files_to_delete
+                .iter()
+                .filter_map(|file| file.size_bytes)
+                .sum(),
+            files_to_delete,
+        };
+
+        let report_path = PathBuf::from(&self.report_dir).join(format!(
+            "{}-{}.json",
+            input.collection_id,
+            generated_at.timestamp_millis()
+        ));
+        let report_path = report_path.to_string_lossy().to_string();
+        let write_error = |e: std::io::Error| WriteDryRunReportError::Write {
+            path: report_path.clone(),
+            message: e.to_string(),
+        };
+        tokio::fs::create_dir_all(&self.report_dir)
+            .await
+            .map_err(write_error)?;
+        tokio::fs::write(&report_path, serde_json::to_vec_pretty(&report)?)
+            .await
+            .map_err(write_error)?;
+
+        tracing::info!(
+            report_path = %report_path,
+            versions = report.versions_to_delete.len(),
+            files = report.files_to_delete.len(),
+            total_size_bytes = report.total_size_bytes,
+            "Wrote garbage collection dry-run report"
+        );
+
+        Ok(WriteDryRunReportOutput {
+            report_path,
+            report,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chroma_storage::local::LocalStorage;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_report() {
+        let storage_dir = TempDir::new().unwrap();
+        let report_dir = TempDir::new().unwrap();
+        let storage = Storage::Local(LocalStorage::new(storage_dir.path().to_str().unwrap()));
+        storage.put_bytes("block/1", vec![0; 100]).await.unwrap();
+        for path in hnsw_index_file_paths("prefix1") {
+            storage.put_bytes(&path, vec![0; 10]).await.unwrap();
+        }
+
+        let operator = WriteDryRunReportOperator::new(
+            storage.clone(),
+            report_dir.path().to_str().unwrap().to_string(),
+        );
+        let input = WriteDryRunReportInput {
+            collection_id: CollectionUuid::new(),
+            version_file_path: "version_file".to_string(),
+            cutoff_time: Utc::now(),
+            versions_to_delete: vec![1, 2],
+            oldest_version_to_keep: 3,
+            unused_s3_files: HashSet::from(["block/1".to_string(), "block/2".to_string()]),
+            hnsw_prefixes_for_deletion: vec!["prefix1".to_string()],
+        };
+        let output = operator.run(&input).await.unwrap();
+
+        assert_eq!(output.report.files_to_delete.len(), 6);
+        assert_eq!(output.report.files_to_delete[1].path, "block/2");
+        assert_eq!(output.report.files_to_delete[1].size_bytes, None);
+        assert_eq!(output.report.total_size_bytes, 140);
+
+        // Nothing is deleted
+        assert!(storage.get("block/1").await.is_ok());
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&output.report_path).unwrap()).unwrap();
+        assert_eq!(written["total_size_bytes"], 140);
+        assert_eq!(written["versions_to_delete"], serde_json::json!([1, 2]));
+    }
+}
@@ -12,7 +12,7 @@ use chroma_distance::DistanceFunction;
 use chroma_error::ChromaError;
 use chroma_error::ErrorCodes;
 use chroma_storage::Storage;
-use chroma_types::CollectionUuid;
+use chroma_types::{CollectionUuid, ScrubReport};
 use parking_lot::RwLock;
 use std::fmt::Debug;
 use std::path::Path;
@@ -141,6 +141,15 @@ impl HnswIndexProvider {
         }
     }
 
+    /// A provider for the indices of `tenant`. It shares this provider's cache and temporary
+    /// files, but its flushes are encrypted under the tenant's key when the storage is encrypted.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            storage: self.storage.for_tenant(tenant),
+            ..self.clone()
+        }
+    }
+
     pub async fn get(&self, index_id: &IndexUuid, cache_key: &CacheKey) -> Option<HnswIndexRef> {
         match self.cache.get(cache_key).await.ok().flatten() {
             Some(index) => {
@@ -160,6 +169,26 @@ impl HnswIndexProvider {
         format!("hnsw/{}/{}", id, file)
     }
 
+    /// Verifies that every file of the index is present and non-empty in storage.
+    /// The index files carry no checksum, so their content is not verified.
+    pub async fn scrub(&self, id: &IndexUuid) -> ScrubReport {
+        let mut report = ScrubReport::default();
+        for file in FILES.iter() {
+            let key = self.format_key(id, file);
+            report.files_checked += 1;
+            report.files_without_checksum += 1;
+            match self.storage.get_parallel(&key).await {
+                Ok(bytes) if bytes.is_empty() => report.add_corruption(key, "Empty file"),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("Failed to read hnsw index file {}: {}", key, e);
+                    report.add_corruption(key, e);
+                }
+            }
+        }
+        report
+    }
+
     pub async fn fork(
         &self,
         source_id: &IndexUuid,
@@ -10,6 +10,7 @@ use chroma_blockstore::{
 use chroma_distance::{normalize, DistanceFunction};
 use chroma_error::{ChromaError, ErrorCodes};
 use chroma_types::CollectionUuid;
+use chroma_types::SpannIndexStats;
 use chroma_types::SpannPostingList;
 use rand::seq::SliceRandom;
 use thiserror::Error;
@@ -1550,6 +1551,71 @@ impl<'me> SpannIndexReader<'me> {
         }
         Ok(posting_lists)
     }
+
+    /// Scans all posting lists and the versions map to report the shape of the index
+    pub async fn stats(&self) -> Result<SpannIndexStats, SpannIndexReaderError> {
+        let versions_map = self
+            .versions_map
+            .get_range(""..="", ..)
+            .await
+            .map_err(|_| SpannIndexReaderError::PostingListReadError)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let posting_lists = self
+            .posting_lists
+            .get_range(""..="", ..)
+            .await
+            .map_err(|_| SpannIndexReaderError::PostingListReadError)?;
+
+        let mut num_stale_postings = 0;
+        let mut posting_list_sizes = Vec::with_capacity(posting_lists.len());
+        for (_, posting_list) in posting_lists.iter() {
+            let mut size = 0;
+            for (doc_offset_id, doc_version) in posting_list
+                .doc_offset_ids
+                .iter()
+                .zip(posting_list.doc_versions.iter())
+            {
+                match versions_map.get(doc_offset_id) {
+                    Some(actual_version)
+                        if *actual_version != 0 && doc_version >= actual_version =>
+                    {
+                        size += 1
+                    }
+                    _ => num_stale_postings += 1,
+                }
+            }
+            posting_list_sizes.push(size);
+        }
+        posting_list_sizes.sort_unstable();
+
+        let percentile = |p: usize| match posting_list_sizes.len() {
+            0 => 0,
+            len => posting_list_sizes[(len - 1) * p / 100] as u64,
+        };
+        Ok(SpannIndexStats {
+            num_centroids: self.hnsw_index.inner.read().len() as u64,
+            num_posting_lists: posting_list_sizes.len() as u64,
+            min_posting_list_size: posting_list_sizes.first().copied().unwrap_or_default() as u64,
+            max_posting_list_size: posting_list_sizes.last().copied().unwrap_or_default() as u64,
+            mean_posting_list_size: if posting_list_sizes.is_empty() {
+                0.0
+            } else {
+                posting_list_sizes.iter().sum::<usize>() as f64 / posting_list_sizes.len() as f64
+            },
+            p50_posting_list_size: percentile(50),
+            p99_posting_list_size: percentile(99),
+            num_stale_postings,
+            num_posting_lists_to_split: posting_list_sizes
+                .iter()
+                .filter(|size| **size > SPLIT_THRESHOLD)
+                .count() as u64,
+            num_posting_lists_to_merge: posting_list_sizes
+                .iter()
+                .filter(|size| **size < MERGE_THRESHOLD)
+                .count() as u64,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,114 @@
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_types::chroma_proto;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// The compression applied to the payload (embedding and metadata) of records pushed to the log.
+/// Each record of a pushed batch is compressed on its own, so that it keeps its own offset and
+/// can be purged independently. Readers detect compressed records, so a log may hold a mix of
+/// compressed and uncompressed records (e.g. after compression is turned on or off).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogCompression {
+    #[default]
+    None,
+    Zstd,
+}
+
+#[derive(Error, Debug)]
+pub enum LogCompressionError {
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to decode compressed record: {0}")]
+    Decode(#[from] prost::DecodeError),
+}
+
+impl ChromaError for LogCompressionError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            LogCompressionError::IOError(_) => ErrorCodes::Internal,
+            LogCompressionError::Decode(_) => ErrorCodes::DataLoss,
+        }
+    }
+}
+
+impl LogCompression {
+    /// A compressor for a batch of records, or `None` if records are stored uncompressed
+    pub(crate) fn compressor(&self) -> Result<Option<RecordCompressor>, LogCompressionError> {
+        match self {
+            LogCompression::None => Ok(None),
+            LogCompression::Zstd => Ok(Some(RecordCompressor(zstd::bulk::Compressor::new(
+                ZSTD_LEVEL,
+            )?))),
+        }
+    }
+}
+
+/// Compresses the records of a batch, reusing one compression context for all of them.
+pub(crate) struct RecordCompressor(zstd::bulk::Compressor<'static>);
+
+impl RecordCompressor {
+    /// Moves the vector and metadata of a record into a compressed payload
+    pub(crate) fn compress(
+        &mut self,
+        record: &mut chroma_proto::OperationRecord,
+    ) -> Result<Vec<u8>, LogCompressionError> {
+        let payload = chroma_proto::OperationRecord {
+            vector: record.vector.take(),
+            metadata: record.metadata.take(),
+            ..Default::default()
+        };
+        Ok(self.0.compress(&payload.encode_to_vec())?)
+    }
+}
+
+/// Restores the vector and metadata of a record from its compressed payload
+pub(crate) fn decompress_payload(
+    payload: &[u8],
+    record: &mut chroma_proto::OperationRecord,
+) -> Result<(), LogCompressionError> {
+    let decompressed = zstd::stream::decode_all(payload)?;
+    let payload = chroma_proto::OperationRecord::decode(decompressed.as_slice())?;
+    record.vector = payload.vector;
+    record.metadata = payload.metadata;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chroma_types::{OperationRecord, UpdateMetadata, UpdateMetadataValue};
+
+    #[test]
+    fn test_round_trip() {
+        let mut metadata = UpdateMetadata::new();
+        metadata.insert(
+            "chroma:document".to_string(),
+            UpdateMetadataValue::Str("the quick brown fox ".repeat(50)),
+        );
+        let record = OperationRecord {
+            id: "id".to_string(),
+            embedding: Some(vec![0.5; 1536]),
+            encoding: None,
+            metadata: Some(metadata),
+            document: None,
+            operation: chroma_types::Operation::Add,
+        };
+        let original = chroma_proto::OperationRecord::try_from(record).unwrap();
+
+        let mut compressor = LogCompression::Zstd.compressor().unwrap().unwrap();
+        let mut compressed = original.clone();
+        let payload = compressor.compress(&mut compressed).unwrap();
+        assert!(compressed.vector.is_none());
+        assert!(compressed.metadata.is_none());
+        assert!(payload.len() < original.encoded_len());
+
+        decompress_payload(&payload, &mut compressed).unwrap();
+        assert_eq!(compressed, original);
+
+        assert!(LogCompression::None.compressor().unwrap().is_none());
+    }
+}
@@ -1,4 +1,8 @@
+use crate::compression::LogCompression;
+use chroma_types::{CollectionUuid, LogRetentionPolicy};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
 
 #[derive(Deserialize, Clone, Serialize, Debug)]
 pub struct GrpcLogConfig {
@@ -10,6 +14,8 @@ pub struct GrpcLogConfig {
     pub connect_timeout_ms: u64,
     #[serde(default = "GrpcLogConfig::default_request_timeout_ms")]
     pub request_timeout_ms: u64,
+    #[serde(default)]
+    pub compression: LogCompression,
 }
 
 impl GrpcLogConfig {
@@ -37,6 +43,7 @@ impl Default for GrpcLogConfig {
             port: GrpcLogConfig::default_port(),
             connect_timeout_ms: GrpcLogConfig::default_connect_timeout_ms(),
             request_timeout_ms: GrpcLogConfig::default_request_timeout_ms(),
+            compression: LogCompression::default(),
         }
     }
 }
@@ -45,6 +52,8 @@ impl Default for GrpcLogConfig {
 pub struct SqliteLogConfig {
     pub tenant_id: String,
     pub topic_namespace: String,
+    #[serde(default)]
+    pub compression: LogCompression,
 }
 
 impl Default for SqliteLogConfig {
@@ -52,15 +61,103 @@ impl Default for SqliteLogConfig {
         SqliteLogConfig {
             tenant_id: "default".to_string(),
             topic_namespace: "default".to_string(),
+            compression: LogCompression::default(),
         }
     }
 }
 
+/// When the file log flushes appended records to disk.
+#[derive(Deserialize, Clone, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FsyncPolicy {
+    /// Sync every push before acknowledging it.
+    #[default]
+    Always,
+    /// Sync pushes in the background every interval instead of before acknowledging them. A
+    /// crash can lose the pushes acknowledged during the last interval.
+    Interval { interval_ms: u64 },
+    /// Leave flushing to the operating system.
+    Never,
+}
+
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct FileLogConfig {
+    /// Directory holding one subdirectory of segment files per collection. It must not be
+    /// shared with a sqlite log: switching a persist directory between the two loses records.
+    pub path: String,
+    #[serde(default)]
+    pub fsync: FsyncPolicy,
+    #[serde(default = "FileLogConfig::default_segment_size_bytes")]
+    pub segment_size_bytes: u64,
+    #[serde(default = "FileLogConfig::default_max_batch_size")]
+    pub max_batch_size: u32,
+}
+
+impl FileLogConfig {
+    fn default_segment_size_bytes() -> u64 {
+        64 * 1024 * 1024
+    }
+
+    fn default_max_batch_size() -> u32 {
+        5461
+    }
+}
+
 #[derive(Deserialize, Clone, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum LogConfig {
     Grpc(GrpcLogConfig),
     Sqlite(SqliteLogConfig),
+    File(FileLogConfig),
+}
+
+/// How long single-node logs keep records after compaction.
+#[derive(Deserialize, Clone, Serialize, Debug, Default)]
+pub struct LogRetentionConfig {
+    #[serde(default)]
+    pub default: LogRetentionPolicy,
+    /// Policies that override the default for individual collections, keyed by collection id.
+    #[serde(default)]
+    pub collections: HashMap<Uuid, LogRetentionPolicy>,
+}
+
+impl LogRetentionConfig {
+    pub fn policy(&self, collection_id: CollectionUuid) -> &LogRetentionPolicy {
+        self.collections
+            .get(&collection_id.0)
+            .unwrap_or(&self.default)
+    }
+}
+
+/// The garbage collection pass of single-node deployments, which removes the persisted hnsw
+/// directories and log records that no longer belong to a live segment or collection, and
+/// purges soft-deleted collections whose restore window has passed. It runs at startup and then
+/// on the configured interval.
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct LocalGarbageCollectionConfig {
+    #[serde(default = "LocalGarbageCollectionConfig::default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "LocalGarbageCollectionConfig::default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl LocalGarbageCollectionConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_interval_secs() -> u64 {
+        3600
+    }
+}
+
+impl Default for LocalGarbageCollectionConfig {
+    fn default() -> Self {
+        LocalGarbageCollectionConfig {
+            enabled: LocalGarbageCollectionConfig::default_enabled(),
+            interval_secs: LocalGarbageCollectionConfig::default_interval_secs(),
+        }
+    }
 }
 
 impl Default for LogConfig {
@@ -0,0 +1,933 @@
+//! An append-only, file-backed write-ahead log for single-node deployments.
+//!
+//! Every collection gets a directory of segment files named after the offset of their first
+//! record. Records are appended to the last segment until it grows past the configured size, at
+//! which point a new segment is started. Each record is framed as
+//!
+//! ```text
+//! [payload length: u32 LE][crc32 of payload: u32 LE][offset: i64 LE][timestamp ns: i64 LE][OperationRecord proto]
+//! ```
+//!
+//! Offsets are per collection, start at 1 and are contiguous. On open, the last segment is
+//! scanned and truncated at the first torn or corrupt frame, so a crash in the middle of an
+//! append loses at most the records that were not yet acknowledged.
+
+use crate::{
+    config::{FileLogConfig, FsyncPolicy},
+    BackfillMessage, CompactionManagerError, LocalCompactionManager, LogStats, PurgeLogsMessage,
+};
+use async_trait::async_trait;
+use chroma_config::Configurable;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_system::{ComponentHandle, RequestError};
+use chroma_types::{
+    chroma_proto, CollectionUuid, LogRecord, OperationRecord, RecordConversionError,
+    UpdateMetadata, UpdateMetadataValue, CHROMA_DOCUMENT_KEY,
+};
+use parking_lot::Mutex;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+const SEGMENT_EXTENSION: &str = "wal";
+// Payload length and checksum
+const FRAME_HEADER_BYTES: u64 = 8;
+// Offset and timestamp at the start of the payload
+const RECORD_HEADER_BYTES: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum FileLogError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Corrupt record in {path} at byte {position}")]
+    Corrupt { path: String, position: u64 },
+    #[error("Failed to convert record: {0}")]
+    InvalidRecord(#[from] RecordConversionError),
+    #[error("Failed to decode record: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("Error in compaction: {0}")]
+    CompactionError(#[from] CompactionManagerError),
+    #[error("Error setting compactor handle")]
+    CompactorHandleSetError,
+    #[error("Error sending message to compactor")]
+    MessageSendingError(#[from] RequestError),
+    #[error("Log task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl ChromaError for FileLogError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            FileLogError::Io(_) => ErrorCodes::Internal,
+            FileLogError::Corrupt { .. } => ErrorCodes::DataLoss,
+            FileLogError::InvalidRecord(e) => e.code(),
+            FileLogError::Decode(_) => ErrorCodes::DataLoss,
+            FileLogError::CompactionError(e) => e.code(),
+            FileLogError::CompactorHandleSetError => ErrorCodes::FailedPrecondition,
+            FileLogError::MessageSendingError(e) => e.code(),
+            FileLogError::Join(_) => ErrorCodes::Internal,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Segment {
+    first_offset: i64,
+    path: PathBuf,
+}
+
+impl Segment {
+    fn new(dir: &Path, first_offset: i64) -> Self {
+        Self {
+            first_offset,
+            path: dir.join(format!("{first_offset:020}.{SEGMENT_EXTENSION}")),
+        }
+    }
+}
+
+struct Entry {
+    offset: i64,
+    timestamp: i64,
+    record: chroma_proto::OperationRecord,
+}
+
+/// Reads the intact frames of a segment in order.
+struct SegmentReader {
+    reader: BufReader<File>,
+    len: u64,
+    position: u64,
+    next_offset: i64,
+}
+
+impl SegmentReader {
+    fn open(segment: &Segment, len: u64) -> Result<Self, FileLogError> {
+        Ok(Self {
+            reader: BufReader::new(File::open(&segment.path)?),
+            len,
+            position: 0,
+            next_offset: segment.first_offset,
+        })
+    }
+
+    /// Returns the next record, or `None` at the end of the segment or at the first torn,
+    /// corrupt, undecodable or out of sequence frame.
+    fn next(&mut self) -> Result<Option<Entry>, FileLogError> {
+        if self.position + FRAME_HEADER_BYTES > self.len {
+            return Ok(None);
+        }
+        let mut header = [0u8; FRAME_HEADER_BYTES as usize];
+        self.reader.read_exact(&mut header)?;
+        let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+        let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if payload_len < RECORD_HEADER_BYTES as u64
+            || self.position + FRAME_HEADER_BYTES + payload_len > self.len
+        {
+            return Ok(None);
+        }
+
+        let mut payload = vec![0u8; payload_len as usize];
+        self.reader.read_exact(&mut payload)?;
+        if crc32fast::hash(&payload) != checksum {
+            return Ok(None);
+        }
+        let offset = i64::from_le_bytes(payload[..8].try_into().unwrap());
+        if offset != self.next_offset {
+            return Ok(None);
+        }
+        let timestamp = i64::from_le_bytes(payload[8..16].try_into().unwrap());
+        let record = match chroma_proto::OperationRecord::decode(&payload[RECORD_HEADER_BYTES..]) {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!("Undecodable record at offset {}: {}", offset, e);
+                return Ok(None);
+            }
+        };
+
+        self.position += FRAME_HEADER_BYTES + payload_len;
+        self.next_offset += 1;
+        Ok(Some(Entry {
+            offset,
+            timestamp,
+            record,
+        }))
+    }
+}
+
+fn encode_frame(
+    offset: i64,
+    timestamp: i64,
+    mut record: OperationRecord,
+) -> Result<Vec<u8>, FileLogError> {
+    if let Some(document) = &record.document {
+        record
+            .metadata
+            .get_or_insert_with(UpdateMetadata::new)
+            .insert(
+                CHROMA_DOCUMENT_KEY.to_string(),
+                UpdateMetadataValue::Str(document.clone()),
+            );
+    }
+    let record = chroma_proto::OperationRecord::try_from(record)?;
+
+    let mut payload = Vec::with_capacity(RECORD_HEADER_BYTES + record.encoded_len());
+    payload.extend_from_slice(&offset.to_le_bytes());
+    payload.extend_from_slice(&timestamp.to_le_bytes());
+    payload.extend(record.encode_to_vec());
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_BYTES as usize + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    frame.extend(payload);
+    Ok(frame)
+}
+
+/// The segments of a single collection. Records are only ever appended to the last one.
+#[derive(Debug)]
+struct CollectionLog {
+    dir: PathBuf,
+    segments: Vec<Segment>,
+    active: Option<File>,
+    active_len: u64,
+    next_offset: i64,
+    // Whether the active segment has appended records that were not synced yet
+    dirty: bool,
+}
+
+impl CollectionLog {
+    fn open(dir: PathBuf) -> Result<Self, FileLogError> {
+        let mut segments = Vec::new();
+        match fs::read_dir(&dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry?.path();
+                    if path.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+                        continue;
+                    }
+                    if let Some(first_offset) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .and_then(|stem| stem.parse().ok())
+                    {
+                        segments.push(Segment { first_offset, path });
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        segments.sort_by_key(|segment| segment.first_offset);
+
+        let mut log = Self {
+            dir,
+            segments,
+            active: None,
+            active_len: 0,
+            next_offset: 1,
+            dirty: false,
+        };
+        log.recover()?;
+        Ok(log)
+    }
+
+    /// Truncates the last segment after its last intact record.
+    fn recover(&mut self) -> Result<(), FileLogError> {
+        let Some(last) = self.segments.last() else {
+            return Ok(());
+        };
+        let file = OpenOptions::new().append(true).open(&last.path)?;
+        let len = file.metadata()?.len();
+        let mut reader = SegmentReader::open(last, len)?;
+        while reader.next()?.is_some() {}
+
+        if reader.position < len {
+            tracing::warn!(
+                "Truncating {} torn bytes at the end of {}",
+                len - reader.position,
+                last.path.display()
+            );
+            file.set_len(reader.position)?;
+            file.sync_all()?;
+        }
+        self.next_offset = reader.next_offset;
+        self.active_len = reader.position;
+        self.active = Some(file);
+        Ok(())
+    }
+
+    /// Seals the active segment and starts a new one at the next offset.
+    fn roll(&mut self) -> Result<(), FileLogError> {
+        if let Some(active) = self.active.take() {
+            active.sync_all()?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        let segment = Segment::new(&self.dir, self.next_offset);
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&segment.path)?;
+        File::open(&self.dir)?.sync_all()?;
+        self.segments.push(segment);
+        self.active = Some(file);
+        self.active_len = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), FileLogError> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        let Some(active) = self.active.as_mut() else {
+            unreachable!("A segment is started before anything is written");
+        };
+        if let Err(e) = active.write_all(buffer) {
+            // Drop whatever part of the write made it to the file so the next append
+            // does not land after a torn frame.
+            let _ = active.set_len(self.active_len);
+            return Err(e.into());
+        }
+        self.active_len += buffer.len() as u64;
+        Ok(())
+    }
+
+    fn append(
+        &mut self,
+        records: Vec<OperationRecord>,
+        config: &FileLogConfig,
+    ) -> Result<(), FileLogError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as i64;
+
+        let mut buffer = Vec::new();
+        let mut next_offset = self.next_offset;
+        for record in records {
+            let frame = encode_frame(next_offset, timestamp, record)?;
+            let pending_len = self.active_len + buffer.len() as u64;
+            if self.active.is_none()
+                || (pending_len > 0 && pending_len + frame.len() as u64 > config.segment_size_bytes)
+            {
+                self.write(&buffer)?;
+                buffer.clear();
+                self.next_offset = next_offset;
+                self.roll()?;
+            }
+            buffer.extend(frame);
+            next_offset += 1;
+        }
+        self.write(&buffer)?;
+        self.next_offset = next_offset;
+
+        match config.fsync {
+            FsyncPolicy::Always => {
+                self.dirty = true;
+                self.sync()
+            }
+            // The background flusher syncs the log
+            FsyncPolicy::Interval { .. } => {
+                self.dirty = true;
+                Ok(())
+            }
+            FsyncPolicy::Never => Ok(()),
+        }
+    }
+
+    /// Syncs the records appended to the active segment since the last sync.
+    fn sync(&mut self) -> Result<(), FileLogError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(active) = &self.active {
+            active.sync_data()?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Reads up to `batch_size` records with an offset greater than `offset`, or all of them
+    /// if `batch_size` is negative.
+    fn read(
+        &self,
+        offset: i64,
+        batch_size: i32,
+        end_timestamp: i64,
+    ) -> Result<Vec<LogRecord>, FileLogError> {
+        let mut records = Vec::new();
+        if batch_size == 0 {
+            return Ok(records);
+        }
+
+        // The segment holding the first record after the offset
+        let start = self
+            .segments
+            .partition_point(|segment| segment.first_offset <= offset + 1)
+            .saturating_sub(1);
+        for (index, segment) in self.segments.iter().enumerate().skip(start) {
+            let len = if index + 1 == self.segments.len() {
+                self.active_len
+            } else {
+                fs::metadata(&segment.path)?.len()
+            };
+            let mut reader = SegmentReader::open(segment, len)?;
+            while let Some(entry) = reader.next()? {
+                if entry.offset <= offset || entry.timestamp > end_timestamp {
+                    continue;
+                }
+                records.push(LogRecord {
+                    log_offset: entry.offset,
+                    record: OperationRecord::try_from(entry.record)?,
+                });
+                if batch_size > 0 && records.len() >= batch_size as usize {
+                    return Ok(records);
+                }
+            }
+            if reader.position < len {
+                return Err(FileLogError::Corrupt {
+                    path: segment.path.display().to_string(),
+                    position: reader.position,
+                });
+            }
+        }
+        Ok(records)
+    }
+
+    fn stats(&self) -> LogStats {
+        match self.segments.first() {
+            Some(first) if first.first_offset < self.next_offset => LogStats {
+                first_offset: Some(first.first_offset),
+                last_offset: Some(self.next_offset - 1),
+                record_count: (self.next_offset - first.first_offset) as u64,
+            },
+            _ => LogStats::default(),
+        }
+    }
+
+    fn nth_latest_offset(&self, offset: i64, n: u64) -> Option<i64> {
+        let LogStats {
+            first_offset: Some(first_offset),
+            last_offset: Some(last_offset),
+            ..
+        } = self.stats()
+        else {
+            return None;
+        };
+        // Offsets are contiguous, so there is nothing to count
+        let nth_offset = offset.min(last_offset) - (n as i64 - 1);
+        (n > 0 && nth_offset >= first_offset).then_some(nth_offset)
+    }
+
+    fn first_offset_since(&self, timestamp_ns: i64) -> Result<Option<i64>, FileLogError> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let len = if index + 1 == self.segments.len() {
+                self.active_len
+            } else {
+                fs::metadata(&segment.path)?.len()
+            };
+            let mut reader = SegmentReader::open(segment, len)?;
+            while let Some(entry) = reader.next()? {
+                if entry.timestamp >= timestamp_ns {
+                    return Ok(Some(entry.offset));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Deletes the sealed segments that only hold records with an offset below `seq_id`. The
+    /// active segment is always kept so offsets keep increasing.
+    fn purge(&mut self, seq_id: i64) -> Result<(), FileLogError> {
+        let removable = self
+            .segments
+            .windows(2)
+            .take_while(|pair| pair[1].first_offset <= seq_id)
+            .count();
+        for segment in self.segments.drain(..removable) {
+            fs::remove_file(&segment.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileLog {
+    config: FileLogConfig,
+    root: PathBuf,
+    collections: Arc<Mutex<HashMap<CollectionUuid, Arc<Mutex<CollectionLog>>>>>,
+    compactor_handle: OnceLock<ComponentHandle<LocalCompactionManager>>,
+}
+
+impl FileLog {
+    pub fn new(config: FileLogConfig) -> Self {
+        let collections = Arc::new(Mutex::new(HashMap::new()));
+        if let FsyncPolicy::Interval { interval_ms } = config.fsync {
+            Self::spawn_flusher(
+                Arc::downgrade(&collections),
+                Duration::from_millis(interval_ms.max(1)),
+            );
+        }
+        Self {
+            root: PathBuf::from(&config.path),
+            config,
+            collections,
+            compactor_handle: OnceLock::new(),
+        }
+    }
+
+    /// Syncs the collections with unsynced appends every `interval`, until the log is dropped.
+    fn spawn_flusher(
+        collections: Weak<Mutex<HashMap<CollectionUuid, Arc<Mutex<CollectionLog>>>>>,
+        interval: Duration,
+    ) {
+        tokio::spawn(async move {
+            // The first tick is skipped, there is nothing to sync yet
+            let mut ticker =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(collections) = collections.upgrade() else {
+                    return;
+                };
+                let logs = collections.lock().values().cloned().collect::<Vec<_>>();
+                drop(collections);
+                let synced = tokio::task::spawn_blocking(move || {
+                    for log in logs {
+                        if let Err(e) = log.lock().sync() {
+                            tracing::error!("Failed to sync the file log: {}", e);
+                        }
+                    }
+                })
+                .await;
+                if let Err(e) = synced {
+                    tracing::error!("Failed to sync the file log: {}", e);
+                }
+            }
+        });
+    }
+
+    pub fn init_compactor_handle(
+        &self,
+        compactor_handle: ComponentHandle<LocalCompactionManager>,
+    ) -> Result<(), FileLogError> {
+        self.compactor_handle
+            .set(compactor_handle)
+            .map_err(|_| FileLogError::CompactorHandleSetError)
+    }
+
+    /// Runs `f` on the log of a collection on the blocking thread pool, opening and recovering
+    /// the log on first use.
+    async fn with_collection<T, F>(
+        &self,
+        collection_id: CollectionUuid,
+        f: F,
+    ) -> Result<T, FileLogError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut CollectionLog) -> Result<T, FileLogError> + Send + 'static,
+    {
+        let collections = self.collections.clone();
+        let dir = self.root.join(collection_id.to_string());
+        tokio::task::spawn_blocking(move || {
+            let collection = {
+                let mut collections = collections.lock();
+                match collections.get(&collection_id) {
+                    Some(collection) => collection.clone(),
+                    None => {
+                        let collection = Arc::new(Mutex::new(CollectionLog::open(dir)?));
+                        collections.insert(collection_id, collection.clone());
+                        collection
+                    }
+                }
+            };
+            let mut collection = collection.lock();
+            f(&mut collection)
+        })
+        .await?
+    }
+
+    pub(super) async fn read(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        batch_size: i32,
+        end_timestamp_ns: Option<i64>,
+    ) -> Result<Vec<LogRecord>, FileLogError> {
+        let end_timestamp_ns = end_timestamp_ns.unwrap_or(i64::MAX);
+        self.with_collection(collection_id, move |log| {
+            log.read(offset, batch_size, end_timestamp_ns)
+        })
+        .await
+    }
+
+    pub(super) async fn push_logs(
+        &mut self,
+        collection_id: CollectionUuid,
+        records: Vec<OperationRecord>,
+    ) -> Result<(), FileLogError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let config = self.config.clone();
+        self.with_collection(collection_id, move |log| log.append(records, &config))
+            .await?;
+
+        if let Some(handle) = self.compactor_handle.get() {
+            let backfill_message = BackfillMessage { collection_id };
+            handle.request(backfill_message, None).await??;
+            let purge_log_msg = PurgeLogsMessage { collection_id };
+            handle.clone().request(purge_log_msg, None).await??;
+        }
+
+        Ok(())
+    }
+
+    pub async fn purge_logs(
+        &mut self,
+        collection_id: CollectionUuid,
+        seq_id: u64,
+    ) -> Result<(), FileLogError> {
+        self.with_collection(collection_id, move |log| log.purge(seq_id as i64))
+            .await
+    }
+
+    pub(super) async fn stats(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<LogStats, FileLogError> {
+        self.with_collection(collection_id, |log| Ok(log.stats()))
+            .await
+    }
+
+    pub(super) async fn nth_latest_offset(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        n: u64,
+    ) -> Result<Option<i64>, FileLogError> {
+        self.with_collection(collection_id, move |log| {
+            Ok(log.nth_latest_offset(offset, n))
+        })
+        .await
+    }
+
+    pub(super) async fn first_offset_since(
+        &mut self,
+        collection_id: CollectionUuid,
+        timestamp_ns: i64,
+    ) -> Result<Option<i64>, FileLogError> {
+        self.with_collection(collection_id, move |log| {
+            log.first_offset_since(timestamp_ns)
+        })
+        .await
+    }
+
+    /// Removes every segment of a collection.
+    pub async fn delete_collection(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<(), FileLogError> {
+        let collections = self.collections.clone();
+        let dir = self.root.join(collection_id.to_string());
+        tokio::task::spawn_blocking(move || {
+            let removed = collections.lock().remove(&collection_id);
+            let _guard = removed.as_ref().map(|collection| collection.lock());
+            match fs::remove_dir_all(&dir) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+        .await?
+    }
+
+    /// Removes the logs of every collection.
+    pub async fn reset(&mut self) -> Result<(), FileLogError> {
+        let collections = self.collections.clone();
+        let root = self.root.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut collections = collections.lock();
+            for collection in collections.values() {
+                let _guard = collection.lock();
+            }
+            collections.clear();
+            if !root.exists() {
+                return Ok(());
+            }
+            for entry in fs::read_dir(&root)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    fs::remove_dir_all(path)?;
+                }
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    pub fn get_max_batch_size(&self) -> u32 {
+        self.config.max_batch_size
+    }
+}
+
+#[async_trait]
+impl Configurable<FileLogConfig> for FileLog {
+    async fn try_from_config(
+        config: &FileLogConfig,
+        _registry: &chroma_config::registry::Registry,
+    ) -> Result<Self, Box<dyn ChromaError>> {
+        fs::create_dir_all(&config.path).map_err(|e| FileLogError::from(e).boxed())?;
+        Ok(Self::new(config.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chroma_types::Operation;
+
+    fn test_config(path: &Path, segment_size_bytes: u64) -> FileLogConfig {
+        FileLogConfig {
+            path: path.to_str().unwrap().to_string(),
+            fsync: FsyncPolicy::Always,
+            segment_size_bytes,
+            max_batch_size: 100,
+        }
+    }
+
+    fn records(range: std::ops::Range<usize>) -> Vec<OperationRecord> {
+        range
+            .map(|i| OperationRecord {
+                id: format!("id_{i}"),
+                embedding: Some(vec![i as f32; 3]),
+                encoding: None,
+                metadata: None,
+                document: Some(format!("document {i}")),
+                operation: Operation::Upsert,
+            })
+            .collect()
+    }
+
+    fn segment_count(dir: &Path) -> usize {
+        fs::read_dir(dir).unwrap().count()
+    }
+
+    #[tokio::test]
+    async fn test_push_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = FileLog::new(test_config(dir.path(), 1 << 20));
+        let collection_id = CollectionUuid::new();
+
+        log.push_logs(collection_id, records(0..5)).await.unwrap();
+        log.push_logs(collection_id, records(5..8)).await.unwrap();
+
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        assert_eq!(read.len(), 8);
+        for (i, record) in read.iter().enumerate() {
+            assert_eq!(record.log_offset, i as i64 + 1);
+            assert_eq!(record.record.id, format!("id_{i}"));
+            assert_eq!(record.record.document, Some(format!("document {i}")));
+            assert_eq!(record.record.embedding, Some(vec![i as f32; 3]));
+        }
+
+        let read = log.read(collection_id, 3, 2, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![4, 5]);
+
+        let read = log.read(collection_id, 0, -1, Some(0)).await.unwrap();
+        assert!(read.is_empty());
+
+        let other = log.read(CollectionUuid::new(), 0, -1, None).await.unwrap();
+        assert!(other.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_segments_roll_and_purge() {
+        let dir = tempfile::tempdir().unwrap();
+        // Small enough that every record gets its own segment
+        let mut log = FileLog::new(test_config(dir.path(), 1));
+        let collection_id = CollectionUuid::new();
+        let collection_dir = dir.path().join(collection_id.to_string());
+
+        log.push_logs(collection_id, records(0..4)).await.unwrap();
+        assert_eq!(segment_count(&collection_dir), 4);
+
+        let read = log.read(collection_id, 2, -1, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![3, 4]);
+
+        log.purge_logs(collection_id, 3).await.unwrap();
+        assert_eq!(segment_count(&collection_dir), 2);
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![3, 4]);
+
+        // The active segment survives a purge past the end of the log
+        log.purge_logs(collection_id, 100).await.unwrap();
+        assert_eq!(segment_count(&collection_dir), 1);
+        log.push_logs(collection_id, records(4..5)).await.unwrap();
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_truncates_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_config(dir.path(), 1 << 20);
+        let collection_id = CollectionUuid::new();
+        {
+            let mut log = FileLog::new(config.clone());
+            log.push_logs(collection_id, records(0..3)).await.unwrap();
+        }
+
+        // Simulate a crash halfway through appending a frame
+        let collection_dir = dir.path().join(collection_id.to_string());
+        let segment = fs::read_dir(&collection_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let intact_len = fs::metadata(&segment).unwrap().len();
+        let torn = encode_frame(4, 0, records(3..4).pop().unwrap()).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&segment)
+            .unwrap()
+            .write_all(&torn[..torn.len() / 2])
+            .unwrap();
+
+        let mut log = FileLog::new(config);
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(fs::metadata(&segment).unwrap().len(), intact_len);
+
+        log.push_logs(collection_id, records(3..5)).await.unwrap();
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_truncates_undecodable_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_config(dir.path(), 1 << 20);
+        let collection_id = CollectionUuid::new();
+        {
+            let mut log = FileLog::new(config.clone());
+            log.push_logs(collection_id, records(0..3)).await.unwrap();
+        }
+
+        // A frame whose checksum matches but whose record is not a valid proto
+        let collection_dir = dir.path().join(collection_id.to_string());
+        let segment = fs::read_dir(&collection_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let intact_len = fs::metadata(&segment).unwrap().len();
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&4i64.to_le_bytes());
+        payload.extend_from_slice(&0i64.to_le_bytes());
+        payload.extend_from_slice(&[0xff; 4]);
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        frame.extend(payload);
+        OpenOptions::new()
+            .append(true)
+            .open(&segment)
+            .unwrap()
+            .write_all(&frame)
+            .unwrap();
+
+        let mut log = FileLog::new(config);
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(fs::metadata(&segment).unwrap().len(), intact_len);
+
+        log.push_logs(collection_id, records(3..4)).await.unwrap();
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        let offsets = read.iter().map(|r| r.log_offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_interval_fsync_flushes_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FileLogConfig {
+            fsync: FsyncPolicy::Interval { interval_ms: 500 },
+            ..test_config(dir.path(), 1 << 20)
+        };
+        let mut log = FileLog::new(config);
+        let collection_id = CollectionUuid::new();
+        let is_dirty = |log: &FileLog| log.collections.lock()[&collection_id].lock().dirty;
+
+        // Pushes are acknowledged before they are synced
+        log.push_logs(collection_id, records(0..3)).await.unwrap();
+        assert!(is_dirty(&log));
+
+        // And synced without waiting for another push
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!is_dirty(&log));
+    }
+
+    #[tokio::test]
+    async fn test_retention_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = FileLog::new(test_config(dir.path(), 1));
+        let collection_id = CollectionUuid::new();
+
+        assert_eq!(log.stats(collection_id).await.unwrap(), LogStats::default());
+        log.push_logs(collection_id, records(0..5)).await.unwrap();
+        log.purge_logs(collection_id, 2).await.unwrap();
+
+        let stats = log.stats(collection_id).await.unwrap();
+        assert_eq!(
+            stats,
+            LogStats {
+                first_offset: Some(2),
+                last_offset: Some(5),
+                record_count: 4,
+            }
+        );
+        let nth = log.nth_latest_offset(collection_id, 4, 2).await.unwrap();
+        assert_eq!(nth, Some(3));
+        let nth = log.nth_latest_offset(collection_id, 100, 1).await.unwrap();
+        assert_eq!(nth, Some(5));
+        let nth = log.nth_latest_offset(collection_id, 4, 4).await.unwrap();
+        assert_eq!(nth, None);
+
+        let first = log.first_offset_since(collection_id, 0).await.unwrap();
+        assert_eq!(first, Some(2));
+        let first = log
+            .first_offset_since(collection_id, i64::MAX)
+            .await
+            .unwrap();
+        assert_eq!(first, None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = FileLog::new(test_config(dir.path(), 1 << 20));
+        let collection_id = CollectionUuid::new();
+
+        log.push_logs(collection_id, records(0..2)).await.unwrap();
+        log.delete_collection(collection_id).await.unwrap();
+        assert!(!dir.path().join(collection_id.to_string()).exists());
+        assert!(log
+            .read(collection_id, 0, -1, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::compression::{decompress_payload, LogCompression, LogCompressionError};
 use crate::config::GrpcLogConfig;
 use crate::types::CollectionInfo;
 use async_trait::async_trait;
@@ -20,6 +21,8 @@ pub enum GrpcPullLogsError {
     FailedToPullLogs(#[from] tonic::Status),
     #[error("Failed to convert proto embedding record into EmbeddingRecord")]
     ConversionError(#[from] RecordConversionError),
+    #[error("Failed to decompress record: {0}")]
+    DecompressionError(#[from] LogCompressionError),
 }
 
 impl ChromaError for GrpcPullLogsError {
@@ -27,6 +30,7 @@ impl ChromaError for GrpcPullLogsError {
         match self {
             GrpcPullLogsError::FailedToPullLogs(err) => err.code().into(),
             GrpcPullLogsError::ConversionError(_) => ErrorCodes::Internal,
+            GrpcPullLogsError::DecompressionError(err) => err.code(),
         }
     }
 }
@@ -37,6 +41,8 @@ pub enum GrpcPushLogsError {
     FailedToPushLogs(#[from] tonic::Status),
     #[error("Failed to convert records to proto")]
     ConversionError(#[from] RecordConversionError),
+    #[error("Failed to compress records: {0}")]
+    CompressionError(#[from] LogCompressionError),
 }
 
 impl ChromaError for GrpcPushLogsError {
@@ -44,6 +50,7 @@ impl ChromaError for GrpcPushLogsError {
         match self {
             GrpcPushLogsError::FailedToPushLogs(_) => ErrorCodes::Internal,
             GrpcPushLogsError::ConversionError(_) => ErrorCodes::Internal,
+            GrpcPushLogsError::CompressionError(err) => err.code(),
         }
     }
 }
@@ -84,14 +91,19 @@ impl ChromaError for GrpcUpdateCollectionLogOffsetError {
 pub struct GrpcLog {
     #[allow(clippy::type_complexity)]
     client: LogServiceClient<chroma_tracing::GrpcTraceService<tonic::transport::Channel>>,
+    compression: LogCompression,
 }
 
 impl GrpcLog {
     #[allow(clippy::type_complexity)]
     pub(crate) fn new(
         client: LogServiceClient<chroma_tracing::GrpcTraceService<tonic::transport::Channel>>,
+        compression: LogCompression,
     ) -> Self {
-        Self { client }
+        Self {
+            client,
+            compression,
+        }
     }
 }
 
@@ -133,7 +145,10 @@ impl Configurable<GrpcLogConfig> for GrpcLog {
                     .layer(chroma_tracing::GrpcTraceLayer)
                     .service(client);
 
-                return Ok(GrpcLog::new(LogServiceClient::new(channel)));
+                return Ok(GrpcLog::new(
+                    LogServiceClient::new(channel),
+                    my_config.compression,
+                ));
             }
             Err(e) => {
                 return Err(Box::new(GrpcLogError::FailedToConnect(e)));
@@ -166,7 +181,12 @@ impl GrpcLog {
             Ok(response) => {
                 let logs = response.into_inner().records;
                 let mut result = Vec::new();
-                for log_record_proto in logs {
+                for mut log_record_proto in logs {
+                    if let Some(record) = log_record_proto.record.as_mut() {
+                        if let Some(payload) = record.compressed_payload.take() {
+                            decompress_payload(&payload, record)?;
+                        }
+                    }
                     let log_record = log_record_proto.try_into();
                     match log_record {
                         Ok(log_record) => {
@@ -191,14 +211,19 @@ impl GrpcLog {
         collection_id: CollectionUuid,
         records: Vec<OperationRecord>,
     ) -> Result<(), GrpcPushLogsError> {
+        let mut records = records
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<Result<Vec<chroma_types::chroma_proto::OperationRecord>, RecordConversionError>>(
+            )?;
+        if let Some(mut compressor) = self.compression.compressor()? {
+            for record in records.iter_mut() {
+                record.compressed_payload = Some(compressor.compress(record)?);
+            }
+        }
         let request = chroma_proto::PushLogsRequest {
             collection_id: collection_id.0.to_string(),
-
-            records:
-                records.into_iter().map(|r| r.try_into()).collect::<Result<
-                    Vec<chroma_types::chroma_proto::OperationRecord>,
-                    RecordConversionError,
-                >>()?,
+            records,
         };
 
         self.client.push_logs(request).await?;
@@ -239,6 +264,7 @@ impl GrpcLog {
                         collection_id,
                         first_log_offset: collection.first_log_offset,
                         first_log_ts: collection.first_log_ts,
+                        last_log_offset: collection.last_log_offset,
                     });
                 }
                 Ok(result)
@@ -268,4 +294,21 @@ impl GrpcLog {
             Err(e) => Err(GrpcUpdateCollectionLogOffsetError::FailedToUpdateCollectionLogOffset(e)),
         }
     }
+
+    /// Records that were purged from the log after compaction are not considered.
+    pub(super) async fn first_offset_since(
+        &mut self,
+        collection_id: CollectionUuid,
+        timestamp_ns: i64,
+    ) -> Result<Option<i64>, GrpcPullLogsError> {
+        let response = self
+            .client
+            .get_first_offset_since(chroma_proto::GetFirstOffsetSinceRequest {
+                // NOTE(rescrv):  Use the untyped string representation of the collection ID.
+                collection_id: collection_id.0.to_string(),
+                timestamp: timestamp_ns,
+            })
+            .await?;
+        Ok(response.into_inner().log_offset)
+    }
 }
@@ -111,6 +111,7 @@ impl InMemoryLog {
                 collection_id: *collection_id,
                 first_log_offset: logs[0].log_offset,
                 first_log_ts: logs[0].log_ts,
+                last_log_offset: logs[logs.len() - 1].log_offset,
             });
         }
         collections
@@ -123,6 +124,18 @@ impl InMemoryLog {
     ) {
         self.offsets.insert(collection_id, new_offset);
     }
+
+    pub(super) async fn first_offset_since(
+        &mut self,
+        collection_id: CollectionUuid,
+        timestamp: i64,
+    ) -> Option<i64> {
+        self.collection_to_log
+            .get(&collection_id)?
+            .iter()
+            .find(|log| log.log_ts >= timestamp)
+            .map(|log| log.log_offset)
+    }
 }
 
 impl Default for InMemoryLog {
@@ -1,4 +1,6 @@
+pub mod compression;
 pub mod config;
+pub mod file_log;
 pub mod grpc_log;
 pub mod in_memory_log;
 pub mod local_compaction_manager;
@@ -32,6 +34,9 @@ impl Configurable<LogConfig> for Log {
             LogConfig::Sqlite(sqlite_log_config) => Self::Sqlite(
                 sqlite_log::SqliteLog::try_from_config(sqlite_log_config, registry).await?,
             ),
+            LogConfig::File(file_log_config) => {
+                Self::File(file_log::FileLog::try_from_config(file_log_config, registry).await?)
+            }
         };
 
         registry.register(res.clone());
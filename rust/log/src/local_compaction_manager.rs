@@ -1,5 +1,6 @@
 use std::fmt::{Debug, Formatter};
 
+use crate::config::{LocalGarbageCollectionConfig, LogRetentionConfig};
 use crate::Log;
 use async_trait::async_trait;
 use chroma_config::registry::{Injectable, Registry};
@@ -14,12 +15,22 @@ use chroma_sqlite::db::SqliteDb;
 use chroma_sysdb::SysDb;
 use chroma_system::Handler;
 use chroma_system::{Component, ComponentContext};
-use chroma_types::{Chunk, CollectionUuid, GetCollectionWithSegmentsError, LogRecord};
+use chroma_types::{
+    Chunk, CollectionUuid, GetCollectionWithSegmentsError, LogRecord, LogRetentionPolicy,
+    LogRetentionStatus,
+};
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use tracing::span;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LocalCompactionManagerConfig {}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalCompactionManagerConfig {
+    #[serde(default)]
+    pub retention: LogRetentionConfig,
+    #[serde(default)]
+    pub garbage_collection: LocalGarbageCollectionConfig,
+}
 
 #[derive(Clone)]
 pub struct LocalCompactionManager {
@@ -27,6 +38,8 @@ pub struct LocalCompactionManager {
     sqlite_db: SqliteDb,
     hnsw_segment_manager: LocalSegmentManager,
     sysdb: SysDb,
+    retention: LogRetentionConfig,
+    garbage_collection: LocalGarbageCollectionConfig,
 }
 
 impl Injectable for LocalCompactionManager {}
@@ -34,7 +47,7 @@ impl Injectable for LocalCompactionManager {}
 #[async_trait]
 impl Configurable<LocalCompactionManagerConfig> for LocalCompactionManager {
     async fn try_from_config(
-        _config: &LocalCompactionManagerConfig,
+        config: &LocalCompactionManagerConfig,
         registry: &Registry,
     ) -> Result<Self, Box<dyn ChromaError>> {
         let log = registry.get::<Log>().map_err(|e| e.boxed())?;
@@ -48,6 +61,8 @@ impl Configurable<LocalCompactionManagerConfig> for LocalCompactionManager {
             sqlite_db,
             hnsw_segment_manager,
             sysdb,
+            retention: config.retention.clone(),
+            garbage_collection: config.garbage_collection.clone(),
         };
         registry.register(res.clone());
         Ok(res)
@@ -65,7 +80,15 @@ impl Component for LocalCompactionManager {
         1000
     }
 
-    async fn start(&mut self, _: &ComponentContext<Self>) -> () {}
+    async fn start(&mut self, ctx: &ComponentContext<Self>) -> () {
+        if self.garbage_collection.enabled {
+            // Collect right away to clean up after previous runs, then on the interval
+            ctx.scheduler
+                .schedule(GarbageCollectMessage {}, Duration::ZERO, ctx, || {
+                    Some(span!(parent: None, tracing::Level::INFO, "Local garbage collection"))
+                });
+        }
+    }
 }
 
 impl Debug for LocalCompactionManager {
@@ -122,6 +145,85 @@ pub struct BackfillMessage {
     pub collection_id: CollectionUuid,
 }
 
+#[derive(Debug)]
+struct GarbageCollectMessage {}
+
+#[derive(Clone, Debug)]
+pub struct LogRetentionStatusMessage {
+    pub collection_id: CollectionUuid,
+}
+
+impl LocalCompactionManager {
+    /// The last log offset applied to both segments of a collection, or `None` if nothing has
+    /// been written to it yet.
+    async fn compacted_offset(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<Option<u64>, CompactionManagerError> {
+        let collection_segments = self
+            .sysdb
+            .get_collection_with_segments(collection_id)
+            .await?;
+        // If dimension is None, that means nothing has been written yet.
+        let dim = match collection_segments.collection.dimension {
+            Some(dim) => dim,
+            None => return Ok(None),
+        };
+        let metadata_reader = SqliteMetadataReader::new(self.sqlite_db.clone());
+        let mt_max_seq_id = metadata_reader
+            .current_max_seq_id(&collection_segments.metadata_segment.id)
+            .await?;
+        let hnsw_reader = self
+            .hnsw_segment_manager
+            .get_hnsw_reader(&collection_segments.vector_segment, dim as usize)
+            .await;
+        let hnsw_max_seq_id = match hnsw_reader {
+            Ok(reader) => {
+                reader
+                    .current_max_seq_id(&collection_segments.vector_segment.id)
+                    .await?
+            }
+            Err(LocalSegmentManagerError::LocalHnswSegmentReaderError(
+                LocalHnswSegmentReaderError::UninitializedSegment,
+            )) => 0,
+            Err(e) => return Err(CompactionManagerError::HnswReaderConstructionError(e)),
+        };
+        Ok(Some(mt_max_seq_id.min(hnsw_max_seq_id)))
+    }
+
+    /// The offset below which records can be purged under the retention policy of the
+    /// collection. It is never beyond the compacted offset.
+    async fn purge_offset(
+        &mut self,
+        collection_id: CollectionUuid,
+        compacted_offset: u64,
+    ) -> Result<u64, CompactionManagerError> {
+        let offset = match self.retention.policy(collection_id).clone() {
+            LogRetentionPolicy::Compacted | LogRetentionPolicy::Records(0) => {
+                return Ok(compacted_offset)
+            }
+            LogRetentionPolicy::Hours(hours) => {
+                let cutoff = SystemTime::now()
+                    .checked_sub(Duration::from_secs(hours.saturating_mul(3600)))
+                    .and_then(|cutoff| cutoff.duration_since(UNIX_EPOCH).ok())
+                    .unwrap_or_default();
+                self.log
+                    .first_offset_since(collection_id, cutoff.as_nanos() as i64)
+                    .await
+                    .map_err(|_| CompactionManagerError::PullLogsFailure)?
+                    .unwrap_or(compacted_offset as i64)
+            }
+            LogRetentionPolicy::Records(count) => self
+                .log
+                .nth_latest_offset(collection_id, compacted_offset as i64, count)
+                .await
+                .map_err(|_| CompactionManagerError::PullLogsFailure)?
+                .unwrap_or(0),
+        };
+        Ok((offset.max(0) as u64).min(compacted_offset))
+    }
+}
+
 #[async_trait]
 impl Handler<BackfillMessage> for LocalCompactionManager {
     type Result = Result<(), CompactionManagerError>;
@@ -227,38 +329,100 @@ impl Handler<PurgeLogsMessage> for LocalCompactionManager {
         message: PurgeLogsMessage,
         _: &ComponentContext<LocalCompactionManager>,
     ) -> Self::Result {
-        let collection_segments = self
-            .sysdb
-            .get_collection_with_segments(message.collection_id)
-            .await?;
-        // If dimension is None, that means nothing has been written yet.
-        let dim = match collection_segments.collection.dimension {
-            Some(dim) => dim,
+        let compacted_offset = match self.compacted_offset(message.collection_id).await? {
+            Some(offset) => offset,
             None => return Ok(()),
         };
-        let metadata_reader = SqliteMetadataReader::new(self.sqlite_db.clone());
-        let mt_max_seq_id = metadata_reader
-            .current_max_seq_id(&collection_segments.metadata_segment.id)
+        let purge_offset = self
+            .purge_offset(message.collection_id, compacted_offset)
             .await?;
-        let hnsw_reader = self
-            .hnsw_segment_manager
-            .get_hnsw_reader(&collection_segments.vector_segment, dim as usize)
-            .await;
-        let hnsw_max_seq_id = match hnsw_reader {
-            Ok(reader) => {
-                reader
-                    .current_max_seq_id(&collection_segments.vector_segment.id)
-                    .await?
-            }
-            Err(LocalSegmentManagerError::LocalHnswSegmentReaderError(
-                LocalHnswSegmentReaderError::UninitializedSegment,
-            )) => 0,
-            Err(e) => return Err(CompactionManagerError::HnswReaderConstructionError(e)),
-        };
-        let max_seq_id = mt_max_seq_id.min(hnsw_max_seq_id);
         self.log
-            .purge_logs(message.collection_id, max_seq_id)
+            .purge_logs(message.collection_id, purge_offset)
             .await
             .map_err(|_| CompactionManagerError::PurgeLogsFailure)
     }
 }
+
+#[async_trait]
+impl Handler<LogRetentionStatusMessage> for LocalCompactionManager {
+    type Result = Result<LogRetentionStatus, CompactionManagerError>;
+
+    async fn handle(
+        &mut self,
+        message: LogRetentionStatusMessage,
+        _: &ComponentContext<LocalCompactionManager>,
+    ) -> Self::Result {
+        let compacted_offset = self
+            .compacted_offset(message.collection_id)
+            .await?
+            .unwrap_or_default();
+        let purge_offset = self
+            .purge_offset(message.collection_id, compacted_offset)
+            .await?;
+        let stats = self
+            .log
+            .stats(message.collection_id)
+            .await
+            .map_err(|_| CompactionManagerError::PullLogsFailure)?;
+        Ok(LogRetentionStatus {
+            collection_id: message.collection_id,
+            policy: self.retention.policy(message.collection_id).clone(),
+            compacted_offset,
+            purge_offset,
+            first_offset: stats.first_offset,
+            last_offset: stats.last_offset,
+            record_count: stats.record_count,
+        })
+    }
+}
+
+#[async_trait]
+impl Handler<GarbageCollectMessage> for LocalCompactionManager {
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        _message: GarbageCollectMessage,
+        ctx: &ComponentContext<LocalCompactionManager>,
+    ) -> Self::Result {
+        // Expired tombstones are purged first so that their segments and logs are collected below
+        if self.sysdb.soft_deletes_collections() {
+            match self.sysdb.purge_deleted_collections().await {
+                Ok(purged) => {
+                    if !purged.is_empty() {
+                        tracing::info!("Purged {} soft-deleted collections", purged.len());
+                    }
+                    for collection_id in purged {
+                        if let Err(e) = self.log.delete_collection(collection_id).await {
+                            tracing::error!(
+                                "Failed to delete log of purged collection {}: {}",
+                                collection_id,
+                                e
+                            );
+                        }
+                    }
+                }
+                Err(e) => tracing::error!("Failed to purge soft-deleted collections: {}", e),
+            }
+        }
+        match self.hnsw_segment_manager.remove_orphaned_segments().await {
+            Ok(removed) if !removed.is_empty() => {
+                tracing::info!("Removed {} orphaned hnsw segments", removed.len())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to remove orphaned hnsw segments: {}", e),
+        }
+        match self.log.purge_orphaned_logs().await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!("Purged {} orphaned log records", purged),
+            Err(e) => tracing::error!("Failed to purge orphaned log records: {}", e),
+        }
+
+        ctx.scheduler.schedule(
+            GarbageCollectMessage {},
+            Duration::from_secs(self.garbage_collection.interval_secs),
+            ctx,
+            || Some(span!(parent: None, tracing::Level::INFO, "Local garbage collection")),
+        );
+    }
+}
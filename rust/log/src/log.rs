@@ -1,7 +1,8 @@
+use crate::file_log::FileLog;
 use crate::grpc_log::GrpcLog;
 use crate::in_memory_log::InMemoryLog;
 use crate::sqlite_log::SqliteLog;
-use crate::types::CollectionInfo;
+use crate::types::{CollectionInfo, LogStats};
 use chroma_error::ChromaError;
 use chroma_types::{CollectionUuid, LogRecord, OperationRecord};
 use std::fmt::Debug;
@@ -11,9 +12,9 @@ pub struct CollectionRecord {
     pub collection_id: CollectionUuid,
     pub tenant_id: String,
     pub last_compaction_time: i64,
-    #[allow(dead_code)]
     pub first_record_time: i64,
     pub offset: i64,
+    pub last_log_offset: i64,
     pub collection_version: i32,
 }
 
@@ -21,6 +22,7 @@ pub struct CollectionRecord {
 pub enum Log {
     Sqlite(SqliteLog),
     Grpc(GrpcLog),
+    File(FileLog),
     #[allow(dead_code)]
     InMemory(InMemoryLog),
 }
@@ -42,12 +44,34 @@ impl Log {
                 .read(collection_id, offset, batch_size, end_timestamp)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .read(collection_id, offset, batch_size, end_timestamp)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
             Log::InMemory(log) => Ok(log
                 .read(collection_id, offset, batch_size, end_timestamp)
                 .await),
         }
     }
 
+    /// Reads up to `batch_size` records with a log offset strictly greater than `offset`.
+    /// Unlike `read`, the offset means the same for every log: the sqlite log reads past the
+    /// given offset, as does the file log, while the other logs read from it.
+    pub async fn read_after(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        batch_size: i32,
+    ) -> Result<Vec<LogRecord>, Box<dyn ChromaError>> {
+        let start = match self {
+            Log::Sqlite(_) | Log::File(_) => offset,
+            Log::Grpc(_) | Log::InMemory(_) => offset + 1,
+        };
+        let mut records = self.read(collection_id, start, batch_size, None).await?;
+        records.retain(|record| record.log_offset > offset);
+        Ok(records)
+    }
+
     pub async fn push_logs(
         &mut self,
         collection_id: CollectionUuid,
@@ -62,6 +86,10 @@ impl Log {
                 .push_logs(collection_id, records)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .push_logs(collection_id, records)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
             Log::InMemory(_) => unimplemented!(),
         }
     }
@@ -79,6 +107,7 @@ impl Log {
                 .get_collections_with_new_data(min_compaction_size)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(_) => unimplemented!(),
             Log::InMemory(log) => Ok(log.get_collections_with_new_data(min_compaction_size).await),
         }
     }
@@ -97,6 +126,7 @@ impl Log {
                 .update_collection_log_offset(collection_id, new_offset)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(_) => unimplemented!(),
             Log::InMemory(log) => {
                 log.update_collection_log_offset(collection_id, new_offset)
                     .await;
@@ -105,7 +135,7 @@ impl Log {
         }
     }
 
-    // Only supported in sqlite. Distributed has a different workflow.
+    // Only supported in single-node logs. Distributed has a different workflow.
     pub async fn purge_logs(
         &mut self,
         collection_id: CollectionUuid,
@@ -116,11 +146,120 @@ impl Log {
                 .purge_logs(collection_id, seq_id)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .purge_logs(collection_id, seq_id)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
             Log::Grpc(_) => unimplemented!(),
             Log::InMemory(_) => unimplemented!(),
         }
     }
 
+    // Only supported in single-node logs. Returns the number of deleted records.
+    pub async fn purge_orphaned_logs(&mut self) -> Result<u64, Box<dyn ChromaError>> {
+        match self {
+            Log::Sqlite(log) => log
+                .purge_orphaned_logs()
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            // The file log removes the logs of a collection when the collection is deleted
+            Log::File(_) => Ok(0),
+            Log::Grpc(_) => unimplemented!(),
+            Log::InMemory(_) => unimplemented!(),
+        }
+    }
+
+    // Only supported in single-node logs, to enforce their retention policies.
+    pub async fn stats(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<LogStats, Box<dyn ChromaError>> {
+        match self {
+            Log::Sqlite(log) => log
+                .stats(collection_id)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .stats(collection_id)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::Grpc(_) => unimplemented!(),
+            Log::InMemory(_) => unimplemented!(),
+        }
+    }
+
+    /// Returns the first offset that can still be read if records after `offset` have been
+    /// purged, in which case a reader resuming after `offset` would silently skip them.
+    /// `log_position` is the compaction offset of the collection, since only compacted records
+    /// are purged. Only single-node logs purge records; the others never report a gap.
+    pub async fn purged_after(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        log_position: i64,
+    ) -> Result<Option<i64>, Box<dyn ChromaError>> {
+        if let Log::Grpc(_) | Log::InMemory(_) = self {
+            return Ok(None);
+        }
+        // Offsets start at 1, so nothing after `offset` can have been purged unless it is
+        // behind the compaction offset
+        if offset.max(0) >= log_position {
+            return Ok(None);
+        }
+        // Once every record is purged, the next one to be written is the first to read
+        let first_available = self
+            .stats(collection_id)
+            .await?
+            .first_offset
+            .unwrap_or(log_position + 1);
+        Ok((offset + 1 < first_available).then_some(first_available))
+    }
+
+    /// The offset of the `n`th most recent record at or before `offset`, counting from 1.
+    pub async fn nth_latest_offset(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        n: u64,
+    ) -> Result<Option<i64>, Box<dyn ChromaError>> {
+        match self {
+            Log::Sqlite(log) => log
+                .nth_latest_offset(collection_id, offset, n)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .nth_latest_offset(collection_id, offset, n)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::Grpc(_) => unimplemented!(),
+            Log::InMemory(_) => unimplemented!(),
+        }
+    }
+
+    /// The offset of the first record written at or after `timestamp_ns`.
+    /// The distributed log does not consider records that were purged after compaction.
+    pub async fn first_offset_since(
+        &mut self,
+        collection_id: CollectionUuid,
+        timestamp_ns: i64,
+    ) -> Result<Option<i64>, Box<dyn ChromaError>> {
+        match self {
+            Log::Sqlite(log) => log
+                .first_offset_since(collection_id, timestamp_ns)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::File(log) => log
+                .first_offset_since(collection_id, timestamp_ns)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::Grpc(log) => log
+                .first_offset_since(collection_id, timestamp_ns)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::InMemory(log) => Ok(log.first_offset_since(collection_id, timestamp_ns).await),
+        }
+    }
+
     pub async fn get_max_batch_size(&mut self) -> Result<u32, Box<dyn ChromaError>> {
         match self {
             Log::Sqlite(log) => log
@@ -128,7 +267,34 @@ impl Log {
                 .await
                 .map_err(|err| Box::new(err) as Box<dyn ChromaError>),
             Log::Grpc(_) => Ok(100),
+            Log::File(log) => Ok(log.get_max_batch_size()),
             Log::InMemory(_) => todo!(),
         }
     }
+
+    /// Drops the records of a deleted collection. The sqlite log is cleaned up along with the
+    /// sysdb, and the distributed log by garbage collection.
+    pub async fn delete_collection(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<(), Box<dyn ChromaError>> {
+        match self {
+            Log::File(log) => log
+                .delete_collection(collection_id)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::Sqlite(_) | Log::Grpc(_) | Log::InMemory(_) => Ok(()),
+        }
+    }
+
+    /// Drops the records of every collection. The sqlite log is reset along with the sysdb.
+    pub async fn reset(&mut self) -> Result<(), Box<dyn ChromaError>> {
+        match self {
+            Log::File(log) => log
+                .reset()
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn ChromaError>),
+            Log::Sqlite(_) | Log::Grpc(_) | Log::InMemory(_) => Ok(()),
+        }
+    }
 }
@@ -1,6 +1,8 @@
 use crate::{
-    config::SqliteLogConfig, BackfillMessage, CollectionInfo, CompactionManagerError,
-    LocalCompactionManager, PurgeLogsMessage,
+    compression::{decompress_payload, LogCompression, LogCompressionError},
+    config::SqliteLogConfig,
+    BackfillMessage, CollectionInfo, CompactionManagerError, LocalCompactionManager, LogStats,
+    PurgeLogsMessage,
 };
 use async_trait::async_trait;
 use chroma_config::Configurable;
@@ -8,8 +10,8 @@ use chroma_error::{ChromaError, ErrorCodes, WrappedSqlxError};
 use chroma_sqlite::{db::SqliteDb, helpers::get_embeddings_queue_topic_name};
 use chroma_system::{ChannelError, ComponentHandle, RequestError};
 use chroma_types::{
-    CollectionUuid, LogRecord, Operation, OperationRecord, ScalarEncoding,
-    ScalarEncodingConversionError, UpdateMetadata, UpdateMetadataValue,
+    chroma_proto, CollectionUuid, LogRecord, Operation, OperationRecord, RecordConversionError,
+    ScalarEncoding, ScalarEncodingConversionError, UpdateMetadata, UpdateMetadataValue,
 };
 use futures::TryStreamExt;
 use sqlx::{QueryBuilder, Row};
@@ -26,6 +28,10 @@ pub enum SqlitePullLogsError {
     InvalidEmbedding(bytemuck::PodCastError),
     #[error("Failed to parse metadata: {0}")]
     InvalidMetadata(#[from] serde_json::Error),
+    #[error("Failed to decompress record: {0}")]
+    DecompressionError(#[from] LogCompressionError),
+    #[error("Failed to parse compressed record: {0}")]
+    InvalidRecord(#[from] RecordConversionError),
 }
 
 impl ChromaError for SqlitePullLogsError {
@@ -35,6 +41,8 @@ impl ChromaError for SqlitePullLogsError {
             SqlitePullLogsError::InvalidEncoding(_) => ErrorCodes::InvalidArgument,
             SqlitePullLogsError::InvalidEmbedding(_) => ErrorCodes::InvalidArgument,
             SqlitePullLogsError::InvalidMetadata(_) => ErrorCodes::InvalidArgument,
+            SqlitePullLogsError::DecompressionError(err) => err.code(),
+            SqlitePullLogsError::InvalidRecord(err) => err.code(),
         }
     }
 }
@@ -45,6 +53,10 @@ pub enum SqlitePushLogsError {
     CompactionError(#[from] CompactionManagerError),
     #[error("Error setting compactor handle")]
     CompactorHandleSetError,
+    #[error("Failed to compress record: {0}")]
+    CompressionError(#[from] LogCompressionError),
+    #[error("Failed to convert record: {0}")]
+    InvalidRecord(#[from] RecordConversionError),
     #[error("Failed to serialize metadata: {0}")]
     InvalidMetadata(#[from] serde_json::Error),
     #[error("Error sending message to compactor")]
@@ -60,6 +72,8 @@ impl ChromaError for SqlitePushLogsError {
         match self {
             SqlitePushLogsError::CompactionError(e) => e.code(),
             SqlitePushLogsError::CompactorHandleSetError => ErrorCodes::FailedPrecondition,
+            SqlitePushLogsError::CompressionError(e) => e.code(),
+            SqlitePushLogsError::InvalidRecord(e) => e.code(),
             SqlitePushLogsError::InvalidMetadata(_) => ErrorCodes::Internal,
             SqlitePushLogsError::MessageSendingError(e) => e.code(),
             SqlitePushLogsError::QueryError(err) => err.code(),
@@ -71,6 +85,8 @@ impl ChromaError for SqlitePushLogsError {
 const DEFAULT_VAR_OPT: u32 = 32766;
 const PRAGMA_MAX_VAR_OPT: &str = "MAX_VARIABLE_NUMBER";
 const VARIABLE_PER_RECORD: u32 = 6;
+// Marks rows whose vector column holds a compressed record payload instead of a raw embedding
+const COMPRESSED_ENCODING: &str = "ZSTD";
 
 #[derive(Error, Debug)]
 pub enum SqliteGetCollectionsWithNewDataError {
@@ -108,15 +124,31 @@ pub struct SqliteLog {
     db: SqliteDb,
     tenant_id: String,
     topic_namespace: String,
+    compression: LogCompression,
     compactor_handle: OnceLock<ComponentHandle<LocalCompactionManager>>,
 }
 
+/// The columns of a row of the embeddings queue, other than the topic
+struct QueueRow {
+    id: String,
+    operation: Operation,
+    vector: Option<Vec<u8>>,
+    encoding: Option<String>,
+    metadata: Option<String>,
+}
+
 impl SqliteLog {
-    pub fn new(db: SqliteDb, tenant_id: String, topic_namespace: String) -> Self {
+    pub fn new(
+        db: SqliteDb,
+        tenant_id: String,
+        topic_namespace: String,
+        compression: LogCompression,
+    ) -> Self {
         Self {
             db,
             tenant_id,
             topic_namespace,
+            compression,
             compactor_handle: OnceLock::new(),
         }
     }
@@ -194,10 +226,19 @@ impl SqliteLog {
             let log_offset: i64 = row.get("seq_id");
             let id: String = row.get("id");
             let embedding_bytes = row.get::<Option<&[u8]>, _>("vector");
-            let encoding = row
-                .get::<Option<&str>, _>("encoding")
-                .map(ScalarEncoding::try_from)
-                .transpose()?;
+            let encoding = row.get::<Option<&str>, _>("encoding");
+
+            if encoding == Some(COMPRESSED_ENCODING) {
+                let mut proto = chroma_proto::OperationRecord::default();
+                decompress_payload(embedding_bytes.unwrap_or_default(), &mut proto)?;
+                let mut record = OperationRecord::try_from(proto)?;
+                record.id = id;
+                record.operation = operation_from_code(row.get("operation"));
+                records.push(LogRecord { log_offset, record });
+                continue;
+            }
+
+            let encoding = encoding.map(ScalarEncoding::try_from).transpose()?;
             let metadata_str = row.get::<Option<&str>, _>("metadata");
 
             // Parse embedding
@@ -270,12 +311,11 @@ impl SqliteLog {
         let topic =
             get_embeddings_queue_topic_name(&self.tenant_id, &self.topic_namespace, collection_id);
 
-        let records_and_serialized_metadatas = records
+        let mut compressor = self.compression.compressor()?;
+        let rows = records
             .into_iter()
             .map(|mut record| {
-                let mut empty_metadata = UpdateMetadata::new();
-
-                let metadata = record.metadata.as_mut().unwrap_or(&mut empty_metadata);
+                let mut metadata = record.metadata.take().unwrap_or_default();
                 if let Some(ref document) = record.document {
                     metadata.insert(
                         "chroma:document".to_string(),
@@ -283,29 +323,45 @@ impl SqliteLog {
                     );
                 }
 
-                let serialized = serde_json::to_string(&metadata)?;
-                Ok::<_, SqlitePushLogsError>((record, serialized))
+                let row = match compressor.as_mut() {
+                    Some(compressor) => {
+                        let id = record.id.clone();
+                        let operation = record.operation;
+                        record.metadata = Some(metadata);
+                        let mut proto = chroma_proto::OperationRecord::try_from(record)?;
+                        QueueRow {
+                            id,
+                            operation,
+                            vector: Some(compressor.compress(&mut proto)?),
+                            encoding: Some(COMPRESSED_ENCODING.to_string()),
+                            metadata: None,
+                        }
+                    }
+                    None => QueueRow {
+                        id: record.id,
+                        operation: record.operation,
+                        vector: record
+                            .embedding
+                            .map(|e| bytemuck::cast_slice(e.as_slice()).to_vec()),
+                        encoding: record.encoding.map(String::from),
+                        metadata: Some(serde_json::to_string(&metadata)?),
+                    },
+                };
+                Ok::<_, SqlitePushLogsError>(row)
             })
-            .collect::<Result<Vec<(OperationRecord, String)>, SqlitePushLogsError>>()?;
+            .collect::<Result<Vec<QueueRow>, SqlitePushLogsError>>()?;
 
         let mut query_builder = QueryBuilder::new(
             "INSERT INTO embeddings_queue (topic, id, operation, vector, encoding, metadata) ",
         );
-        query_builder.push_values(
-            records_and_serialized_metadatas,
-            |mut builder, (record, serialized_metadata)| {
-                builder.push_bind(&topic);
-                builder.push_bind(record.id);
-                builder.push_bind(operation_to_code(record.operation));
-                builder.push_bind::<Option<Vec<u8>>>(
-                    record
-                        .embedding
-                        .map(|e| bytemuck::cast_slice(e.as_slice()).to_vec()),
-                );
-                builder.push_bind(record.encoding.map(String::from));
-                builder.push_bind::<String>(serialized_metadata);
-            },
-        );
+        query_builder.push_values(rows, |mut builder, row| {
+            builder.push_bind(&topic);
+            builder.push_bind(row.id);
+            builder.push_bind(operation_to_code(row.operation));
+            builder.push_bind::<Option<Vec<u8>>>(row.vector);
+            builder.push_bind(row.encoding);
+            builder.push_bind::<Option<String>>(row.metadata);
+        });
         let query = query_builder.build();
         query
             .execute(self.db.get_conn())
@@ -331,7 +387,8 @@ impl SqliteLog {
             SELECT
                 collections.id AS collection_id,
                 MIN(COALESCE(CAST(max_seq_id.seq_id AS INTEGER), 0)) AS first_log_offset,
-                CAST(strftime('%s', MIN(created_at)) AS INTEGER) * 1000000000 AS first_log_ts
+                CAST(strftime('%s', MIN(created_at)) AS INTEGER) * 1000000000 AS first_log_ts,
+                MAX(embeddings_queue.seq_id) AS last_log_offset
             FROM collections
             INNER JOIN segments           ON segments.collection    = collections.id
             INNER JOIN embeddings_queue   ON embeddings_queue.topic = CONCAT('persistent://', ?, '/', ?, '/', collections.id)
@@ -355,6 +412,7 @@ impl SqliteLog {
                 collection_id: CollectionUuid::from_str(row.get::<&str, _>("collection_id"))?,
                 first_log_offset: row.get("first_log_offset"),
                 first_log_ts: row.get("first_log_ts"),
+                last_log_offset: row.get("last_log_offset"),
             });
         }
 
@@ -402,6 +460,121 @@ impl SqliteLog {
         Ok(())
     }
 
+    /// Deletes the records and log offsets left behind by collections and segments that no
+    /// longer exist in the sysdb. Returns the number of deleted records.
+    pub async fn purge_orphaned_logs(&mut self) -> Result<u64, SqlitePurgeLogsError> {
+        let topic_prefix = format!("persistent://{}/{}/", self.tenant_id, self.topic_namespace);
+        let mut tx = self.db.get_conn().begin().await.map_err(WrappedSqlxError)?;
+        let deleted_records = sqlx::query(
+            r#"
+            DELETE FROM embeddings_queue
+            WHERE substr(topic, 1, length(?)) = ?
+            AND topic NOT IN (SELECT CONCAT(?, id) FROM collections)
+            "#,
+        )
+        .bind(&topic_prefix)
+        .bind(&topic_prefix)
+        .bind(&topic_prefix)
+        .execute(&mut *tx)
+        .await
+        .map_err(WrappedSqlxError)?
+        .rows_affected();
+        sqlx::query("DELETE FROM max_seq_id WHERE segment_id NOT IN (SELECT id FROM segments)")
+            .execute(&mut *tx)
+            .await
+            .map_err(WrappedSqlxError)?;
+        tx.commit().await.map_err(WrappedSqlxError)?;
+
+        Ok(deleted_records)
+    }
+
+    pub(super) async fn stats(
+        &mut self,
+        collection_id: CollectionUuid,
+    ) -> Result<LogStats, SqlitePullLogsError> {
+        let topic =
+            get_embeddings_queue_topic_name(&self.tenant_id, &self.topic_namespace, collection_id);
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+              MIN(seq_id) AS first_offset,
+              MAX(seq_id) AS last_offset,
+              COUNT(*) AS record_count
+            FROM embeddings_queue
+            WHERE topic = ?
+            "#,
+        )
+        .bind(topic)
+        .fetch_one(self.db.get_conn())
+        .await
+        .map_err(WrappedSqlxError)?;
+
+        Ok(LogStats {
+            first_offset: row.get("first_offset"),
+            last_offset: row.get("last_offset"),
+            record_count: row.get::<i64, _>("record_count") as u64,
+        })
+    }
+
+    pub(super) async fn nth_latest_offset(
+        &mut self,
+        collection_id: CollectionUuid,
+        offset: i64,
+        n: u64,
+    ) -> Result<Option<i64>, SqlitePullLogsError> {
+        if n == 0 {
+            return Ok(None);
+        }
+        let topic =
+            get_embeddings_queue_topic_name(&self.tenant_id, &self.topic_namespace, collection_id);
+
+        // Offsets are shared by every collection, so they have to be counted
+        let row = sqlx::query(
+            r#"
+            SELECT seq_id
+            FROM embeddings_queue
+            WHERE topic = ?
+            AND seq_id <= ?
+            ORDER BY seq_id DESC
+            LIMIT 1 OFFSET ?
+            "#,
+        )
+        .bind(topic)
+        .bind(offset)
+        .bind((n - 1) as i64) // (SQLite doesn't support u64)
+        .fetch_optional(self.db.get_conn())
+        .await
+        .map_err(WrappedSqlxError)?;
+
+        Ok(row.map(|row| row.get("seq_id")))
+    }
+
+    pub(super) async fn first_offset_since(
+        &mut self,
+        collection_id: CollectionUuid,
+        timestamp_ns: i64,
+    ) -> Result<Option<i64>, SqlitePullLogsError> {
+        let topic =
+            get_embeddings_queue_topic_name(&self.tenant_id, &self.topic_namespace, collection_id);
+
+        let row = sqlx::query(
+            r#"
+            SELECT MIN(seq_id) AS seq_id
+            FROM embeddings_queue
+            WHERE topic = ?
+            AND CAST(strftime('%s', created_at) AS INTEGER) >= (? / 1000000000)
+            "#,
+        )
+        .bind(topic)
+        .bind(timestamp_ns)
+        .fetch_one(self.db.get_conn())
+        .await
+        .map_err(WrappedSqlxError)?;
+
+        Ok(row.get("seq_id"))
+    }
+
     pub async fn get_max_batch_size(&self) -> Result<u32, SqliteGetMaxBatchSizeError> {
         let opt_strs = sqlx::query("PRAGMA compile_options")
             .fetch_all(self.db.get_conn())
@@ -487,6 +660,7 @@ impl Configurable<SqliteLogConfig> for SqliteLog {
             sqlite_db,
             config.tenant_id.clone(),
             config.topic_namespace.clone(),
+            config.compression,
         ))
     }
 }
@@ -513,7 +687,12 @@ mod tests {
         .await
         .unwrap();
 
-        SqliteLog::new(db, "default".to_string(), "default".to_string())
+        SqliteLog::new(
+            db,
+            "default".to_string(),
+            "default".to_string(),
+            LogCompression::None,
+        )
     }
 
     #[tokio::test]
@@ -569,6 +748,259 @@ mod tests {
         assert_eq!(collections_with_data.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_read_after() {
+        let log = setup_sqlite_log().await;
+        let mut log = crate::Log::Sqlite(log);
+        let collection_id = CollectionUuid::new();
+
+        let operations = (0..3)
+            .map(|i| OperationRecord {
+                id: format!("id{}", i),
+                embedding: Some(vec![1.0, 2.0, 3.0]),
+                encoding: Some(ScalarEncoding::FLOAT32),
+                metadata: None,
+                document: None,
+                operation: Operation::Upsert,
+            })
+            .collect::<Vec<_>>();
+        log.push_logs(collection_id, operations).await.unwrap();
+
+        let all = log.read_after(collection_id, -1, 10).await.unwrap();
+        assert_eq!(all.len(), 3);
+
+        let rest = log
+            .read_after(collection_id, all[0].log_offset, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            rest.iter().map(|r| r.log_offset).collect::<Vec<_>>(),
+            all[1..].iter().map(|r| r.log_offset).collect::<Vec<_>>()
+        );
+        assert_eq!(rest[0].record.id, "id1");
+
+        let none = log
+            .read_after(collection_id, all[2].log_offset, 10)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purged_after() {
+        let log = setup_sqlite_log().await;
+        let mut log = crate::Log::Sqlite(log);
+        let collection_id = CollectionUuid::new();
+
+        // Nothing is purged before the collection is compacted
+        assert_eq!(log.purged_after(collection_id, -1, 0).await.unwrap(), None);
+
+        let operations = (0..5)
+            .map(|i| OperationRecord {
+                id: format!("id{}", i),
+                embedding: Some(vec![1.0, 2.0, 3.0]),
+                encoding: Some(ScalarEncoding::FLOAT32),
+                metadata: None,
+                document: None,
+                operation: Operation::Upsert,
+            })
+            .collect::<Vec<_>>();
+        log.push_logs(collection_id, operations).await.unwrap();
+        let offsets = log
+            .read_after(collection_id, -1, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.log_offset)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log.purged_after(collection_id, offsets[0], offsets[2])
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Compact and purge the first three records
+        log.purge_logs(collection_id, offsets[3] as u64)
+            .await
+            .unwrap();
+        // A reader that had only seen the first record would miss the next two
+        assert_eq!(
+            log.purged_after(collection_id, offsets[0], offsets[2])
+                .await
+                .unwrap(),
+            Some(offsets[3])
+        );
+        // One that has seen all purged records can resume
+        assert_eq!(
+            log.purged_after(collection_id, offsets[2], offsets[2])
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Once every record is purged, only a reader that has seen all of them can resume
+        log.purge_logs(collection_id, offsets[4] as u64 + 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            log.purged_after(collection_id, offsets[2], offsets[4])
+                .await
+                .unwrap(),
+            Some(offsets[4] + 1)
+        );
+        assert_eq!(
+            log.purged_after(collection_id, offsets[4], offsets[4])
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed_records() {
+        let mut log = setup_sqlite_log().await;
+        let collection_id = CollectionUuid::new();
+
+        let record = |i: i64| OperationRecord {
+            id: format!("id{}", i),
+            embedding: Some(vec![i as f32; 4]),
+            encoding: Some(ScalarEncoding::FLOAT32),
+            metadata: Some(UpdateMetadata::from([(
+                "key".to_string(),
+                UpdateMetadataValue::Int(i),
+            )])),
+            document: Some(format!("document{}", i)),
+            operation: Operation::Upsert,
+        };
+        // Rows written before compression was turned on stay readable
+        log.push_logs(collection_id, vec![record(0)]).await.unwrap();
+        log.compression = LogCompression::Zstd;
+        log.push_logs(collection_id, vec![record(1), record(2)])
+            .await
+            .unwrap();
+
+        let read = log.read(collection_id, 0, -1, None).await.unwrap();
+        assert_eq!(read.len(), 3);
+        for (i, log_record) in read.into_iter().enumerate() {
+            let expected = record(i as i64);
+            assert_eq!(log_record.record.id, expected.id);
+            assert_eq!(log_record.record.embedding, expected.embedding);
+            assert_eq!(log_record.record.encoding, expected.encoding);
+            assert_eq!(log_record.record.document, expected.document);
+            assert_eq!(log_record.record.operation, expected.operation);
+            assert!(are_metadatas_close_to_equal(
+                &log_record.record.metadata.unwrap(),
+                &expected.metadata.unwrap()
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retention_lookups() {
+        let mut log = setup_sqlite_log().await;
+        let collection_id = CollectionUuid::new();
+        let other_collection_id = CollectionUuid::new();
+
+        let operations = |count: usize| {
+            (0..count)
+                .map(|i| OperationRecord {
+                    id: format!("id{}", i),
+                    embedding: None,
+                    encoding: None,
+                    metadata: None,
+                    document: None,
+                    operation: Operation::Delete,
+                })
+                .collect::<Vec<_>>()
+        };
+        // Interleave collections so that offsets are not contiguous
+        for _ in 0..2 {
+            log.push_logs(collection_id, operations(2)).await.unwrap();
+            log.push_logs(other_collection_id, operations(1))
+                .await
+                .unwrap();
+        }
+        let offsets = log
+            .read(collection_id, 0, -1, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.log_offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets.len(), 4);
+
+        let stats = log.stats(collection_id).await.unwrap();
+        assert_eq!(
+            stats,
+            LogStats {
+                first_offset: Some(offsets[0]),
+                last_offset: Some(offsets[3]),
+                record_count: 4,
+            }
+        );
+
+        let nth = log.nth_latest_offset(collection_id, offsets[3], 3).await;
+        assert_eq!(nth.unwrap(), Some(offsets[1]));
+        let nth = log.nth_latest_offset(collection_id, offsets[2], 1).await;
+        assert_eq!(nth.unwrap(), Some(offsets[2]));
+        let nth = log.nth_latest_offset(collection_id, offsets[3], 5).await;
+        assert_eq!(nth.unwrap(), None);
+
+        let first = log.first_offset_since(collection_id, 0).await.unwrap();
+        assert_eq!(first, Some(offsets[0]));
+        let first = log
+            .first_offset_since(collection_id, i64::MAX)
+            .await
+            .unwrap();
+        assert_eq!(first, None);
+    }
+
+    #[tokio::test]
+    async fn test_purge_orphaned_logs() {
+        let mut log = setup_sqlite_log().await;
+
+        let live = CollectionUuid::new();
+        let deleted = CollectionUuid::new();
+        sqlx::query(
+            r#"
+            INSERT INTO segments (id, type, scope, collection) VALUES ('foo', 'foo', 'foo', ?);
+            INSERT INTO collections (id, name, database_id) VALUES (?, 'foo', 0);
+            INSERT INTO max_seq_id (segment_id, seq_id) VALUES ('foo', 1), ('bar', 1);
+        "#,
+        )
+        .bind(live.0.to_string())
+        .bind(live.0.to_string())
+        .execute(log.db.get_conn())
+        .await
+        .unwrap();
+
+        let operations = vec![OperationRecord {
+            id: "id".to_string(),
+            embedding: Some(vec![1.0, 2.0, 3.0]),
+            encoding: Some(ScalarEncoding::FLOAT32),
+            metadata: None,
+            document: None,
+            operation: Operation::Add,
+        }];
+        log.push_logs(live, operations.clone()).await.unwrap();
+        log.push_logs(deleted, operations.clone()).await.unwrap();
+        log.push_logs(deleted, operations).await.unwrap();
+
+        assert_eq!(log.purge_orphaned_logs().await.unwrap(), 2);
+        assert_eq!(log.stats(live).await.unwrap().record_count, 1);
+        assert_eq!(log.stats(deleted).await.unwrap().record_count, 0);
+        let segment_ids = sqlx::query("SELECT segment_id FROM max_seq_id")
+            .fetch_all(log.db.get_conn())
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<String, _>("segment_id"))
+            .collect::<Vec<_>>();
+        assert_eq!(segment_ids, vec!["foo".to_string()]);
+        assert_eq!(log.purge_orphaned_logs().await.unwrap(), 0);
+    }
+
     proptest! {
         #[test]
          fn test_push_pull_logs(
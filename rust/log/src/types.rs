@@ -6,9 +6,19 @@ use chroma_types::CollectionUuid;
 /// - collection_id: the id of the collection that needs to be compacted
 /// - first_log_offset: the offset of the first log entry in the collection that needs to be compacted
 /// - first_log_ts: the timestamp of the first log entry in the collection that needs to be compacted
+/// - last_log_offset: the offset of the last log entry in the collection
 #[derive(Debug)]
 pub struct CollectionInfo {
     pub collection_id: CollectionUuid,
     pub first_log_offset: i64,
     pub first_log_ts: i64,
+    pub last_log_offset: i64,
+}
+
+/// LogStats summarizes the records a log currently holds for a collection.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LogStats {
+    pub first_offset: Option<i64>,
+    pub last_offset: Option<i64>,
+    pub record_count: u64,
 }
@@ -81,11 +81,13 @@ impl Bindings {
         let sysdb_config = SysDbConfig::Sqlite(SqliteSysDbConfig {
             log_topic_namespace: "default".to_string(),
             log_tenant: "default".to_string(),
+            soft_delete_window_secs: None,
         });
 
         let log_config = LogConfig::Sqlite(SqliteLogConfig {
             tenant_id: "default".to_string(),
             topic_namespace: "default".to_string(),
+            compression: Default::default(),
         });
 
         let collection_cache_config = CollectionsWithSegmentsProviderConfig {
@@ -104,6 +106,7 @@ impl Bindings {
             sysdb: sysdb_config,
             collections_with_segments_provider: collection_cache_config,
             log: log_config,
+            compaction_manager: Default::default(),
             executor: executor_config,
         };
 
@@ -373,6 +376,7 @@ impl Bindings {
             documents,
             uris,
             metadatas,
+            None,
         )?;
 
         let mut frontend_clone = self.frontend.clone();
@@ -469,6 +473,7 @@ impl Bindings {
             documents,
             uris,
             metadatas,
+            None,
         )?;
 
         self.runtime
@@ -527,7 +532,7 @@ impl Bindings {
             uuid::Uuid::parse_str(&collection_id).map_err(WrappedUuidError)?,
         );
 
-        let request = chroma_types::CountRequest::try_new(tenant, database, collection_id)?;
+        let request = chroma_types::CountRequest::try_new(tenant, database, collection_id, None)?;
 
         let mut frontend_clone = self.frontend.clone();
         let result = self
@@ -574,6 +579,7 @@ impl Bindings {
             limit,
             offset,
             include,
+            None,
         )?;
 
         let mut frontend_clone = self.frontend.clone();
@@ -621,6 +627,8 @@ impl Bindings {
             query_embeddings,
             n_results,
             include,
+            false,
+            None,
         )?;
 
         let mut frontend_clone = self.frontend.clone();
@@ -1,5 +1,11 @@
 use super::distributed_spann::SpannSegmentWriterError;
 use super::types::{HydratedMaterializedLogRecord, LogMaterializerError, MaterializeLogsResult};
+use arrow::array::{
+    ArrayRef, Float32Builder, ListBuilder, RecordBatch, StringBuilder, UInt32Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use chroma_blockstore::export::{ParquetExportError, ParquetExportSummary, ParquetExporter};
 use chroma_blockstore::provider::{BlockfileProvider, CreateError, OpenError};
 use chroma_blockstore::{
     BlockfileFlusher, BlockfileReader, BlockfileWriter, BlockfileWriterOptions,
@@ -852,6 +858,28 @@ impl RecordSegmentReader<'_> {
         self.id_to_user_id.count().await
     }
 
+    /// Export every record of the segment to Parquet, sorted by offset id, with the columns
+    /// offset_id, id, embedding, document and metadata (as JSON).
+    /// Records are read and written `batch_size` at a time.
+    pub async fn export_parquet(
+        &self,
+        exporter: &ParquetExporter,
+        batch_size: usize,
+    ) -> Result<ParquetExportSummary, ParquetExportError> {
+        let schema = record_export_schema();
+        let batch_schema = schema.clone();
+        let batches = self
+            .id_to_data
+            .get_range_stream(""..="", ..)
+            .chunks(batch_size.max(1))
+            .map(move |records| {
+                let records = records.into_iter().collect::<Result<Vec<_>, _>>()?;
+                records_to_batch(batch_schema.clone(), &records)
+                    .map_err(|e| Box::new(ParquetExportError::Arrow(e)) as Box<dyn ChromaError>)
+            });
+        exporter.export_batches(Some(schema), batches).await
+    }
+
     pub async fn prefetch_id_to_data(&self, keys: &[u32]) {
         let prefixes = vec![""; keys.len()];
         self.id_to_data.load_blocks_for_keys(&prefixes, keys).await
@@ -866,20 +894,72 @@ impl RecordSegmentReader<'_> {
     }
 }
 
+fn record_export_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("offset_id", DataType::UInt32, false),
+        Field::new("id", DataType::Utf8, false),
+        Field::new(
+            "embedding",
+            DataType::List(Arc::new(Field::new("item", DataType::Float32, true))),
+            false,
+        ),
+        Field::new("document", DataType::Utf8, true),
+        Field::new("metadata", DataType::Utf8, true),
+    ]))
+}
+
+fn records_to_batch(
+    schema: SchemaRef,
+    records: &[(u32, DataRecord)],
+) -> Result<RecordBatch, ArrowError> {
+    let mut offset_ids = UInt32Builder::with_capacity(records.len());
+    let mut ids = StringBuilder::new();
+    let mut embeddings = ListBuilder::new(Float32Builder::new());
+    let mut documents = StringBuilder::new();
+    let mut metadatas = StringBuilder::new();
+    for (offset_id, record) in records {
+        offset_ids.append_value(*offset_id);
+        ids.append_value(record.id);
+        embeddings.values().append_slice(record.embedding);
+        embeddings.append(true);
+        documents.append_option(record.document);
+        let metadata = record
+            .metadata
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        metadatas.append_option(metadata);
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(offset_ids.finish()),
+        Arc::new(ids.finish()),
+        Arc::new(embeddings.finish()),
+        Arc::new(documents.finish()),
+        Arc::new(metadatas.finish()),
+    ];
+    RecordBatch::try_new(schema, columns)
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{atomic::AtomicU32, Arc};
 
+    use std::fs::File;
+
+    use arrow::array::{Array, StringArray, UInt32Array};
+    use chroma_blockstore::export::{ParquetExporter, ParquetSink};
     use chroma_blockstore::BlockfileWriter;
     use chroma_log::test::{upsert_generator, LogGenerator};
     use chroma_types::Chunk;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
     use shuttle::{future, thread};
 
     use crate::{
         blockfile_record::MAX_OFFSET_ID, test::TestDistributedSegment, types::materialize_logs,
     };
 
-    use super::RecordSegmentWriter;
+    use super::{RecordSegmentReader, RecordSegmentWriter};
 
     // The same record segment writer should be able to run concurrently on different threads without conflict
     #[test]
@@ -972,4 +1052,59 @@ mod tests {
             60,
         );
     }
+
+    #[tokio::test]
+    async fn test_export_parquet() {
+        let mut test_segment = TestDistributedSegment::default();
+        test_segment
+            .compact_log(upsert_generator.generate_chunk(1..=100), 1)
+            .await;
+        let reader = RecordSegmentReader::from_segment(
+            &test_segment.record_segment,
+            &test_segment.blockfile_provider,
+        )
+        .await
+        .expect("Record segment reader should be initialized");
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("records.parquet");
+        let summary = reader
+            .export_parquet(&ParquetExporter::new(ParquetSink::Local(path.clone())), 16)
+            .await
+            .expect("Export should succeed");
+        assert_eq!(summary.num_rows, 100);
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut offset_id = 0;
+        for batch in batches {
+            let offset_ids = batch
+                .column_by_name("offset_id")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .unwrap();
+            let ids = batch
+                .column_by_name("id")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            for row in 0..batch.num_rows() {
+                offset_id += 1;
+                assert_eq!(offset_ids.value(row), offset_id);
+                let record = reader
+                    .get_data_for_offset_id(offset_id)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(ids.value(row), record.id);
+            }
+        }
+        assert_eq!(offset_id, 100);
+    }
 }
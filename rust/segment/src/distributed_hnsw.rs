@@ -6,14 +6,16 @@ use chroma_index::hnsw_provider::{
     HnswIndexProviderOpenError, HnswIndexRef,
 };
 use chroma_index::{Index, IndexUuid};
-use chroma_types::{DistributedHnswParameters, HnswParametersFromSegmentError, SegmentUuid};
+use chroma_types::{
+    DistributedHnswParameters, HnswIndexStats, HnswParametersFromSegmentError, SegmentUuid,
+};
 use chroma_types::{MaterializedLogOperation, Segment};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use thiserror::Error;
 use uuid::Uuid;
 
-const HNSW_INDEX: &str = "hnsw_index";
+pub const HNSW_INDEX: &str = "hnsw_index";
 
 pub struct HnswIndexParamsFromSegment {
     pub m: usize,
@@ -190,26 +192,7 @@ impl DistributedHNSWSegmentWriter {
                         .hydrate(record_segment_reader.as_ref())
                         .await
                         .map_err(ApplyMaterializedLogError::Materialization)?;
-                    let embedding = record.merged_embeddings_ref();
-
-                    let mut index = self.index.inner.upgradable_read();
-                    let index_len = index.len_with_deleted();
-                    let index_capacity = index.capacity();
-                    if index_len + 1 > index_capacity {
-                        index.with_upgraded(|index| {
-                            // Bump allocation by 2x
-                            index
-                                .resize(index_capacity * 2)
-                                .map(|_| ApplyMaterializedLogError::Allocation)
-                        })?;
-                    }
-
-                    match index.add(record.get_offset_id() as usize, embedding) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            return Err(ApplyMaterializedLogError::HnswIndex(e));
-                        }
-                    }
+                    self.add_embedding(record.get_offset_id(), record.merged_embeddings_ref())?;
                 }
                 MaterializedLogOperation::DeleteExisting => {
                     // HNSW segment does not perform validation of any sort. So,
@@ -236,6 +219,30 @@ impl DistributedHNSWSegmentWriter {
         Ok(())
     }
 
+    /// Adds the embedding for the given offset id, growing the index if it is full
+    pub fn add_embedding(
+        &self,
+        offset_id: u32,
+        embedding: &[f32],
+    ) -> Result<(), ApplyMaterializedLogError> {
+        let mut index = self.index.inner.upgradable_read();
+        let index_len = index.len_with_deleted();
+        let index_capacity = index.capacity();
+        if index_len + 1 > index_capacity {
+            index.with_upgraded(|index| {
+                // Bump allocation by 2x
+                index
+                    .resize(index_capacity * 2)
+                    .map(|_| ApplyMaterializedLogError::Allocation)
+            })?;
+        }
+
+        match index.add(offset_id as usize, embedding) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(ApplyMaterializedLogError::HnswIndex(e)),
+        }
+    }
+
     pub async fn commit(self) -> Result<DistributedHNSWSegmentWriter, Box<dyn ChromaError>> {
         let res = self.hnsw_index_provider.commit(self.index.clone());
         match res {
@@ -367,6 +374,25 @@ impl DistributedHNSWSegmentReader {
         let index = self.index.inner.read();
         index.query(vector, k, allowed_ids, disallowd_ids)
     }
+
+    /// Returns the number of live (non-deleted) elements in the index
+    pub fn len(&self) -> usize {
+        self.index.inner.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.inner.read().is_empty()
+    }
+
+    pub fn stats(&self) -> HnswIndexStats {
+        let index = self.index.inner.read();
+        HnswIndexStats {
+            len: index.len() as u64,
+            len_with_deleted: index.len_with_deleted() as u64,
+            capacity: index.capacity() as u64,
+            dimensionality: index.dimensionality() as u32,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -13,12 +13,13 @@ use chroma_index::{hnsw_provider::HnswIndexProvider, spann::types::SpannIndexWri
 use chroma_types::DistributedHnswParameters;
 use chroma_types::HnswParametersFromSegmentError;
 use chroma_types::SegmentUuid;
+use chroma_types::SpannIndexStats;
 use chroma_types::{MaterializedLogOperation, Segment, SegmentScope, SegmentType};
 use std::collections::HashMap;
 use thiserror::Error;
 use uuid::Uuid;
 
-const HNSW_PATH: &str = "hnsw_path";
+pub const HNSW_PATH: &str = "hnsw_path";
 const VERSION_MAP_PATH: &str = "version_map_path";
 const POSTING_LIST_PATH: &str = "posting_list_path";
 const MAX_HEAD_ID_BF_PATH: &str = "max_head_id_path";
@@ -472,6 +473,13 @@ impl<'me> SpannSegmentReader<'me> {
             .await
             .map_err(|_| SpannSegmentReaderError::KeyReadError)
     }
+
+    pub async fn stats(&self) -> Result<SpannIndexStats, SpannSegmentReaderError> {
+        self.index_reader
+            .stats()
+            .await
+            .map_err(|_| SpannSegmentReaderError::KeyReadError)
+    }
 }
 
 #[cfg(test)]
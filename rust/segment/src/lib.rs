@@ -5,6 +5,7 @@ pub mod distributed_hnsw;
 pub mod distributed_spann;
 pub mod local_hnsw;
 pub mod local_segment_manager;
+pub mod scrub;
 pub mod sqlite_metadata;
 pub mod test;
 pub mod types;
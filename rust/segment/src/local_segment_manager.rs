@@ -6,8 +6,12 @@ use chroma_config::{
 use chroma_error::{ChromaError, ErrorCodes};
 use chroma_index::IndexUuid;
 use chroma_sqlite::db::SqliteDb;
-use chroma_types::Segment;
+use chroma_types::{Segment, SegmentUuid};
 use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -80,6 +84,10 @@ pub enum LocalSegmentManagerError {
     PoolCacheError(#[from] CacheError),
     #[error("Error creating hnsw segment writer")]
     LocalHnswSegmentWriterError(#[from] LocalHnswSegmentWriterError),
+    #[error("Error accessing the persist path: {0}")]
+    PersistPathError(#[from] std::io::Error),
+    #[error("Error listing segments: {0}")]
+    ListSegmentsError(#[from] sqlx::Error),
 }
 
 impl ChromaError for LocalSegmentManagerError {
@@ -88,6 +96,8 @@ impl ChromaError for LocalSegmentManagerError {
             LocalSegmentManagerError::LocalHnswSegmentReaderError(e) => e.code(),
             LocalSegmentManagerError::PoolCacheError(e) => e.code(),
             LocalSegmentManagerError::LocalHnswSegmentWriterError(e) => e.code(),
+            LocalSegmentManagerError::PersistPathError(_) => ErrorCodes::Internal,
+            LocalSegmentManagerError::ListSegmentsError(_) => ErrorCodes::Internal,
         }
     }
 }
@@ -150,4 +160,105 @@ impl LocalSegmentManager {
         self.hnsw_index_pool.clear().await?;
         Ok(())
     }
+    /// Removes the persisted hnsw directories that do not belong to a segment in the sysdb
+    /// anymore, e.g. those of deleted collections or of segments dropped by a reset. Returns
+    /// the ids of the removed segments.
+    pub async fn remove_orphaned_segments(
+        &self,
+    ) -> Result<Vec<SegmentUuid>, LocalSegmentManagerError> {
+        let persist_root = match &self.persist_root {
+            Some(persist_root) => persist_root,
+            None => return Ok(Vec::new()),
+        };
+        let persist_root = Path::new(persist_root);
+        if !persist_root.exists() {
+            return Ok(Vec::new());
+        }
+
+        // The directories are listed before the segments: a segment is created in the sysdb
+        // before its directory, so a directory created concurrently is never mistaken for an
+        // orphan.
+        let mut segment_dirs = Vec::new();
+        let mut entries = tokio::fs::read_dir(persist_root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            // Other directories, e.g. the ones of the sqlite database, are left alone
+            if let Some(segment_id) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| SegmentUuid::from_str(name).ok())
+            {
+                segment_dirs.push((segment_id, entry.path()));
+            }
+        }
+
+        let live_segments = sqlx::query("SELECT id FROM segments")
+            .fetch_all(self.sqlite.get_conn())
+            .await?
+            .iter()
+            .filter_map(|row| SegmentUuid::from_str(row.get::<&str, _>("id")).ok())
+            .collect::<HashSet<_>>();
+
+        let mut removed = Vec::new();
+        for (segment_id, path) in segment_dirs {
+            if live_segments.contains(&segment_id) {
+                continue;
+            }
+            self.hnsw_index_pool.remove(&IndexUuid(segment_id.0)).await;
+            tokio::fs::remove_dir_all(&path).await?;
+            tracing::info!(
+                "Removed orphaned hnsw directory {} of segment {}",
+                path.display(),
+                segment_id
+            );
+            removed.push(segment_id);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chroma_sqlite::db::test_utils::get_new_sqlite_db;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_remove_orphaned_segments() {
+        let persist_dir = TempDir::new().unwrap();
+        let registry = Registry::new();
+        let sqlite = get_new_sqlite_db().await;
+        registry.register(sqlite.clone());
+        let manager = LocalSegmentManager::try_from_config(
+            &LocalSegmentManagerConfig {
+                hnsw_index_pool_cache_config: default_hnsw_index_pool_cache_config(),
+                persist_path: Some(persist_dir.path().to_str().unwrap().to_string()),
+            },
+            &registry,
+        )
+        .await
+        .unwrap();
+
+        let live = SegmentUuid::new();
+        let orphaned = SegmentUuid::new();
+        sqlx::query(
+            "INSERT INTO segments (id, type, scope, collection) VALUES (?, 'foo', 'VECTOR', 'bar')",
+        )
+        .bind(live.to_string())
+        .execute(sqlite.get_conn())
+        .await
+        .unwrap();
+        for dir in [live.to_string(), orphaned.to_string(), "other".to_string()] {
+            std::fs::create_dir(persist_dir.path().join(dir)).unwrap();
+        }
+
+        let removed = manager.remove_orphaned_segments().await.unwrap();
+        assert_eq!(removed, vec![orphaned]);
+        assert!(persist_dir.path().join(live.to_string()).exists());
+        assert!(!persist_dir.path().join(orphaned.to_string()).exists());
+        assert!(persist_dir.path().join("other").exists());
+        assert!(manager.remove_orphaned_segments().await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,78 @@
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_index::{hnsw_provider::HnswIndexProvider, IndexUuid};
+use chroma_types::{ScrubReport, Segment};
+use uuid::Uuid;
+
+use crate::{distributed_hnsw::HNSW_INDEX, distributed_spann::HNSW_PATH};
+
+/// Verifies every persisted file of a segment against storage: the root and blocks of each
+/// blockfile, and the files of the HNSW index of vector segments. Corruption is reported in the
+/// returned report, so that every file is checked even if some are corrupt.
+pub async fn scrub_segment(
+    segment: &Segment,
+    blockfile_provider: &BlockfileProvider,
+    hnsw_provider: &HnswIndexProvider,
+) -> ScrubReport {
+    let mut report = ScrubReport::default();
+    for (name, ids) in &segment.file_path {
+        let is_hnsw_index = name == HNSW_INDEX || name == HNSW_PATH;
+        for id in ids {
+            let id = match Uuid::parse_str(id) {
+                Ok(id) => id,
+                Err(e) => {
+                    report.add_corruption(format!("{}/{}", name, id), e);
+                    continue;
+                }
+            };
+            if is_hnsw_index {
+                report.merge(hnsw_provider.scrub(&IndexUuid(id)).await);
+            } else {
+                report.merge(blockfile_provider.scrub(&id).await);
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use chroma_log::test::{upsert_generator, LogGenerator, TEST_EMBEDDING_DIMENSION};
+
+    use super::scrub_segment;
+    use crate::test::TestDistributedSegment;
+
+    #[tokio::test]
+    async fn test_scrub_segment() {
+        let mut test_segment = TestDistributedSegment::new_with_dimension(TEST_EMBEDDING_DIMENSION);
+        test_segment
+            .compact_log(upsert_generator.generate_chunk(1..=100), 1)
+            .await;
+
+        for segment in [
+            &test_segment.metadata_segment,
+            &test_segment.record_segment,
+            &test_segment.vector_segment,
+        ] {
+            let report = scrub_segment(
+                segment,
+                &test_segment.blockfile_provider,
+                &test_segment.hnsw_provider,
+            )
+            .await;
+            assert!(report.is_healthy(), "{:?}", report.corruptions);
+            assert!(report.files_checked > 0);
+        }
+
+        let mut missing_segment = test_segment.record_segment.clone();
+        for ids in missing_segment.file_path.values_mut() {
+            *ids = vec![uuid::Uuid::new_v4().to_string()];
+        }
+        let report = scrub_segment(
+            &missing_segment,
+            &test_segment.blockfile_provider,
+            &test_segment.hnsw_provider,
+        )
+        .await;
+        assert_eq!(report.corruptions.len(), missing_segment.file_path.len());
+    }
+}
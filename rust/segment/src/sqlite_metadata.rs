@@ -584,6 +584,7 @@ impl SqliteMetadataReader {
         Count {
             scan: Scan {
                 collection_and_segments,
+                ..
             },
         }: Count,
     ) -> Result<CountResult, SqliteMetadataError> {
@@ -608,6 +609,7 @@ impl SqliteMetadataReader {
         Get {
             scan: Scan {
                 collection_and_segments,
+                ..
             },
             filter: Filter {
                 query_ids,
@@ -783,7 +785,7 @@ mod tests {
             let sqlite_seg_reader = SqliteMetadataReader {
                 db: sqlite_seg_writer.db
             };
-            let plan = Count { scan: Scan { collection_and_segments: test_data.collection_and_segments.clone() }};
+            let plan = Count { scan: Scan { collection_and_segments: test_data.collection_and_segments.clone(), as_of: None }};
             let ref_count = ref_seg.count(plan.clone()).expect("Count should not fail");
             let sqlite_count = runtime.block_on(sqlite_seg_reader.count(plan)).expect("Count should not fail");
             assert_eq!(sqlite_count, ref_count);
@@ -816,6 +818,7 @@ mod tests {
             let plan = Get {
                 scan: Scan {
                     collection_and_segments: test_data.collection_and_segments.clone(),
+                    as_of: None,
                 },
                 filter: Filter {
                     query_ids: None,
@@ -1,24 +1,24 @@
-use crate::StorageConfigError;
 use crate::{
-    config::{RateLimitingConfig, StorageConfig},
+    config::{HedgingConfig, RateLimitingConfig, ReadRetryConfig, StorageConfig},
     s3::{S3GetError, S3PutError, S3Storage},
 };
+use crate::{ListPage, StorageConfigError};
 use async_trait::async_trait;
 use aws_sdk_s3::primitives::{ByteStream, Length};
+use backon::{ExponentialBuilder, Retryable};
 use bytes::Bytes;
 use chroma_config::registry::Registry;
 use chroma_config::Configurable;
 use chroma_error::{ChromaError, ErrorCodes};
-use futures::future::BoxFuture;
+use futures::future::{BoxFuture, Either};
 use futures::{future::Shared, stream, FutureExt, StreamExt};
 use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::ops::Range;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
 use thiserror::Error;
-use tokio::{
-    io::AsyncReadExt,
-    sync::{Semaphore, SemaphorePermit},
-};
+use tokio::sync::{Semaphore, SemaphorePermit};
 use tracing::{Instrument, Span};
 
 /// Wrapper over s3 storage that provides proxy features such as
@@ -26,6 +26,8 @@ use tracing::{Instrument, Span};
 /// For reads, it will coalesce requests for the same key and rate limit
 /// the number of concurrent requests.
 /// For writes, it will rate limit the number of concurrent requests.
+/// Every read, including each part of a parallel fetch, is retried and optionally hedged
+/// according to its `ReadPolicy`.
 #[derive(Clone)]
 pub struct AdmissionControlledS3Storage {
     storage: S3Storage,
@@ -51,6 +53,7 @@ pub struct AdmissionControlledS3Storage {
         >,
     >,
     rate_limiter: Arc<RateLimitPolicy>,
+    read_policy: Arc<ReadPolicy>,
 }
 
 #[derive(Error, Debug, Clone)]
@@ -73,6 +76,7 @@ impl AdmissionControlledS3Storage {
             storage,
             outstanding_read_requests: Arc::new(Mutex::new(HashMap::new())),
             rate_limiter: Arc::new(RateLimitPolicy::CountBasedPolicy(CountBasedPolicy::new(2))),
+            read_policy: Arc::new(ReadPolicy::default()),
         }
     }
 
@@ -81,93 +85,58 @@ impl AdmissionControlledS3Storage {
             storage,
             outstanding_read_requests: Arc::new(Mutex::new(HashMap::new())),
             rate_limiter: Arc::new(policy),
+            read_policy: Arc::new(ReadPolicy::default()),
         }
     }
 
+    pub fn with_read_policy(mut self, read_policy: ReadPolicy) -> Self {
+        self.read_policy = Arc::new(read_policy);
+        self
+    }
+
     async fn parallel_fetch(
         storage: S3Storage,
         rate_limiter: Arc<RateLimitPolicy>,
+        read_policy: Arc<ReadPolicy>,
         key: String,
     ) -> Result<Arc<Vec<u8>>, AdmissionControlledS3StorageError> {
-        let (content_length, ranges) = match storage.get_key_ranges(&key).await {
-            Ok(ranges) => ranges,
+        let content_length = match read_policy.read(&rate_limiter, || storage.size(&key)).await {
+            Ok(content_length) => content_length,
             Err(e) => {
                 tracing::error!("Error heading s3: {}", e);
                 return Err(AdmissionControlledS3StorageError::S3GetError(e));
             }
         };
-
-        // .buffer_unordered() below will hang if the range is empty (https://github.com/rust-lang/futures-rs/issues/2740), so we short-circuit here
-        if content_length == 0 {
-            return Ok(Arc::new(Vec::new()));
-        }
-
-        let part_size = storage.download_part_size_bytes;
         tracing::info!(
-            "[AdmissionControlledS3][Parallel fetch] Content length: {}, key ranges: {:?}",
-            content_length,
-            ranges
+            "[AdmissionControlledS3][Parallel fetch] Content length: {}",
+            content_length
         );
         let mut output_buffer: Vec<u8> = vec![0; content_length as usize];
-        let mut output_slices = output_buffer.chunks_mut(part_size).collect::<Vec<_>>();
-        let range_and_output_slices = ranges.iter().zip(output_slices.drain(..));
-        let mut futures = Vec::new();
-        let num_parts = range_and_output_slices.len();
-        for (range, output_slice) in range_and_output_slices {
-            let rate_limiter_clone = rate_limiter.clone();
-            let storage_clone = storage.clone();
-            let key_clone = key.clone();
-            let fut = async move {
-                // Acquire permit.
-                let token = rate_limiter_clone.enter().await;
-                let range_str = format!("bytes={}-{}", range.0, range.1);
-                storage_clone
-                    .fetch_range(key_clone, range_str)
-                    .then(|res| async move {
-                        let _token = token;
-                        match res {
-                            Ok(output) => {
-                                let body = output.body;
-                                let mut reader = body.into_async_read();
-                                match reader.read_exact(output_slice).await {
-                                    Ok(_) => Ok(()),
-                                    Err(e) => {
-                                        tracing::error!("Error reading from s3: {}", e);
-                                        Err(AdmissionControlledS3StorageError::S3GetError(
-                                            S3GetError::ByteStreamError(e.to_string()),
-                                        ))
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("Error reading from s3: {}", e);
-                                Err(AdmissionControlledS3StorageError::S3GetError(e))
-                            }
-                        }
-                        // _token gets dropped due to RAII and we've released the permit.
-                    })
-                    .await
-            };
-            futures.push(fut);
-        }
-        // Await all futures and return the result.
-        let _ = stream::iter(futures)
-            .buffer_unordered(num_parts)
-            .collect::<Vec<_>>()
-            .await;
+        read_parts(
+            &read_policy,
+            &rate_limiter,
+            &mut output_buffer,
+            storage.download_part_size_bytes,
+            |range| storage.get_range(&key, range),
+        )
+        .await
+        .inspect_err(|e| tracing::error!("Error reading from s3: {}", e))?;
         Ok(Arc::new(output_buffer))
     }
 
     async fn read_from_storage(
         storage: S3Storage,
         rate_limiter: Arc<RateLimitPolicy>,
+        read_policy: Arc<ReadPolicy>,
         key: String,
     ) -> Result<Arc<Vec<u8>>, AdmissionControlledS3StorageError> {
-        // Acquire permit.
-        let _permit = rate_limiter.enter().await;
-        let bytes_res = storage
-            .get(&key)
-            .instrument(tracing::trace_span!(parent: Span::current(), "S3 get"))
+        // Every attempt and hedge acquires its own permit.
+        let bytes_res = read_policy
+            .read(&rate_limiter, || {
+                storage
+                    .get(&key)
+                    .instrument(tracing::trace_span!(parent: Span::current(), "S3 get"))
+            })
             .await;
         match bytes_res {
             Ok(bytes) => Ok(bytes),
@@ -176,7 +145,6 @@ impl AdmissionControlledS3Storage {
                 Err(AdmissionControlledS3StorageError::S3GetError(e))
             }
         }
-        // Permit gets dropped here due to RAII.
     }
 
     pub async fn get_parallel(
@@ -199,6 +167,7 @@ impl AdmissionControlledS3Storage {
                     let get_parallel_storage_future = AdmissionControlledS3Storage::parallel_fetch(
                         self.storage.clone(),
                         self.rate_limiter.clone(),
+                        self.read_policy.clone(),
                         key.clone(),
                     )
                     .boxed()
@@ -234,6 +203,7 @@ impl AdmissionControlledS3Storage {
                     let get_storage_future = AdmissionControlledS3Storage::read_from_storage(
                         self.storage.clone(),
                         self.rate_limiter.clone(),
+                        self.read_policy.clone(),
                         key.clone(),
                     )
                     .boxed()
@@ -252,6 +222,20 @@ impl AdmissionControlledS3Storage {
         res
     }
 
+    pub async fn get_range(
+        &self,
+        key: &str,
+        range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, AdmissionControlledS3StorageError> {
+        // Ranged reads are small and rarely shared, so they are not coalesced
+        Ok(self
+            .read_policy
+            .read(&self.rate_limiter, || {
+                self.storage.get_range(key, range.clone())
+            })
+            .await?)
+    }
+
     async fn oneshot_upload(
         &self,
         key: &str,
@@ -356,6 +340,43 @@ impl AdmissionControlledS3Storage {
         })
         .await
     }
+
+    pub async fn delete(&self, key: &str) -> Result<(), S3PutError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage.delete(key).await
+    }
+
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), S3PutError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage.copy(src_key, dst_key).await
+    }
+
+    pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), S3PutError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage.rename(src_key, dst_key).await
+    }
+
+    pub async fn size(&self, key: &str) -> Result<i64, S3GetError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage.size(key).await
+    }
+
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, S3GetError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage.list_prefix(prefix).await
+    }
+
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, S3GetError> {
+        let _permit = self.rate_limiter.enter().await;
+        self.storage
+            .list_prefix_page(prefix, start_after, limit)
+            .await
+    }
 }
 
 #[async_trait]
@@ -374,7 +395,8 @@ impl Configurable<StorageConfig> for AdmissionControlledS3Storage {
                 let policy =
                     RateLimitPolicy::try_from_config(&nacconfig.rate_limiting_policy, registry)
                         .await?;
-                return Ok(Self::new(s3_storage, policy));
+                let read_policy = ReadPolicy::new(&nacconfig.retry, nacconfig.hedging.clone());
+                return Ok(Self::new(s3_storage, policy).with_read_policy(read_policy));
             }
             _ => {
                 return Err(Box::new(StorageConfigError::InvalidStorageConfig));
@@ -383,6 +405,55 @@ impl Configurable<StorageConfig> for AdmissionControlledS3Storage {
     }
 }
 
+/// Reads `output` in parts of `part_size` bytes. Every part is retried and hedged on its own,
+/// and each attempt acquires a permit.
+async fn read_parts<F, Fut>(
+    read_policy: &ReadPolicy,
+    rate_limiter: &RateLimitPolicy,
+    output: &mut [u8],
+    part_size: usize,
+    fetch_part: F,
+) -> Result<(), S3GetError>
+where
+    F: Fn(Range<usize>) -> Fut,
+    Fut: Future<Output = Result<Arc<Vec<u8>>, S3GetError>>,
+{
+    // .buffer_unordered() below will hang if there are no parts (https://github.com/rust-lang/futures-rs/issues/2740), so we short-circuit here
+    if output.is_empty() {
+        return Ok(());
+    }
+    let parts = output
+        .chunks_mut(part_size)
+        .enumerate()
+        .map(|(index, output_slice)| {
+            let range = index * part_size..index * part_size + output_slice.len();
+            let fetch_part = &fetch_part;
+            async move {
+                let part = read_policy
+                    .read(rate_limiter, || fetch_part(range.clone()))
+                    .await?;
+                if part.len() != output_slice.len() {
+                    return Err(S3GetError::ByteStreamError(format!(
+                        "Expected {} bytes for range {:?} but got {}",
+                        output_slice.len(),
+                        range,
+                        part.len()
+                    )));
+                }
+                output_slice.copy_from_slice(&part);
+                Ok(())
+            }
+        })
+        .collect::<Vec<_>>();
+    let num_parts = parts.len();
+    stream::iter(parts)
+        .buffer_unordered(num_parts)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
 // Prefer enum dispatch over dyn since there could
 // only be a handful of these policies.
 #[derive(Debug)]
@@ -396,6 +467,12 @@ impl RateLimitPolicy {
             RateLimitPolicy::CountBasedPolicy(policy) => policy.acquire().await,
         }
     }
+
+    fn try_enter(&self) -> Option<SemaphorePermit<'_>> {
+        match self {
+            RateLimitPolicy::CountBasedPolicy(policy) => policy.try_acquire(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -416,6 +493,156 @@ impl CountBasedPolicy {
             Err(e) => panic!("AcquireToken Failed {}", e),
         }
     }
+
+    fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.remaining_tokens.try_acquire().ok()
+    }
+}
+
+// The number of recent read latencies the hedging percentile is computed over.
+const LATENCY_WINDOW: usize = 1024;
+// The number of new latencies after which the hedging percentile is recomputed.
+const LATENCY_REFRESH_INTERVAL: usize = 64;
+
+/// Recent read latencies and the hedge delay last computed from them
+#[derive(Debug, Default)]
+struct LatencyWindow {
+    samples: VecDeque<Duration>,
+    new_samples: usize,
+    hedge_delay: Option<Duration>,
+}
+
+/// Retries failed reads with exponential backoff and, if hedging is enabled,
+/// races a second request against reads slower than a percentile of recent latencies.
+#[derive(Debug)]
+pub struct ReadPolicy {
+    backoff: ExponentialBuilder,
+    hedging: Option<HedgingConfig>,
+    latencies: Mutex<LatencyWindow>,
+}
+
+impl Default for ReadPolicy {
+    fn default() -> Self {
+        Self::new(&ReadRetryConfig::default(), None)
+    }
+}
+
+impl ReadPolicy {
+    pub fn new(retry: &ReadRetryConfig, hedging: Option<HedgingConfig>) -> Self {
+        let backoff = ExponentialBuilder::default()
+            .with_factor(retry.factor)
+            .with_min_delay(Duration::from_millis(retry.min_delay_ms))
+            .with_max_delay(Duration::from_millis(retry.max_delay_ms))
+            .with_max_times(retry.max_attempts);
+        Self {
+            backoff: if retry.jitter {
+                backoff.with_jitter()
+            } else {
+                backoff
+            },
+            hedging,
+            latencies: Mutex::new(LatencyWindow {
+                samples: VecDeque::with_capacity(LATENCY_WINDOW),
+                ..Default::default()
+            }),
+        }
+    }
+
+    async fn read<T, F, Fut>(
+        &self,
+        rate_limiter: &RateLimitPolicy,
+        fetch: F,
+    ) -> Result<T, S3GetError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S3GetError>>,
+    {
+        (|| self.hedged_read(rate_limiter, &fetch))
+            .retry(self.backoff)
+            .when(|e| !matches!(e, S3GetError::NoSuchKey(_)))
+            .notify(|e, delay| {
+                tracing::warn!("Retrying s3 read in {:?} after error: {}", delay, e);
+            })
+            .await
+    }
+
+    async fn hedged_read<T, F, Fut>(
+        &self,
+        rate_limiter: &RateLimitPolicy,
+        fetch: &F,
+    ) -> Result<T, S3GetError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S3GetError>>,
+    {
+        let _permit = rate_limiter.enter().await;
+        let start = Instant::now();
+        let primary = fetch();
+        let result = match self.hedge_delay() {
+            None => primary.await,
+            Some(delay) => {
+                let mut primary = std::pin::pin!(primary);
+                match tokio::time::timeout(delay, &mut primary).await {
+                    Ok(result) => result,
+                    // Hedges never wait for a permit, so they cannot starve regular requests
+                    Err(_) => match rate_limiter.try_enter() {
+                        None => primary.await,
+                        Some(_hedge_permit) => {
+                            tracing::debug!("Hedging s3 read after {:?}", delay);
+                            let hedge = std::pin::pin!(fetch());
+                            match futures::future::select(primary, hedge).await {
+                                Either::Left((Ok(value), _)) | Either::Right((Ok(value), _)) => {
+                                    Ok(value)
+                                }
+                                // If the first request to finish failed, wait for the other one
+                                Either::Left((Err(_), hedge)) => hedge.await,
+                                Either::Right((Err(_), primary)) => primary.await,
+                            }
+                        }
+                    },
+                }
+            }
+        };
+        if result.is_ok() {
+            self.record_latency(start.elapsed());
+        }
+        result
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        let Some(hedging) = self.hedging.as_ref() else {
+            return;
+        };
+        let mut latencies = self.latencies.lock();
+        if latencies.samples.len() == LATENCY_WINDOW {
+            latencies.samples.pop_front();
+        }
+        latencies.samples.push_back(latency);
+        latencies.new_samples += 1;
+
+        // Reads only look up the last computed delay, which is refreshed every so often
+        if latencies.samples.len() >= hedging.min_samples
+            && (latencies.hedge_delay.is_none()
+                || latencies.new_samples >= LATENCY_REFRESH_INTERVAL)
+        {
+            let mut samples = latencies.samples.iter().copied().collect::<Vec<_>>();
+            let percentile = hedging.latency_percentile.clamp(0.0, 1.0);
+            let index = ((samples.len() - 1) as f64 * percentile).round() as usize;
+            let (_, hedge_delay, _) = samples.select_nth_unstable(index);
+            latencies.hedge_delay = Some(*hedge_delay);
+            latencies.new_samples = 0;
+        }
+    }
+
+    fn hedge_delay(&self) -> Option<Duration> {
+        let hedging = self.hedging.as_ref()?;
+        Some(
+            self.latencies
+                .lock()
+                .hedge_delay
+                .unwrap_or(Duration::from_millis(hedging.initial_delay_ms)),
+        )
+    }
 }
 
 #[async_trait]
@@ -442,6 +669,179 @@ mod tests {
 
     use crate::{admissioncontrolleds3::AdmissionControlledS3Storage, s3::S3Storage};
 
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn test_read_policy(hedging: Option<HedgingConfig>) -> ReadPolicy {
+        let retry = ReadRetryConfig {
+            min_delay_ms: 1,
+            max_delay_ms: 1,
+            jitter: false,
+            ..Default::default()
+        };
+        ReadPolicy::new(&retry, hedging)
+    }
+
+    fn test_hedging() -> HedgingConfig {
+        HedgingConfig {
+            min_samples: 1,
+            initial_delay_ms: 20,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_policy_retries() {
+        let policy = test_read_policy(None);
+        let rate_limiter = RateLimitPolicy::CountBasedPolicy(CountBasedPolicy::new(1));
+
+        let attempts = AtomicUsize::new(0);
+        let result = policy
+            .read(&rate_limiter, || async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(S3GetError::S3GetError("slow down".to_string())),
+                    _ => Ok(42),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Missing keys are not retried
+        let attempts = AtomicUsize::new(0);
+        let result: Result<(), _> = policy
+            .read(&rate_limiter, || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(S3GetError::NoSuchKey("key".to_string()))
+            })
+            .await;
+        assert!(matches!(result, Err(S3GetError::NoSuchKey(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_policy_hedges_slow_reads() {
+        let policy = test_read_policy(Some(test_hedging()));
+        let rate_limiter = RateLimitPolicy::CountBasedPolicy(CountBasedPolicy::new(2));
+
+        let attempts = AtomicUsize::new(0);
+        let start = Instant::now();
+        let result = policy
+            .read(&rate_limiter, || async {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                if attempt == 0 {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                }
+                Ok(attempt)
+            })
+            .await;
+        // The hedge answered long before the stuck primary would have
+        assert_eq!(result.unwrap(), 1);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(policy.latencies.lock().samples.len(), 1);
+    }
+
+    #[test]
+    fn test_hedge_delay_refresh() {
+        let policy = test_read_policy(Some(HedgingConfig {
+            latency_percentile: 0.5,
+            min_samples: 3,
+            initial_delay_ms: 20,
+        }));
+        let ms = Duration::from_millis;
+
+        // Until there are enough samples the initial delay is used
+        policy.record_latency(ms(1));
+        policy.record_latency(ms(3));
+        assert_eq!(policy.hedge_delay(), Some(ms(20)));
+        policy.record_latency(ms(2));
+        assert_eq!(policy.hedge_delay(), Some(ms(2)));
+
+        // Later samples only move the delay once the refresh interval has passed
+        for _ in 1..LATENCY_REFRESH_INTERVAL {
+            policy.record_latency(ms(10));
+        }
+        assert_eq!(policy.hedge_delay(), Some(ms(2)));
+        policy.record_latency(ms(10));
+        assert_eq!(policy.hedge_delay(), Some(ms(10)));
+    }
+
+    #[tokio::test]
+    async fn test_read_policy_hedges_respect_admission_control() {
+        let policy = test_read_policy(Some(test_hedging()));
+        // The primary holds the only permit, so there is none to hedge with
+        let rate_limiter = RateLimitPolicy::CountBasedPolicy(CountBasedPolicy::new(1));
+
+        let attempts = AtomicUsize::new(0);
+        let result = policy
+            .read(&rate_limiter, || async {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(attempt)
+            })
+            .await;
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_parts_retries_each_part() {
+        let policy = test_read_policy(None);
+        let rate_limiter = RateLimitPolicy::CountBasedPolicy(CountBasedPolicy::new(2));
+        let object = (0..10).collect::<Vec<u8>>();
+
+        // The second part fails twice before it is read
+        let attempts = Mutex::new(HashMap::<usize, usize>::new());
+        let mut output = vec![0; object.len()];
+        read_parts(
+            &policy,
+            &rate_limiter,
+            &mut output,
+            4,
+            |range: Range<usize>| {
+                let attempt = {
+                    let mut attempts = attempts.lock();
+                    let attempt = attempts.entry(range.start).or_default();
+                    *attempt += 1;
+                    *attempt
+                };
+                let part = object[range.clone()].to_vec();
+                async move {
+                    if range.start == 4 && attempt <= 2 {
+                        return Err(S3GetError::S3GetError("slow down".to_string()));
+                    }
+                    Ok(Arc::new(part))
+                }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(output, object);
+        assert_eq!(*attempts.lock(), HashMap::from([(0, 1), (4, 3), (8, 1)]));
+
+        // A part that cannot be read fails the whole read instead of leaving a gap
+        let mut output = vec![0; object.len()];
+        let result = read_parts(&policy, &rate_limiter, &mut output, 4, |range| {
+            let part = object[range.clone()].to_vec();
+            async move {
+                if range.start == 8 {
+                    return Err(S3GetError::NoSuchKey("key".to_string()));
+                }
+                Ok(Arc::new(part))
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(S3GetError::NoSuchKey(_))));
+
+        // So does a short part
+        let mut output = vec![0; object.len()];
+        let result = read_parts(&policy, &rate_limiter, &mut output, 4, |_| async {
+            Ok(Arc::new(vec![0; 2]))
+        })
+        .await;
+        assert!(matches!(result, Err(S3GetError::ByteStreamError(_))));
+    }
+
     fn get_s3_client() -> aws_sdk_s3::Client {
         // Set up credentials assuming minio is running locally
         let cred = aws_sdk_s3::config::Credentials::new(
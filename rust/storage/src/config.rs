@@ -18,6 +18,10 @@ pub enum StorageConfig {
     Local(LocalStorageConfig),
     #[serde(alias = "admissioncontrolleds3")]
     AdmissionControlledS3(AdmissionControlledS3StorageConfig),
+    #[serde(alias = "memory")]
+    InMemory(InMemoryStorageConfig),
+    #[serde(alias = "encrypted")]
+    Encrypted(EncryptedStorageConfig),
 }
 
 impl Default for StorageConfig {
@@ -120,12 +124,78 @@ pub struct LocalStorageConfig {
     pub root: String,
 }
 
+#[derive(Default, Deserialize, Debug, Clone, Serialize)]
+/// The configuration for the in-memory storage type
+/// # Fields
+/// - faults: The faults to inject into storage operations.
+/// # Notes
+/// Objects are lost when the process exits.
+/// This is intended for tests and ephemeral deployments.
+pub struct InMemoryStorageConfig {
+    #[serde(default)]
+    pub faults: FaultInjectionConfig,
+}
+
+#[derive(Default, Deserialize, Debug, Clone, PartialEq, Serialize)]
+/// Faults injected into the operations of an in-memory storage.
+/// # Fields
+/// - latency_ms: The latency added to every operation.
+/// - read_error_rate: The probability in [0, 1] that a get or list fails.
+/// - write_error_rate: The probability in [0, 1] that a put, copy, rename or delete fails.
+/// - partial_write_rate: The probability in [0, 1] that a put stores a truncated object and fails.
+pub struct FaultInjectionConfig {
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub read_error_rate: f64,
+    #[serde(default)]
+    pub write_error_rate: f64,
+    #[serde(default)]
+    pub partial_write_rate: f64,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+/// The configuration for client-side encryption of another storage
+/// # Fields
+/// - storage: The storage that holds the encrypted objects.
+/// - key_provider: Where the key-encryption keys come from.
+/// - key_id: The key for objects that do not belong to a tenant. Tenant objects use the key the
+///   key provider assigns to their tenant.
+/// - allow_plaintext: Read objects without an encryption header as plaintext. Only meant for
+///   migrating data written before encryption was enabled; off by default.
+pub struct EncryptedStorageConfig {
+    pub storage: Box<StorageConfig>,
+    pub key_provider: KeyProviderConfig,
+    pub key_id: String,
+    #[serde(default)]
+    pub allow_plaintext: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProviderConfig {
+    Keyfile(KeyfileKeyProviderConfig),
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+/// The configuration for a key provider backed by a local keyfile
+/// # Fields
+/// - path: The path of a JSON file mapping key ids to hex-encoded 256-bit keys, and optionally
+///   tenants to key ids.
+pub struct KeyfileKeyProviderConfig {
+    pub path: String,
+}
+
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct AdmissionControlledS3StorageConfig {
     #[serde(default)]
     pub s3_config: S3StorageConfig,
     #[serde(default)]
     pub rate_limiting_policy: RateLimitingConfig,
+    #[serde(default)]
+    pub retry: ReadRetryConfig,
+    #[serde(default)]
+    pub hedging: Option<HedgingConfig>,
 }
 
 impl Default for AdmissionControlledS3StorageConfig {
@@ -140,6 +210,107 @@ impl Default for AdmissionControlledS3StorageConfig {
                 download_part_size_bytes: S3StorageConfig::default_download_part_size_bytes(),
             },
             rate_limiting_policy: RateLimitingConfig::default(),
+            retry: ReadRetryConfig::default(),
+            hedging: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+/// The retry policy for reads from s3.
+/// # Fields
+/// - factor: The factor to multiply the delay by after each attempt.
+/// - min_delay_ms: The delay before the first retry.
+/// - max_delay_ms: The maximum delay between retries.
+/// - max_attempts: The maximum number of retries.
+/// - jitter: Whether to randomize the delays.
+pub struct ReadRetryConfig {
+    #[serde(default = "ReadRetryConfig::default_factor")]
+    pub factor: f32,
+    #[serde(default = "ReadRetryConfig::default_min_delay_ms")]
+    pub min_delay_ms: u64,
+    #[serde(default = "ReadRetryConfig::default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    #[serde(default = "ReadRetryConfig::default_max_attempts")]
+    pub max_attempts: usize,
+    #[serde(default = "ReadRetryConfig::default_jitter")]
+    pub jitter: bool,
+}
+
+impl ReadRetryConfig {
+    fn default_factor() -> f32 {
+        2.0
+    }
+
+    fn default_min_delay_ms() -> u64 {
+        50
+    }
+
+    fn default_max_delay_ms() -> u64 {
+        1000
+    }
+
+    fn default_max_attempts() -> usize {
+        3
+    }
+
+    fn default_jitter() -> bool {
+        true
+    }
+}
+
+impl Default for ReadRetryConfig {
+    fn default() -> Self {
+        ReadRetryConfig {
+            factor: ReadRetryConfig::default_factor(),
+            min_delay_ms: ReadRetryConfig::default_min_delay_ms(),
+            max_delay_ms: ReadRetryConfig::default_max_delay_ms(),
+            max_attempts: ReadRetryConfig::default_max_attempts(),
+            jitter: ReadRetryConfig::default_jitter(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+/// The policy for hedging reads from s3: when a read takes longer than the given
+/// percentile of recent read latencies, a second identical request is sent and
+/// whichever finishes first wins.
+/// # Fields
+/// - latency_percentile: The percentile in [0, 1] of recent latencies after which to hedge.
+/// - min_samples: The number of latencies to observe before trusting the percentile.
+/// - initial_delay_ms: The delay before hedging while fewer than min_samples have been observed.
+/// # Notes
+/// Hedges only go out if the rate limiting policy has a permit available right away,
+/// so they never queue ahead of regular requests.
+pub struct HedgingConfig {
+    #[serde(default = "HedgingConfig::default_latency_percentile")]
+    pub latency_percentile: f64,
+    #[serde(default = "HedgingConfig::default_min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "HedgingConfig::default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+}
+
+impl HedgingConfig {
+    fn default_latency_percentile() -> f64 {
+        0.95
+    }
+
+    fn default_min_samples() -> usize {
+        100
+    }
+
+    fn default_initial_delay_ms() -> u64 {
+        500
+    }
+}
+
+impl Default for HedgingConfig {
+    fn default() -> Self {
+        HedgingConfig {
+            latency_percentile: HedgingConfig::default_latency_percentile(),
+            min_samples: HedgingConfig::default_min_samples(),
+            initial_delay_ms: HedgingConfig::default_initial_delay_ms(),
         }
     }
 }
@@ -0,0 +1,626 @@
+// Client-side envelope encryption for everything persisted through `Storage`.
+// Every object is encrypted with a fresh data key, and the data key is wrapped
+// with a key-encryption key (KEK) fetched from a pluggable `KeyProvider`.
+// The id of the KEK travels in the object header, so objects stay readable after
+// the configured key id is rotated, as long as the provider still knows the old key.
+// Writes made through a tenant-scoped handle (`Storage::for_tenant`) use the key the
+// provider assigns to that tenant, so tenants sharing a bucket never share a KEK.
+// The ciphertext is authenticated together with the key id and the storage key of the
+// object, so an object moved or copied to another key by the backing store fails to decrypt.
+//
+// Layout of an encrypted object:
+// | magic | key id length (u16 LE) | key id | wrapped data key nonce | wrapped data key | nonce | ciphertext |
+
+use super::config::{KeyProviderConfig, StorageConfig};
+use super::{GetError, ListPage, PutError, RenameError, Storage, StorageConfigError};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use chroma_config::registry::Registry;
+use chroma_config::Configurable;
+use chroma_error::{ChromaError, ErrorCodes};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+use thiserror::Error;
+
+const MAGIC: &[u8] = b"CHROMAENC1";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+// A wrapped data key is the encrypted key followed by the GCM tag
+const WRAPPED_KEY_LEN: usize = KEY_LEN + 16;
+
+#[derive(Error, Debug, Clone)]
+pub enum EncryptionError {
+    #[error("No encryption key with id {0}")]
+    NoSuchKey(String),
+    #[error("No encryption key configured for tenant {0}")]
+    NoTenantKey(String),
+    #[error("Invalid encryption key {0}: {1}")]
+    InvalidKey(String, String),
+    #[error("Failed to load keyfile: {0}")]
+    Keyfile(String),
+    #[error("Failed to encrypt object")]
+    Encrypt,
+    #[error("Failed to decrypt object: {0}")]
+    Decrypt(String),
+    #[error("Object is not encrypted")]
+    NotEncrypted,
+    #[error("Ranged reads of encrypted objects are not supported")]
+    RangeUnsupported,
+}
+
+impl ChromaError for EncryptionError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            EncryptionError::NoSuchKey(_) => ErrorCodes::NotFound,
+            EncryptionError::NoTenantKey(_) => ErrorCodes::FailedPrecondition,
+            EncryptionError::InvalidKey(_, _) => ErrorCodes::InvalidArgument,
+            EncryptionError::Keyfile(_) => ErrorCodes::InvalidArgument,
+            EncryptionError::Encrypt => ErrorCodes::Internal,
+            EncryptionError::Decrypt(_) => ErrorCodes::DataLoss,
+            EncryptionError::NotEncrypted => ErrorCodes::DataLoss,
+            EncryptionError::RangeUnsupported => ErrorCodes::Unimplemented,
+        }
+    }
+}
+
+/// A 256-bit AES key. The bytes are never printed.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    pub fn new(bytes: [u8; KEY_LEN]) -> Self {
+        EncryptionKey(bytes)
+    }
+
+    /// Parses a key from its hex encoding.
+    pub fn from_hex(key_id: &str, hex: &str) -> Result<Self, EncryptionError> {
+        let hex = hex.trim();
+        if hex.len() != KEY_LEN * 2 || !hex.is_ascii() {
+            return Err(EncryptionError::InvalidKey(
+                key_id.to_string(),
+                format!("expected {} hex characters", KEY_LEN * 2),
+            ));
+        }
+        let mut bytes = [0u8; KEY_LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|e| EncryptionError::InvalidKey(key_id.to_string(), e.to_string()))?;
+        }
+        Ok(EncryptionKey(bytes))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptionKey(<redacted>)")
+    }
+}
+
+/// Supplies the key-encryption keys used to wrap per-object data keys.
+/// Implementations may fetch keys from a KMS; they should cache them, since
+/// every storage operation looks its key up.
+#[async_trait]
+pub trait KeyProvider: Send + Sync + Debug {
+    async fn get_key(&self, key_id: &str) -> Result<EncryptionKey, EncryptionError>;
+
+    /// The id of the key that new objects of `tenant` are encrypted under.
+    async fn key_id_for_tenant(&self, tenant: &str) -> Result<String, EncryptionError>;
+}
+
+#[derive(Deserialize)]
+struct Keyfile {
+    keys: HashMap<String, String>,
+    #[serde(default)]
+    tenants: HashMap<String, String>,
+}
+
+/// A key provider backed by a local JSON keyfile of the form
+/// `{"keys": {"<key id>": "<64 hex characters>"}, "tenants": {"<tenant>": "<key id>"}}`.
+/// Tenants missing from `tenants` use the key whose id is the tenant name, if there is one.
+#[derive(Debug, Clone)]
+pub struct KeyfileKeyProvider {
+    keys: HashMap<String, EncryptionKey>,
+    tenants: HashMap<String, String>,
+}
+
+impl KeyfileKeyProvider {
+    pub fn new(keys: HashMap<String, EncryptionKey>) -> Self {
+        KeyfileKeyProvider {
+            keys,
+            tenants: HashMap::new(),
+        }
+    }
+
+    pub fn with_tenant_keys(mut self, tenants: HashMap<String, String>) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, EncryptionError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| EncryptionError::Keyfile(e.to_string()))?;
+        let keyfile: Keyfile =
+            serde_json::from_str(&contents).map_err(|e| EncryptionError::Keyfile(e.to_string()))?;
+        let keys = keyfile
+            .keys
+            .iter()
+            .map(|(key_id, hex)| Ok((key_id.clone(), EncryptionKey::from_hex(key_id, hex)?)))
+            .collect::<Result<HashMap<_, _>, EncryptionError>>()?;
+        if let Some((tenant, key_id)) = keyfile
+            .tenants
+            .iter()
+            .find(|(_, key_id)| !keys.contains_key(*key_id))
+        {
+            return Err(EncryptionError::Keyfile(format!(
+                "tenant {} uses unknown key {}",
+                tenant, key_id
+            )));
+        }
+        Ok(KeyfileKeyProvider {
+            keys,
+            tenants: keyfile.tenants,
+        })
+    }
+}
+
+#[async_trait]
+impl KeyProvider for KeyfileKeyProvider {
+    async fn get_key(&self, key_id: &str) -> Result<EncryptionKey, EncryptionError> {
+        self.keys
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| EncryptionError::NoSuchKey(key_id.to_string()))
+    }
+
+    async fn key_id_for_tenant(&self, tenant: &str) -> Result<String, EncryptionError> {
+        match self.tenants.get(tenant) {
+            Some(key_id) => Ok(key_id.clone()),
+            None if self.keys.contains_key(tenant) => Ok(tenant.to_string()),
+            None => Err(EncryptionError::NoTenantKey(tenant.to_string())),
+        }
+    }
+}
+
+/// The additional authenticated data of an object's ciphertext
+fn data_aad(key_id: &str, key: &str) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(MAGIC.len() + 2 + key_id.len() + key.len());
+    aad.extend_from_slice(MAGIC);
+    aad.extend_from_slice(&(key_id.len() as u16).to_le_bytes());
+    aad.extend_from_slice(key_id.as_bytes());
+    aad.extend_from_slice(key.as_bytes());
+    aad
+}
+
+/// Wraps another storage and transparently encrypts objects on write and decrypts them on read.
+/// New objects are encrypted under the key the provider assigns to the handle's tenant
+/// (see `for_tenant`), or under `key_id` for objects that do not belong to a tenant.
+/// Objects without an encryption header are rejected unless `allow_plaintext` is set, which
+/// is only meant for migrating data written before encryption was enabled.
+#[derive(Clone)]
+pub struct EncryptedStorage {
+    storage: Box<Storage>,
+    key_provider: Arc<dyn KeyProvider>,
+    key_id: String,
+    tenant: Option<String>,
+    allow_plaintext: bool,
+}
+
+impl EncryptedStorage {
+    pub fn new(storage: Storage, key_provider: Arc<dyn KeyProvider>, key_id: &str) -> Self {
+        EncryptedStorage {
+            storage: Box::new(storage),
+            key_provider,
+            key_id: key_id.to_string(),
+            tenant: None,
+            allow_plaintext: false,
+        }
+    }
+
+    /// Returns objects without an encryption header as is instead of failing the read.
+    pub fn with_allow_plaintext(mut self, allow_plaintext: bool) -> Self {
+        self.allow_plaintext = allow_plaintext;
+        self
+    }
+
+    /// A handle on the same storage whose writes are encrypted under `tenant`'s key.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        EncryptedStorage {
+            tenant: Some(tenant.to_string()),
+            ..self.clone()
+        }
+    }
+
+    async fn write_key_id(&self) -> Result<String, EncryptionError> {
+        match &self.tenant {
+            Some(tenant) => self.key_provider.key_id_for_tenant(tenant).await,
+            None => Ok(self.key_id.clone()),
+        }
+    }
+
+    pub async fn encrypt(&self, key: &str, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let key_id = self.write_key_id().await?;
+        let kek = self.key_provider.get_key(&key_id).await?;
+        if key_id.len() > u16::MAX as usize {
+            return Err(EncryptionError::InvalidKey(
+                key_id,
+                "key id is too long".to_string(),
+            ));
+        }
+
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let key_nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let wrapped_key = kek
+            .cipher()
+            .encrypt(
+                &key_nonce,
+                Payload {
+                    msg: data_key.as_slice(),
+                    aad: key_id.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Encrypt)?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(&data_key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &data_aad(&key_id, key),
+                },
+            )
+            .map_err(|_| EncryptionError::Encrypt)?;
+
+        let mut output = Vec::with_capacity(
+            MAGIC.len()
+                + 2
+                + key_id.len()
+                + NONCE_LEN
+                + WRAPPED_KEY_LEN
+                + NONCE_LEN
+                + ciphertext.len(),
+        );
+        output.extend_from_slice(MAGIC);
+        output.extend_from_slice(&(key_id.len() as u16).to_le_bytes());
+        output.extend_from_slice(key_id.as_bytes());
+        output.extend_from_slice(&key_nonce);
+        output.extend_from_slice(&wrapped_key);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    pub async fn decrypt(
+        &self,
+        key: &str,
+        bytes: Arc<Vec<u8>>,
+    ) -> Result<Arc<Vec<u8>>, EncryptionError> {
+        let Some(rest) = bytes.strip_prefix(MAGIC) else {
+            if self.allow_plaintext {
+                return Ok(bytes);
+            }
+            return Err(EncryptionError::NotEncrypted);
+        };
+        let malformed = || EncryptionError::Decrypt("truncated header".to_string());
+
+        let (key_id_len, rest) = rest.split_at_checked(2).ok_or_else(malformed)?;
+        let key_id_len = u16::from_le_bytes([key_id_len[0], key_id_len[1]]) as usize;
+        let (key_id, rest) = rest.split_at_checked(key_id_len).ok_or_else(malformed)?;
+        let key_id = std::str::from_utf8(key_id)
+            .map_err(|e| EncryptionError::Decrypt(format!("invalid key id: {}", e)))?;
+        let (key_nonce, rest) = rest.split_at_checked(NONCE_LEN).ok_or_else(malformed)?;
+        let (wrapped_key, rest) = rest
+            .split_at_checked(WRAPPED_KEY_LEN)
+            .ok_or_else(malformed)?;
+        let (nonce, ciphertext) = rest.split_at_checked(NONCE_LEN).ok_or_else(malformed)?;
+
+        let kek = self.key_provider.get_key(key_id).await?;
+        let data_key = kek
+            .cipher()
+            .decrypt(
+                Nonce::from_slice(key_nonce),
+                Payload {
+                    msg: wrapped_key,
+                    aad: key_id.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                EncryptionError::Decrypt(format!("cannot unwrap data key with {}", key_id))
+            })?;
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key))
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &data_aad(key_id, key),
+                },
+            )
+            .map_err(|_| EncryptionError::Decrypt("authentication failed".to_string()))?;
+        Ok(Arc::new(plaintext))
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Arc<Vec<u8>>, GetError> {
+        let bytes = Box::pin(self.storage.get(key)).await?;
+        Ok(self.decrypt(key, bytes).await?)
+    }
+
+    pub async fn get_parallel(&self, key: &str) -> Result<Arc<Vec<u8>>, GetError> {
+        let bytes = Box::pin(self.storage.get_parallel(key)).await?;
+        Ok(self.decrypt(key, bytes).await?)
+    }
+
+    /// An object is authenticated as a whole, so a range of it cannot be read without fetching
+    /// all of it. Callers that need part of an encrypted object read it with `get` instead.
+    pub async fn get_range(
+        &self,
+        _key: &str,
+        _range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, GetError> {
+        Err(EncryptionError::RangeUnsupported.into())
+    }
+
+    pub async fn put_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<(), PutError> {
+        let bytes = self.encrypt(key, &bytes).await?;
+        Box::pin(self.storage.put_bytes(key, bytes)).await
+    }
+
+    pub async fn put_file(&self, key: &str, path: &str) -> Result<(), PutError> {
+        let bytes = tokio::fs::read(path).await?;
+        self.put_bytes(key, bytes).await
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), PutError> {
+        Box::pin(self.storage.delete(key)).await
+    }
+
+    /// Objects are bound to their key, so the copy is decrypted and encrypted again for `dst_key`.
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), PutError> {
+        let bytes = self.get(src_key).await.map_err(PutError::SourceError)?;
+        self.put_bytes(dst_key, bytes.to_vec()).await
+    }
+
+    /// A copy followed by a delete, which is not atomic even if the backing storage renames
+    /// atomically.
+    pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), RenameError> {
+        self.copy(src_key, dst_key).await?;
+        Box::pin(self.storage.delete(src_key)).await?;
+        Ok(())
+    }
+
+    /// The size of the stored (encrypted) object
+    pub async fn size(&self, key: &str) -> Result<u64, GetError> {
+        Box::pin(self.storage.size(key)).await
+    }
+
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, GetError> {
+        Box::pin(self.storage.list_prefix(prefix)).await
+    }
+
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, GetError> {
+        Box::pin(self.storage.list_prefix_page(prefix, start_after, limit)).await
+    }
+}
+
+#[async_trait]
+impl Configurable<StorageConfig> for EncryptedStorage {
+    async fn try_from_config(
+        config: &StorageConfig,
+        registry: &Registry,
+    ) -> Result<Self, Box<dyn ChromaError>> {
+        match &config {
+            StorageConfig::Encrypted(encrypted_config) => {
+                let storage = Storage::try_from_config(&encrypted_config.storage, registry).await?;
+                let key_provider: Arc<dyn KeyProvider> = match &encrypted_config.key_provider {
+                    KeyProviderConfig::Keyfile(keyfile_config) => Arc::new(
+                        KeyfileKeyProvider::from_path(&keyfile_config.path)
+                            .map_err(|e| e.boxed())?,
+                    ),
+                };
+                // Fail at startup rather than on the first write if the key is missing
+                key_provider
+                    .get_key(&encrypted_config.key_id)
+                    .await
+                    .map_err(|e| e.boxed())?;
+                Ok(
+                    EncryptedStorage::new(storage, key_provider, &encrypted_config.key_id)
+                        .with_allow_plaintext(encrypted_config.allow_plaintext),
+                )
+            }
+            _ => Err(Box::new(StorageConfigError::InvalidStorageConfig)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStorage;
+
+    fn key_provider() -> Arc<dyn KeyProvider> {
+        Arc::new(KeyfileKeyProvider::new(HashMap::from([
+            ("tenant-a".to_string(), EncryptionKey::new([1; KEY_LEN])),
+            ("tenant-b".to_string(), EncryptionKey::new([2; KEY_LEN])),
+        ])))
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        let inner = InMemoryStorage::new();
+        let storage =
+            EncryptedStorage::new(Storage::InMemory(inner.clone()), key_provider(), "tenant-a");
+
+        let plaintext = b"block bytes".repeat(100);
+        storage
+            .put_bytes("block/1", plaintext.clone())
+            .await
+            .unwrap();
+        assert_eq!(storage.get("block/1").await.unwrap().as_slice(), plaintext);
+        assert!(matches!(
+            storage.get_range("block/1", 6..11).await,
+            Err(GetError::EncryptionError(EncryptionError::RangeUnsupported))
+        ));
+
+        // The backing storage only ever sees ciphertext
+        let stored = inner.get("block/1").await.unwrap();
+        assert!(stored.starts_with(MAGIC));
+        assert!(!stored.windows(11).any(|window| window == b"block bytes"));
+
+        // Objects without a header are rejected unless plaintext reads are allowed for a migration
+        inner.put_bytes("legacy", b"plain".to_vec()).await.unwrap();
+        assert!(matches!(
+            storage.get("legacy").await,
+            Err(GetError::EncryptionError(EncryptionError::NotEncrypted))
+        ));
+        let migrating = storage.with_allow_plaintext(true);
+        assert_eq!(migrating.get("legacy").await.unwrap().as_slice(), b"plain");
+    }
+
+    #[tokio::test]
+    async fn test_tenant_scoped_keys() {
+        let inner = InMemoryStorage::new();
+        let key_provider = Arc::new(
+            KeyfileKeyProvider::new(HashMap::from([
+                ("default".to_string(), EncryptionKey::new([0; KEY_LEN])),
+                ("tenant-a".to_string(), EncryptionKey::new([1; KEY_LEN])),
+                ("kek-b".to_string(), EncryptionKey::new([2; KEY_LEN])),
+            ]))
+            .with_tenant_keys(HashMap::from([(
+                "tenant-b".to_string(),
+                "kek-b".to_string(),
+            )])),
+        );
+        let storage =
+            EncryptedStorage::new(Storage::InMemory(inner.clone()), key_provider, "default");
+
+        let key_id_of = |bytes: &[u8]| {
+            let len = u16::from_le_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]) as usize;
+            String::from_utf8(bytes[MAGIC.len() + 2..MAGIC.len() + 2 + len].to_vec()).unwrap()
+        };
+
+        storage.put_bytes("shared", b"0".to_vec()).await.unwrap();
+        storage
+            .for_tenant("tenant-a")
+            .put_bytes("a", b"a".to_vec())
+            .await
+            .unwrap();
+        storage
+            .for_tenant("tenant-b")
+            .put_bytes("b", b"b".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(key_id_of(&inner.get("shared").await.unwrap()), "default");
+        assert_eq!(key_id_of(&inner.get("a").await.unwrap()), "tenant-a");
+        assert_eq!(key_id_of(&inner.get("b").await.unwrap()), "kek-b");
+
+        // Any handle can read objects of any tenant whose key the provider knows
+        assert_eq!(storage.get("b").await.unwrap().as_slice(), b"b");
+        assert_eq!(
+            storage
+                .for_tenant("tenant-a")
+                .get("b")
+                .await
+                .unwrap()
+                .as_slice(),
+            b"b"
+        );
+
+        // Tenants without a key cannot write instead of silently using the default key
+        assert!(matches!(
+            storage
+                .for_tenant("tenant-c")
+                .put_bytes("c", b"c".to_vec())
+                .await,
+            Err(PutError::EncryptionError(EncryptionError::NoTenantKey(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_and_tampering() {
+        let inner = InMemoryStorage::new();
+        let old =
+            EncryptedStorage::new(Storage::InMemory(inner.clone()), key_provider(), "tenant-a");
+        old.put_bytes("key", b"secret".to_vec()).await.unwrap();
+
+        // Readers configured with another key id still find the key the object was written with
+        let rotated =
+            EncryptedStorage::new(Storage::InMemory(inner.clone()), key_provider(), "tenant-b");
+        assert_eq!(rotated.get("key").await.unwrap().as_slice(), b"secret");
+
+        // But not if the provider no longer has it
+        let without_key = EncryptedStorage::new(
+            Storage::InMemory(inner.clone()),
+            Arc::new(KeyfileKeyProvider::new(HashMap::new())),
+            "tenant-a",
+        );
+        assert!(matches!(
+            without_key.get("key").await,
+            Err(GetError::EncryptionError(EncryptionError::NoSuchKey(_)))
+        ));
+
+        // Objects only decrypt under the key they were written to
+        old.put_bytes("other", b"other".to_vec()).await.unwrap();
+        inner.copy("other", "swapped").await.unwrap();
+        assert!(matches!(
+            old.get("swapped").await,
+            Err(GetError::EncryptionError(EncryptionError::Decrypt(_)))
+        ));
+        inner.rename("other", "key").await.unwrap();
+        assert!(matches!(
+            old.get("key").await,
+            Err(GetError::EncryptionError(EncryptionError::Decrypt(_)))
+        ));
+        old.put_bytes("key", b"secret".to_vec()).await.unwrap();
+
+        let mut tampered = inner.get("key").await.unwrap().to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        inner.put_bytes("key", tampered).await.unwrap();
+        assert!(matches!(
+            old.get("key").await,
+            Err(GetError::EncryptionError(EncryptionError::Decrypt(_)))
+        ));
+    }
+
+    #[test]
+    fn test_keyfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+        std::fs::write(
+            &path,
+            format!(r#"{{"keys": {{"tenant-a": "{}"}}}}"#, "ab".repeat(KEY_LEN)),
+        )
+        .unwrap();
+        assert!(KeyfileKeyProvider::from_path(path.to_str().unwrap()).is_ok());
+
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"keys": {{"tenant-a": "{}"}}, "tenants": {{"tenant-b": "missing"}}}}"#,
+                "ab".repeat(KEY_LEN)
+            ),
+        )
+        .unwrap();
+        assert!(matches!(
+            KeyfileKeyProvider::from_path(path.to_str().unwrap()),
+            Err(EncryptionError::Keyfile(_))
+        ));
+
+        std::fs::write(&path, r#"{"keys": {"tenant-a": "not hex"}}"#).unwrap();
+        assert!(matches!(
+            KeyfileKeyProvider::from_path(path.to_str().unwrap()),
+            Err(EncryptionError::InvalidKey(_, _))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 use self::config::StorageConfig;
@@ -6,10 +7,14 @@ use admissioncontrolleds3::AdmissionControlledS3StorageError;
 use async_trait::async_trait;
 use chroma_config::{registry::Registry, Configurable};
 use chroma_error::{ChromaError, ErrorCodes};
+use encryption::EncryptionError;
+use memory::InMemoryStorageError;
 
 pub mod admissioncontrolleds3;
 pub mod config;
+pub mod encryption;
 pub mod local;
+pub mod memory;
 pub mod object_store;
 pub mod s3;
 pub mod stream;
@@ -23,6 +28,33 @@ pub enum Storage {
     S3(s3::S3Storage),
     Local(local::LocalStorage),
     AdmissionControlledS3(admissioncontrolleds3::AdmissionControlledS3Storage),
+    InMemory(memory::InMemoryStorage),
+    Encrypted(encryption::EncryptedStorage),
+}
+
+/// A page of keys returned by `Storage::list_prefix_page`.
+/// `next_start_after` is set when more keys may follow and should be passed back to fetch the next page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListPage {
+    pub keys: Vec<String>,
+    pub next_start_after: Option<String>,
+}
+
+impl ListPage {
+    /// Builds a page from `keys`, which must already be sorted and filtered to the keys after
+    /// `start_after`. At most `limit + 1` keys are consumed, so lazily listed keys are only
+    /// read up to the end of the page.
+    pub(crate) fn from_sorted_keys(mut keys: impl Iterator<Item = String>, limit: usize) -> Self {
+        let page = keys.by_ref().take(limit).collect::<Vec<_>>();
+        let next_start_after = match keys.next() {
+            Some(_) => page.last().cloned(),
+            None => None,
+        };
+        ListPage {
+            keys: page,
+            next_start_after,
+        }
+    }
 }
 
 #[derive(Error, Debug, Clone)]
@@ -35,6 +67,14 @@ pub enum GetError {
     S3Error(#[from] S3GetError),
     #[error("Local storage error: {0}")]
     LocalError(String),
+    #[error("In-memory storage error: {0}")]
+    InMemoryError(InMemoryStorageError),
+    #[error("Range {range:?} is out of bounds for key {key}")]
+    RangeOutOfBounds { key: String, range: Range<usize> },
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+    #[error("The limit of a list page must be positive")]
+    InvalidListLimit,
 }
 
 impl ChromaError for GetError {
@@ -44,6 +84,10 @@ impl ChromaError for GetError {
             GetError::ObjectStoreError(_) => ErrorCodes::Internal,
             GetError::S3Error(_) => ErrorCodes::Internal,
             GetError::LocalError(_) => ErrorCodes::Internal,
+            GetError::InMemoryError(_) => ErrorCodes::Internal,
+            GetError::RangeOutOfBounds { .. } => ErrorCodes::InvalidArgument,
+            GetError::EncryptionError(e) => e.code(),
+            GetError::InvalidListLimit => ErrorCodes::InvalidArgument,
         }
     }
 }
@@ -59,6 +103,21 @@ impl From<::object_store::Error> for GetError {
     }
 }
 
+impl From<InMemoryStorageError> for GetError {
+    fn from(e: InMemoryStorageError) -> Self {
+        match e {
+            InMemoryStorageError::NoSuchKey(key) => GetError::NoSuchKey(key),
+            InMemoryStorageError::RangeOutOfBounds {
+                key, start, end, ..
+            } => GetError::RangeOutOfBounds {
+                key,
+                range: start..end,
+            },
+            _ => GetError::InMemoryError(e),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum PutError {
     #[error("ObjectStore error: {0}")]
@@ -67,6 +126,12 @@ pub enum PutError {
     S3Error(#[from] s3::S3PutError),
     #[error("Local storage error: {0}")]
     LocalError(String),
+    #[error("In-memory storage error: {0}")]
+    InMemoryError(InMemoryStorageError),
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+    #[error("Failed to read the source object: {0}")]
+    SourceError(GetError),
 }
 
 impl ChromaError for PutError {
@@ -75,6 +140,9 @@ impl ChromaError for PutError {
             PutError::ObjectStoreError(_) => ErrorCodes::Internal,
             PutError::S3Error(_) => ErrorCodes::Internal,
             PutError::LocalError(_) => ErrorCodes::Internal,
+            PutError::InMemoryError(_) => ErrorCodes::Internal,
+            PutError::EncryptionError(e) => e.code(),
+            PutError::SourceError(e) => e.code(),
         }
     }
 }
@@ -116,6 +184,12 @@ pub enum RenameError {
     S3Error(#[from] s3::S3PutError),
     #[error("Local storage error: {0}")]
     LocalError(String),
+    #[error("In-memory storage error: {0}")]
+    InMemoryError(InMemoryStorageError),
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+    #[error("Failed to read the source object: {0}")]
+    SourceError(GetError),
 }
 
 impl ChromaError for RenameError {
@@ -124,6 +198,22 @@ impl ChromaError for RenameError {
             RenameError::ObjectStoreError(_) => ErrorCodes::Internal,
             RenameError::S3Error(_) => ErrorCodes::Internal,
             RenameError::LocalError(_) => ErrorCodes::Internal,
+            RenameError::InMemoryError(_) => ErrorCodes::Internal,
+            RenameError::EncryptionError(e) => e.code(),
+            RenameError::SourceError(e) => e.code(),
+        }
+    }
+}
+
+impl From<PutError> for RenameError {
+    fn from(e: PutError) -> Self {
+        match e {
+            PutError::ObjectStoreError(e) => RenameError::ObjectStoreError(e),
+            PutError::S3Error(e) => RenameError::S3Error(e),
+            PutError::LocalError(e) => RenameError::LocalError(e),
+            PutError::InMemoryError(e) => RenameError::InMemoryError(e),
+            PutError::EncryptionError(e) => RenameError::EncryptionError(e),
+            PutError::SourceError(e) => RenameError::SourceError(e),
         }
     }
 }
@@ -135,6 +225,15 @@ impl From<::object_store::Error> for RenameError {
 }
 
 impl Storage {
+    /// A handle on the same storage for objects that belong to `tenant`.
+    /// Only encrypted storage behaves differently: it encrypts writes under the tenant's key.
+    pub fn for_tenant(&self, tenant: &str) -> Storage {
+        match self {
+            Storage::Encrypted(encrypted) => Storage::Encrypted(encrypted.for_tenant(tenant)),
+            _ => self.clone(),
+        }
+    }
+
     pub async fn get(&self, key: &str) -> Result<Arc<Vec<u8>>, GetError> {
         match self {
             Storage::ObjectStore(object_store) => object_store.get(key).await,
@@ -167,6 +266,8 @@ impl Storage {
                     },
                 }
             }
+            Storage::InMemory(memory) => Ok(memory.get(key).await?),
+            Storage::Encrypted(encrypted) => encrypted.get(key).await,
         }
     }
 
@@ -204,7 +305,52 @@ impl Storage {
                     },
                 }
             }
+            Storage::InMemory(memory) => Ok(memory.get(key).await?),
+            Storage::Encrypted(encrypted) => encrypted.get_parallel(key).await,
+        }
+    }
+
+    /// Fetches the bytes of `key` in `range`, without reading the rest of the object.
+    /// The range must lie within the object.
+    pub async fn get_range(
+        &self,
+        key: &str,
+        range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, GetError> {
+        if range.is_empty() {
+            return Ok(Arc::new(Vec::new()));
+        }
+        let bytes = match self {
+            Storage::ObjectStore(object_store) => object_store.get_range(key, range.clone()).await,
+            Storage::S3(s3) => s3.get_range(key, range.clone()).await.map_err(|e| match e {
+                S3GetError::NoSuchKey(_) => GetError::NoSuchKey(key.to_string()),
+                _ => GetError::S3Error(e),
+            }),
+            Storage::Local(local) => local
+                .get_range(key, range.clone())
+                .await
+                .map_err(GetError::LocalError),
+            Storage::AdmissionControlledS3(as3) => {
+                as3.get_range(key, range.clone())
+                    .await
+                    .map_err(|e| match e {
+                        AdmissionControlledS3StorageError::S3GetError(S3GetError::NoSuchKey(_)) => {
+                            GetError::NoSuchKey(key.to_string())
+                        }
+                        AdmissionControlledS3StorageError::S3GetError(e) => GetError::S3Error(e),
+                    })
+            }
+            Storage::InMemory(memory) => Ok(memory.get_range(key, range.clone()).await?),
+            Storage::Encrypted(encrypted) => encrypted.get_range(key, range.clone()).await,
+        }?;
+        // Backends clamp ranges that run past the end of the object, so check we got all of it
+        if bytes.len() != range.len() {
+            return Err(GetError::RangeOutOfBounds {
+                key: key.to_string(),
+                range,
+            });
         }
+        Ok(bytes)
     }
 
     pub async fn put_file(&self, key: &str, path: &str) -> Result<(), PutError> {
@@ -218,6 +364,11 @@ impl Storage {
             Storage::AdmissionControlledS3(as3) => {
                 as3.put_file(key, path).await.map_err(PutError::S3Error)
             }
+            Storage::InMemory(memory) => memory
+                .put_file(key, path)
+                .await
+                .map_err(PutError::InMemoryError),
+            Storage::Encrypted(encrypted) => encrypted.put_file(key, path).await,
         }
     }
 
@@ -232,6 +383,11 @@ impl Storage {
             Storage::AdmissionControlledS3(as3) => {
                 as3.put_bytes(key, bytes).await.map_err(PutError::S3Error)
             }
+            Storage::InMemory(memory) => memory
+                .put_bytes(key, bytes)
+                .await
+                .map_err(PutError::InMemoryError),
+            Storage::Encrypted(encrypted) => encrypted.put_bytes(key, bytes).await,
         }
     }
 
@@ -240,22 +396,40 @@ impl Storage {
             Storage::ObjectStore(object_store) => object_store.delete(key).await,
             Storage::S3(s3) => s3.delete(key).await.map_err(PutError::S3Error),
             Storage::Local(local) => local.delete(key).await.map_err(PutError::LocalError),
-            Storage::AdmissionControlledS3(_) => {
-                unimplemented!("delete not implemented for AdmissionControlledS3")
+            Storage::AdmissionControlledS3(as3) => as3.delete(key).await.map_err(PutError::S3Error),
+            Storage::InMemory(memory) => memory.delete(key).await.map_err(PutError::InMemoryError),
+            Storage::Encrypted(encrypted) => encrypted.delete(key).await,
+        }
+    }
+
+    /// Copies `src_key` to `dst_key`, overwriting the destination if it exists.
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), PutError> {
+        match self {
+            Storage::ObjectStore(object_store) => object_store.copy(src_key, dst_key).await,
+            Storage::S3(s3) => s3.copy(src_key, dst_key).await.map_err(PutError::S3Error),
+            Storage::Local(local) => local
+                .copy(src_key, dst_key)
+                .await
+                .map_err(PutError::LocalError),
+            Storage::AdmissionControlledS3(as3) => {
+                as3.copy(src_key, dst_key).await.map_err(PutError::S3Error)
             }
+            Storage::InMemory(memory) => memory
+                .copy(src_key, dst_key)
+                .await
+                .map_err(PutError::InMemoryError),
+            Storage::Encrypted(encrypted) => encrypted.copy(src_key, dst_key).await,
         }
     }
 
+    /// Moves `src_key` to `dst_key`, overwriting the destination if it exists.
+    /// On object stores this is a copy followed by a delete and is not atomic.
     pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), RenameError> {
         match self {
             Storage::ObjectStore(object_store) => object_store
                 .rename(src_key, dst_key)
                 .await
-                .map_err(|e| match e {
-                    PutError::ObjectStoreError(e) => RenameError::ObjectStoreError(e),
-                    PutError::S3Error(e) => RenameError::S3Error(e),
-                    PutError::LocalError(e) => RenameError::LocalError(e),
-                }),
+                .map_err(RenameError::from),
             Storage::S3(s3) => s3
                 .rename(src_key, dst_key)
                 .await
@@ -264,23 +438,82 @@ impl Storage {
                 .rename(src_key, dst_key)
                 .await
                 .map_err(RenameError::LocalError),
-            Storage::AdmissionControlledS3(_) => {
-                unimplemented!("rename not implemented for AdmissionControlledS3")
-            }
+            Storage::AdmissionControlledS3(as3) => as3
+                .rename(src_key, dst_key)
+                .await
+                .map_err(RenameError::S3Error),
+            Storage::InMemory(memory) => memory
+                .rename(src_key, dst_key)
+                .await
+                .map_err(RenameError::InMemoryError),
+            Storage::Encrypted(encrypted) => encrypted.rename(src_key, dst_key).await,
+        }
+    }
+
+    /// Returns the size in bytes of the object stored at `key`.
+    pub async fn size(&self, key: &str) -> Result<u64, GetError> {
+        match self {
+            Storage::ObjectStore(object_store) => object_store.size(key).await,
+            Storage::S3(s3) => Ok(s3.size(key).await? as u64),
+            Storage::Local(local) => local.size(key).await.map_err(GetError::LocalError),
+            Storage::AdmissionControlledS3(as3) => Ok(as3.size(key).await? as u64),
+            Storage::InMemory(memory) => Ok(memory.size(key).await?),
+            Storage::Encrypted(encrypted) => encrypted.size(key).await,
         }
     }
 
+    /// Lists every key under `prefix` in lexicographic order.
+    /// Prefixes should end at a path segment (e.g. `"hnsw/"`), since object stores match whole segments.
     pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, GetError> {
         match self {
-            Storage::Local(_) => {
-                unimplemented!("list_prefix not implemented for LocalStorage")
+            Storage::Local(local) => local
+                .list_prefix(prefix)
+                .await
+                .map_err(GetError::LocalError),
+            Storage::S3(s3) => s3.list_prefix(prefix).await.map_err(GetError::S3Error),
+            Storage::ObjectStore(object_store) => object_store.list_prefix(prefix).await,
+            Storage::AdmissionControlledS3(as3) => {
+                as3.list_prefix(prefix).await.map_err(GetError::S3Error)
             }
-            Storage::S3(_) => {
-                unimplemented!("list_prefix not implemented for S3")
+            Storage::InMemory(memory) => Ok(memory.list_prefix(prefix).await?),
+            Storage::Encrypted(encrypted) => encrypted.list_prefix(prefix).await,
+        }
+    }
+
+    /// Lists at most `limit` keys under `prefix` that sort strictly after `start_after`.
+    /// `limit` must be positive, otherwise a page could never report that more keys follow.
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, GetError> {
+        if limit == 0 {
+            return Err(GetError::InvalidListLimit);
+        }
+        match self {
+            Storage::Local(local) => local
+                .list_prefix_page(prefix, start_after, limit)
+                .await
+                .map_err(GetError::LocalError),
+            Storage::S3(s3) => s3
+                .list_prefix_page(prefix, start_after, limit)
+                .await
+                .map_err(GetError::S3Error),
+            Storage::ObjectStore(object_store) => {
+                object_store
+                    .list_prefix_page(prefix, start_after, limit)
+                    .await
             }
-            Storage::ObjectStore(object_store) => object_store.list_prefix(prefix).await,
-            Storage::AdmissionControlledS3(_) => {
-                unimplemented!("list_prefix not implemented for AdmissionControlledS3")
+            Storage::AdmissionControlledS3(as3) => as3
+                .list_prefix_page(prefix, start_after, limit)
+                .await
+                .map_err(GetError::S3Error),
+            Storage::InMemory(memory) => {
+                Ok(memory.list_prefix_page(prefix, start_after, limit).await?)
+            }
+            Storage::Encrypted(encrypted) => {
+                encrypted.list_prefix_page(prefix, start_after, limit).await
             }
         }
     }
@@ -308,6 +541,12 @@ impl Configurable<StorageConfig> for Storage {
                 )
                 .await?,
             )),
+            StorageConfig::InMemory(_) => Ok(Storage::InMemory(
+                memory::InMemoryStorage::try_from_config(config, registry).await?,
+            )),
+            StorageConfig::Encrypted(_) => Ok(Storage::Encrypted(
+                encryption::EncryptedStorage::try_from_config(config, registry).await?,
+            )),
         }
     }
 }
@@ -321,3 +560,196 @@ pub fn test_storage() -> Storage {
             .expect("Should be able to convert temporary directory path to string"),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Behaviour every storage backend is expected to share
+    async fn run_conformance_suite(storage: Storage) {
+        for key in ["a/1", "a/2", "a/3", "a/nested/4", "b/1"] {
+            storage
+                .put_bytes(key, key.as_bytes().to_vec())
+                .await
+                .expect("Put should succeed");
+        }
+
+        // Listing
+        assert_eq!(
+            storage.list_prefix("a/").await.unwrap(),
+            vec!["a/1", "a/2", "a/3", "a/nested/4"]
+        );
+        assert_eq!(storage.list_prefix("b/").await.unwrap(), vec!["b/1"]);
+        assert!(storage.list_prefix("missing/").await.unwrap().is_empty());
+
+        // Pagination
+        let mut pages = Vec::new();
+        let mut start_after = None;
+        loop {
+            let page = storage
+                .list_prefix_page("a/", start_after.as_deref(), 2)
+                .await
+                .expect("List page should succeed");
+            assert!(page.keys.len() <= 2);
+            pages.extend(page.keys);
+            match page.next_start_after {
+                Some(next) => start_after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(pages, vec!["a/1", "a/2", "a/3", "a/nested/4"]);
+        let page = storage
+            .list_prefix_page("a/", Some("a/3"), 10)
+            .await
+            .unwrap();
+        assert_eq!(page.keys, vec!["a/nested/4"]);
+        assert_eq!(page.next_start_after, None);
+        assert!(matches!(
+            storage.list_prefix_page("a/", None, 0).await,
+            Err(GetError::InvalidListLimit)
+        ));
+
+        // Ranged reads, which encrypted storage refuses
+        if let Storage::Encrypted(_) = storage {
+            assert!(matches!(
+                storage.get_range("a/nested/4", 2..7).await,
+                Err(GetError::EncryptionError(
+                    encryption::EncryptionError::RangeUnsupported
+                ))
+            ));
+        } else {
+            assert_eq!(
+                storage
+                    .get_range("a/nested/4", 2..7)
+                    .await
+                    .unwrap()
+                    .as_slice(),
+                b"neste"
+            );
+            assert!(storage.get_range("a/1", 0..0).await.unwrap().is_empty());
+            assert!(matches!(
+                storage.get_range("a/1", 1..10).await,
+                Err(GetError::RangeOutOfBounds { .. })
+            ));
+            assert!(storage.get_range("missing", 0..1).await.is_err());
+        }
+
+        // Copy keeps the source
+        storage
+            .copy("a/1", "c/1")
+            .await
+            .expect("Copy should succeed");
+        assert_eq!(storage.get("a/1").await.unwrap().as_slice(), b"a/1");
+        assert_eq!(storage.get("c/1").await.unwrap().as_slice(), b"a/1");
+
+        // Rename moves the source, overwriting the destination
+        storage
+            .rename("a/2", "c/1")
+            .await
+            .expect("Rename should succeed");
+        assert!(storage.get("a/2").await.is_err());
+        assert_eq!(storage.get("c/1").await.unwrap().as_slice(), b"a/2");
+        assert!(storage.rename("a/2", "c/2").await.is_err());
+        assert_eq!(
+            storage.list_prefix("a/").await.unwrap(),
+            vec!["a/1", "a/3", "a/nested/4"]
+        );
+
+        // Delete
+        storage.delete("a/1").await.expect("Delete should succeed");
+        assert!(storage.get("a/1").await.is_err());
+        assert_eq!(
+            storage.list_prefix("a/").await.unwrap(),
+            vec!["a/3", "a/nested/4"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_conformance() {
+        run_conformance_suite(test_storage()).await;
+    }
+
+    #[tokio::test]
+    async fn test_list_prefix_page_order() {
+        for storage in [
+            test_storage(),
+            Storage::InMemory(memory::InMemoryStorage::new()),
+        ] {
+            // A separator sorts after '.', so "x/a.c" comes before the keys under "x/a/"
+            for key in ["x/a/b", "x/a.c", "x/a/c/d", "x/ab", "y/1"] {
+                storage.put_bytes(key, vec![0]).await.unwrap();
+            }
+            let mut keys = Vec::new();
+            let mut start_after = None;
+            loop {
+                let page = storage
+                    .list_prefix_page("x/", start_after.as_deref(), 1)
+                    .await
+                    .unwrap();
+                keys.extend(page.keys);
+                match page.next_start_after {
+                    Some(next) => start_after = Some(next),
+                    None => break,
+                }
+            }
+            assert_eq!(keys, vec!["x/a.c", "x/a/b", "x/a/c/d", "x/ab"]);
+            assert_eq!(storage.list_prefix("x/").await.unwrap(), keys);
+            assert_eq!(
+                storage
+                    .list_prefix_page("x/a", Some("x/a/b"), 10)
+                    .await
+                    .unwrap()
+                    .keys,
+                vec!["x/a/c/d", "x/ab"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_storage_conformance() {
+        run_conformance_suite(Storage::InMemory(memory::InMemoryStorage::new())).await;
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_conformance() {
+        let key_provider =
+            encryption::KeyfileKeyProvider::new(std::collections::HashMap::from([(
+                "tenant".to_string(),
+                encryption::EncryptionKey::new([7; 32]),
+            )]));
+        run_conformance_suite(Storage::Encrypted(encryption::EncryptedStorage::new(
+            Storage::InMemory(memory::InMemoryStorage::new()),
+            Arc::new(key_provider),
+            "tenant",
+        )))
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_storage_from_config() {
+        let config = StorageConfig::InMemory(config::InMemoryStorageConfig {
+            faults: config::FaultInjectionConfig {
+                read_error_rate: 1.0,
+                ..Default::default()
+            },
+        });
+        let storage = Storage::try_from_config(&config, &Registry::new())
+            .await
+            .unwrap();
+        storage.put_bytes("key", vec![1, 2, 3]).await.unwrap();
+        assert!(matches!(
+            storage.get("key").await,
+            Err(GetError::InMemoryError(
+                InMemoryStorageError::InjectedFault(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_object_store_conformance() {
+        run_conformance_suite(Storage::ObjectStore(
+            object_store::ObjectStore::new_in_memory(),
+        ))
+        .await;
+    }
+}
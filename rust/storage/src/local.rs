@@ -1,9 +1,12 @@
 use super::config::StorageConfig;
 use super::StorageConfigError;
+use crate::ListPage;
 use async_trait::async_trait;
 use chroma_config::registry::Registry;
 use chroma_config::Configurable;
 use chroma_error::ChromaError;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -27,6 +30,18 @@ impl LocalStorage {
         }
     }
 
+    pub async fn get_range(&self, key: &str, range: Range<usize>) -> Result<Arc<Vec<u8>>, String> {
+        let file_path = format!("{}/{}", self.root, key);
+        let mut file = std::fs::File::open(file_path).map_err(|e| e.to_string())?;
+        file.seek(SeekFrom::Start(range.start as u64))
+            .map_err(|e| e.to_string())?;
+        let mut bytes = Vec::with_capacity(range.len());
+        file.take(range.len() as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+        Ok(Arc::new(bytes))
+    }
+
     pub async fn put_bytes(&self, key: &str, bytes: &[u8]) -> Result<(), String> {
         let path = format!("{}/{}", self.root, key);
         tracing::debug!("Writing to path: {}", path);
@@ -65,6 +80,23 @@ impl LocalStorage {
         }
     }
 
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), String> {
+        let src_path = format!("{}/{}", self.root, src_key);
+        let dst_path = format!("{}/{}", self.root, dst_key);
+        tracing::info!(src = %src_path, dst = %dst_path, "Copying file");
+
+        if let Some(parent) = std::path::Path::new(&dst_path).parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        match std::fs::copy(&src_path, &dst_path) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(error = %e, src = %src_path, dst = %dst_path, "Failed to copy file");
+                Err(e.to_string())
+            }
+        }
+    }
+
     pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), String> {
         let src_path = format!("{}/{}", self.root, src_key);
         let dst_path = format!("{}/{}", self.root, dst_key);
@@ -89,6 +121,110 @@ impl LocalStorage {
             }
         }
     }
+
+    pub async fn size(&self, key: &str) -> Result<u64, String> {
+        let file_path = format!("{}/{}", self.root, key);
+        std::fs::metadata(file_path)
+            .map(|metadata| metadata.len())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Lists the keys under `prefix` in lexicographic order.
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.list_keys(prefix, None, usize::MAX)
+    }
+
+    /// Lists at most `limit` keys under `prefix` that sort after `start_after`.
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, String> {
+        let keys = self.list_keys(prefix, start_after, limit.saturating_add(1))?;
+        Ok(ListPage::from_sorted_keys(keys.into_iter(), limit))
+    }
+
+    /// Lists at most `max_keys` keys under `prefix` that sort after `start_after`, in
+    /// lexicographic order. Directories are walked in key order and those that only hold
+    /// keys outside of the requested range are skipped, so a page does not walk the whole
+    /// prefix.
+    fn list_keys(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_keys: usize,
+    ) -> Result<Vec<String>, String> {
+        // Only walk the deepest directory the prefix is guaranteed to live under
+        let dir_key = match prefix.rfind('/') {
+            Some(idx) => &prefix[..idx],
+            None => "",
+        };
+        let mut keys = Vec::new();
+        self.walk_keys(dir_key, prefix, start_after, max_keys, &mut keys)?;
+        Ok(keys)
+    }
+
+    fn walk_keys(
+        &self,
+        dir_key: &str,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_keys: usize,
+        keys: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let dir = match dir_key {
+            "" => std::path::PathBuf::from(&self.root),
+            _ => std::path::Path::new(&self.root).join(dir_key),
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut children = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let key = match dir_key {
+                "" => name,
+                _ => format!("{}/{}", dir_key, name),
+            };
+            // The keys under a directory sort as if its name ended with a separator
+            if entry.path().is_dir() {
+                children.push((format!("{}/", key), true));
+            } else {
+                children.push((key, false));
+            }
+        }
+        children.sort();
+
+        for (key, is_dir) in children {
+            if keys.len() >= max_keys {
+                break;
+            }
+            if is_dir {
+                let overlaps_prefix = key.starts_with(prefix) || prefix.starts_with(&key);
+                let before_start = start_after.is_some_and(|start_after| {
+                    start_after > key.as_str() && !start_after.starts_with(&key)
+                });
+                if overlaps_prefix && !before_start {
+                    self.walk_keys(
+                        key.trim_end_matches('/'),
+                        prefix,
+                        start_after,
+                        max_keys,
+                        keys,
+                    )?;
+                }
+            } else if key.starts_with(prefix)
+                && start_after.is_none_or(|start_after| key.as_str() > start_after)
+            {
+                keys.push(key);
+            }
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
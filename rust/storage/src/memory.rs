@@ -0,0 +1,278 @@
+use super::config::{FaultInjectionConfig, StorageConfig};
+use super::{ListPage, StorageConfigError};
+use async_trait::async_trait;
+use chroma_config::registry::Registry;
+use chroma_config::Configurable;
+use chroma_error::ChromaError;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum InMemoryStorageError {
+    #[error("No such key: {0}")]
+    NoSuchKey(String),
+    #[error("Range {start}..{end} out of bounds for {key} of length {len}")]
+    RangeOutOfBounds {
+        key: String,
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    #[error("IO error: {0}")]
+    IOError(String),
+    #[error("Injected fault: {0}")]
+    InjectedFault(String),
+}
+
+/// A storage backend that keeps every object in memory.
+/// Clones share the same objects, so it can be handed to several components in a test.
+/// Faults (latency, errors and partial writes) can be injected through `FaultInjectionConfig`,
+/// either from the config or at runtime with `set_faults`.
+#[derive(Clone, Default)]
+pub struct InMemoryStorage {
+    objects: Arc<RwLock<BTreeMap<String, Arc<Vec<u8>>>>>,
+    faults: Arc<Mutex<FaultInjectionConfig>>,
+}
+
+enum Operation {
+    Read,
+    Write,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_faults(faults: FaultInjectionConfig) -> Self {
+        InMemoryStorage {
+            objects: Arc::default(),
+            faults: Arc::new(Mutex::new(faults)),
+        }
+    }
+
+    /// Replaces the faults injected into subsequent operations.
+    pub fn set_faults(&self, faults: FaultInjectionConfig) {
+        *self.faults.lock() = faults;
+    }
+
+    /// Applies the configured latency, then decides whether the operation should fail.
+    async fn inject(&self, operation: Operation, key: &str) -> Result<(), InMemoryStorageError> {
+        let (latency_ms, error_rate) = {
+            let faults = self.faults.lock();
+            let error_rate = match operation {
+                Operation::Read => faults.read_error_rate,
+                Operation::Write => faults.write_error_rate,
+            };
+            (faults.latency_ms, error_rate)
+        };
+        if latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+        }
+        if error_rate > 0.0 && rand::thread_rng().gen_bool(error_rate.min(1.0)) {
+            return Err(InMemoryStorageError::InjectedFault(key.to_string()));
+        }
+        Ok(())
+    }
+
+    fn should_tear_write(&self) -> bool {
+        let partial_write_rate = self.faults.lock().partial_write_rate;
+        partial_write_rate > 0.0 && rand::thread_rng().gen_bool(partial_write_rate.min(1.0))
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Arc<Vec<u8>>, InMemoryStorageError> {
+        self.inject(Operation::Read, key).await?;
+        self.objects
+            .read()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| InMemoryStorageError::NoSuchKey(key.to_string()))
+    }
+
+    pub async fn get_range(
+        &self,
+        key: &str,
+        range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, InMemoryStorageError> {
+        let bytes = self.get(key).await?;
+        if range.start > range.end || range.end > bytes.len() {
+            return Err(InMemoryStorageError::RangeOutOfBounds {
+                key: key.to_string(),
+                start: range.start,
+                end: range.end,
+                len: bytes.len(),
+            });
+        }
+        Ok(Arc::new(bytes[range].to_vec()))
+    }
+
+    pub async fn put_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<(), InMemoryStorageError> {
+        self.inject(Operation::Write, key).await?;
+        if self.should_tear_write() {
+            // A torn write leaves a truncated object behind and reports the failure
+            let truncated = bytes[..bytes.len() / 2].to_vec();
+            self.objects
+                .write()
+                .insert(key.to_string(), Arc::new(truncated));
+            return Err(InMemoryStorageError::InjectedFault(format!(
+                "partial write to {}",
+                key
+            )));
+        }
+        self.objects
+            .write()
+            .insert(key.to_string(), Arc::new(bytes));
+        Ok(())
+    }
+
+    pub async fn put_file(&self, key: &str, path: &str) -> Result<(), InMemoryStorageError> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| InMemoryStorageError::IOError(e.to_string()))?;
+        self.put_bytes(key, bytes).await
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), InMemoryStorageError> {
+        self.inject(Operation::Write, key).await?;
+        match self.objects.write().remove(key) {
+            Some(_) => Ok(()),
+            None => Err(InMemoryStorageError::NoSuchKey(key.to_string())),
+        }
+    }
+
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), InMemoryStorageError> {
+        self.inject(Operation::Write, dst_key).await?;
+        let mut objects = self.objects.write();
+        let bytes = objects
+            .get(src_key)
+            .cloned()
+            .ok_or_else(|| InMemoryStorageError::NoSuchKey(src_key.to_string()))?;
+        objects.insert(dst_key.to_string(), bytes);
+        Ok(())
+    }
+
+    pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), InMemoryStorageError> {
+        self.inject(Operation::Write, dst_key).await?;
+        let mut objects = self.objects.write();
+        let bytes = objects
+            .remove(src_key)
+            .ok_or_else(|| InMemoryStorageError::NoSuchKey(src_key.to_string()))?;
+        objects.insert(dst_key.to_string(), bytes);
+        Ok(())
+    }
+
+    pub async fn size(&self, key: &str) -> Result<u64, InMemoryStorageError> {
+        Ok(self.get(key).await?.len() as u64)
+    }
+
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, InMemoryStorageError> {
+        self.inject(Operation::Read, prefix).await?;
+        Ok(self
+            .objects
+            .read()
+            .range(prefix.to_string()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, InMemoryStorageError> {
+        self.inject(Operation::Read, prefix).await?;
+        let lower = match start_after {
+            Some(start_after) if start_after >= prefix => Bound::Excluded(start_after.to_string()),
+            _ => Bound::Included(prefix.to_string()),
+        };
+        let objects = self.objects.read();
+        let keys = objects
+            .range((lower, Bound::Unbounded))
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned();
+        Ok(ListPage::from_sorted_keys(keys, limit))
+    }
+}
+
+#[async_trait]
+impl Configurable<StorageConfig> for InMemoryStorage {
+    async fn try_from_config(
+        config: &StorageConfig,
+        _registry: &Registry,
+    ) -> Result<Self, Box<dyn ChromaError>> {
+        match &config {
+            StorageConfig::InMemory(in_memory_config) => Ok(InMemoryStorage::with_faults(
+                in_memory_config.faults.clone(),
+            )),
+            _ => Err(Box::new(StorageConfigError::InvalidStorageConfig)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ranged_get() {
+        let storage = InMemoryStorage::new();
+        storage
+            .put_bytes("key", b"hello world".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_range("key", 6..11).await.unwrap().as_slice(),
+            b"world"
+        );
+        assert!(matches!(
+            storage.get_range("key", 6..12).await,
+            Err(InMemoryStorageError::RangeOutOfBounds { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection() {
+        let storage = InMemoryStorage::new();
+        storage.put_bytes("key", b"value".to_vec()).await.unwrap();
+
+        storage.set_faults(FaultInjectionConfig {
+            read_error_rate: 1.0,
+            ..Default::default()
+        });
+        assert!(matches!(
+            storage.get("key").await,
+            Err(InMemoryStorageError::InjectedFault(_))
+        ));
+        // Writes are unaffected by read faults
+        storage.put_bytes("other", b"value".to_vec()).await.unwrap();
+
+        storage.set_faults(FaultInjectionConfig {
+            partial_write_rate: 1.0,
+            ..Default::default()
+        });
+        assert!(storage
+            .put_bytes("torn", b"0123456789".to_vec())
+            .await
+            .is_err());
+        assert_eq!(storage.get("torn").await.unwrap().as_slice(), b"01234");
+
+        storage.set_faults(FaultInjectionConfig {
+            write_error_rate: 1.0,
+            ..Default::default()
+        });
+        assert!(storage.delete("key").await.is_err());
+        assert!(storage.rename("key", "moved").await.is_err());
+
+        storage.set_faults(FaultInjectionConfig::default());
+        assert_eq!(storage.get("key").await.unwrap().as_slice(), b"value");
+    }
+}
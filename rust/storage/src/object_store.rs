@@ -8,7 +8,7 @@ use object_store::{GetOptions, GetRange, ObjectStore as ObjectStoreTrait, PutOpt
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::Mutex;
 
-use super::{GetError, PutError, StorageConfigError};
+use super::{GetError, ListPage, PutError, StorageConfigError};
 
 #[derive(Clone)]
 pub struct ObjectStore {
@@ -87,6 +87,25 @@ impl ObjectStore {
             .into())
     }
 
+    pub async fn get_range(
+        &self,
+        key: &str,
+        range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, GetError> {
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(range)),
+            ..Default::default()
+        };
+        Ok(self
+            .object_store
+            .get_opts(&Path::from(key), options)
+            .await?
+            .bytes()
+            .await?
+            .to_vec()
+            .into())
+    }
+
     pub async fn get_parallel(&self, key: &str) -> Result<Arc<Vec<u8>>, GetError> {
         let meta = self.object_store.head(&Path::from(key)).await?;
         let file_size = meta.size;
@@ -190,15 +209,18 @@ impl ObjectStore {
         }
     }
 
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), PutError> {
+        self.object_store
+            .copy(&Path::from(src_key), &Path::from(dst_key))
+            .await?;
+        Ok(())
+    }
+
     pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), PutError> {
         tracing::info!(src = %src_key, dst = %dst_key, "Renaming object");
 
         // Copy the object
-        match self
-            .object_store
-            .copy(&Path::from(src_key), &Path::from(dst_key))
-            .await
-        {
+        match self.copy(src_key, dst_key).await {
             Ok(_) => {
                 tracing::info!(src = %src_key, dst = %dst_key, "Successfully copied object");
                 // After successful copy, delete the original
@@ -220,6 +242,10 @@ impl ObjectStore {
         }
     }
 
+    pub async fn size(&self, key: &str) -> Result<u64, GetError> {
+        Ok(self.object_store.head(&Path::from(key)).await?.size as u64)
+    }
+
     pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, GetError> {
         let mut files = Vec::new();
         let mut stream = self.object_store.list(Some(&Path::from(prefix)));
@@ -234,8 +260,50 @@ impl ObjectStore {
                 }
             }
         }
+        files.sort();
         Ok(files)
     }
+
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, GetError> {
+        let prefix = Path::from(prefix);
+        let mut stream = match start_after {
+            Some(start_after) => self
+                .object_store
+                .list_with_offset(Some(&prefix), &Path::from(start_after)),
+            None => self.object_store.list(Some(&prefix)),
+        };
+
+        // Object stores list in lexicographic order, so the first `limit` entries form the page
+        let mut keys = Vec::new();
+        while let Some(obj) = stream.next().await {
+            let obj = obj?;
+            if keys.len() == limit {
+                return Ok(ListPage {
+                    next_start_after: keys.last().cloned(),
+                    keys,
+                });
+            }
+            keys.push(obj.location.to_string());
+        }
+        Ok(ListPage {
+            keys,
+            next_start_after: None,
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new_in_memory() -> Self {
+        ObjectStore {
+            object_store: Arc::new(object_store::memory::InMemory::new()),
+            upload_part_size_bytes: 1024 * 1024 * 5,
+            download_part_size_bytes: 1024 * 1024 * 5,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -249,11 +317,7 @@ mod tests {
     }
 
     fn get_object_store() -> ObjectStore {
-        ObjectStore {
-            object_store: Arc::new(object_store::memory::InMemory::new()),
-            upload_part_size_bytes: 1024 * 1024 * 5,
-            download_part_size_bytes: 1024 * 1024 * 5,
-        }
+        ObjectStore::new_in_memory()
     }
 
     #[tokio::test]
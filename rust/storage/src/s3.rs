@@ -13,6 +13,7 @@ use super::stream::ByteStreamItem;
 use super::stream::S3ByteStream;
 use super::StorageConfigError;
 use crate::GetError;
+use crate::ListPage;
 use async_trait::async_trait;
 use aws_config::retry::RetryConfig;
 use aws_config::timeout::TimeoutConfigBuilder;
@@ -176,11 +177,8 @@ impl S3Storage {
         }
     }
 
-    pub(super) async fn get_key_ranges(
-        &self,
-        key: &str,
-    ) -> Result<(i64, Vec<(i64, i64)>), S3GetError> {
-        let part_size = self.download_part_size_bytes as i64;
+    /// Returns the size of an object in bytes
+    pub async fn size(&self, key: &str) -> Result<i64, S3GetError> {
         let head_res = self
             .client
             .head_object()
@@ -188,17 +186,21 @@ impl S3Storage {
             .key(key)
             .send()
             .await;
-        let content_length = match head_res {
+        match head_res {
             Ok(res) => match res.content_length {
-                Some(len) => len,
-                None => {
-                    return Err(S3GetError::S3GetError("No content length".to_string()));
-                }
+                Some(len) => Ok(len),
+                None => Err(S3GetError::S3GetError("No content length".to_string())),
             },
-            Err(e) => {
-                return Err(S3GetError::S3GetError(e.to_string()));
-            }
-        };
+            Err(e) => Err(S3GetError::S3GetError(e.to_string())),
+        }
+    }
+
+    pub(super) async fn get_key_ranges(
+        &self,
+        key: &str,
+    ) -> Result<(i64, Vec<(i64, i64)>), S3GetError> {
+        let part_size = self.download_part_size_bytes as i64;
+        let content_length = self.size(key).await?;
         // Round up.
         let num_parts = (content_length as f64 / part_size as f64).ceil() as i64;
         let mut ranges = Vec::new();
@@ -252,6 +254,24 @@ impl S3Storage {
         }
     }
 
+    pub async fn get_range(
+        &self,
+        key: &str,
+        range: Range<usize>,
+    ) -> Result<Arc<Vec<u8>>, S3GetError> {
+        // HTTP ranges are inclusive of the last byte
+        let range_str = format!("bytes={}-{}", range.start, range.end - 1);
+        let output = self.fetch_range(key.to_string(), range_str).await?;
+        let mut bytes = Vec::with_capacity(range.len());
+        output
+            .body
+            .into_async_read()
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|e| S3GetError::ByteStreamError(e.to_string()))?;
+        Ok(Arc::new(bytes))
+    }
+
     pub(super) async fn get_parallel(&self, key: &str) -> Result<Arc<Vec<u8>>, S3GetError> {
         let (content_length, ranges) = self.get_key_ranges(key).await?;
 
@@ -322,7 +342,9 @@ impl S3Storage {
                                 GetError::NoSuchKey(e) => {
                                     return Err(S3GetError::NoSuchKey(e));
                                 }
-                                GetError::LocalError(_) => unreachable!(),
+                                other => {
+                                    return Err(S3GetError::S3GetError(other.to_string()));
+                                }
                             }
                         }
                     }
@@ -566,19 +588,23 @@ impl S3Storage {
         }
     }
 
-    pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), S3PutError> {
-        tracing::info!(src = %src_key, dst = %dst_key, "Renaming object in S3");
-
-        // S3 doesn't have a native rename operation, so we need to copy and delete
-        match self
-            .client
+    pub async fn copy(&self, src_key: &str, dst_key: &str) -> Result<(), S3PutError> {
+        self.client
             .copy_object()
             .bucket(&self.bucket)
             .copy_source(format!("{}/{}", self.bucket, src_key))
             .key(dst_key)
             .send()
             .await
-        {
+            .map_err(|e| S3PutError::S3PutError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn rename(&self, src_key: &str, dst_key: &str) -> Result<(), S3PutError> {
+        tracing::info!(src = %src_key, dst = %dst_key, "Renaming object in S3");
+
+        // S3 doesn't have a native rename operation, so we need to copy and delete
+        match self.copy(src_key, dst_key).await {
             Ok(_) => {
                 tracing::info!(src = %src_key, dst = %dst_key, "Successfully copied object");
                 // After successful copy, delete the original
@@ -595,10 +621,73 @@ impl S3Storage {
             }
             Err(e) => {
                 tracing::error!(error = %e, src = %src_key, dst = %dst_key, "Failed to copy object");
-                Err(S3PutError::S3PutError(e.to_string()))
+                Err(e)
             }
         }
     }
+
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>, S3GetError> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|e| S3GetError::S3GetError(e.to_string()))?;
+            keys.extend(
+                output
+                    .contents()
+                    .iter()
+                    .filter_map(|object| object.key().map(str::to_string)),
+            );
+            match output.next_continuation_token() {
+                Some(token) if output.is_truncated().unwrap_or(false) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    pub async fn list_prefix_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<ListPage, S3GetError> {
+        // S3 caps a single listing at 1000 keys
+        let max_keys = limit.min(1000) as i32;
+        let output = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .set_start_after(start_after.map(str::to_string))
+            .max_keys(max_keys)
+            .send()
+            .await
+            .map_err(|e| S3GetError::S3GetError(e.to_string()))?;
+        let keys = output
+            .contents()
+            .iter()
+            .filter_map(|object| object.key().map(str::to_string))
+            .collect::<Vec<_>>();
+        let next_start_after = if output.is_truncated().unwrap_or(false) {
+            keys.last().cloned()
+        } else {
+            None
+        };
+        Ok(ListPage {
+            keys,
+            next_start_after,
+        })
+    }
 }
 
 #[async_trait]
@@ -63,6 +63,10 @@ impl Default for GrpcSysDbConfig {
 pub struct SqliteSysDbConfig {
     pub log_topic_namespace: String,
     pub log_tenant: String,
+    /// When set, deleted collections are tombstoned and can be restored for this many seconds
+    /// before the local garbage collection purges them. Otherwise they are deleted right away.
+    #[serde(default)]
+    pub soft_delete_window_secs: Option<u64>,
 }
 
 impl Default for SqliteSysDbConfig {
@@ -70,6 +74,7 @@ impl Default for SqliteSysDbConfig {
         SqliteSysDbConfig {
             log_topic_namespace: "default".to_string(),
             log_tenant: "default".to_string(),
+            soft_delete_window_secs: None,
         }
     }
 }
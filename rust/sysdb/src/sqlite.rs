@@ -12,8 +12,8 @@ use chroma_types::{
     CreateTenantError, CreateTenantResponse, Database, DeleteCollectionError, DeleteDatabaseError,
     DeleteDatabaseResponse, GetCollectionWithSegmentsError, GetCollectionsError, GetDatabaseError,
     GetSegmentsError, GetTenantError, GetTenantResponse, ListDatabasesError, Metadata,
-    MetadataValue, ResetError, ResetResponse, Segment, SegmentScope, SegmentType, SegmentUuid,
-    UpdateCollectionError,
+    MetadataValue, ResetError, ResetResponse, RestoreCollectionError, Segment, SegmentScope,
+    SegmentType, SegmentUuid, UpdateCollectionError,
 };
 use futures::TryStreamExt;
 use sea_query_binder::SqlxBinder;
@@ -22,8 +22,13 @@ use sqlx::sqlite::SqliteRow;
 use sqlx::Row;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+// Tombstoned collections are renamed to `_deleted_{name}_{id}`, which frees their name for new
+// collections until they are restored
+const DELETED_COLLECTION_PREFIX: &str = "_deleted_";
+
 //////////////////////// SqliteSysDb ////////////////////////
 
 #[derive(Debug, Clone)]
@@ -37,6 +42,7 @@ pub struct SqliteSysDb {
     db: SqliteDb,
     log_topic_namespace: String,
     log_tenant: String,
+    soft_delete_window: Option<Duration>,
 }
 
 impl SqliteSysDb {
@@ -46,9 +52,21 @@ impl SqliteSysDb {
             db,
             log_topic_namespace,
             log_tenant,
+            soft_delete_window: None,
         }
     }
 
+    /// Tombstones deleted collections instead of deleting them, so that they can be restored
+    /// until they are older than the window
+    pub fn with_soft_delete_window(mut self, soft_delete_window: Duration) -> Self {
+        self.soft_delete_window = Some(soft_delete_window);
+        self
+    }
+
+    pub(crate) fn soft_deletes_collections(&self) -> bool {
+        self.soft_delete_window.is_some()
+    }
+
     ////////////////////////// Database Methods ////////////////////////
     #[allow(dead_code)]
     pub(crate) async fn create_database(
@@ -125,12 +143,36 @@ impl SqliteSysDb {
             .await
             .map_err(|e| e.boxed())?;
 
-        for collection in collections {
+        // Tombstoned collections cannot be restored without their database
+        let tombstoned_collection_ids = sqlx::query(
+            r#"
+            SELECT collections.id FROM collections
+            INNER JOIN databases ON databases.id = collections.database_id
+            WHERE collections.deleted_at IS NOT NULL
+            AND databases.name = $1
+            AND databases.tenant_id = $2
+            "#,
+        )
+        .bind(&database_name)
+        .bind(&tenant)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| DeleteDatabaseError::Internal(e.into()))?
+        .iter()
+        .map(|row| CollectionUuid::from_str(row.get::<&str, _>(0)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| GetCollectionsError::CollectionId(e).boxed())?;
+
+        for collection_id in collections
+            .into_iter()
+            .map(|collection| collection.collection_id)
+            .chain(tombstoned_collection_ids)
+        {
             self.delete_collection_with_conn(
                 &mut *tx,
                 tenant.clone(),
                 database_name.clone(),
-                collection.collection_id,
+                collection_id,
                 vec![],
             )
             .await
@@ -356,11 +398,31 @@ impl SqliteSysDb {
             .await
             .map_err(|e| UpdateCollectionError::Internal(e.into()))?;
 
+        // Deleted collections cannot be updated until they are restored
+        let live = sqlx::query("SELECT 1 FROM collections WHERE id = $1 AND deleted_at IS NULL")
+            .bind(collection_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| UpdateCollectionError::Internal(e.into()))?;
+        if live.is_none() {
+            return Err(UpdateCollectionError::NotFound(collection_id.to_string()));
+        }
+
         if name.is_some() || dimension.is_some() {
             let mut query = sea_query::Query::update();
             let mut query = query.table(table::Collections::Table).cond_where(
-                sea_query::Expr::col((table::Collections::Table, table::Collections::Id))
-                    .eq(collection_id.to_string()),
+                sea_query::Cond::all()
+                    .add(
+                        sea_query::Expr::col((table::Collections::Table, table::Collections::Id))
+                            .eq(collection_id.to_string()),
+                    )
+                    .add(
+                        sea_query::Expr::col((
+                            table::Collections::Table,
+                            table::Collections::DeletedAt,
+                        ))
+                        .is_null(),
+                    ),
             );
 
             if let Some(name) = name {
@@ -474,10 +536,15 @@ impl SqliteSysDb {
             .await
             .map_err(|e| DeleteCollectionError::Internal(e.into()))?;
 
-        let was_found = self
-            .delete_collection_with_conn(&mut *tx, tenant, database, collection_id, segment_ids)
-            .await
-            .map_err(|e| e.boxed())?;
+        let was_found = if self.soft_deletes_collections() {
+            self.tombstone_collection_with_conn(&mut *tx, tenant, database, collection_id)
+                .await
+                .map_err(|e| e.boxed())?
+        } else {
+            self.delete_collection_with_conn(&mut *tx, tenant, database, collection_id, segment_ids)
+                .await
+                .map_err(|e| e.boxed())?
+        };
         if !was_found {
             return Err(DeleteCollectionError::NotFound(collection_id.to_string()));
         }
@@ -489,6 +556,130 @@ impl SqliteSysDb {
         Ok(())
     }
 
+    /// Brings back a tombstoned collection under the name it had when it was deleted
+    pub(crate) async fn restore_collection(
+        &self,
+        tenant: String,
+        database: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Collection, RestoreCollectionError> {
+        let mut tx = self
+            .db
+            .get_conn()
+            .begin()
+            .await
+            .map_err(|e| RestoreCollectionError::Internal(e.into()))?;
+
+        let tombstone_name = sqlx::query(
+            r#"
+            SELECT collections.name FROM collections
+            INNER JOIN databases ON databases.id = collections.database_id
+            WHERE collections.id = $1
+            AND collections.deleted_at IS NOT NULL
+            AND databases.name = $2
+            AND databases.tenant_id = $3
+            "#,
+        )
+        .bind(collection_id.to_string())
+        .bind(&database)
+        .bind(&tenant)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| RestoreCollectionError::Internal(e.into()))?
+        .map(|row| row.get::<String, _>(0))
+        .ok_or_else(|| RestoreCollectionError::NotFound(collection_id.to_string()))?;
+        let name = tombstone_name
+            .strip_prefix(DELETED_COLLECTION_PREFIX)
+            .and_then(|name| name.strip_suffix(&format!("_{}", collection_id)))
+            .unwrap_or(tombstone_name.as_str())
+            .to_string();
+
+        sqlx::query("UPDATE collections SET name = $1, deleted_at = NULL WHERE id = $2")
+            .bind(&name)
+            .bind(collection_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db_err)
+                    if db_err.kind() == ErrorKind::UniqueViolation =>
+                {
+                    RestoreCollectionError::AlreadyExists(name.clone())
+                }
+                _ => RestoreCollectionError::Internal(e.into()),
+            })?;
+
+        let collection = self
+            .get_collections_with_conn(&mut *tx, Some(collection_id), None, None, None, None, 0)
+            .await
+            .map_err(|e| e.boxed())?
+            .pop()
+            .ok_or_else(|| RestoreCollectionError::NotFound(collection_id.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RestoreCollectionError::Internal(e.into()))?;
+
+        Ok(collection)
+    }
+
+    /// Deletes the tombstoned collections that are older than the soft delete window, along with
+    /// their segments and logs. Returns the ids of the purged collections.
+    pub(crate) async fn purge_deleted_collections(
+        &self,
+    ) -> Result<Vec<CollectionUuid>, Box<dyn ChromaError>> {
+        // Tombstones left behind while soft deletes were enabled are purged right away otherwise
+        let cutoff = SystemTime::now()
+            .checked_sub(self.soft_delete_window.unwrap_or_default())
+            .and_then(|cutoff| cutoff.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        let mut tx = self
+            .db
+            .get_conn()
+            .begin()
+            .await
+            .map_err(|e| WrappedSqlxError(e).boxed())?;
+
+        let tombstones = sqlx::query(
+            r#"
+            SELECT collections.id, databases.name, databases.tenant_id FROM collections
+            INNER JOIN databases ON databases.id = collections.database_id
+            WHERE collections.deleted_at IS NOT NULL
+            AND collections.deleted_at <= $1
+            "#,
+        )
+        .bind(cutoff.as_secs() as i64)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| WrappedSqlxError(e).boxed())?;
+
+        let mut purged = Vec::with_capacity(tombstones.len());
+        for row in tombstones {
+            let collection_id = CollectionUuid::from_str(row.get::<&str, _>(0))
+                .map_err(|e| GetCollectionsError::CollectionId(e).boxed())?;
+            let segment_ids = self
+                .get_segments_with_conn(&mut *tx, collection_id, None, None, None)
+                .await
+                .map_err(|e| e.boxed())?
+                .into_iter()
+                .map(|segment| segment.id)
+                .collect();
+            self.delete_collection_with_conn(
+                &mut *tx,
+                row.get(2),
+                row.get(1),
+                collection_id,
+                segment_ids,
+            )
+            .await
+            .map_err(|e| e.boxed())?;
+            purged.push(collection_id);
+        }
+
+        tx.commit().await.map_err(|e| WrappedSqlxError(e).boxed())?;
+
+        Ok(purged)
+    }
+
     pub(crate) async fn get_segments(
         &self,
         id: Option<SegmentUuid>,
@@ -598,7 +789,14 @@ impl SqliteSysDb {
                     .add_option(collection_id.map(|collection_id| {
                         sea_query::Expr::col((table::Collections::Table, table::Collections::Id))
                             .eq(collection_id.to_string())
-                    })),
+                    }))
+                    .add(
+                        sea_query::Expr::col((
+                            table::Collections::Table,
+                            table::Collections::DeletedAt,
+                        ))
+                        .is_null(),
+                    ),
             )
             .order_by(
                 (table::Collections::Table, table::Collections::Id),
@@ -805,6 +1003,41 @@ impl SqliteSysDb {
         Ok(segments)
     }
 
+    /// Returns true if the collection was tombstoned, false if it was not found
+    async fn tombstone_collection_with_conn<C>(
+        &self,
+        conn: &mut C,
+        tenant: String,
+        database: String,
+        collection_id: CollectionUuid,
+    ) -> Result<bool, WrappedSqlxError>
+    where
+        for<'connection> &'connection mut C: sqlx::Executor<'connection, Database = sqlx::Sqlite>,
+    {
+        let deleted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let tombstoned_rows = sqlx::query(
+            r#"
+            UPDATE collections
+            SET name = $1 || name || '_' || id, deleted_at = $2
+            WHERE id = $3
+            AND deleted_at IS NULL
+            AND database_id = (SELECT id FROM databases WHERE name = $4 AND tenant_id = $5)
+        "#,
+        )
+        .bind(DELETED_COLLECTION_PREFIX)
+        .bind(deleted_at as i64)
+        .bind(collection_id.to_string())
+        .bind(&database)
+        .bind(&tenant)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(tombstoned_rows.rows_affected() > 0)
+    }
+
     /// Returns true if the collection was deleted, false if it was not found
     async fn delete_collection_with_conn<C>(
         &self,
@@ -922,11 +1155,15 @@ impl Configurable<SqliteSysDbConfig> for SqliteSysDb {
     ) -> Result<Self, Box<dyn ChromaError>> {
         // Assume the registry has a sqlite db
         let db = registry.get::<SqliteDb>().map_err(|e| e.boxed())?;
-        Ok(Self::new(
+        let sysdb = Self::new(
             db,
             config.log_tenant.clone(),
             config.log_topic_namespace.clone(),
-        ))
+        );
+        Ok(match config.soft_delete_window_secs {
+            Some(window_secs) => sysdb.with_soft_delete_window(Duration::from_secs(window_secs)),
+            None => sysdb,
+        })
     }
 }
 
@@ -1310,6 +1547,212 @@ mod tests {
         assert_eq!(result.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_soft_delete_and_restore_collection() {
+        let db = get_new_sqlite_db().await;
+        let sysdb = SqliteSysDb::new(db, "default".to_string(), "default".to_string())
+            .with_soft_delete_window(Duration::from_secs(3600));
+
+        let collection_id = CollectionUuid::new();
+        let segment = Segment {
+            id: SegmentUuid::new(),
+            r#type: SegmentType::BlockfileMetadata,
+            scope: SegmentScope::METADATA,
+            collection: collection_id,
+            metadata: None,
+            file_path: HashMap::new(),
+        };
+        sysdb
+            .create_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+                "test_collection".to_string(),
+                vec![segment.clone()],
+                serde_json::Value::Null,
+                None,
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        // Restoring a live collection fails
+        let result = sysdb
+            .restore_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+            )
+            .await;
+        assert!(matches!(result, Err(RestoreCollectionError::NotFound(_))));
+
+        sysdb
+            .delete_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+                vec![segment.id],
+            )
+            .await
+            .unwrap();
+
+        // The collection is hidden, but its segments are kept
+        let result = sysdb
+            .get_collections(Some(collection_id), None, None, None, None, 0)
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 0);
+        let segments = sysdb
+            .get_segments(None, None, None, collection_id)
+            .await
+            .unwrap();
+        assert_eq!(segments.len(), 1);
+        // Deleting it again fails
+        let result = sysdb
+            .delete_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+                vec![],
+            )
+            .await;
+        assert!(matches!(result, Err(DeleteCollectionError::NotFound(_))));
+        // And so does updating it
+        let result = sysdb
+            .update_collection(collection_id, Some("renamed".to_string()), None, None)
+            .await;
+        assert!(matches!(result, Err(UpdateCollectionError::NotFound(_))));
+        let result = sysdb
+            .update_collection(
+                collection_id,
+                None,
+                Some(CollectionMetadataUpdate::ResetMetadata),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(UpdateCollectionError::NotFound(_))));
+        // Nothing is purged within the window
+        assert!(sysdb.purge_deleted_collections().await.unwrap().is_empty());
+
+        // The name is free until the collection is restored
+        let other_collection_id = CollectionUuid::new();
+        sysdb
+            .create_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                other_collection_id,
+                "test_collection".to_string(),
+                vec![],
+                serde_json::Value::Null,
+                None,
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+        let result = sysdb
+            .restore_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(RestoreCollectionError::AlreadyExists(_))
+        ));
+        sysdb
+            .delete_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                other_collection_id,
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let restored = sysdb
+            .restore_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+            )
+            .await
+            .unwrap();
+        assert_eq!(restored.collection_id, collection_id);
+        assert_eq!(restored.name, "test_collection");
+        let result = sysdb
+            .get_collections(
+                None,
+                Some("test_collection".to_string()),
+                None,
+                None,
+                None,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].collection_id, collection_id);
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted_collections() {
+        let db = get_new_sqlite_db().await;
+        let sysdb = SqliteSysDb::new(db, "default".to_string(), "default".to_string())
+            .with_soft_delete_window(Duration::ZERO);
+
+        let collection_id = CollectionUuid::new();
+        let segment = Segment {
+            id: SegmentUuid::new(),
+            r#type: SegmentType::BlockfileMetadata,
+            scope: SegmentScope::METADATA,
+            collection: collection_id,
+            metadata: None,
+            file_path: HashMap::new(),
+        };
+        sysdb
+            .create_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+                "test_collection".to_string(),
+                vec![segment.clone()],
+                serde_json::Value::Null,
+                None,
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+        sysdb
+            .delete_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+                vec![segment.id],
+            )
+            .await
+            .unwrap();
+
+        let purged = sysdb.purge_deleted_collections().await.unwrap();
+        assert_eq!(purged, vec![collection_id]);
+        let segments = sysdb
+            .get_segments(None, None, None, collection_id)
+            .await
+            .unwrap();
+        assert!(segments.is_empty());
+        let result = sysdb
+            .restore_collection(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                collection_id,
+            )
+            .await;
+        assert!(matches!(result, Err(RestoreCollectionError::NotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_get_collection_with_segments() {
         let db = get_new_sqlite_db().await;
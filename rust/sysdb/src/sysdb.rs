@@ -14,8 +14,8 @@ use chroma_types::{
     DeleteDatabaseResponse, GetCollectionSizeError, GetCollectionWithSegmentsError,
     GetCollectionsError, GetDatabaseError, GetDatabaseResponse, GetSegmentsError, GetTenantError,
     GetTenantResponse, ListDatabasesError, ListDatabasesResponse, Metadata, ResetError,
-    ResetResponse, SegmentFlushInfo, SegmentFlushInfoConversionError, SegmentUuid,
-    UpdateCollectionError,
+    ResetResponse, RestoreCollectionError, SegmentFlushInfo, SegmentFlushInfoConversionError,
+    SegmentUuid, UpdateCollectionError,
 };
 use chroma_types::{
     Collection, CollectionConversionError, CollectionUuid, FlushCompactionResponse,
@@ -277,6 +277,46 @@ impl SysDb {
         }
     }
 
+    pub async fn restore_collection(
+        &mut self,
+        tenant: String,
+        database: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Collection, RestoreCollectionError> {
+        match self {
+            SysDb::Grpc(grpc) => {
+                grpc.restore_collection(tenant, database, collection_id)
+                    .await
+            }
+            SysDb::Sqlite(sqlite) => {
+                sqlite
+                    .restore_collection(tenant, database, collection_id)
+                    .await
+            }
+            SysDb::Test(_) => Err(RestoreCollectionError::Unimplemented),
+        }
+    }
+
+    /// Whether deleted collections are tombstoned and kept until they are purged. The
+    /// distributed sysdb decides this on its own, so it reports false.
+    pub fn soft_deletes_collections(&self) -> bool {
+        match self {
+            SysDb::Sqlite(sqlite) => sqlite.soft_deletes_collections(),
+            SysDb::Grpc(_) | SysDb::Test(_) => false,
+        }
+    }
+
+    // Only purges in single-node. The distributed sysdb purges tombstoned collections itself and
+    // the test sysdb never tombstones them, so there is nothing to purge for either.
+    pub async fn purge_deleted_collections(
+        &mut self,
+    ) -> Result<Vec<CollectionUuid>, Box<dyn ChromaError>> {
+        match self {
+            SysDb::Sqlite(sqlite) => sqlite.purge_deleted_collections().await,
+            SysDb::Grpc(_) | SysDb::Test(_) => Ok(vec![]),
+        }
+    }
+
     pub async fn get_collections_to_gc(
         &mut self,
     ) -> Result<Vec<CollectionToGcInfo>, GetCollectionsToGcError> {
@@ -363,6 +403,25 @@ impl SysDb {
         }
     }
 
+    pub async fn update_segment(
+        &mut self,
+        segment_id: SegmentUuid,
+        collection_id: CollectionUuid,
+        metadata: Metadata,
+    ) -> Result<(), UpdateSegmentError> {
+        match self {
+            SysDb::Grpc(grpc) => {
+                grpc.update_segment(segment_id, collection_id, metadata)
+                    .await
+            }
+            SysDb::Sqlite(_) => Err(UpdateSegmentError::Unimplemented),
+            SysDb::Test(test) => {
+                test.update_segment(segment_id, collection_id, metadata)
+                    .await
+            }
+        }
+    }
+
     pub async fn mark_version_for_deletion(
         &mut self,
         epoch_id: i64,
@@ -828,6 +887,37 @@ impl GrpcSysDb {
         Ok(())
     }
 
+    async fn restore_collection(
+        &mut self,
+        tenant: String,
+        database: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Collection, RestoreCollectionError> {
+        let res = self
+            .client
+            .restore_deleted_collection(chroma_proto::RestoreDeletedCollectionRequest {
+                id: collection_id.0.to_string(),
+                tenant,
+                database,
+            })
+            .await
+            .map_err(|e| match e.code() {
+                Code::NotFound => RestoreCollectionError::NotFound(collection_id.to_string()),
+                Code::AlreadyExists => {
+                    RestoreCollectionError::AlreadyExists(collection_id.to_string())
+                }
+                _ => RestoreCollectionError::Internal(e.into()),
+            })?;
+
+        res.into_inner()
+            .collection
+            .ok_or(RestoreCollectionError::Internal(
+                TonicMissingFieldError("collection").boxed(),
+            ))?
+            .try_into()
+            .map_err(|e: CollectionConversionError| RestoreCollectionError::Internal(e.boxed()))
+    }
+
     pub async fn get_collections_to_gc(
         &mut self,
     ) -> Result<Vec<CollectionToGcInfo>, GetCollectionsToGcError> {
@@ -1002,6 +1092,31 @@ impl GrpcSysDb {
         }
     }
 
+    async fn update_segment(
+        &mut self,
+        segment_id: SegmentUuid,
+        collection_id: CollectionUuid,
+        metadata: Metadata,
+    ) -> Result<(), UpdateSegmentError> {
+        let req = chroma_proto::UpdateSegmentRequest {
+            id: segment_id.to_string(),
+            collection: collection_id.to_string(),
+            metadata_update: Some(
+                chroma_proto::update_segment_request::MetadataUpdate::Metadata(metadata.into()),
+            ),
+        };
+
+        self.client.update_segment(req).await.map_err(|e| {
+            if e.code() == Code::NotFound {
+                UpdateSegmentError::SegmentNotFound
+            } else {
+                UpdateSegmentError::FailedToUpdateSegment(e)
+            }
+        })?;
+
+        Ok(())
+    }
+
     async fn mark_version_for_deletion(
         &mut self,
         epoch_id: i64,
@@ -1079,6 +1194,26 @@ impl ChromaError for FlushCompactionError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum UpdateSegmentError {
+    #[error("Failed to update segment")]
+    FailedToUpdateSegment(#[from] tonic::Status),
+    #[error("Segment not found in sysdb")]
+    SegmentNotFound,
+    #[error("Updating segments is not supported by this sysdb")]
+    Unimplemented,
+}
+
+impl ChromaError for UpdateSegmentError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            UpdateSegmentError::FailedToUpdateSegment(_) => ErrorCodes::Internal,
+            UpdateSegmentError::SegmentNotFound => ErrorCodes::NotFound,
+            UpdateSegmentError::Unimplemented => ErrorCodes::Unimplemented,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum MarkVersionForDeletionError {
     #[error("Failed to mark version for deletion")]
@@ -1,5 +1,5 @@
 use chroma_types::{
-    Collection, CollectionUuid, FlushCompactionResponse, GetSegmentsError, Segment,
+    Collection, CollectionUuid, FlushCompactionResponse, GetSegmentsError, Metadata, Segment,
     SegmentFlushInfo, SegmentScope, SegmentType, Tenant,
 };
 use chroma_types::{GetCollectionsError, SegmentUuid};
@@ -9,6 +9,7 @@ use std::sync::Arc;
 
 use super::sysdb::FlushCompactionError;
 use super::sysdb::GetLastCompactionTimeError;
+use super::sysdb::UpdateSegmentError;
 use chroma_types::chroma_proto::VersionListForCollection;
 
 #[derive(Clone, Debug)]
@@ -200,6 +201,9 @@ impl TestSysDb {
             }
             let mut segment = segment.unwrap().clone();
             segment.file_path = segment_flush_info.file_paths.clone();
+            if let Some(metadata) = &segment_flush_info.metadata {
+                segment.metadata = Some(metadata.clone());
+            }
             inner.segments.insert(segment.id, segment);
         }
 
@@ -210,6 +214,24 @@ impl TestSysDb {
         ))
     }
 
+    pub(crate) async fn update_segment(
+        &mut self,
+        segment_id: SegmentUuid,
+        collection_id: CollectionUuid,
+        metadata: Metadata,
+    ) -> Result<(), UpdateSegmentError> {
+        let mut inner = self.inner.lock();
+        let segment = match inner.segments.get_mut(&segment_id) {
+            Some(segment) if segment.collection == collection_id => segment,
+            _ => return Err(UpdateSegmentError::SegmentNotFound),
+        };
+        segment
+            .metadata
+            .get_or_insert_with(Metadata::new)
+            .extend(metadata);
+        Ok(())
+    }
+
     pub(crate) async fn mark_version_for_deletion(
         &self,
         _epoch_id: i64,
@@ -2,12 +2,58 @@ use crate::{ChannelError, Component, ComponentContext, ComponentHandle, PanicErr
 use async_trait::async_trait;
 use chroma_error::ChromaError;
 use core::fmt::Debug;
+use parking_lot::Mutex;
 use std::any::type_name;
+use std::sync::Arc;
 use tokio::sync::oneshot::{self, error::RecvError, Sender};
+use tokio_util::sync::CancellationToken;
 use tracing::Span;
 
 use crate::{Dispatcher, TaskMessage};
 
+#[derive(Debug, Default, PartialEq)]
+enum CommitGuardState {
+    #[default]
+    Open,
+    Committed,
+    Cancelled,
+}
+
+/// Marks the point after which an orchestrator run with `run_until_cancelled` can no longer be
+/// cancelled, e.g. because it has started to commit its output to another service. Whichever of
+/// the commit and the cancellation comes first wins.
+#[derive(Clone, Debug, Default)]
+pub struct CommitGuard {
+    state: Arc<Mutex<CommitGuardState>>,
+}
+
+impl CommitGuard {
+    /// Returns whether the orchestrator may commit. Once this returns true, cancellation is
+    /// ignored and the orchestrator runs to completion.
+    pub fn try_commit(&self) -> bool {
+        let mut state = self.state.lock();
+        match *state {
+            CommitGuardState::Open | CommitGuardState::Committed => {
+                *state = CommitGuardState::Committed;
+                true
+            }
+            CommitGuardState::Cancelled => false,
+        }
+    }
+
+    /// Returns whether the orchestrator may be cancelled, i.e. it has not started to commit
+    fn try_cancel(&self) -> bool {
+        let mut state = self.state.lock();
+        match *state {
+            CommitGuardState::Open | CommitGuardState::Cancelled => {
+                *state = CommitGuardState::Cancelled;
+                true
+            }
+            CommitGuardState::Committed => false,
+        }
+    }
+}
+
 #[async_trait]
 pub trait Orchestrator: Debug + Send + Sized + 'static {
     type Output: Send;
@@ -27,6 +73,11 @@ pub trait Orchestrator: Debug + Send + Sized + 'static {
         1000
     }
 
+    /// Returns the guard that protects the commit of the orchestrator from cancellation
+    fn commit_guard(&self) -> Option<CommitGuard> {
+        None
+    }
+
     /// Runs the orchestrator in a system and returns the result
     async fn run(mut self, system: System) -> Result<Self::Output, Self::Error> {
         let (tx, rx) = oneshot::channel();
@@ -37,6 +88,40 @@ pub trait Orchestrator: Debug + Send + Sized + 'static {
         res?
     }
 
+    /// Runs the orchestrator in a system like `run`, but stops it as soon as the token is
+    /// cancelled. Returns `None` if the orchestrator was stopped before it produced a result.
+    /// The cancellation is ignored if the orchestrator has already committed through its
+    /// commit guard.
+    async fn run_until_cancelled(
+        mut self,
+        system: System,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<Self::Output>, Self::Error> {
+        let (tx, mut rx) = oneshot::channel();
+        self.set_result_channel(tx);
+        let commit_guard = self.commit_guard();
+        let mut handle = system.start_component(self);
+        let res = tokio::select! {
+            res = &mut rx => Some(res),
+            _ = cancellation_token.cancelled() => None,
+        };
+        let res = match res {
+            None if commit_guard.is_some_and(|guard| !guard.try_cancel()) => {
+                tracing::info!(
+                    "Ignoring cancellation of {} because it is committing",
+                    Self::name()
+                );
+                Some(rx.await)
+            }
+            res => res,
+        };
+        handle.stop();
+        match res {
+            Some(res) => res?.map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Sends a task to the dispatcher and return whether the task is successfully sent
     async fn send(&mut self, task: TaskMessage, ctx: &ComponentContext<Self>) -> bool {
         let res = self.dispatcher().send(task, Some(Span::current())).await;
@@ -115,3 +200,159 @@ impl<O: Orchestrator> Component for O {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chroma_error::ErrorCodes;
+    use thiserror::Error;
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::{operator::*, DispatcherConfig, Handler};
+
+    #[derive(Debug)]
+    struct SleepOperator {}
+    #[async_trait]
+    impl Operator<Duration, ()> for SleepOperator {
+        type Error = ();
+
+        fn get_name(&self) -> &'static str {
+            "SleepOperator"
+        }
+
+        async fn run(&self, input: &Duration) -> Result<(), Self::Error> {
+            tokio::time::sleep(*input).await;
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Error)]
+    enum MockError {
+        #[error("Panic: {0}")]
+        Panic(#[from] PanicError),
+        #[error("Channel error: {0}")]
+        Channel(#[from] ChannelError),
+        #[error("Result channel dropped: {0}")]
+        Recv(#[from] RecvError),
+    }
+
+    impl ChromaError for MockError {
+        fn code(&self) -> ErrorCodes {
+            ErrorCodes::Internal
+        }
+    }
+
+    // Sleeps, commits and then sleeps again while committing
+    #[derive(Debug)]
+    struct MockOrchestrator {
+        dispatcher: ComponentHandle<Dispatcher>,
+        commit_guard: CommitGuard,
+        committing: Arc<Notify>,
+        committed: bool,
+        result_channel: Option<Sender<Result<(), MockError>>>,
+    }
+
+    #[async_trait]
+    impl Orchestrator for MockOrchestrator {
+        type Output = ();
+        type Error = MockError;
+
+        fn dispatcher(&self) -> ComponentHandle<Dispatcher> {
+            self.dispatcher.clone()
+        }
+
+        fn initial_tasks(&self, ctx: &ComponentContext<Self>) -> Vec<TaskMessage> {
+            vec![wrap(
+                Box::new(SleepOperator {}),
+                Duration::from_millis(50),
+                ctx.receiver(),
+            )]
+        }
+
+        fn commit_guard(&self) -> Option<CommitGuard> {
+            Some(self.commit_guard.clone())
+        }
+
+        fn set_result_channel(&mut self, sender: Sender<Result<(), MockError>>) {
+            self.result_channel = Some(sender);
+        }
+
+        fn take_result_channel(&mut self) -> Sender<Result<(), MockError>> {
+            self.result_channel
+                .take()
+                .expect("The result channel should be set before take")
+        }
+    }
+
+    #[async_trait]
+    impl Handler<TaskResult<(), ()>> for MockOrchestrator {
+        type Result = ();
+
+        async fn handle(&mut self, _: TaskResult<(), ()>, ctx: &ComponentContext<Self>) {
+            if self.committed {
+                self.terminate_with_result(Ok(()), ctx);
+                return;
+            }
+            if !self.commit_guard.try_commit() {
+                return;
+            }
+            self.committed = true;
+            self.committing.notify_one();
+            let task = wrap(
+                Box::new(SleepOperator {}),
+                Duration::from_millis(200),
+                ctx.receiver(),
+            );
+            self.send(task, ctx).await;
+        }
+    }
+
+    fn mock_orchestrator(system: &System) -> MockOrchestrator {
+        let dispatcher = Dispatcher::new(DispatcherConfig {
+            num_worker_threads: 1,
+            task_queue_limit: 1000,
+            dispatcher_queue_size: 1000,
+            worker_queue_size: 1000,
+            active_io_tasks: 1000,
+        });
+        MockOrchestrator {
+            dispatcher: system.start_component(dispatcher),
+            commit_guard: CommitGuard::default(),
+            committing: Arc::new(Notify::new()),
+            committed: false,
+            result_channel: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_before_commit() {
+        let system = System::new();
+        let orchestrator = mock_orchestrator(&system);
+        let commit_guard = orchestrator.commit_guard.clone();
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+
+        let res = orchestrator
+            .run_until_cancelled(system, cancellation_token)
+            .await;
+        assert!(matches!(res, Ok(None)));
+        assert!(!commit_guard.try_commit());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_commit_is_ignored() {
+        let system = System::new();
+        let orchestrator = mock_orchestrator(&system);
+        let committing = orchestrator.committing.clone();
+        let cancellation_token = CancellationToken::new();
+
+        let run =
+            tokio::spawn(orchestrator.run_until_cancelled(system, cancellation_token.clone()));
+        committing.notified().await;
+        cancellation_token.cancel();
+        let res = run.await.expect("The orchestrator should not panic");
+        assert!(matches!(res, Ok(Some(()))));
+    }
+}
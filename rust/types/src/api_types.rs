@@ -1,19 +1,25 @@
 use crate::error::QueryConversionError;
+use crate::operator::AsOf;
 use crate::operator::GetResult;
 use crate::operator::KnnBatchResult;
 use crate::operator::KnnProjectionRecord;
 use crate::operator::ProjectionRecord;
 use crate::validators::{
-    validate_name, validate_non_empty_collection_update_metadata, validate_non_empty_metadata,
+    validate_dedup_options, validate_name, validate_non_empty_collection_update_metadata,
+    validate_non_empty_metadata,
 };
 use crate::Collection;
 use crate::CollectionConversionError;
 use crate::CollectionUuid;
 use crate::HnswParametersFromSegmentError;
+use crate::LogRecord;
 use crate::Metadata;
+use crate::Operation;
 use crate::SegmentConversionError;
 use crate::SegmentScopeConversionError;
+use crate::SegmentStats;
 use crate::UpdateMetadata;
+use crate::UpdateMetadataValue;
 use crate::Where;
 use chroma_config::assignment::rendezvous_hash::AssignmentError;
 use chroma_error::ChromaValidationError;
@@ -728,6 +734,58 @@ impl ChromaError for DeleteCollectionError {
     }
 }
 
+#[non_exhaustive]
+#[derive(Clone, Validate)]
+pub struct RestoreCollectionRequest {
+    pub tenant_id: String,
+    pub database_name: String,
+    pub collection_id: CollectionUuid,
+}
+
+impl RestoreCollectionRequest {
+    pub fn try_new(
+        tenant_id: String,
+        database_name: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Self, ChromaValidationError> {
+        let request = Self {
+            tenant_id,
+            database_name,
+            collection_id,
+        };
+        request.validate().map_err(ChromaValidationError::from)?;
+        Ok(request)
+    }
+}
+
+pub type RestoreCollectionResponse = Collection;
+
+#[derive(Error, Debug)]
+pub enum RestoreCollectionError {
+    #[error("Deleted collection [{0}] does not exists")]
+    NotFound(String),
+    #[error("Collection [{0}] already exists")]
+    AlreadyExists(String),
+    #[error("Restoring deleted collections is not supported by this sysdb")]
+    Unimplemented,
+    #[error(transparent)]
+    Validation(#[from] ChromaValidationError),
+    #[error(transparent)]
+    Internal(#[from] Box<dyn ChromaError>),
+}
+
+impl ChromaError for RestoreCollectionError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            RestoreCollectionError::NotFound(_) => ErrorCodes::NotFound,
+            RestoreCollectionError::AlreadyExists(_) => ErrorCodes::AlreadyExists,
+            RestoreCollectionError::Unimplemented => ErrorCodes::Unimplemented,
+            RestoreCollectionError::Validation(err) => err.code(),
+            RestoreCollectionError::Internal(err) => err.code(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum GetCollectionSizeError {
     #[error(transparent)]
@@ -751,6 +809,37 @@ pub const CHROMA_KEY: &str = "chroma:";
 pub const CHROMA_DOCUMENT_KEY: &str = "chroma:document";
 pub const CHROMA_URI_KEY: &str = "chroma:uri";
 
+////////////////////////// Dedup //////////////////////////
+
+/// How near-duplicates of existing records are handled on `add` and `upsert`
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupMode {
+    /// Near-duplicates are not written
+    Reject,
+    /// The documents, uris and metadatas of near-duplicates are written to the records they duplicate
+    Merge,
+    /// Near-duplicates are written as usual
+    Report,
+}
+
+/// # Fields
+/// - `mode`: How the near-duplicates are handled
+/// - `threshold`: The maximum distance between near-duplicates, in the distance function of the collection
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct DedupOptions {
+    pub mode: DedupMode,
+    pub threshold: f32,
+}
+
+/// A record that is a near-duplicate of an existing record, or of an earlier record of the same batch
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct DuplicateRecord {
+    pub id: String,
+    pub duplicate_of: String,
+    pub distance: f32,
+}
+
 ////////////////////////// AddCollectionRecords //////////////////////////
 
 #[non_exhaustive]
@@ -764,6 +853,8 @@ pub struct AddCollectionRecordsRequest {
     pub documents: Option<Vec<Option<String>>>,
     pub uris: Option<Vec<Option<String>>>,
     pub metadatas: Option<Vec<Option<Metadata>>>,
+    #[validate(custom(function = "validate_dedup_options"))]
+    pub dedup: Option<DedupOptions>,
 }
 
 impl AddCollectionRecordsRequest {
@@ -777,6 +868,7 @@ impl AddCollectionRecordsRequest {
         documents: Option<Vec<Option<String>>>,
         uris: Option<Vec<Option<String>>>,
         metadatas: Option<Vec<Option<Metadata>>>,
+        dedup: Option<DedupOptions>,
     ) -> Result<Self, ChromaValidationError> {
         let request = Self {
             tenant_id,
@@ -787,14 +879,18 @@ impl AddCollectionRecordsRequest {
             documents,
             uris,
             metadatas,
+            dedup,
         };
         request.validate().map_err(ChromaValidationError::from)?;
         Ok(request)
     }
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct AddCollectionRecordsResponse {}
+#[derive(Default, Serialize, ToSchema)]
+pub struct AddCollectionRecordsResponse {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<DuplicateRecord>,
+}
 
 #[derive(Error, Debug)]
 pub enum AddCollectionRecordsError {
@@ -885,6 +981,8 @@ pub struct UpsertCollectionRecordsRequest {
     pub documents: Option<Vec<Option<String>>>,
     pub uris: Option<Vec<Option<String>>>,
     pub metadatas: Option<Vec<Option<UpdateMetadata>>>,
+    #[validate(custom(function = "validate_dedup_options"))]
+    pub dedup: Option<DedupOptions>,
 }
 
 impl UpsertCollectionRecordsRequest {
@@ -898,6 +996,7 @@ impl UpsertCollectionRecordsRequest {
         documents: Option<Vec<Option<String>>>,
         uris: Option<Vec<Option<String>>>,
         metadatas: Option<Vec<Option<UpdateMetadata>>>,
+        dedup: Option<DedupOptions>,
     ) -> Result<Self, ChromaValidationError> {
         let request = Self {
             tenant_id,
@@ -908,14 +1007,18 @@ impl UpsertCollectionRecordsRequest {
             documents,
             uris,
             metadatas,
+            dedup,
         };
         request.validate().map_err(ChromaValidationError::from)?;
         Ok(request)
     }
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct UpsertCollectionRecordsResponse {}
+#[derive(Default, Serialize, ToSchema)]
+pub struct UpsertCollectionRecordsResponse {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<DuplicateRecord>,
+}
 
 #[derive(Error, Debug)]
 pub enum UpsertCollectionRecordsError {
@@ -1075,6 +1178,7 @@ pub struct CountRequest {
     pub tenant_id: String,
     pub database_name: String,
     pub collection_id: CollectionUuid,
+    pub as_of: Option<AsOf>,
 }
 
 impl CountRequest {
@@ -1082,11 +1186,13 @@ impl CountRequest {
         tenant_id: String,
         database_name: String,
         collection_id: CollectionUuid,
+        as_of: Option<AsOf>,
     ) -> Result<Self, ChromaValidationError> {
         let request = Self {
             tenant_id,
             database_name,
             collection_id,
+            as_of,
         };
         request.validate().map_err(ChromaValidationError::from)?;
         Ok(request)
@@ -1095,6 +1201,211 @@ impl CountRequest {
 
 pub type CountResponse = u32;
 
+////////////////////////// Segment Stats //////////////////////////
+
+#[non_exhaustive]
+#[derive(Clone, Deserialize, Serialize, Validate)]
+pub struct SegmentStatsRequest {
+    pub tenant_id: String,
+    pub database_name: String,
+    pub collection_id: CollectionUuid,
+}
+
+impl SegmentStatsRequest {
+    pub fn try_new(
+        tenant_id: String,
+        database_name: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Self, ChromaValidationError> {
+        let request = Self {
+            tenant_id,
+            database_name,
+            collection_id,
+        };
+        request.validate().map_err(ChromaValidationError::from)?;
+        Ok(request)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct SegmentStatsResponse {
+    pub collection_id: CollectionUuid,
+    pub segments: Vec<SegmentStats>,
+}
+
+////////////////////////// Changes //////////////////////////
+
+#[non_exhaustive]
+#[derive(Clone, Deserialize, Serialize, Validate)]
+pub struct ReadChangesRequest {
+    pub tenant_id: String,
+    pub database_name: String,
+    pub collection_id: CollectionUuid,
+    /// Only changes with a log offset greater than this one are returned. `None` reads from the
+    /// start of the log.
+    pub after_offset: Option<i64>,
+    #[validate(range(min = 1))]
+    pub batch_size: u32,
+}
+
+impl ReadChangesRequest {
+    pub fn try_new(
+        tenant_id: String,
+        database_name: String,
+        collection_id: CollectionUuid,
+        after_offset: Option<i64>,
+        batch_size: u32,
+    ) -> Result<Self, ChromaValidationError> {
+        let request = Self {
+            tenant_id,
+            database_name,
+            collection_id,
+            after_offset,
+            batch_size,
+        };
+        request.validate().map_err(ChromaValidationError::from)?;
+        Ok(request)
+    }
+}
+
+/// A mutation of a collection, as it was pushed to the log.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CollectionChange {
+    pub offset: i64,
+    pub operation: Operation,
+    pub id: String,
+    pub embedding: Option<Vec<f32>>,
+    pub document: Option<String>,
+    pub uri: Option<String>,
+    pub metadata: Option<UpdateMetadata>,
+}
+
+impl From<LogRecord> for CollectionChange {
+    fn from(LogRecord { log_offset, record }: LogRecord) -> Self {
+        let mut metadata = record.metadata;
+        let uri = metadata.as_mut().and_then(|meta| {
+            meta.remove(CHROMA_URI_KEY).and_then(|v| {
+                if let UpdateMetadataValue::Str(uri) = v {
+                    Some(uri)
+                } else {
+                    None
+                }
+            })
+        });
+        let metadata = metadata.map(|m| {
+            m.into_iter()
+                .filter(|(k, _)| !k.starts_with(CHROMA_KEY))
+                .collect()
+        });
+        CollectionChange {
+            offset: log_offset,
+            operation: record.operation,
+            id: record.id,
+            embedding: record.embedding,
+            document: record.document,
+            uri,
+            metadata,
+        }
+    }
+}
+
+pub type ReadChangesResponse = Vec<CollectionChange>;
+
+#[derive(Error, Debug)]
+pub enum ReadChangesError {
+    #[error("Error reading the log: {0}")]
+    Log(Box<dyn ChromaError>),
+    #[error("Changes after offset {after_offset} were purged, the first available offset is {first_available_offset}")]
+    Purged {
+        after_offset: i64,
+        first_available_offset: i64,
+    },
+    #[error(transparent)]
+    Other(#[from] Box<dyn ChromaError>),
+}
+
+impl ChromaError for ReadChangesError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            ReadChangesError::Log(err) => err.code(),
+            ReadChangesError::Purged { .. } => ErrorCodes::FailedPrecondition,
+            ReadChangesError::Other(err) => err.code(),
+        }
+    }
+}
+
+////////////////////////// Log Retention //////////////////////////
+
+/// How long the records of a collection are kept in the log once they have been compacted into
+/// its segments. Records that have not been compacted are never purged.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRetentionPolicy {
+    /// Purge records as soon as they are compacted.
+    #[default]
+    Compacted,
+    /// Keep compacted records for this many hours after they were written.
+    Hours(u64),
+    /// Keep this many of the most recent compacted records.
+    Records(u64),
+}
+
+#[non_exhaustive]
+#[derive(Clone, Deserialize, Serialize, Validate)]
+pub struct GetLogRetentionRequest {
+    pub tenant_id: String,
+    pub database_name: String,
+    pub collection_id: CollectionUuid,
+}
+
+impl GetLogRetentionRequest {
+    pub fn try_new(
+        tenant_id: String,
+        database_name: String,
+        collection_id: CollectionUuid,
+    ) -> Result<Self, ChromaValidationError> {
+        let request = Self {
+            tenant_id,
+            database_name,
+            collection_id,
+        };
+        request.validate().map_err(ChromaValidationError::from)?;
+        Ok(request)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct LogRetentionStatus {
+    pub collection_id: CollectionUuid,
+    pub policy: LogRetentionPolicy,
+    /// The last log offset applied to the segments of the collection.
+    pub compacted_offset: u64,
+    /// Records before this offset are purged the next time the log is written to.
+    pub purge_offset: u64,
+    pub first_offset: Option<i64>,
+    pub last_offset: Option<i64>,
+    pub record_count: u64,
+}
+
+pub type GetLogRetentionResponse = LogRetentionStatus;
+
+#[derive(Error, Debug)]
+pub enum GetLogRetentionError {
+    #[error("Log retention is only managed by single-node deployments")]
+    Unsupported,
+    #[error(transparent)]
+    Internal(#[from] Box<dyn ChromaError>),
+}
+
+impl ChromaError for GetLogRetentionError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            GetLogRetentionError::Unsupported => ErrorCodes::Unimplemented,
+            GetLogRetentionError::Internal(err) => err.code(),
+        }
+    }
+}
+
 ////////////////////////// Get //////////////////////////
 
 #[non_exhaustive]
@@ -1108,6 +1419,7 @@ pub struct GetRequest {
     pub limit: Option<u32>,
     pub offset: u32,
     pub include: IncludeList,
+    pub as_of: Option<AsOf>,
 }
 
 impl GetRequest {
@@ -1121,6 +1433,7 @@ impl GetRequest {
         limit: Option<u32>,
         offset: u32,
         include: IncludeList,
+        as_of: Option<AsOf>,
     ) -> Result<Self, ChromaValidationError> {
         let request = Self {
             tenant_id,
@@ -1131,6 +1444,7 @@ impl GetRequest {
             limit,
             offset,
             include,
+            as_of,
         };
         request.validate().map_err(ChromaValidationError::from)?;
         Ok(request)
@@ -1247,6 +1561,8 @@ pub struct QueryRequest {
     pub embeddings: Vec<Vec<f32>>,
     pub n_results: u32,
     pub include: IncludeList,
+    pub brute_force: bool,
+    pub as_of: Option<AsOf>,
 }
 
 impl QueryRequest {
@@ -1260,6 +1576,8 @@ impl QueryRequest {
         embeddings: Vec<Vec<f32>>,
         n_results: u32,
         include: IncludeList,
+        brute_force: bool,
+        as_of: Option<AsOf>,
     ) -> Result<Self, ChromaValidationError> {
         let request = Self {
             tenant_id,
@@ -1270,6 +1588,8 @@ impl QueryRequest {
             embeddings,
             n_results,
             include,
+            brute_force,
+            as_of,
         };
         request.validate().map_err(ChromaValidationError::from)?;
         Ok(request)
@@ -1455,6 +1775,8 @@ pub enum ExecutorError {
     NoClientFound(String),
     #[error("Error sending backfill request to compactor")]
     BackfillError,
+    #[error("Operation not supported by this executor: {0}")]
+    Unsupported(String),
 }
 
 impl ChromaError for ExecutorError {
@@ -1468,6 +1790,7 @@ impl ChromaError for ExecutorError {
             ExecutorError::Internal(e) => e.code(),
             ExecutorError::NoClientFound(_) => ErrorCodes::Internal,
             ExecutorError::BackfillError => ErrorCodes::Internal,
+            ExecutorError::Unsupported(_) => ErrorCodes::Unimplemented,
         }
     }
 }
@@ -1487,4 +1810,30 @@ mod test {
         let request = CreateTenantRequest::try_new("a".to_string());
         assert!(request.is_err());
     }
+
+    #[test]
+    fn test_dedup_threshold() {
+        let add = |threshold| {
+            AddCollectionRecordsRequest::try_new(
+                "default_tenant".to_string(),
+                "default_database".to_string(),
+                CollectionUuid::new(),
+                vec!["a".to_string()],
+                Some(vec![vec![1.0]]),
+                None,
+                None,
+                None,
+                Some(DedupOptions {
+                    mode: DedupMode::Reject,
+                    threshold,
+                }),
+            )
+        };
+        assert!(add(0.0).is_ok());
+        assert!(add(0.5).is_ok());
+        for threshold in [-0.1, f32::NAN, f32::INFINITY] {
+            let err = add(threshold).expect_err("The threshold should be rejected");
+            assert_eq!(err.code(), ErrorCodes::InvalidArgument);
+        }
+    }
 }
@@ -3,19 +3,66 @@ use std::{
     collections::BinaryHeap,
 };
 
-use crate::{chroma_proto, CollectionAndSegments, CollectionUuid, Metadata, ScalarEncoding, Where};
+use crate::{
+    chroma_proto, CollectionAndSegments, CollectionUuid, Metadata, ScalarEncoding, SegmentStats,
+    Where,
+};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use super::error::QueryConversionError;
 
 pub type InitialInput = ();
 
+/// A point in the history of a collection to read at
+///
+/// # Variants
+/// - `LogOffset`: Only the log records up to and including this offset are visible
+/// - `Timestamp`: Only the log records pushed at or before this time (in nanoseconds since the
+///   epoch) are visible
+///
+/// # Notes
+/// Records that were already compacted into the segments are always visible, so a log offset
+/// below the compacted log position of the collection cannot be read at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AsOf {
+    LogOffset(i64),
+    Timestamp(i64),
+}
+
+impl From<chroma_proto::scan_operator::AsOf> for AsOf {
+    fn from(value: chroma_proto::scan_operator::AsOf) -> Self {
+        match value {
+            chroma_proto::scan_operator::AsOf::AsOfLogOffset(offset) => AsOf::LogOffset(offset),
+            chroma_proto::scan_operator::AsOf::AsOfTimestamp(timestamp) => {
+                AsOf::Timestamp(timestamp)
+            }
+        }
+    }
+}
+
+impl From<AsOf> for chroma_proto::scan_operator::AsOf {
+    fn from(value: AsOf) -> Self {
+        match value {
+            AsOf::LogOffset(offset) => chroma_proto::scan_operator::AsOf::AsOfLogOffset(offset),
+            AsOf::Timestamp(timestamp) => {
+                chroma_proto::scan_operator::AsOf::AsOfTimestamp(timestamp)
+            }
+        }
+    }
+}
+
 /// The `Scan` opeartor pins the data used by all downstream operators
 ///
 /// # Parameters
 /// - `collection_and_segments`: The consistent snapshot of collection
+/// - `as_of`: The point in the log to read at, or the end of the log if absent
 #[derive(Clone, Debug)]
 pub struct Scan {
     pub collection_and_segments: CollectionAndSegments,
+    pub as_of: Option<AsOf>,
 }
 
 impl TryFrom<chroma_proto::ScanOperator> for Scan {
@@ -23,6 +70,7 @@ impl TryFrom<chroma_proto::ScanOperator> for Scan {
 
     fn try_from(value: chroma_proto::ScanOperator) -> Result<Self, Self::Error> {
         Ok(Self {
+            as_of: value.as_of.map(Into::into),
             collection_and_segments: CollectionAndSegments {
                 collection: value
                     .collection
@@ -52,12 +100,25 @@ impl From<Scan> for chroma_proto::ScanOperator {
             knn: Some(value.collection_and_segments.vector_segment.into()),
             metadata: Some(value.collection_and_segments.metadata_segment.into()),
             record: Some(value.collection_and_segments.record_segment.into()),
+            as_of: value.as_of.map(Into::into),
         }
     }
 }
 
 pub type CountResult = u32;
 
+pub type StatsResult = Vec<SegmentStats>;
+
+pub fn from_proto_stats_result(results: chroma_proto::SegmentStatsResult) -> StatsResult {
+    results.segments.into_iter().map(Into::into).collect()
+}
+
+pub fn to_proto_stats_result(results: StatsResult) -> chroma_proto::SegmentStatsResult {
+    chroma_proto::SegmentStatsResult {
+        segments: results.into_iter().map(Into::into).collect(),
+    }
+}
+
 /// The `FetchLog` operator fetches logs from the log service
 ///
 /// # Parameters
@@ -142,10 +203,12 @@ impl From<KnnBatch> for Vec<Knn> {
 /// # Parameters
 /// - `embedding`: The target embedding to search around
 /// - `fetch`: The number of records to fetch around the target
+/// - `brute_force`: Whether to compute the exact distances of all candidates instead of searching the vector index
 #[derive(Clone, Debug)]
 pub struct KnnBatch {
     pub embeddings: Vec<Vec<f32>>,
     pub fetch: u32,
+    pub brute_force: bool,
 }
 
 impl TryFrom<chroma_proto::KnnOperator> for KnnBatch {
@@ -159,6 +222,7 @@ impl TryFrom<chroma_proto::KnnOperator> for KnnBatch {
                 .map(|vec| vec.try_into().map(|(v, _)| v))
                 .collect::<Result<_, _>>()?,
             fetch: value.fetch,
+            brute_force: value.brute_force,
         })
     }
 }
@@ -177,6 +241,7 @@ impl TryFrom<KnnBatch> for chroma_proto::KnnOperator {
                 })
                 .collect::<Result<_, _>>()?,
             fetch: value.fetch,
+            brute_force: value.brute_force,
         })
     }
 }
@@ -32,6 +32,33 @@ impl From<Count> for chroma_proto::CountPlan {
     }
 }
 
+/// The `Stats` plan should output the internal statistics of the segments in the collection
+#[derive(Clone)]
+pub struct Stats {
+    pub scan: Scan,
+}
+
+impl TryFrom<chroma_proto::SegmentStatsPlan> for Stats {
+    type Error = QueryConversionError;
+
+    fn try_from(value: chroma_proto::SegmentStatsPlan) -> Result<Self, Self::Error> {
+        Ok(Self {
+            scan: value
+                .scan
+                .ok_or(QueryConversionError::field("scan"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<Stats> for chroma_proto::SegmentStatsPlan {
+    fn from(value: Stats) -> Self {
+        Self {
+            scan: Some(value.scan.into()),
+        }
+    }
+}
+
 /// The `Get` plan should output records matching the specified filter and limit in the collection
 #[derive(Clone)]
 pub struct Get {
@@ -1,7 +1,7 @@
 use super::{CollectionUuid, ConversionError};
 use crate::{
     chroma_proto::{FilePaths, FlushCollectionCompactionResponse, FlushSegmentCompactionInfo},
-    SegmentUuid,
+    Metadata, SegmentUuid,
 };
 use chroma_error::{ChromaError, ErrorCodes};
 use std::collections::HashMap;
@@ -12,6 +12,8 @@ use uuid::Uuid;
 pub struct SegmentFlushInfo {
     pub segment_id: SegmentUuid,
     pub file_paths: HashMap<String, Vec<String>>,
+    // Replaces the metadata of the segment together with its file paths when set
+    pub metadata: Option<Metadata>,
 }
 
 impl TryInto<FlushSegmentCompactionInfo> for &SegmentFlushInfo {
@@ -26,6 +28,7 @@ impl TryInto<FlushSegmentCompactionInfo> for &SegmentFlushInfo {
         Ok(FlushSegmentCompactionInfo {
             segment_id: self.segment_id.to_string(),
             file_paths,
+            metadata: self.metadata.clone().map(Into::into),
         })
     }
 }
@@ -11,8 +11,10 @@ mod metadata;
 mod operation;
 mod record;
 mod scalar_encoding;
+mod scrub_report;
 mod segment;
 mod segment_scope;
+mod segment_stats;
 mod signed_rbm;
 mod spann_posting_list;
 #[cfg(feature = "testing")]
@@ -33,8 +35,10 @@ pub use metadata::*;
 pub use operation::*;
 pub use record::*;
 pub use scalar_encoding::*;
+pub use scrub_report::*;
 pub use segment::*;
 pub use segment_scope::*;
+pub use segment_stats::*;
 pub use signed_rbm::*;
 pub use spann_posting_list::*;
 pub use tenant::*;
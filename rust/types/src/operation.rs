@@ -1,9 +1,12 @@
 use super::ConversionError;
 use crate::chroma_proto;
 use chroma_error::{ChromaError, ErrorCodes};
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum Operation {
     Add,
     Update,
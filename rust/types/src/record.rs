@@ -101,6 +101,7 @@ impl TryFrom<OperationRecord> for chroma_proto::OperationRecord {
             vector: proto_vector,
             metadata,
             operation: operation_record.operation as i32,
+            compressed_payload: None,
         })
     }
 }
@@ -366,6 +367,7 @@ mod tests {
             vector: Some(proto_vector),
             metadata: Some(metadata),
             operation: chroma_proto::Operation::Add as i32,
+            compressed_payload: None,
         };
         let converted_operation_record = OperationRecord::try_from(proto_submit).unwrap();
         assert_eq!(converted_operation_record.id, Uuid::nil().to_string());
@@ -411,6 +413,7 @@ mod tests {
             vector: Some(proto_vector),
             metadata: Some(metadata),
             operation: chroma_proto::Operation::Add as i32,
+            compressed_payload: None,
         };
         let record_log = chroma_proto::LogRecord {
             log_offset: 42,
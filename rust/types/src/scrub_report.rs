@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+/// A persisted file, or a part of one, that failed verification
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CorruptFile {
+    /// The storage key of the file
+    pub path: String,
+    pub reason: String,
+}
+
+/// The outcome of verifying the persisted files of a blockfile, index or segment
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ScrubReport {
+    pub files_checked: u64,
+    /// Files that could be decoded but have no recorded checksum to verify against
+    pub files_without_checksum: u64,
+    pub corruptions: Vec<CorruptFile>,
+}
+
+impl ScrubReport {
+    pub fn is_healthy(&self) -> bool {
+        self.corruptions.is_empty()
+    }
+
+    pub fn add_corruption(&mut self, path: impl Into<String>, reason: impl ToString) {
+        self.corruptions.push(CorruptFile {
+            path: path.into(),
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn merge(&mut self, other: ScrubReport) {
+        self.files_checked += other.files_checked;
+        self.files_without_checksum += other.files_without_checksum;
+        self.corruptions.extend(other.corruptions);
+    }
+}
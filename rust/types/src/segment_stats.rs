@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::chroma_proto;
+
+/// Physical layout of a blockfile
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct BlockfileStats {
+    pub block_count: u64,
+    pub total_size_bytes: u64,
+    pub min_block_size_bytes: u64,
+    pub max_block_size_bytes: u64,
+}
+
+impl BlockfileStats {
+    pub fn add_block(&mut self, size_bytes: u64) {
+        if self.block_count == 0 {
+            self.min_block_size_bytes = size_bytes;
+        } else {
+            self.min_block_size_bytes = self.min_block_size_bytes.min(size_bytes);
+        }
+        self.max_block_size_bytes = self.max_block_size_bytes.max(size_bytes);
+        self.block_count += 1;
+        self.total_size_bytes += size_bytes;
+    }
+
+    pub fn merge(&mut self, other: &BlockfileStats) {
+        if other.block_count == 0 {
+            return;
+        }
+        if self.block_count == 0 {
+            *self = other.clone();
+            return;
+        }
+        self.block_count += other.block_count;
+        self.total_size_bytes += other.total_size_bytes;
+        self.min_block_size_bytes = self.min_block_size_bytes.min(other.min_block_size_bytes);
+        self.max_block_size_bytes = self.max_block_size_bytes.max(other.max_block_size_bytes);
+    }
+}
+
+impl From<chroma_proto::BlockfileStats> for BlockfileStats {
+    fn from(value: chroma_proto::BlockfileStats) -> Self {
+        Self {
+            block_count: value.block_count,
+            total_size_bytes: value.total_size_bytes,
+            min_block_size_bytes: value.min_block_size_bytes,
+            max_block_size_bytes: value.max_block_size_bytes,
+        }
+    }
+}
+
+impl From<BlockfileStats> for chroma_proto::BlockfileStats {
+    fn from(value: BlockfileStats) -> Self {
+        Self {
+            block_count: value.block_count,
+            total_size_bytes: value.total_size_bytes,
+            min_block_size_bytes: value.min_block_size_bytes,
+            max_block_size_bytes: value.max_block_size_bytes,
+        }
+    }
+}
+
+/// Statistics of an HNSW index
+///
+/// # Fields
+/// - `len`: The number of live elements in the index
+/// - `len_with_deleted`: The number of elements in the index, including the ones marked as deleted
+/// - `capacity`: The number of elements the index can hold before it is resized
+/// - `dimensionality`: The dimension of the indexed embeddings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct HnswIndexStats {
+    pub len: u64,
+    pub len_with_deleted: u64,
+    pub capacity: u64,
+    pub dimensionality: u32,
+}
+
+impl From<chroma_proto::HnswIndexStats> for HnswIndexStats {
+    fn from(value: chroma_proto::HnswIndexStats) -> Self {
+        Self {
+            len: value.len,
+            len_with_deleted: value.len_with_deleted,
+            capacity: value.capacity,
+            dimensionality: value.dimensionality,
+        }
+    }
+}
+
+impl From<HnswIndexStats> for chroma_proto::HnswIndexStats {
+    fn from(value: HnswIndexStats) -> Self {
+        Self {
+            len: value.len,
+            len_with_deleted: value.len_with_deleted,
+            capacity: value.capacity,
+            dimensionality: value.dimensionality,
+        }
+    }
+}
+
+/// Statistics of a SPANN index
+///
+/// # Fields
+/// - `num_centroids`: The number of centroids in the head index
+/// - `num_posting_lists`: The number of posting lists
+/// - `min_posting_list_size`, `max_posting_list_size`, `mean_posting_list_size`,
+///   `p50_posting_list_size`, `p99_posting_list_size`: The distribution of the number of
+///   up-to-date postings in each posting list
+/// - `num_stale_postings`: The number of postings whose version is behind the version map
+/// - `num_posting_lists_to_split`: The number of posting lists above the split threshold
+/// - `num_posting_lists_to_merge`: The number of posting lists below the merge threshold
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct SpannIndexStats {
+    pub num_centroids: u64,
+    pub num_posting_lists: u64,
+    pub min_posting_list_size: u64,
+    pub max_posting_list_size: u64,
+    pub mean_posting_list_size: f64,
+    pub p50_posting_list_size: u64,
+    pub p99_posting_list_size: u64,
+    pub num_stale_postings: u64,
+    pub num_posting_lists_to_split: u64,
+    pub num_posting_lists_to_merge: u64,
+}
+
+impl From<chroma_proto::SpannIndexStats> for SpannIndexStats {
+    fn from(value: chroma_proto::SpannIndexStats) -> Self {
+        Self {
+            num_centroids: value.num_centroids,
+            num_posting_lists: value.num_posting_lists,
+            min_posting_list_size: value.min_posting_list_size,
+            max_posting_list_size: value.max_posting_list_size,
+            mean_posting_list_size: value.mean_posting_list_size,
+            p50_posting_list_size: value.p50_posting_list_size,
+            p99_posting_list_size: value.p99_posting_list_size,
+            num_stale_postings: value.num_stale_postings,
+            num_posting_lists_to_split: value.num_posting_lists_to_split,
+            num_posting_lists_to_merge: value.num_posting_lists_to_merge,
+        }
+    }
+}
+
+impl From<SpannIndexStats> for chroma_proto::SpannIndexStats {
+    fn from(value: SpannIndexStats) -> Self {
+        Self {
+            num_centroids: value.num_centroids,
+            num_posting_lists: value.num_posting_lists,
+            min_posting_list_size: value.min_posting_list_size,
+            max_posting_list_size: value.max_posting_list_size,
+            mean_posting_list_size: value.mean_posting_list_size,
+            p50_posting_list_size: value.p50_posting_list_size,
+            p99_posting_list_size: value.p99_posting_list_size,
+            num_stale_postings: value.num_stale_postings,
+            num_posting_lists_to_split: value.num_posting_lists_to_split,
+            num_posting_lists_to_merge: value.num_posting_lists_to_merge,
+        }
+    }
+}
+
+/// Statistics of a segment
+///
+/// # Fields
+/// - `segment_id`: The id of the segment
+/// - `segment_type`: The type of the segment
+/// - `blockfiles`: The layout of the blockfiles of the segment, keyed by their file path name
+/// - `hnsw`: The statistics of the HNSW index, if the segment is an initialized HNSW segment
+/// - `spann`: The statistics of the SPANN index, if the segment is an initialized SPANN segment
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct SegmentStats {
+    pub segment_id: String,
+    pub segment_type: String,
+    pub blockfiles: HashMap<String, BlockfileStats>,
+    pub hnsw: Option<HnswIndexStats>,
+    pub spann: Option<SpannIndexStats>,
+}
+
+impl From<chroma_proto::SegmentStats> for SegmentStats {
+    fn from(value: chroma_proto::SegmentStats) -> Self {
+        Self {
+            segment_id: value.segment_id,
+            segment_type: value.segment_type,
+            blockfiles: value
+                .blockfiles
+                .into_iter()
+                .map(|(name, stats)| (name, stats.into()))
+                .collect(),
+            hnsw: value.hnsw.map(Into::into),
+            spann: value.spann.map(Into::into),
+        }
+    }
+}
+
+impl From<SegmentStats> for chroma_proto::SegmentStats {
+    fn from(value: SegmentStats) -> Self {
+        Self {
+            segment_id: value.segment_id,
+            segment_type: value.segment_type,
+            blockfiles: value
+                .blockfiles
+                .into_iter()
+                .map(|(name, stats)| (name, stats.into()))
+                .collect(),
+            hnsw: value.hnsw.map(Into::into),
+            spann: value.spann.map(Into::into),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::CollectionMetadataUpdate;
+use crate::{CollectionMetadataUpdate, DedupOptions};
 use regex::Regex;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -32,6 +32,19 @@ pub(crate) fn validate_non_empty_metadata<V>(
     }
 }
 
+pub(crate) fn validate_dedup_options(dedup: &DedupOptions) -> Result<(), ValidationError> {
+    if !dedup.threshold.is_finite() || dedup.threshold < 0.0 {
+        return Err(ValidationError::new("dedup").with_message(
+            format!(
+                "Expected a finite, non-negative dedup threshold. Got: {}",
+                dedup.threshold
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
 pub(crate) fn validate_name(name: impl AsRef<str>) -> Result<(), ValidationError> {
     let name_str = name.as_ref();
     if !ALNUM_RE.is_match(name_str) {
@@ -57,6 +57,8 @@ pub fn empty_fetch_log(collection_uuid: CollectionUuid) -> FetchLogOperator {
         batch_size: 100,
         start_log_offset_id: 0,
         maximum_fetch_count: Some(0),
+        end_log_offset_id: None,
+        end_timestamp: None,
         collection_uuid,
     }
 }
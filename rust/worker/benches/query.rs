@@ -16,7 +16,7 @@ use load::{
 };
 use rand::{seq::SliceRandom, thread_rng};
 use worker::{
-    config::RootConfig,
+    config::{KnnQueryConfig, RootConfig},
     execution::{
         operators::{knn::KnnOperator, knn_projection::KnnProjectionOperator},
         orchestration::{
@@ -38,6 +38,7 @@ fn trivial_knn_filter(
         dispatcher_handle,
         hnsw_provider,
         1000,
+        KnnQueryConfig::default(),
         test_segments.into(),
         empty_fetch_log(collection_uuid),
         trivial_filter(),
@@ -56,6 +57,7 @@ fn always_true_knn_filter(
         dispatcher_handle,
         hnsw_provider,
         1000,
+        KnnQueryConfig::default(),
         test_segments.into(),
         empty_fetch_log(collection_uuid),
         always_true_filter_for_modulo_metadata(),
@@ -74,6 +76,7 @@ fn always_false_knn_filter(
         dispatcher_handle,
         hnsw_provider,
         1000,
+        KnnQueryConfig::default(),
         test_segments.into(),
         empty_fetch_log(collection_uuid),
         always_false_filter_for_modulo_metadata(),
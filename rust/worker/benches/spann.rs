@@ -3,6 +3,7 @@ use std::{collections::HashSet, path::PathBuf};
 use chroma_benchmark::{
     benchmark::tokio_multi_thread,
     datasets::{gist::GistDataset, types::RecordDataset},
+    recall::recall_at_k,
 };
 use chroma_blockstore::{arrow::provider::ArrowBlockfileProvider, provider::BlockfileProvider};
 use chroma_cache::{new_cache_for_test, new_non_persistent_cache_for_test};
@@ -221,28 +222,30 @@ fn calculate_recall<'a>(
                 .run(&bf_operator_input)
                 .await
                 .expect("Error running operator");
-            let mut recall = 0;
-            for bf_record in bf_output.records.iter() {
-                for spann_record in knn_output.merged_records.iter() {
-                    if bf_record.offset_id == spann_record.offset_id {
-                        recall += 1;
-                    }
-                }
-            }
+            let recall = recall_at_k(
+                &knn_output
+                    .merged_records
+                    .iter()
+                    .map(|record| record.offset_id)
+                    .collect::<Vec<_>>(),
+                &bf_output
+                    .records
+                    .iter()
+                    .map(|record| record.offset_id)
+                    .collect::<Vec<_>>(),
+                k,
+            );
             println!(
                 "Recall@{} with probe_nbr_count {} for query {}: {}",
-                k,
-                probe_nbr,
-                index,
-                recall as f32 / k as f32
+                k, probe_nbr, index, recall
             );
-            avg_recall += recall as f32 / k as f32;
+            avg_recall += recall;
         }
         println!(
             "Avg recall@{} with probe_nbr_count {} across 1000 queries: {}",
             k,
             probe_nbr,
-            avg_recall / query_emb.len() as f32
+            avg_recall / query_emb.len() as f64
         );
     });
 }
@@ -0,0 +1,4 @@
+#[tokio::main]
+async fn main() {
+    worker::scrubber_entrypoint().await;
+}
@@ -1,5 +1,7 @@
 use chroma_types::chroma_proto::{
-    compactor_client::CompactorClient, CollectionIds, CompactionRequest,
+    compactor_client::CompactorClient, CancelCompactionRequest, CollectionIds, CompactionJobInfo,
+    CompactionJobState, CompactionRequest, GetCompactionHistoryRequest, ListCompactionJobsRequest,
+    PauseCompactionRequest, ReindexRequest, ResumeCompactionRequest,
 };
 use clap::{Parser, Subcommand};
 use thiserror::Error;
@@ -35,6 +37,91 @@ pub enum CompactionCommand {
         #[arg(short, long)]
         id: Vec<Uuid>,
     },
+    /// Rebuild the vector index of a collection with new parameters
+    Reindex {
+        /// Uuid of the collection to reindex
+        #[arg(short, long)]
+        id: Uuid,
+        /// Segment type of the new vector index. Only the distributed HNSW segment is supported
+        #[arg(long)]
+        segment_type: Option<String>,
+        /// Distance function of the new index (l2, cosine or ip)
+        #[arg(long)]
+        space: Option<String>,
+        /// Maximum number of neighbors per node in the new index
+        #[arg(short, long)]
+        m: Option<u32>,
+        /// Size of the candidate list during construction of the new index
+        #[arg(long)]
+        ef_construction: Option<u32>,
+        /// Size of the candidate list during search on the new index
+        #[arg(long)]
+        ef_search: Option<u32>,
+    },
+    /// List the running and queued compaction jobs, and the paused collections
+    Jobs,
+    /// Show the most recent finished compaction jobs
+    History {
+        /// Only show the jobs of this collection
+        #[arg(short, long)]
+        id: Option<Uuid>,
+        /// Only show failed jobs, with the failure reason and the failing operator
+        #[arg(long)]
+        failed: bool,
+        /// Maximum number of jobs to show
+        #[arg(short, long)]
+        limit: Option<u32>,
+    },
+    /// Cancel the running or queued compaction of a collection
+    Cancel {
+        /// Uuid of the collection
+        #[arg(short, long)]
+        id: Uuid,
+    },
+    /// Stop scheduling compaction for a collection until it is resumed
+    Pause {
+        /// Uuid of the collection
+        #[arg(short, long)]
+        id: Uuid,
+    },
+    /// Resume compaction for a paused collection
+    Resume {
+        /// Uuid of the collection
+        #[arg(short, long)]
+        id: Uuid,
+    },
+}
+
+fn print_job(job: &CompactionJobInfo) {
+    let state = CompactionJobState::try_from(job.state)
+        .map(|state| state.as_str_name())
+        .unwrap_or("UNKNOWN");
+    let mut line = format!("{} {state}", job.collection_id);
+    if let Some(tenant_id) = &job.tenant_id {
+        line.push_str(&format!(" tenant={tenant_id}"));
+    }
+    if let Some(log_offset) = job.log_offset {
+        line.push_str(&format!(" offset={log_offset}"));
+    }
+    if let Some(collection_version) = job.collection_version {
+        line.push_str(&format!(" version={collection_version}"));
+    }
+    for (name, timestamp) in [
+        ("queued_at_ms", job.queued_at_ms),
+        ("started_at_ms", job.started_at_ms),
+        ("finished_at_ms", job.finished_at_ms),
+    ] {
+        if let Some(timestamp) = timestamp {
+            line.push_str(&format!(" {name}={timestamp}"));
+        }
+    }
+    if let Some(operator) = &job.failed_operator {
+        line.push_str(&format!(" operator={operator}"));
+    }
+    if let Some(error) = &job.error {
+        line.push_str(&format!(" error={error:?}"));
+    }
+    println!("{line}");
 }
 
 impl CompactionClient {
@@ -57,6 +144,89 @@ impl CompactionClient {
                     return Err(CompactionClientError::Compactor(status.to_string()));
                 }
             }
+            CompactionCommand::Reindex {
+                id,
+                segment_type,
+                space,
+                m,
+                ef_construction,
+                ef_search,
+            } => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .reindex(ReindexRequest {
+                        collection_id: id.to_string(),
+                        segment_type: segment_type.clone(),
+                        space: space.clone(),
+                        m: *m,
+                        ef_construction: *ef_construction,
+                        ef_search: *ef_search,
+                    })
+                    .await;
+                if let Err(status) = response {
+                    return Err(CompactionClientError::Compactor(status.to_string()));
+                }
+            }
+            CompactionCommand::Jobs => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .list_compaction_jobs(ListCompactionJobsRequest {})
+                    .await
+                    .map_err(|status| CompactionClientError::Compactor(status.to_string()))?
+                    .into_inner();
+                response.jobs.iter().for_each(print_job);
+                for collection_id in response.paused_collection_ids {
+                    println!("{collection_id} PAUSED");
+                }
+            }
+            CompactionCommand::History { id, failed, limit } => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .get_compaction_history(GetCompactionHistoryRequest {
+                        collection_id: id.map(|id| id.to_string()),
+                        failed_only: *failed,
+                        limit: *limit,
+                    })
+                    .await
+                    .map_err(|status| CompactionClientError::Compactor(status.to_string()))?
+                    .into_inner();
+                response.jobs.iter().for_each(print_job);
+            }
+            CompactionCommand::Cancel { id } => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .cancel_compaction(CancelCompactionRequest {
+                        collection_id: id.to_string(),
+                    })
+                    .await
+                    .map_err(|status| CompactionClientError::Compactor(status.to_string()))?
+                    .into_inner();
+                if !response.cancelled {
+                    println!("No running or queued compaction for collection {id}");
+                }
+            }
+            CompactionCommand::Pause { id } => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .pause_compaction(PauseCompactionRequest {
+                        collection_id: id.to_string(),
+                    })
+                    .await;
+                if let Err(status) = response {
+                    return Err(CompactionClientError::Compactor(status.to_string()));
+                }
+            }
+            CompactionCommand::Resume { id } => {
+                let mut client = self.grpc_client().await?;
+                let response = client
+                    .resume_compaction(ResumeCompactionRequest {
+                        collection_id: id.to_string(),
+                    })
+                    .await;
+                if let Err(status) = response {
+                    return Err(CompactionClientError::Compactor(status.to_string()));
+                }
+            }
         };
         Ok(())
     }
@@ -1,11 +1,16 @@
+use super::job_tracker::{CompactionJobOutcome, CompactionJobTracker};
 use super::scheduler::Scheduler;
-use super::scheduler_policy::LasCompactionTimeSchedulerPolicy;
+use super::scheduler_policy::SchedulerPolicy;
 use super::OneOffCompactionMessage;
+use super::ReindexMessage;
 use crate::compactor::types::CompactionJob;
 use crate::compactor::types::ScheduledCompactionMessage;
 use crate::config::CompactionServiceConfig;
 use crate::execution::orchestration::CompactOrchestrator;
 use crate::execution::orchestration::CompactionResponse;
+use crate::execution::orchestration::ReindexError;
+use crate::execution::orchestration::ReindexOrchestrator;
+use crate::execution::orchestration::ReindexResponse;
 use async_trait::async_trait;
 use chroma_blockstore::provider::BlockfileProvider;
 use chroma_config::assignment::assignment_policy::AssignmentPolicy;
@@ -21,14 +26,20 @@ use chroma_system::Dispatcher;
 use chroma_system::Orchestrator;
 use chroma_system::{Component, ComponentContext, ComponentHandle, Handler, System};
 use chroma_types::CollectionUuid;
+use chroma_types::DistributedHnswParameters;
+use chroma_types::MetadataValue;
+use chroma_types::SegmentScope;
+use chroma_types::SegmentType;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::str::FromStr;
 use std::time::Duration;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 use tracing::span;
 use tracing::Instrument;
@@ -38,6 +49,10 @@ use uuid::Uuid;
 pub(crate) struct CompactionManager {
     system: Option<System>,
     scheduler: Scheduler,
+    // Reindex jobs waiting for the next compaction cycle
+    pending_reindexes: Vec<ReindexMessage>,
+    // Queued, running and recently finished compaction jobs
+    job_tracker: CompactionJobTracker,
     // Dependencies
     log: Log,
     sysdb: SysDb,
@@ -60,12 +75,15 @@ pub(crate) struct CompactionManager {
 pub(crate) enum CompactionError {
     #[error("Failed to compact")]
     FailedToCompact,
+    #[error("Compaction cancelled")]
+    Cancelled,
 }
 
 impl ChromaError for CompactionError {
     fn code(&self) -> ErrorCodes {
         match self {
             CompactionError::FailedToCompact => ErrorCodes::Internal,
+            CompactionError::Cancelled => ErrorCodes::Cancelled,
         }
     }
 }
@@ -84,10 +102,13 @@ impl CompactionManager {
         min_compaction_size: usize,
         max_compaction_size: usize,
         max_partition_size: usize,
+        job_history_size: usize,
     ) -> Self {
         CompactionManager {
             system: None,
             scheduler,
+            pending_reindexes: Vec::new(),
+            job_tracker: CompactionJobTracker::new(job_history_size),
             log,
             sysdb,
             storage,
@@ -102,15 +123,37 @@ impl CompactionManager {
         }
     }
 
+    /// The tracker of the compaction jobs, shared with the compaction server
+    pub(crate) fn job_tracker(&self) -> CompactionJobTracker {
+        self.job_tracker.clone()
+    }
+
+    fn fail_job(
+        &self,
+        compaction_job: &CompactionJob,
+        error: &dyn ChromaError,
+        operator: Option<&str>,
+    ) {
+        self.job_tracker.finish(
+            compaction_job.collection_id,
+            CompactionJobOutcome::Failed {
+                error: error.to_string(),
+                operator: operator.map(ToString::to_string),
+            },
+        );
+    }
+
     #[instrument(name = "CompactionManager::compact")]
     async fn compact(
         &self,
         compaction_job: &CompactionJob,
+        cancellation_token: CancellationToken,
     ) -> Result<CompactionResponse, Box<dyn ChromaError>> {
         let dispatcher = match self.dispatcher {
             Some(ref dispatcher) => dispatcher.clone(),
             None => {
                 tracing::error!("No dispatcher found");
+                self.fail_job(compaction_job, &CompactionError::FailedToCompact, None);
                 return Err(Box::new(CompactionError::FailedToCompact));
             }
         };
@@ -130,19 +173,39 @@ impl CompactionManager {
                     self.max_partition_size,
                 );
 
-                match orchestrator.run(system.clone()).await {
-                    Ok(result) => {
+                match orchestrator
+                    .run_until_cancelled(system.clone(), cancellation_token)
+                    .await
+                {
+                    Ok(Some(result)) => {
                         tracing::info!("Compaction Job completed: {:?}", result);
+                        self.job_tracker.finish(
+                            compaction_job.collection_id,
+                            CompactionJobOutcome::Succeeded,
+                        );
                         return Ok(result);
                     }
+                    Ok(None) => {
+                        tracing::info!(
+                            "Compaction Job cancelled for collection {}",
+                            compaction_job.collection_id
+                        );
+                        self.job_tracker.finish(
+                            compaction_job.collection_id,
+                            CompactionJobOutcome::Cancelled,
+                        );
+                        return Err(Box::new(CompactionError::Cancelled));
+                    }
                     Err(e) => {
                         tracing::error!("Compaction Job failed: {:?}", e);
+                        self.fail_job(compaction_job, &e, e.operator());
                         return Err(Box::new(e));
                     }
                 }
             }
             None => {
                 tracing::error!("No system found");
+                self.fail_job(compaction_job, &CompactionError::FailedToCompact, None);
                 return Err(Box::new(CompactionError::FailedToCompact));
             }
         };
@@ -150,14 +213,21 @@ impl CompactionManager {
 
     #[instrument(name = "CompactionManager::compact_batch")]
     pub(crate) async fn compact_batch(&mut self) -> Vec<CollectionUuid> {
+        // Drop the one-off requests that were cancelled while they were queued
+        self.scheduler
+            .remove_oneoff_collections(self.job_tracker.take_cancelled());
         self.scheduler.schedule().await;
         let job_futures = self
             .scheduler
             .get_jobs()
-            .map(|job| {
+            .filter_map(|job| {
+                let cancellation_token = self.job_tracker.start(job)?;
                 let instrumented_span = span!(parent: None, tracing::Level::INFO, "Compacting job", collection_id = ?job.collection_id);
                 instrumented_span.follows_from(Span::current());
-                self.compact(job).instrument(instrumented_span)
+                Some(
+                    self.compact(job, cancellation_token)
+                        .instrument(instrumented_span),
+                )
             })
             .collect::<FuturesUnordered<_>>();
 
@@ -180,6 +250,138 @@ impl CompactionManager {
             .await
     }
 
+    #[instrument(name = "CompactionManager::reindex")]
+    async fn reindex(
+        &self,
+        reindex_job: &ReindexMessage,
+    ) -> Result<ReindexResponse, Box<dyn ChromaError>> {
+        let dispatcher = match self.dispatcher {
+            Some(ref dispatcher) => dispatcher.clone(),
+            None => {
+                tracing::error!("No dispatcher found");
+                return Err(Box::new(CompactionError::FailedToCompact));
+            }
+        };
+        let system = match self.system {
+            Some(ref system) => system.clone(),
+            None => {
+                tracing::error!("No system found");
+                return Err(Box::new(CompactionError::FailedToCompact));
+            }
+        };
+
+        let mut sysdb = self.sysdb.clone();
+        let collection = sysdb
+            .get_collections(Some(reindex_job.collection_id), None, None, None, None, 0)
+            .await
+            .map_err(|e| ReindexError::GetCollection(e).boxed())?
+            .pop()
+            .ok_or_else(|| {
+                ReindexError::CollectionNotFound(reindex_job.collection_id.to_string()).boxed()
+            })?;
+        if collection.dimension.is_none() {
+            return Err(ReindexError::CollectionMissingDimension.boxed());
+        }
+
+        let segments = sysdb
+            .get_segments(None, None, None, reindex_job.collection_id)
+            .await
+            .map_err(|e| ReindexError::GetSegments(e).boxed())?;
+        let record_segment = segments
+            .iter()
+            .find(|segment| segment.r#type == SegmentType::BlockfileRecord)
+            .cloned()
+            .ok_or(ReindexError::MissingSegment(SegmentType::BlockfileRecord).boxed())?;
+        let mut vector_segment = segments
+            .into_iter()
+            .find(|segment| segment.scope == SegmentScope::VECTOR)
+            .ok_or(ReindexError::MissingSegment(SegmentType::HnswDistributed).boxed())?;
+        if vector_segment.r#type != SegmentType::HnswDistributed {
+            return Err(
+                ReindexError::UnsupportedSegmentType(String::from(vector_segment.r#type)).boxed(),
+            );
+        }
+
+        // Overlay the requested parameters on the current ones and start from an empty index
+        let metadata = vector_segment.metadata.get_or_insert_with(HashMap::new);
+        if let Some(space) = &reindex_job.space {
+            metadata.insert("hnsw:space".to_string(), MetadataValue::Str(space.clone()));
+        }
+        if let Some(m) = reindex_job.m {
+            metadata.insert("hnsw:M".to_string(), MetadataValue::Int(m as i64));
+        }
+        if let Some(ef_construction) = reindex_job.ef_construction {
+            metadata.insert(
+                "hnsw:construction_ef".to_string(),
+                MetadataValue::Int(ef_construction as i64),
+            );
+        }
+        if let Some(ef_search) = reindex_job.ef_search {
+            metadata.insert(
+                "hnsw:search_ef".to_string(),
+                MetadataValue::Int(ef_search as i64),
+            );
+        }
+        DistributedHnswParameters::try_from(&vector_segment)
+            .map_err(|e| ReindexError::InvalidParameters(e).boxed())?;
+        vector_segment.file_path = HashMap::new();
+
+        let orchestrator = ReindexOrchestrator::new(
+            self.blockfile_provider.for_tenant(&collection.tenant),
+            self.hnsw_index_provider.for_tenant(&collection.tenant),
+            dispatcher,
+            self.log.clone(),
+            sysdb,
+            collection,
+            record_segment,
+            vector_segment,
+        );
+
+        match orchestrator.run(system).await {
+            Ok(result) => {
+                tracing::info!("Reindex Job completed: {:?}", result);
+                Ok(result)
+            }
+            Err(e) => {
+                tracing::error!("Reindex Job failed: {:?}", e);
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    #[instrument(name = "CompactionManager::reindex_batch")]
+    pub(crate) async fn reindex_batch(&mut self) -> Vec<CollectionUuid> {
+        let reindex_jobs = std::mem::take(&mut self.pending_reindexes);
+        if reindex_jobs.is_empty() {
+            return Vec::new();
+        }
+        tracing::info!("Running {} reindex jobs", reindex_jobs.len());
+
+        let mut reindexed_collections = Vec::with_capacity(reindex_jobs.len());
+        for reindex_job in reindex_jobs {
+            let instrumented_span = span!(parent: None, tracing::Level::INFO, "Reindexing job", collection_id = ?reindex_job.collection_id);
+            instrumented_span.follows_from(Span::current());
+            match self
+                .reindex(&reindex_job)
+                .instrument(instrumented_span)
+                .await
+            {
+                Ok(response) => {
+                    tracing::info!(
+                        "Reindexed {} records of collection {}",
+                        response.total_records,
+                        response.collection.collection_id
+                    );
+                    reindexed_collections.push(response.collection.collection_id);
+                }
+                Err(err) => {
+                    tracing::error!("Reindex failed {err}");
+                }
+            }
+        }
+        reindexed_collections
+    }
+
     pub(crate) fn set_dispatcher(&mut self, dispatcher: ComponentHandle<Dispatcher>) {
         self.dispatcher = Some(dispatcher);
     }
@@ -218,7 +420,11 @@ impl Configurable<CompactionServiceConfig> for CompactionManager {
         };
 
         let my_ip = config.my_member_id.clone();
-        let policy = Box::new(LasCompactionTimeSchedulerPolicy {});
+        let policy = Box::<dyn SchedulerPolicy>::try_from_config(
+            &config.compactor.scheduler_policy,
+            registry,
+        )
+        .await?;
         let compaction_interval_sec = config.compactor.compaction_interval_sec;
         let max_concurrent_jobs = config.compactor.max_concurrent_jobs;
         let compaction_manager_queue_size = config.compactor.compaction_manager_queue_size;
@@ -270,6 +476,7 @@ impl Configurable<CompactionServiceConfig> for CompactionManager {
             min_compaction_size,
             max_compaction_size,
             max_partition_size,
+            config.compactor.job_history_size,
         ))
     }
 }
@@ -313,7 +520,9 @@ impl Handler<ScheduledCompactionMessage> for CompactionManager {
         ctx: &ComponentContext<CompactionManager>,
     ) {
         tracing::info!("CompactionManager: Performing scheduled compaction");
-        let ids = self.compact_batch().await;
+        let mut ids = self.compact_batch().await;
+        // Reindex after compaction so that the rebuilt index includes the latest compacted records
+        ids.extend(self.reindex_batch().await);
         self.hnsw_index_provider.purge_by_id(&ids).await;
 
         // Compaction is done, schedule the next compaction
@@ -334,6 +543,9 @@ impl Handler<OneOffCompactionMessage> for CompactionManager {
         message: OneOffCompactionMessage,
         _ctx: &ComponentContext<CompactionManager>,
     ) {
+        for collection_id in &message.collection_ids {
+            self.job_tracker.enqueue(*collection_id);
+        }
         self.scheduler
             .add_oneoff_collections(message.collection_ids);
         tracing::info!(
@@ -343,6 +555,19 @@ impl Handler<OneOffCompactionMessage> for CompactionManager {
     }
 }
 
+#[async_trait]
+impl Handler<ReindexMessage> for CompactionManager {
+    type Result = ();
+    async fn handle(
+        &mut self,
+        message: ReindexMessage,
+        _ctx: &ComponentContext<CompactionManager>,
+    ) {
+        tracing::info!("Reindex queued for collection {}", message.collection_id);
+        self.pending_reindexes.push(message);
+    }
+}
+
 #[async_trait]
 impl Handler<Memberlist> for CompactionManager {
     type Result = ();
@@ -355,6 +580,8 @@ impl Handler<Memberlist> for CompactionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::compactor::scheduler_policy::LasCompactionTimeSchedulerPolicy;
+    use crate::compactor::CompactionJobState;
     use chroma_blockstore::arrow::config::TEST_MAX_BLOCK_SIZE_BYTES;
     use chroma_cache::{new_cache_for_test, new_non_persistent_cache_for_test};
     use chroma_config::assignment::assignment_policy::RendezvousHashingAssignmentPolicy;
@@ -587,6 +814,7 @@ mod tests {
             min_compaction_size,
             max_compaction_size,
             max_partition_size,
+            1000,
         );
 
         let system = System::new();
@@ -605,5 +833,12 @@ mod tests {
             (compacted == vec![collection_uuid_1, collection_uuid_2])
                 || (compacted == vec![collection_uuid_2, collection_uuid_1])
         );
+
+        let history = manager.job_tracker().history(None, false, None);
+        assert_eq!(history.len(), 2);
+        assert!(history
+            .iter()
+            .all(|record| record.state == CompactionJobState::Succeeded));
+        assert!(manager.job_tracker().jobs().is_empty());
     }
 }
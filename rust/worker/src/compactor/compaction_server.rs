@@ -1,22 +1,35 @@
 use async_trait::async_trait;
 use chroma_system::ComponentHandle;
+use std::str::FromStr;
+
 use chroma_types::chroma_proto::{
     compactor_server::{Compactor, CompactorServer},
-    CompactionRequest, CompactionResponse,
+    CancelCompactionRequest, CancelCompactionResponse, CompactionJobInfo, CompactionRequest,
+    CompactionResponse, GetCompactionHistoryRequest, GetCompactionHistoryResponse,
+    ListCompactionJobsRequest, ListCompactionJobsResponse, PauseCompactionRequest,
+    PauseCompactionResponse, ReindexRequest, ReindexResponse, ResumeCompactionRequest,
+    ResumeCompactionResponse,
 };
+use chroma_types::{CollectionUuid, SegmentType};
 use tokio::signal::unix::{signal, SignalKind};
 use tonic::{transport::Server, Request, Response, Status};
 use tracing::trace_span;
 
-use crate::compactor::OneOffCompactionMessage;
+use crate::compactor::{CompactionJobTracker, OneOffCompactionMessage, ReindexMessage};
 
 use super::CompactionManager;
 
 pub struct CompactionServer {
     pub manager: ComponentHandle<CompactionManager>,
+    pub(crate) job_tracker: CompactionJobTracker,
     pub port: u16,
 }
 
+fn parse_collection_id(collection_id: &str) -> Result<CollectionUuid, Status> {
+    CollectionUuid::from_str(collection_id)
+        .map_err(|_| Status::invalid_argument(format!("Invalid collection id: {collection_id}")))
+}
+
 impl CompactionServer {
     pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
         let addr = format!("[::]:{}", self.port).parse().unwrap();
@@ -57,4 +70,106 @@ impl Compactor for CompactionServer {
             .map_err(|e| Status::internal(e.to_string()))?;
         Ok(Response::new(CompactionResponse {}))
     }
+
+    async fn reindex(
+        &self,
+        request: Request<ReindexRequest>,
+    ) -> Result<Response<ReindexResponse>, Status> {
+        let reindex_span = trace_span!("ReindexRequest", request = ?request);
+        let request = request.into_inner();
+        if let Some(segment_type) = &request.segment_type {
+            if *segment_type != String::from(SegmentType::HnswDistributed) {
+                // The compactor and the query service only handle distributed HNSW vector
+                // segments, so a collection migrated to another segment type would be unusable
+                return Err(Status::unimplemented(format!(
+                    "Reindexing into segment type {segment_type} is not supported, only {} is",
+                    String::from(SegmentType::HnswDistributed)
+                )));
+            }
+        }
+        self.manager
+            .receiver()
+            .send(
+                ReindexMessage::try_from(request)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?,
+                Some(reindex_span),
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(ReindexResponse {}))
+    }
+
+    async fn list_compaction_jobs(
+        &self,
+        _request: Request<ListCompactionJobsRequest>,
+    ) -> Result<Response<ListCompactionJobsResponse>, Status> {
+        Ok(Response::new(ListCompactionJobsResponse {
+            jobs: self
+                .job_tracker
+                .jobs()
+                .into_iter()
+                .map(CompactionJobInfo::from)
+                .collect(),
+            paused_collection_ids: self
+                .job_tracker
+                .paused()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }))
+    }
+
+    async fn get_compaction_history(
+        &self,
+        request: Request<GetCompactionHistoryRequest>,
+    ) -> Result<Response<GetCompactionHistoryResponse>, Status> {
+        let request = request.into_inner();
+        let collection_id = request
+            .collection_id
+            .as_deref()
+            .map(parse_collection_id)
+            .transpose()?;
+        Ok(Response::new(GetCompactionHistoryResponse {
+            jobs: self
+                .job_tracker
+                .history(
+                    collection_id,
+                    request.failed_only,
+                    request.limit.map(|limit| limit as usize),
+                )
+                .into_iter()
+                .map(CompactionJobInfo::from)
+                .collect(),
+        }))
+    }
+
+    async fn cancel_compaction(
+        &self,
+        request: Request<CancelCompactionRequest>,
+    ) -> Result<Response<CancelCompactionResponse>, Status> {
+        let collection_id = parse_collection_id(&request.into_inner().collection_id)?;
+        let cancelled = self.job_tracker.cancel(collection_id);
+        tracing::info!("Cancel compaction of collection {collection_id}: cancelled={cancelled}");
+        Ok(Response::new(CancelCompactionResponse { cancelled }))
+    }
+
+    async fn pause_compaction(
+        &self,
+        request: Request<PauseCompactionRequest>,
+    ) -> Result<Response<PauseCompactionResponse>, Status> {
+        let collection_id = parse_collection_id(&request.into_inner().collection_id)?;
+        self.job_tracker.pause(collection_id);
+        tracing::info!("Paused compaction of collection {collection_id}");
+        Ok(Response::new(PauseCompactionResponse {}))
+    }
+
+    async fn resume_compaction(
+        &self,
+        request: Request<ResumeCompactionRequest>,
+    ) -> Result<Response<ResumeCompactionResponse>, Status> {
+        let collection_id = parse_collection_id(&request.into_inner().collection_id)?;
+        self.job_tracker.resume(collection_id);
+        tracing::info!("Resumed compaction of collection {collection_id}");
+        Ok(Response::new(ResumeCompactionResponse {}))
+    }
 }
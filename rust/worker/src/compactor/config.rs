@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -16,6 +18,10 @@ pub struct CompactorConfig {
     pub max_partition_size: usize,
     #[serde(default = "CompactorConfig::default_disabled_collections")]
     pub disabled_collections: Vec<String>,
+    #[serde(default)]
+    pub scheduler_policy: SchedulerPolicyConfig,
+    #[serde(default = "CompactorConfig::default_job_history_size")]
+    pub job_history_size: usize,
 }
 
 impl CompactorConfig {
@@ -46,6 +52,10 @@ impl CompactorConfig {
     fn default_disabled_collections() -> Vec<String> {
         vec![]
     }
+
+    fn default_job_history_size() -> usize {
+        1000
+    }
 }
 
 impl Default for CompactorConfig {
@@ -58,6 +68,59 @@ impl Default for CompactorConfig {
             max_compaction_size: CompactorConfig::default_max_compaction_size(),
             max_partition_size: CompactorConfig::default_max_partition_size(),
             disabled_collections: CompactorConfig::default_disabled_collections(),
+            scheduler_policy: SchedulerPolicyConfig::default(),
+            job_history_size: CompactorConfig::default_job_history_size(),
+        }
+    }
+}
+
+/// How the scheduler orders the collections that have new data in the log.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulerRanking {
+    /// Collections of the tenants compacted least recently go first
+    #[default]
+    LastCompactionTime,
+    /// Collections with the most uncompacted log records go first
+    UncompactedSize,
+    /// Collections with the oldest uncompacted log record go first
+    UncompactedAge,
+}
+
+/// Weighted fair share of the compaction jobs across tenants. Jobs are handed out to
+/// tenants in rounds, and each round a tenant gets at most as many jobs as its weight.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TenantFairShareConfig {
+    #[serde(default = "TenantFairShareConfig::default_weight")]
+    pub default_weight: u32,
+    #[serde(default)]
+    pub tenant_weights: HashMap<String, u32>,
+}
+
+impl TenantFairShareConfig {
+    fn default_weight() -> u32 {
+        1
+    }
+}
+
+impl Default for TenantFairShareConfig {
+    fn default() -> Self {
+        TenantFairShareConfig {
+            default_weight: TenantFairShareConfig::default_weight(),
+            tenant_weights: HashMap::new(),
         }
     }
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct SchedulerPolicyConfig {
+    #[serde(default)]
+    pub ranking: SchedulerRanking,
+    /// When set, the ranked collections are shared out fairly across tenants
+    #[serde(default)]
+    pub tenant_fair_share: Option<TenantFairShareConfig>,
+    /// Collections that are always scheduled before any other collection,
+    /// e.g. collections with heavy query traffic
+    #[serde(default)]
+    pub boosted_collections: Vec<String>,
+}
@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chroma_types::CollectionUuid;
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::compactor::types::CompactionJob;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CompactionJobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// How a running compaction job ended
+#[derive(Clone, Debug)]
+pub(crate) enum CompactionJobOutcome {
+    Succeeded,
+    Failed {
+        error: String,
+        operator: Option<String>,
+    },
+    Cancelled,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CompactionJobRecord {
+    pub(crate) collection_id: CollectionUuid,
+    // Unset for one-off compactions that have not been scheduled yet
+    pub(crate) job: Option<CompactionJob>,
+    pub(crate) state: CompactionJobState,
+    pub(crate) queued_at_ms: Option<i64>,
+    pub(crate) started_at_ms: Option<i64>,
+    pub(crate) finished_at_ms: Option<i64>,
+    pub(crate) error: Option<String>,
+    pub(crate) failed_operator: Option<String>,
+}
+
+impl CompactionJobRecord {
+    fn queued(collection_id: CollectionUuid) -> Self {
+        CompactionJobRecord {
+            collection_id,
+            job: None,
+            state: CompactionJobState::Queued,
+            queued_at_ms: Some(now_ms()),
+            started_at_ms: None,
+            finished_at_ms: None,
+            error: None,
+            failed_operator: None,
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct JobTrackerState {
+    queued: HashMap<CollectionUuid, CompactionJobRecord>,
+    running: HashMap<CollectionUuid, (CompactionJobRecord, CancellationToken)>,
+    // Collections whose queued one-off compaction was cancelled before it was scheduled
+    cancelled: HashSet<CollectionUuid>,
+    paused: HashSet<CollectionUuid>,
+    history: VecDeque<CompactionJobRecord>,
+}
+
+/// Keeps track of the queued and running compaction jobs, and of the most recent finished
+/// jobs in a ring buffer. The compaction manager processes its messages one at a time and is
+/// busy for the whole duration of a compaction batch, so the tracker is shared with the
+/// compaction server, which reads it and cancels or pauses jobs directly.
+#[derive(Clone)]
+pub(crate) struct CompactionJobTracker {
+    state: Arc<Mutex<JobTrackerState>>,
+    history_size: usize,
+}
+
+impl CompactionJobTracker {
+    pub(crate) fn new(history_size: usize) -> Self {
+        CompactionJobTracker {
+            state: Arc::new(Mutex::new(JobTrackerState::default())),
+            history_size,
+        }
+    }
+
+    /// Records a one-off compaction request that waits for the next compaction cycle
+    pub(crate) fn enqueue(&self, collection_id: CollectionUuid) {
+        let mut state = self.state.lock();
+        state.cancelled.remove(&collection_id);
+        state
+            .queued
+            .entry(collection_id)
+            .or_insert_with(|| CompactionJobRecord::queued(collection_id));
+    }
+
+    /// Marks a scheduled job as running. Returns the token that cancels the job, or `None`
+    /// if the job must not run because its collection is paused or the job was cancelled.
+    pub(crate) fn start(&self, job: &CompactionJob) -> Option<CancellationToken> {
+        let mut state = self.state.lock();
+        if state.paused.contains(&job.collection_id) {
+            tracing::info!(
+                "Skipping compaction of collection {} because it is paused",
+                job.collection_id
+            );
+            return None;
+        }
+        if state.cancelled.remove(&job.collection_id) {
+            tracing::info!(
+                "Skipping compaction of collection {} because it was cancelled",
+                job.collection_id
+            );
+            return None;
+        }
+        if state.running.contains_key(&job.collection_id) {
+            return None;
+        }
+
+        let mut record = state
+            .queued
+            .remove(&job.collection_id)
+            .unwrap_or_else(|| CompactionJobRecord::queued(job.collection_id));
+        record.job = Some(job.clone());
+        record.state = CompactionJobState::Running;
+        record.started_at_ms = Some(now_ms());
+        let cancellation_token = CancellationToken::new();
+        state
+            .running
+            .insert(job.collection_id, (record, cancellation_token.clone()));
+        Some(cancellation_token)
+    }
+
+    /// Moves a running job to the history
+    pub(crate) fn finish(&self, collection_id: CollectionUuid, outcome: CompactionJobOutcome) {
+        let mut state = self.state.lock();
+        let Some((mut record, _)) = state.running.remove(&collection_id) else {
+            return;
+        };
+        record.finished_at_ms = Some(now_ms());
+        match outcome {
+            CompactionJobOutcome::Succeeded => record.state = CompactionJobState::Succeeded,
+            CompactionJobOutcome::Failed { error, operator } => {
+                record.state = CompactionJobState::Failed;
+                record.error = Some(error);
+                record.failed_operator = operator;
+            }
+            CompactionJobOutcome::Cancelled => record.state = CompactionJobState::Cancelled,
+        }
+        self.push_history(&mut state, record);
+    }
+
+    fn push_history(&self, state: &mut JobTrackerState, record: CompactionJobRecord) {
+        if self.history_size == 0 {
+            return;
+        }
+        while state.history.len() >= self.history_size {
+            state.history.pop_front();
+        }
+        state.history.push_back(record);
+    }
+
+    /// Cancels the running or queued compaction of a collection. Returns whether there was
+    /// such a job. The collection is scheduled again in later cycles unless it is paused. A
+    /// running job that has started to register its result is left to finish.
+    pub(crate) fn cancel(&self, collection_id: CollectionUuid) -> bool {
+        let mut state = self.state.lock();
+        if let Some((_, cancellation_token)) = state.running.get(&collection_id) {
+            cancellation_token.cancel();
+            return true;
+        }
+        match state.queued.remove(&collection_id) {
+            Some(mut record) => {
+                record.state = CompactionJobState::Cancelled;
+                record.finished_at_ms = Some(now_ms());
+                state.cancelled.insert(collection_id);
+                self.push_history(&mut state, record);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Takes the collections whose queued one-off compaction was cancelled since the last
+    /// call, so that the caller can drop their one-off requests before the next cycle
+    pub(crate) fn take_cancelled(&self) -> Vec<CollectionUuid> {
+        self.state.lock().cancelled.drain().collect()
+    }
+
+    /// Stops scheduling compaction for a collection until it is resumed. A job that is
+    /// already running is left to finish.
+    pub(crate) fn pause(&self, collection_id: CollectionUuid) {
+        self.state.lock().paused.insert(collection_id);
+    }
+
+    pub(crate) fn resume(&self, collection_id: CollectionUuid) {
+        self.state.lock().paused.remove(&collection_id);
+    }
+
+    pub(crate) fn paused(&self) -> Vec<CollectionUuid> {
+        self.state.lock().paused.iter().cloned().collect()
+    }
+
+    /// The running jobs followed by the queued jobs
+    pub(crate) fn jobs(&self) -> Vec<CompactionJobRecord> {
+        let state = self.state.lock();
+        let mut running = state
+            .running
+            .values()
+            .map(|(record, _)| record.clone())
+            .collect::<Vec<_>>();
+        running.sort_by_key(|record| record.started_at_ms);
+        let mut queued = state.queued.values().cloned().collect::<Vec<_>>();
+        queued.sort_by_key(|record| record.queued_at_ms);
+        running.extend(queued);
+        running
+    }
+
+    /// The finished jobs, most recent first
+    pub(crate) fn history(
+        &self,
+        collection_id: Option<CollectionUuid>,
+        failed_only: bool,
+        limit: Option<usize>,
+    ) -> Vec<CompactionJobRecord> {
+        let state = self.state.lock();
+        state
+            .history
+            .iter()
+            .rev()
+            .filter(|record| collection_id.is_none_or(|id| record.collection_id == id))
+            .filter(|record| !failed_only || record.state == CompactionJobState::Failed)
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: u128) -> CompactionJob {
+        CompactionJob {
+            collection_id: CollectionUuid(uuid::Uuid::from_u128(id)),
+            tenant_id: "tenant".to_string(),
+            offset: 1,
+            collection_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let tracker = CompactionJobTracker::new(2);
+        let first = job(1);
+        let second = job(2);
+
+        tracker.enqueue(first.collection_id);
+        assert_eq!(tracker.jobs()[0].state, CompactionJobState::Queued);
+
+        let token = tracker.start(&first).unwrap();
+        assert!(tracker.start(&first).is_none());
+        tracker.start(&second).unwrap();
+        let jobs = tracker.jobs();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs
+            .iter()
+            .all(|record| record.state == CompactionJobState::Running));
+
+        assert!(tracker.cancel(first.collection_id));
+        assert!(token.is_cancelled());
+        tracker.finish(first.collection_id, CompactionJobOutcome::Cancelled);
+        tracker.finish(
+            second.collection_id,
+            CompactionJobOutcome::Failed {
+                error: "boom".to_string(),
+                operator: Some("FetchLogOperator".to_string()),
+            },
+        );
+        assert!(tracker.jobs().is_empty());
+
+        let failed = tracker.history(None, true, None);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].collection_id, second.collection_id);
+        assert_eq!(
+            failed[0].failed_operator.as_deref(),
+            Some("FetchLogOperator")
+        );
+
+        // The ring buffer only keeps the most recent jobs
+        tracker.start(&first).unwrap();
+        tracker.finish(first.collection_id, CompactionJobOutcome::Succeeded);
+        let history = tracker.history(None, false, None);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].state, CompactionJobState::Succeeded);
+        assert_eq!(history[1].state, CompactionJobState::Failed);
+        assert_eq!(
+            tracker
+                .history(Some(first.collection_id), false, Some(1))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_cancel_queued_and_pause() {
+        let tracker = CompactionJobTracker::new(10);
+        let first = job(1);
+
+        tracker.enqueue(first.collection_id);
+        assert!(tracker.cancel(first.collection_id));
+        assert!(!tracker.cancel(first.collection_id));
+        assert!(tracker.start(&first).is_none());
+        // Only the cancelled run is skipped
+        assert!(tracker.start(&first).is_some());
+        tracker.finish(first.collection_id, CompactionJobOutcome::Succeeded);
+
+        // A cancelled collection that is not scheduled in the same cycle is not skipped later
+        let second = job(2);
+        tracker.enqueue(second.collection_id);
+        assert!(tracker.cancel(second.collection_id));
+        assert_eq!(tracker.take_cancelled(), vec![second.collection_id]);
+        assert!(tracker.take_cancelled().is_empty());
+        assert!(tracker.start(&second).is_some());
+
+        tracker.pause(first.collection_id);
+        assert_eq!(tracker.paused(), vec![first.collection_id]);
+        assert!(tracker.start(&first).is_none());
+        tracker.resume(first.collection_id);
+        assert!(tracker.start(&first).is_some());
+    }
+}
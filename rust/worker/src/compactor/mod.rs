@@ -1,10 +1,12 @@
 mod compaction_manager;
 pub(crate) mod config;
+mod job_tracker;
 mod scheduler;
 mod scheduler_policy;
 mod types;
 
 pub(crate) use compaction_manager::*;
+pub(crate) use job_tracker::*;
 pub(crate) use types::*;
 
 pub mod compaction_client;
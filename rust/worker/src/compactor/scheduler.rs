@@ -64,6 +64,12 @@ impl Scheduler {
         self.oneoff_collections.extend(ids);
     }
 
+    pub(crate) fn remove_oneoff_collections(&mut self, ids: Vec<CollectionUuid>) {
+        for id in ids {
+            self.oneoff_collections.remove(&id);
+        }
+    }
+
     pub(crate) fn get_oneoff_collections(&self) -> Vec<CollectionUuid> {
         self.oneoff_collections.iter().cloned().collect()
     }
@@ -154,6 +160,7 @@ impl Scheduler {
                         last_compaction_time,
                         first_record_time: collection_info.first_log_ts,
                         offset,
+                        last_log_offset: collection_info.last_log_offset,
                         collection_version: collection[0].version,
                     });
                 }
@@ -218,10 +225,17 @@ impl Scheduler {
         }
 
         let filtered_collections = self.filter_collections(scheduled_collections);
-        self.job_queue.extend(
-            self.policy
-                .determine(filtered_collections, self.max_concurrent_jobs as i32),
+        let number_candidates = filtered_collections.len();
+        let jobs = self
+            .policy
+            .determine(filtered_collections, self.max_concurrent_jobs as i32);
+        tracing::info!(
+            policy = %self.policy.name(),
+            candidates = number_candidates,
+            scheduled = jobs.len(),
+            "Scheduled compaction jobs"
         );
+        self.job_queue.extend(jobs);
         self.job_queue.truncate(self.max_concurrent_jobs);
     }
 
@@ -1,10 +1,26 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chroma_config::registry::Registry;
+use chroma_config::Configurable;
+use chroma_error::{ChromaError, ErrorCodes};
 use chroma_log::CollectionRecord;
+use chroma_types::CollectionUuid;
+use thiserror::Error;
 
+use crate::compactor::config::{SchedulerPolicyConfig, SchedulerRanking, TenantFairShareConfig};
 use crate::compactor::types::CompactionJob;
 
+/// A scheduler policy picks which of the collections with new data get compacted, and in
+/// which order. Policies trace every collection they pick along with the reason, so that
+/// scheduling decisions can be followed in the logs.
 pub(crate) trait SchedulerPolicy: Send + Sync + SchedulerPolicyClone {
     fn determine(&self, collections: Vec<CollectionRecord>, number_jobs: i32)
         -> Vec<CompactionJob>;
+
+    /// A description of the policy, e.g. `boosted(tenant_fair_share(uncompacted_size))`
+    fn name(&self) -> String;
 }
 
 pub(crate) trait SchedulerPolicyClone {
@@ -26,6 +42,19 @@ impl Clone for Box<dyn SchedulerPolicy> {
     }
 }
 
+fn job_for(collection: &CollectionRecord) -> CompactionJob {
+    CompactionJob {
+        collection_id: collection.collection_id,
+        tenant_id: collection.tenant_id.clone(),
+        offset: collection.offset,
+        collection_version: collection.collection_version,
+    }
+}
+
+fn number_tasks(collections: &[CollectionRecord], number_jobs: i32) -> usize {
+    collections.len().min(number_jobs.max(0) as usize)
+}
+
 #[derive(Clone)]
 pub(crate) struct LasCompactionTimeSchedulerPolicy {}
 
@@ -37,22 +66,284 @@ impl SchedulerPolicy for LasCompactionTimeSchedulerPolicy {
     ) -> Vec<CompactionJob> {
         let mut collections = collections;
         collections.sort_by(|a, b| a.last_compaction_time.cmp(&b.last_compaction_time));
-        let number_tasks = if number_jobs > collections.len() as i32 {
-            collections.len() as i32
-        } else {
-            number_jobs
-        };
+        let number_tasks = number_tasks(&collections, number_jobs);
+        let mut tasks = Vec::new();
+        for collection in &collections[0..number_tasks] {
+            tracing::debug!(
+                policy = "last_compaction_time",
+                collection_id = %collection.collection_id,
+                tenant_id = %collection.tenant_id,
+                last_compaction_time = collection.last_compaction_time,
+                "Picked collection for compaction"
+            );
+            tasks.push(job_for(collection));
+        }
+        tasks
+    }
+
+    fn name(&self) -> String {
+        "last_compaction_time".to_string()
+    }
+}
+
+/// Compacts the collections with the most uncompacted log records first.
+#[derive(Clone)]
+pub(crate) struct UncompactedSizeSchedulerPolicy {}
+
+impl UncompactedSizeSchedulerPolicy {
+    fn uncompacted_records(collection: &CollectionRecord) -> i64 {
+        // The offset is the first offset that has not been compacted yet.
+        (collection.last_log_offset - collection.offset + 1).max(0)
+    }
+}
+
+impl SchedulerPolicy for UncompactedSizeSchedulerPolicy {
+    fn determine(
+        &self,
+        collections: Vec<CollectionRecord>,
+        number_jobs: i32,
+    ) -> Vec<CompactionJob> {
+        let mut collections = collections;
+        collections.sort_by(|a, b| {
+            Self::uncompacted_records(b)
+                .cmp(&Self::uncompacted_records(a))
+                .then(a.last_compaction_time.cmp(&b.last_compaction_time))
+        });
+        let number_tasks = number_tasks(&collections, number_jobs);
+        let mut tasks = Vec::new();
+        for collection in &collections[0..number_tasks] {
+            tracing::debug!(
+                policy = "uncompacted_size",
+                collection_id = %collection.collection_id,
+                tenant_id = %collection.tenant_id,
+                uncompacted_records = Self::uncompacted_records(collection),
+                "Picked collection for compaction"
+            );
+            tasks.push(job_for(collection));
+        }
+        tasks
+    }
+
+    fn name(&self) -> String {
+        "uncompacted_size".to_string()
+    }
+}
+
+/// Compacts the collections whose oldest uncompacted log record is the oldest first.
+#[derive(Clone)]
+pub(crate) struct UncompactedAgeSchedulerPolicy {}
+
+impl SchedulerPolicy for UncompactedAgeSchedulerPolicy {
+    fn determine(
+        &self,
+        collections: Vec<CollectionRecord>,
+        number_jobs: i32,
+    ) -> Vec<CompactionJob> {
+        let mut collections = collections;
+        collections.sort_by(|a, b| {
+            a.first_record_time
+                .cmp(&b.first_record_time)
+                .then(a.last_compaction_time.cmp(&b.last_compaction_time))
+        });
+        let number_tasks = number_tasks(&collections, number_jobs);
         let mut tasks = Vec::new();
-        for collection in &collections[0..number_tasks as usize] {
-            tasks.push(CompactionJob {
-                collection_id: collection.collection_id,
-                tenant_id: collection.tenant_id.clone(),
-                offset: collection.offset,
-                collection_version: collection.collection_version,
-            });
+        for collection in &collections[0..number_tasks] {
+            tracing::debug!(
+                policy = "uncompacted_age",
+                collection_id = %collection.collection_id,
+                tenant_id = %collection.tenant_id,
+                first_record_time = collection.first_record_time,
+                "Picked collection for compaction"
+            );
+            tasks.push(job_for(collection));
         }
         tasks
     }
+
+    fn name(&self) -> String {
+        "uncompacted_age".to_string()
+    }
+}
+
+/// Shares the jobs out across tenants with a weighted round robin, so that a tenant with
+/// many collections cannot starve the others. Within a tenant, the collections keep the
+/// order of the inner policy.
+#[derive(Clone)]
+pub(crate) struct TenantFairShareSchedulerPolicy {
+    inner: Box<dyn SchedulerPolicy>,
+    default_weight: u32,
+    tenant_weights: HashMap<String, u32>,
+}
+
+impl TenantFairShareSchedulerPolicy {
+    pub(crate) fn new(inner: Box<dyn SchedulerPolicy>, config: &TenantFairShareConfig) -> Self {
+        TenantFairShareSchedulerPolicy {
+            inner,
+            default_weight: config.default_weight,
+            tenant_weights: config.tenant_weights.clone(),
+        }
+    }
+
+    fn weight(&self, tenant_id: &str) -> usize {
+        // Every tenant gets at least one job per round, otherwise its collections would
+        // never be compacted.
+        self.tenant_weights
+            .get(tenant_id)
+            .copied()
+            .unwrap_or(self.default_weight)
+            .max(1) as usize
+    }
+}
+
+impl SchedulerPolicy for TenantFairShareSchedulerPolicy {
+    fn determine(
+        &self,
+        collections: Vec<CollectionRecord>,
+        number_jobs: i32,
+    ) -> Vec<CompactionJob> {
+        let number_jobs = number_jobs.max(0) as usize;
+        let number_collections = collections.len() as i32;
+        let ranked = self.inner.determine(collections, number_collections);
+
+        // Tenants take turns in the order of their highest ranked collection.
+        let mut tenant_index = HashMap::new();
+        let mut tenants: Vec<(String, VecDeque<CompactionJob>)> = Vec::new();
+        for job in ranked {
+            let index = *tenant_index
+                .entry(job.tenant_id.clone())
+                .or_insert_with(|| {
+                    tenants.push((job.tenant_id.clone(), VecDeque::new()));
+                    tenants.len() - 1
+                });
+            tenants[index].1.push_back(job);
+        }
+
+        let mut jobs = Vec::new();
+        let mut round = 0;
+        while jobs.len() < number_jobs && tenants.iter().any(|(_, queue)| !queue.is_empty()) {
+            round += 1;
+            for (tenant_id, queue) in tenants.iter_mut() {
+                let weight = self.weight(tenant_id);
+                for _ in 0..weight {
+                    if jobs.len() >= number_jobs {
+                        break;
+                    }
+                    let Some(job) = queue.pop_front() else {
+                        break;
+                    };
+                    tracing::debug!(
+                        policy = "tenant_fair_share",
+                        collection_id = %job.collection_id,
+                        tenant_id = %tenant_id,
+                        weight,
+                        round,
+                        "Picked collection for compaction"
+                    );
+                    jobs.push(job);
+                }
+            }
+        }
+        jobs
+    }
+
+    fn name(&self) -> String {
+        format!("tenant_fair_share({})", self.inner.name())
+    }
+}
+
+/// Schedules the boosted collections (e.g. those with heavy query traffic) before any
+/// other collection. Both groups are ordered by the inner policy.
+#[derive(Clone)]
+pub(crate) struct BoostedSchedulerPolicy {
+    inner: Box<dyn SchedulerPolicy>,
+    boosted_collections: HashSet<CollectionUuid>,
+}
+
+impl BoostedSchedulerPolicy {
+    pub(crate) fn new(
+        inner: Box<dyn SchedulerPolicy>,
+        boosted_collections: HashSet<CollectionUuid>,
+    ) -> Self {
+        BoostedSchedulerPolicy {
+            inner,
+            boosted_collections,
+        }
+    }
+}
+
+impl SchedulerPolicy for BoostedSchedulerPolicy {
+    fn determine(
+        &self,
+        collections: Vec<CollectionRecord>,
+        number_jobs: i32,
+    ) -> Vec<CompactionJob> {
+        let (boosted, others): (Vec<_>, Vec<_>) = collections
+            .into_iter()
+            .partition(|collection| self.boosted_collections.contains(&collection.collection_id));
+
+        let mut jobs = self.inner.determine(boosted, number_jobs);
+        for job in &jobs {
+            tracing::debug!(
+                policy = "boosted",
+                collection_id = %job.collection_id,
+                tenant_id = %job.tenant_id,
+                "Boosted collection for compaction"
+            );
+        }
+        let remaining = number_jobs - jobs.len() as i32;
+        if remaining > 0 {
+            jobs.extend(self.inner.determine(others, remaining));
+        }
+        jobs
+    }
+
+    fn name(&self) -> String {
+        format!("boosted({})", self.inner.name())
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum SchedulerPolicyConfigError {
+    #[error("Invalid boosted collection id: {0}")]
+    InvalidCollectionId(String),
+}
+
+impl ChromaError for SchedulerPolicyConfigError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            SchedulerPolicyConfigError::InvalidCollectionId(_) => ErrorCodes::InvalidArgument,
+        }
+    }
+}
+
+#[async_trait]
+impl Configurable<SchedulerPolicyConfig> for Box<dyn SchedulerPolicy> {
+    async fn try_from_config(
+        config: &SchedulerPolicyConfig,
+        _registry: &Registry,
+    ) -> Result<Self, Box<dyn ChromaError>> {
+        let mut policy: Box<dyn SchedulerPolicy> = match config.ranking {
+            SchedulerRanking::LastCompactionTime => Box::new(LasCompactionTimeSchedulerPolicy {}),
+            SchedulerRanking::UncompactedSize => Box::new(UncompactedSizeSchedulerPolicy {}),
+            SchedulerRanking::UncompactedAge => Box::new(UncompactedAgeSchedulerPolicy {}),
+        };
+        if let Some(fair_share) = &config.tenant_fair_share {
+            policy = Box::new(TenantFairShareSchedulerPolicy::new(policy, fair_share));
+        }
+        if !config.boosted_collections.is_empty() {
+            let mut boosted_collections = HashSet::new();
+            for collection_id in &config.boosted_collections {
+                let collection_id = CollectionUuid::from_str(collection_id).map_err(|_| {
+                    Box::new(SchedulerPolicyConfigError::InvalidCollectionId(
+                        collection_id.clone(),
+                    )) as Box<dyn ChromaError>
+                })?;
+                boosted_collections.insert(collection_id);
+            }
+            policy = Box::new(BoostedSchedulerPolicy::new(policy, boosted_collections));
+        }
+        Ok(policy)
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +366,7 @@ mod tests {
                 last_compaction_time: 1,
                 first_record_time: 1,
                 offset: 0,
+                last_log_offset: 0,
                 collection_version: 0,
             },
             CollectionRecord {
@@ -83,6 +375,7 @@ mod tests {
                 last_compaction_time: 0,
                 first_record_time: 0,
                 offset: 0,
+                last_log_offset: 0,
                 collection_version: 0,
             },
         ];
@@ -95,4 +388,121 @@ mod tests {
         assert_eq!(jobs[0].collection_id, collection_uuid_2);
         assert_eq!(jobs[1].collection_id, collection_uuid_1);
     }
+
+    fn record(
+        id: u128,
+        tenant_id: &str,
+        last_compaction_time: i64,
+        first_record_time: i64,
+        offset: i64,
+        last_log_offset: i64,
+    ) -> CollectionRecord {
+        CollectionRecord {
+            collection_id: CollectionUuid(uuid::Uuid::from_u128(id)),
+            tenant_id: tenant_id.to_string(),
+            last_compaction_time,
+            first_record_time,
+            offset,
+            last_log_offset,
+            collection_version: 0,
+        }
+    }
+
+    fn ids(jobs: &[CompactionJob]) -> Vec<u128> {
+        jobs.iter()
+            .map(|job| job.collection_id.0.as_u128())
+            .collect()
+    }
+
+    #[test]
+    fn test_uncompacted_size_and_age_policies() {
+        let collections = vec![
+            record(1, "test", 0, 30, 1, 10),
+            record(2, "test", 0, 10, 5, 100),
+            record(3, "test", 0, 20, 50, 60),
+        ];
+
+        let jobs = UncompactedSizeSchedulerPolicy {}.determine(collections.clone(), 2);
+        assert_eq!(ids(&jobs), vec![2, 3]);
+
+        let jobs = UncompactedAgeSchedulerPolicy {}.determine(collections.clone(), 3);
+        assert_eq!(ids(&jobs), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_tenant_fair_share_policy() {
+        // Tenant a has the largest backlogs, but must not take every job.
+        let collections = vec![
+            record(1, "a", 0, 0, 1, 100),
+            record(2, "a", 0, 0, 1, 90),
+            record(3, "a", 0, 0, 1, 80),
+            record(4, "a", 0, 0, 1, 70),
+            record(5, "b", 0, 0, 1, 20),
+            record(6, "c", 0, 0, 1, 10),
+            record(7, "c", 0, 0, 1, 5),
+        ];
+
+        let policy = TenantFairShareSchedulerPolicy::new(
+            Box::new(UncompactedSizeSchedulerPolicy {}),
+            &TenantFairShareConfig::default(),
+        );
+        assert_eq!(
+            ids(&policy.determine(collections.clone(), 3)),
+            vec![1, 5, 6]
+        );
+        assert_eq!(
+            ids(&policy.determine(collections.clone(), 7)),
+            vec![1, 5, 6, 2, 7, 3, 4]
+        );
+
+        let policy = TenantFairShareSchedulerPolicy::new(
+            Box::new(UncompactedSizeSchedulerPolicy {}),
+            &TenantFairShareConfig {
+                default_weight: 1,
+                tenant_weights: HashMap::from([("a".to_string(), 2), ("c".to_string(), 0)]),
+            },
+        );
+        assert_eq!(
+            ids(&policy.determine(collections.clone(), 5)),
+            vec![1, 2, 5, 6, 3]
+        );
+        assert_eq!(policy.name(), "tenant_fair_share(uncompacted_size)");
+    }
+
+    #[tokio::test]
+    async fn test_policy_from_config() {
+        let collections = vec![
+            record(1, "a", 0, 0, 1, 100),
+            record(2, "a", 0, 0, 1, 90),
+            record(3, "b", 0, 0, 1, 10),
+            record(4, "b", 0, 0, 1, 5),
+        ];
+        let config = SchedulerPolicyConfig {
+            ranking: SchedulerRanking::UncompactedSize,
+            tenant_fair_share: Some(TenantFairShareConfig::default()),
+            boosted_collections: vec![uuid::Uuid::from_u128(4).to_string()],
+        };
+        let policy = Box::<dyn SchedulerPolicy>::try_from_config(&config, &Registry::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            policy.name(),
+            "boosted(tenant_fair_share(uncompacted_size))"
+        );
+        assert_eq!(
+            ids(&policy.determine(collections.clone(), 3)),
+            vec![4, 1, 3]
+        );
+        assert_eq!(ids(&policy.determine(collections.clone(), 1)), vec![4]);
+
+        let config = SchedulerPolicyConfig {
+            boosted_collections: vec!["not-a-uuid".to_string()],
+            ..Default::default()
+        };
+        assert!(
+            Box::<dyn SchedulerPolicy>::try_from_config(&config, &Registry::new())
+                .await
+                .is_err()
+        );
+    }
 }
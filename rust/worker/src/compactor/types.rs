@@ -15,3 +15,14 @@ pub struct ScheduledCompactionMessage {}
 pub struct OneOffCompactionMessage {
     pub collection_ids: Vec<CollectionUuid>,
 }
+
+/// Requests that the vector segment of a collection is rebuilt from its record segment.
+/// Parameters that are not specified keep their current value.
+#[derive(Clone, Debug)]
+pub struct ReindexMessage {
+    pub collection_id: CollectionUuid,
+    pub space: Option<String>,
+    pub m: Option<usize>,
+    pub ef_construction: Option<usize>,
+    pub ef_search: Option<usize>,
+}
@@ -127,6 +127,8 @@ pub struct QueryServiceConfig {
     pub blockfile_provider: chroma_blockstore::config::BlockfileProviderConfig,
     #[serde(default)]
     pub hnsw_provider: chroma_index::config::HnswProviderConfig,
+    #[serde(default)]
+    pub knn: KnnQueryConfig,
 }
 
 impl QueryServiceConfig {
@@ -147,6 +149,38 @@ impl QueryServiceConfig {
     }
 }
 
+/// Controls how the compacted vector segment is searched under a filter
+/// - brute_force_threshold: The maximum number of candidate records for which the exact
+///   distances are computed from the record segment instead of traversing the HNSW graph.
+/// - max_ef_expansion: The maximum factor by which the HNSW search breadth is expanded
+///   under selective filters.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct KnnQueryConfig {
+    #[serde(default = "KnnQueryConfig::default_brute_force_threshold")]
+    pub brute_force_threshold: u64,
+    #[serde(default = "KnnQueryConfig::default_max_ef_expansion")]
+    pub max_ef_expansion: usize,
+}
+
+impl KnnQueryConfig {
+    fn default_brute_force_threshold() -> u64 {
+        1000
+    }
+
+    fn default_max_ef_expansion() -> usize {
+        32
+    }
+}
+
+impl Default for KnnQueryConfig {
+    fn default() -> Self {
+        KnnQueryConfig {
+            brute_force_threshold: KnnQueryConfig::default_brute_force_threshold(),
+            max_ef_expansion: KnnQueryConfig::default_max_ef_expansion(),
+        }
+    }
+}
+
 #[derive(Default, Deserialize, Serialize)]
 /// # Description
 /// The primary config for the compaction service.
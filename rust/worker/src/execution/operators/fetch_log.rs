@@ -15,6 +15,8 @@ use tracing::trace;
 /// - `batch_size`: The maximum number of logs to fetch by `log_client` at a time
 /// - `start_log_offset_id`: The offset id of the first log to read
 /// - `maximum_fetch_count`: The maximum number of logs to fetch in total
+/// - `end_log_offset_id`: The offset id of the last log to read, if any
+/// - `end_timestamp`: The time of the last log to read in nanoseconds, or the current time if absent
 /// - `collection_uuid`: The uuid of the collection where the fetched logs should belong
 ///
 /// # Inputs
@@ -23,7 +25,7 @@ use tracing::trace;
 /// # Outputs
 /// - The contiguous chunk of logs belong to the collection with `collection_uuid`
 ///   starting from `start_log_offset_id`. At most `maximum_fetch_count` number of logs
-///   will be fetched
+///   will be fetched, and none after `end_log_offset_id` or `end_timestamp`
 ///
 /// # Usage
 /// It should be run at the start of an orchestrator to get the latest data of a collection
@@ -33,6 +35,8 @@ pub struct FetchLogOperator {
     pub batch_size: u32,
     pub start_log_offset_id: u32,
     pub maximum_fetch_count: Option<u32>,
+    pub end_log_offset_id: Option<u32>,
+    pub end_timestamp: Option<i64>,
     pub collection_uuid: CollectionUuid,
 }
 
@@ -71,7 +75,10 @@ impl Operator<FetchLogInput, FetchLogOutput> for FetchLogOperator {
         let mut fetched = Vec::new();
         let mut log_client = self.log_client.clone();
         let mut offset = self.start_log_offset_id as i64;
-        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as i64;
+        let timestamp = match self.end_timestamp {
+            Some(timestamp) => timestamp,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as i64,
+        };
         loop {
             let mut log_batch = log_client
                 .read(
@@ -83,6 +90,11 @@ impl Operator<FetchLogInput, FetchLogOutput> for FetchLogOperator {
                 .await?;
 
             let retrieve_count = log_batch.len();
+            let mut reached_end = false;
+            if let Some(end_log_offset_id) = self.end_log_offset_id {
+                log_batch.retain(|log| log.log_offset <= end_log_offset_id as i64);
+                reached_end = log_batch.len() < retrieve_count;
+            }
 
             if let Some(last_log) = log_batch.last() {
                 offset = last_log.log_offset + 1;
@@ -96,7 +108,7 @@ impl Operator<FetchLogInput, FetchLogOutput> for FetchLogOperator {
                 }
             }
 
-            if retrieve_count < self.batch_size as usize {
+            if reached_end || retrieve_count < self.batch_size as usize {
                 // No more logs to fetch
                 break;
             }
@@ -148,6 +160,8 @@ mod tests {
             batch_size: 2,
             start_log_offset_id: 0,
             maximum_fetch_count: None,
+            end_log_offset_id: None,
+            end_timestamp: None,
             collection_uuid,
         };
 
@@ -172,6 +186,8 @@ mod tests {
             batch_size: 2,
             start_log_offset_id: 3,
             maximum_fetch_count: Some(3),
+            end_log_offset_id: None,
+            end_timestamp: None,
             collection_uuid,
         };
 
@@ -186,4 +202,52 @@ mod tests {
             .zip(3..6)
             .for_each(|(log, offset)| assert_eq!(log.log_offset, offset));
     }
+
+    #[tokio::test]
+    async fn test_pull_as_of() {
+        let (collection_uuid, log_client) = setup_in_memory_log();
+
+        let fetch_log_operator = FetchLogOperator {
+            log_client: log_client.clone(),
+            batch_size: 2,
+            start_log_offset_id: 2,
+            maximum_fetch_count: None,
+            end_log_offset_id: Some(6),
+            end_timestamp: None,
+            collection_uuid,
+        };
+
+        let logs = fetch_log_operator
+            .run(&())
+            .await
+            .expect("FetchLogOperator should not fail");
+
+        assert_eq!(logs.len(), 5);
+        logs.iter()
+            .map(|(log, _)| log)
+            .zip(2..7)
+            .for_each(|(log, offset)| assert_eq!(log.log_offset, offset));
+
+        // The in memory log uses the log offsets as timestamps
+        let fetch_log_operator = FetchLogOperator {
+            log_client,
+            batch_size: 2,
+            start_log_offset_id: 0,
+            maximum_fetch_count: None,
+            end_log_offset_id: None,
+            end_timestamp: Some(4),
+            collection_uuid,
+        };
+
+        let logs = fetch_log_operator
+            .run(&())
+            .await
+            .expect("FetchLogOperator should not fail");
+
+        assert_eq!(logs.len(), 5);
+        logs.iter()
+            .map(|(log, _)| log)
+            .zip(0..5)
+            .for_each(|(log, offset)| assert_eq!(log.log_offset, offset));
+    }
 }
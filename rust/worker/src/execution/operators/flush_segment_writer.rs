@@ -91,6 +91,7 @@ impl Operator<FlushSegmentWriterInput, FlushSegmentWriterOutput> for FlushSegmen
             flush_info: SegmentFlushInfo {
                 file_paths,
                 segment_id: id,
+                metadata: None,
             },
         })
     }
@@ -36,6 +36,7 @@ impl PartialOrd for RecordDistance {
 /// `KnnOperator` has multiple implementations for the `Operator<I, O>` trait:
 /// - `Operator<KnnLogInput, KnnLogOutput>`: Searches the nearest embeddings in the materialized log
 /// - `Operator<KnnHnswInput, KnnHnswOutput>`: Searches the nearest embeddings in the HNSW index
+/// - `Operator<KnnBruteForceInput, KnnBruteForceOutput>`: Computes the exact nearest embeddings in the record segment
 ///
 /// # Usage
 /// It can be used to derive the range of offset ids that should be used by the next operator
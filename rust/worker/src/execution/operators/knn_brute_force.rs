@@ -0,0 +1,261 @@
+use std::collections::BinaryHeap;
+
+use async_trait::async_trait;
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_distance::{normalize, DistanceFunction};
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_segment::blockfile_record::{RecordSegmentReader, RecordSegmentReaderCreationError};
+use chroma_system::Operator;
+use chroma_types::{Segment, SignedRoaringBitmap};
+use futures::StreamExt;
+use thiserror::Error;
+use tracing::trace;
+
+use super::knn::{KnnOperator, RecordDistance};
+
+#[derive(Clone, Debug)]
+pub struct KnnBruteForceInput {
+    pub blockfile_provider: BlockfileProvider,
+    pub record_segment: Segment,
+    pub compact_offset_ids: SignedRoaringBitmap,
+    pub distance_function: DistanceFunction,
+}
+
+#[derive(Debug)]
+pub struct KnnBruteForceOutput {
+    pub record_distances: Vec<RecordDistance>,
+}
+
+#[derive(Error, Debug)]
+pub enum KnnBruteForceError {
+    #[error("Error reading record segment: {0}")]
+    RecordSegment(#[from] Box<dyn ChromaError>),
+    #[error("Error creating record segment reader: {0}")]
+    RecordReader(#[from] RecordSegmentReaderCreationError),
+}
+
+impl ChromaError for KnnBruteForceError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            KnnBruteForceError::RecordSegment(e) => e.code(),
+            KnnBruteForceError::RecordReader(e) => e.code(),
+        }
+    }
+}
+
+/// Computes the exact distances between the target embedding and the embeddings
+/// of the compacted records in the record segment, bypassing the vector index.
+/// This is used when the filter leaves too few candidates for graph traversal to be effective.
+#[async_trait]
+impl Operator<KnnBruteForceInput, KnnBruteForceOutput> for KnnOperator {
+    type Error = KnnBruteForceError;
+
+    async fn run(
+        &self,
+        input: &KnnBruteForceInput,
+    ) -> Result<KnnBruteForceOutput, KnnBruteForceError> {
+        trace!(
+            "[{}]: {:?}",
+            <Self as Operator<KnnBruteForceInput, KnnBruteForceOutput>>::get_name(self),
+            input
+        );
+
+        let record_segment_reader = match RecordSegmentReader::from_segment(
+            &input.record_segment,
+            &input.blockfile_provider,
+        )
+        .await
+        {
+            Ok(reader) => reader,
+            Err(e) if matches!(*e, RecordSegmentReaderCreationError::UninitializedSegment) => {
+                return Ok(KnnBruteForceOutput {
+                    record_distances: Vec::new(),
+                })
+            }
+            Err(e) => return Err((*e).into()),
+        };
+
+        let offset_ids = match &input.compact_offset_ids {
+            SignedRoaringBitmap::Include(rbm) => rbm.iter().collect::<Vec<_>>(),
+            SignedRoaringBitmap::Exclude(rbm) => {
+                let mut offset_ids = Vec::new();
+                let mut offset_stream = record_segment_reader.get_offset_stream(..);
+                while let Some(offset_id) = offset_stream.next().await.transpose()? {
+                    if !rbm.contains(offset_id) {
+                        offset_ids.push(offset_id);
+                    }
+                }
+                offset_ids
+            }
+        };
+
+        if offset_ids.is_empty() {
+            return Ok(KnnBruteForceOutput {
+                record_distances: Vec::new(),
+            });
+        }
+
+        record_segment_reader.prefetch_id_to_data(&offset_ids).await;
+
+        let target_vector;
+        let target_embedding = if let DistanceFunction::Cosine = input.distance_function {
+            target_vector = normalize(&self.embedding);
+            &target_vector
+        } else {
+            &self.embedding
+        };
+
+        let mut max_heap = BinaryHeap::with_capacity(self.fetch as usize);
+        for offset_id in offset_ids {
+            let record = match record_segment_reader
+                .get_data_for_offset_id(offset_id)
+                .await?
+            {
+                Some(record) => record,
+                None => continue,
+            };
+
+            let record_vector;
+            let record_embedding = if let DistanceFunction::Cosine = input.distance_function {
+                record_vector = normalize(record.embedding);
+                &record_vector
+            } else {
+                record.embedding
+            };
+
+            let distance = RecordDistance {
+                offset_id,
+                measure: input
+                    .distance_function
+                    .distance(target_embedding, record_embedding),
+            };
+            if max_heap.len() < self.fetch as usize {
+                max_heap.push(distance);
+            } else if let Some(furthest_distance) = max_heap.peek() {
+                if &distance < furthest_distance {
+                    max_heap.pop();
+                    max_heap.push(distance);
+                }
+            }
+        }
+
+        Ok(KnnBruteForceOutput {
+            record_distances: max_heap.into_sorted_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chroma_distance::DistanceFunction;
+    use chroma_log::test::{
+        random_embedding, upsert_generator, LogGenerator, TEST_EMBEDDING_DIMENSION,
+    };
+    use chroma_segment::test::TestDistributedSegment;
+    use chroma_system::Operator;
+    use chroma_types::SignedRoaringBitmap;
+
+    use crate::execution::operators::knn::KnnOperator;
+
+    use super::KnnBruteForceInput;
+
+    /// The unit tests for `KnnBruteForceOperator` compact 100 log records
+    /// with random embeddings into the record segment
+    async fn setup_knn_brute_force_input(
+        compact_offset_ids: SignedRoaringBitmap,
+    ) -> (KnnBruteForceInput, Vec<(u32, Vec<f32>)>) {
+        let mut test_segment = TestDistributedSegment::new_with_dimension(TEST_EMBEDDING_DIMENSION);
+        let logs = upsert_generator.generate_chunk(1..=100);
+        let embeddings = logs
+            .iter()
+            .map(|(log, _)| {
+                (
+                    log.log_offset as u32,
+                    log.record
+                        .embedding
+                        .clone()
+                        .expect("Embedding should be present in generated logs"),
+                )
+            })
+            .collect();
+        test_segment.compact_log(logs, 1).await;
+        (
+            KnnBruteForceInput {
+                blockfile_provider: test_segment.blockfile_provider,
+                record_segment: test_segment.record_segment,
+                compact_offset_ids,
+                distance_function: DistanceFunction::Euclidean,
+            },
+            embeddings,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_include() {
+        let (knn_brute_force_input, embeddings) =
+            setup_knn_brute_force_input(SignedRoaringBitmap::Include(
+                (1..=100).filter(|offset_id| offset_id % 7 == 0).collect(),
+            ))
+            .await;
+
+        let knn_operator = KnnOperator {
+            embedding: random_embedding(TEST_EMBEDDING_DIMENSION),
+            fetch: 5,
+        };
+
+        let mut brute_force_distances: Vec<_> = embeddings
+            .iter()
+            .filter(|(offset_id, _)| offset_id % 7 == 0)
+            .map(|(offset_id, embedding)| {
+                (
+                    *offset_id,
+                    DistanceFunction::Euclidean.distance(embedding, &knn_operator.embedding),
+                )
+            })
+            .collect();
+        brute_force_distances.sort_by(|x, y| x.1.total_cmp(&y.1));
+
+        let knn_brute_force_output = knn_operator
+            .run(&knn_brute_force_input)
+            .await
+            .expect("KnnBruteForceOperator should not fail");
+
+        assert_eq!(knn_brute_force_output.record_distances.len(), 5);
+        assert!(knn_brute_force_output
+            .record_distances
+            .iter()
+            .zip(brute_force_distances)
+            .all(
+                |(record, (offset_id, distance))| record.offset_id == offset_id
+                    && record.measure == distance
+            ));
+    }
+
+    #[tokio::test]
+    async fn test_exclude() {
+        let (knn_brute_force_input, embeddings) =
+            setup_knn_brute_force_input(SignedRoaringBitmap::Exclude(
+                (1..=100).filter(|offset_id| offset_id % 2 == 0).collect(),
+            ))
+            .await;
+
+        let knn_operator = KnnOperator {
+            embedding: random_embedding(TEST_EMBEDDING_DIMENSION),
+            fetch: 100,
+        };
+
+        let knn_brute_force_output = knn_operator
+            .run(&knn_brute_force_input)
+            .await
+            .expect("KnnBruteForceOperator should not fail");
+
+        assert_eq!(
+            knn_brute_force_output.record_distances.len(),
+            embeddings.len() / 2
+        );
+        assert!(knn_brute_force_output
+            .record_distances
+            .iter()
+            .all(|record| record.offset_id % 2 != 0));
+    }
+}
@@ -14,6 +14,10 @@ pub struct KnnHnswInput {
     pub(crate) hnsw_reader: Box<DistributedHNSWSegmentReader>,
     pub compact_offset_ids: SignedRoaringBitmap,
     pub distance_function: DistanceFunction,
+    // The estimated fraction of the index allowed by the filter
+    pub selectivity: f64,
+    // The maximum factor by which the search breadth is expanded under selective filters
+    pub max_ef_expansion: usize,
 }
 
 #[derive(Debug)]
@@ -64,17 +68,30 @@ impl Operator<KnnHnswInput, KnnHnswOutput> for KnnOperator {
             &self.embedding
         };
 
+        // hnswlib searches with a beam of max(ef_search, k), so requesting more neighbours
+        // widens the traversal when the filter rejects most of the visited nodes
+        let fetch = self.fetch as usize;
+        let expanded_fetch = ((fetch as f64 / input.selectivity.max(f64::EPSILON)).ceil() as usize)
+            .clamp(fetch, fetch.saturating_mul(input.max_ef_expansion.max(1)));
+        tracing::debug!(
+            fetch,
+            expanded_fetch,
+            selectivity = input.selectivity,
+            "Querying hnsw index with expanded search breadth"
+        );
+
         let (offset_ids, distances) =
             input
                 .hnsw_reader
-                .query(embedding, self.fetch as usize, &allowed, &disallowed)?;
-        Ok(KnnHnswOutput {
-            record_distances: offset_ids
-                .into_iter()
-                .map(|offset_id| offset_id as u32)
-                .zip(distances)
-                .map(|(offset_id, measure)| RecordDistance { offset_id, measure })
-                .collect(),
-        })
+                .query(embedding, expanded_fetch, &allowed, &disallowed)?;
+        let mut record_distances: Vec<_> = offset_ids
+            .into_iter()
+            .map(|offset_id| offset_id as u32)
+            .zip(distances)
+            .map(|(offset_id, measure)| RecordDistance { offset_id, measure })
+            .collect();
+        record_distances.sort();
+        record_distances.truncate(fetch);
+        Ok(KnnHnswOutput { record_distances })
     }
 }
@@ -5,7 +5,9 @@ pub mod flush_segment_writer;
 pub mod materialize_logs;
 pub(super) mod partition;
 pub mod prefetch_segment;
+pub mod rebuild_vector_segment;
 pub(super) mod register;
+pub mod segment_stats;
 pub mod spann_bf_pl;
 pub(super) mod spann_centers_search;
 pub(super) mod spann_fetch_pl;
@@ -15,6 +17,7 @@ pub mod spann_knn_merge;
 pub mod fetch_log;
 pub mod filter;
 pub mod knn;
+pub mod knn_brute_force;
 pub mod knn_hnsw;
 pub mod knn_log;
 pub mod knn_merge;
@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_index::hnsw_provider::HnswIndexProvider;
+use chroma_segment::{
+    blockfile_record::{
+        ApplyMaterializedLogError, RecordSegmentReader, RecordSegmentReaderCreationError,
+    },
+    distributed_hnsw::{DistributedHNSWSegmentFromSegmentError, DistributedHNSWSegmentWriter},
+};
+use chroma_system::{Operator, OperatorType};
+use chroma_types::{Segment, SegmentFlushInfo};
+use futures::StreamExt;
+use thiserror::Error;
+use tracing::trace;
+
+/// The number of records loaded from the record segment at a time
+const REBUILD_BATCH_SIZE: usize = 1000;
+
+/// The `RebuildVectorSegmentOperator` builds a brand new vector index from the embeddings
+/// stored in the record segment, using the parameters in the metadata of the target segment.
+///
+/// # Parameters
+/// - `blockfile_provider`: The provider for the record segment blockfiles
+/// - `hnsw_provider`: The provider for the new vector index
+/// - `record_segment`: The record segment to read the embeddings from
+/// - `vector_segment`: The vector segment to rebuild. Its file paths are expected to be empty
+///   so that a fresh index is created, and its metadata carries the new index parameters
+/// - `dimension`: The dimension of the embeddings in the collection
+///
+/// # Outputs
+/// - `flush_info`: The file paths of the flushed index and the metadata of the target segment,
+///   ready to be registered in the sysdb in a single flush
+/// - `total_records`: The number of records in the rebuilt index
+#[derive(Debug)]
+pub struct RebuildVectorSegmentOperator {}
+
+impl RebuildVectorSegmentOperator {
+    pub fn new() -> Box<Self> {
+        Box::new(RebuildVectorSegmentOperator {})
+    }
+}
+
+#[derive(Debug)]
+pub struct RebuildVectorSegmentInput {
+    pub blockfile_provider: BlockfileProvider,
+    pub hnsw_provider: HnswIndexProvider,
+    pub record_segment: Segment,
+    pub vector_segment: Segment,
+    pub dimension: usize,
+}
+
+#[derive(Debug)]
+pub struct RebuildVectorSegmentOutput {
+    pub flush_info: SegmentFlushInfo,
+    pub total_records: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum RebuildVectorSegmentError {
+    #[error("Error adding embedding to vector index: {0}")]
+    AddEmbedding(#[from] ApplyMaterializedLogError),
+    #[error("Error flushing vector index: {0}")]
+    HnswFlush(Box<dyn ChromaError>),
+    #[error("Error creating vector segment writer: {0}")]
+    HnswWriter(#[from] DistributedHNSWSegmentFromSegmentError),
+    #[error("Error reading record segment: {0}")]
+    RecordSegment(#[from] Box<dyn ChromaError>),
+    #[error("Error creating record segment reader: {0}")]
+    RecordReader(#[from] RecordSegmentReaderCreationError),
+}
+
+impl ChromaError for RebuildVectorSegmentError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            RebuildVectorSegmentError::AddEmbedding(e) => e.code(),
+            RebuildVectorSegmentError::HnswFlush(e) => e.code(),
+            RebuildVectorSegmentError::HnswWriter(e) => e.code(),
+            RebuildVectorSegmentError::RecordSegment(e) => e.code(),
+            RebuildVectorSegmentError::RecordReader(e) => e.code(),
+        }
+    }
+}
+
+#[async_trait]
+impl Operator<RebuildVectorSegmentInput, RebuildVectorSegmentOutput>
+    for RebuildVectorSegmentOperator
+{
+    type Error = RebuildVectorSegmentError;
+
+    fn get_name(&self) -> &'static str {
+        "RebuildVectorSegmentOperator"
+    }
+
+    fn get_type(&self) -> OperatorType {
+        OperatorType::IO
+    }
+
+    async fn run(
+        &self,
+        input: &RebuildVectorSegmentInput,
+    ) -> Result<RebuildVectorSegmentOutput, RebuildVectorSegmentError> {
+        trace!("[{}]: {:?}", self.get_name(), input);
+
+        let vector_segment_writer = DistributedHNSWSegmentWriter::from_segment(
+            &input.vector_segment,
+            input.dimension,
+            input.hnsw_provider.clone(),
+        )
+        .await
+        .map_err(|e| *e)?;
+
+        let mut total_records = 0;
+        match RecordSegmentReader::from_segment(&input.record_segment, &input.blockfile_provider)
+            .await
+        {
+            Ok(reader) => {
+                let mut offset_stream = reader.get_offset_stream(..).chunks(REBUILD_BATCH_SIZE);
+                while let Some(offset_ids) = offset_stream.next().await {
+                    let offset_ids = offset_ids.into_iter().collect::<Result<Vec<_>, _>>()?;
+                    reader.prefetch_id_to_data(&offset_ids).await;
+                    for offset_id in offset_ids {
+                        if let Some(record) = reader.get_data_for_offset_id(offset_id).await? {
+                            vector_segment_writer.add_embedding(offset_id, record.embedding)?;
+                            total_records += 1;
+                        }
+                    }
+                }
+            }
+            // An uninitialized record segment has nothing to index
+            Err(e) if matches!(*e, RecordSegmentReaderCreationError::UninitializedSegment) => {}
+            Err(e) => return Err((*e).into()),
+        };
+
+        let vector_segment_writer = vector_segment_writer
+            .commit()
+            .await
+            .map_err(RebuildVectorSegmentError::HnswFlush)?;
+        let file_paths = vector_segment_writer
+            .flush()
+            .await
+            .map_err(RebuildVectorSegmentError::HnswFlush)?;
+
+        tracing::info!(
+            "Rebuilt vector segment {} with {} records",
+            input.vector_segment.id,
+            total_records
+        );
+
+        Ok(RebuildVectorSegmentOutput {
+            flush_info: SegmentFlushInfo {
+                segment_id: input.vector_segment.id,
+                file_paths,
+                metadata: input.vector_segment.metadata.clone(),
+            },
+            total_records,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chroma_log::test::{upsert_generator, LogGenerator, TEST_EMBEDDING_DIMENSION};
+    use chroma_segment::{
+        distributed_hnsw::DistributedHNSWSegmentReader, test::TestDistributedSegment,
+    };
+    use chroma_system::Operator;
+    use chroma_types::MetadataValue;
+    use std::collections::HashMap;
+
+    use super::{RebuildVectorSegmentInput, RebuildVectorSegmentOperator};
+
+    #[tokio::test]
+    async fn test_rebuild_with_new_parameters() {
+        let mut test_segment = TestDistributedSegment::new_with_dimension(TEST_EMBEDDING_DIMENSION);
+        test_segment
+            .compact_log(upsert_generator.generate_chunk(1..=100), 1)
+            .await;
+
+        let mut vector_segment = test_segment.vector_segment.clone();
+        vector_segment.file_path = HashMap::new();
+        vector_segment
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .insert("hnsw:M".to_string(), MetadataValue::Int(32));
+
+        let rebuild_output = RebuildVectorSegmentOperator::new()
+            .run(&RebuildVectorSegmentInput {
+                blockfile_provider: test_segment.blockfile_provider.clone(),
+                hnsw_provider: test_segment.hnsw_provider.clone(),
+                record_segment: test_segment.record_segment.clone(),
+                vector_segment: vector_segment.clone(),
+                dimension: TEST_EMBEDDING_DIMENSION,
+            })
+            .await
+            .expect("RebuildVectorSegmentOperator should not fail");
+
+        assert_eq!(rebuild_output.total_records, 100);
+        assert_eq!(rebuild_output.flush_info.segment_id, vector_segment.id);
+        assert_eq!(
+            rebuild_output
+                .flush_info
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("hnsw:M")),
+            Some(&MetadataValue::Int(32))
+        );
+        assert_ne!(
+            rebuild_output.flush_info.file_paths,
+            test_segment.vector_segment.file_path
+        );
+
+        vector_segment.file_path = rebuild_output.flush_info.file_paths;
+        let reader = DistributedHNSWSegmentReader::from_segment(
+            &vector_segment,
+            TEST_EMBEDDING_DIMENSION,
+            test_segment.hnsw_provider.clone(),
+        )
+        .await
+        .expect("Rebuilt vector segment should be readable");
+        assert_eq!(reader.len(), 100);
+    }
+}
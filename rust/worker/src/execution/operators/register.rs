@@ -238,10 +238,12 @@ mod tests {
             SegmentFlushInfo {
                 segment_id: segment_id_1,
                 file_paths: file_path_3.clone(),
+                metadata: None,
             },
             SegmentFlushInfo {
                 segment_id: segment_id_2,
                 file_paths: file_path_4.clone(),
+                metadata: None,
             },
         ];
 
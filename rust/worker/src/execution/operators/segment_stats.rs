@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_index::hnsw_provider::HnswIndexProvider;
+use chroma_segment::{
+    distributed_hnsw::{
+        DistributedHNSWSegmentFromSegmentError, DistributedHNSWSegmentReader, HNSW_INDEX,
+    },
+    distributed_spann::{SpannSegmentReader, SpannSegmentReaderError, HNSW_PATH},
+};
+use chroma_system::{Operator, OperatorType};
+use chroma_types::{Segment, SegmentStats, SegmentType};
+use thiserror::Error;
+use tracing::trace;
+use uuid::Uuid;
+
+/// The `SegmentStatsOperator` inspects the persisted files of a segment
+///
+/// # Parameters
+/// - `segment`: The segment to inspect
+/// - `blockfile_provider`: The provider for the blockfiles of the segment
+/// - `hnsw_provider`: The provider for the HNSW index of the segment, if any
+/// - `dimension`: The dimension of the embeddings in the collection
+///
+/// # Outputs
+/// - The layout of every blockfile of the segment, keyed by file path name
+/// - The statistics of the vector index if the segment is an initialized vector segment
+///
+/// # Usage
+/// Every block of every blockfile is loaded, so this is meant for diagnosis only
+#[derive(Debug)]
+pub struct SegmentStatsOperator {}
+
+impl SegmentStatsOperator {
+    pub fn new() -> Box<Self> {
+        Box::new(SegmentStatsOperator {})
+    }
+}
+
+#[derive(Debug)]
+pub struct SegmentStatsInput {
+    pub segment: Segment,
+    pub blockfile_provider: BlockfileProvider,
+    pub hnsw_provider: HnswIndexProvider,
+    pub dimension: usize,
+}
+
+pub type SegmentStatsOutput = SegmentStats;
+
+#[derive(Error, Debug)]
+pub enum SegmentStatsError {
+    #[error("Error reading blockfile: {0}")]
+    Blockfile(#[from] Box<dyn ChromaError>),
+    #[error("Invalid blockfile id: {0}")]
+    InvalidBlockfileId(String),
+    #[error("Error creating hnsw segment reader: {0}")]
+    HnswReader(#[from] DistributedHNSWSegmentFromSegmentError),
+    #[error("Error reading spann segment: {0}")]
+    SpannReader(#[from] SpannSegmentReaderError),
+}
+
+impl ChromaError for SegmentStatsError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            SegmentStatsError::Blockfile(e) => e.code(),
+            SegmentStatsError::InvalidBlockfileId(_) => ErrorCodes::Internal,
+            SegmentStatsError::HnswReader(e) => e.code(),
+            SegmentStatsError::SpannReader(e) => e.code(),
+        }
+    }
+}
+
+#[async_trait]
+impl Operator<SegmentStatsInput, SegmentStatsOutput> for SegmentStatsOperator {
+    type Error = SegmentStatsError;
+
+    fn get_name(&self) -> &'static str {
+        "SegmentStatsOperator"
+    }
+
+    fn get_type(&self) -> OperatorType {
+        OperatorType::IO
+    }
+
+    async fn run(
+        &self,
+        input: &SegmentStatsInput,
+    ) -> Result<SegmentStatsOutput, SegmentStatsError> {
+        trace!("[{}]: {:?}", self.get_name(), input);
+
+        let segment = &input.segment;
+        let mut stats = SegmentStats {
+            segment_id: segment.id.to_string(),
+            segment_type: String::from(segment.r#type),
+            ..Default::default()
+        };
+
+        // The HNSW index files are not blockfiles
+        let hnsw_path = match segment.r#type {
+            SegmentType::HnswDistributed => Some(HNSW_INDEX),
+            SegmentType::Spann => Some(HNSW_PATH),
+            _ => None,
+        };
+        for (name, ids) in &segment.file_path {
+            if Some(name.as_str()) == hnsw_path {
+                continue;
+            }
+            let blockfile_stats = stats.blockfiles.entry(name.clone()).or_default();
+            for id in ids {
+                let id = Uuid::parse_str(id)
+                    .map_err(|_| SegmentStatsError::InvalidBlockfileId(id.clone()))?;
+                blockfile_stats.merge(&input.blockfile_provider.stats(&id).await?);
+            }
+        }
+
+        match segment.r#type {
+            SegmentType::HnswDistributed => {
+                match DistributedHNSWSegmentReader::from_segment(
+                    segment,
+                    input.dimension,
+                    input.hnsw_provider.clone(),
+                )
+                .await
+                {
+                    Ok(reader) => stats.hnsw = Some(reader.stats()),
+                    Err(e)
+                        if matches!(*e, DistributedHNSWSegmentFromSegmentError::Uninitialized) => {}
+                    Err(e) => return Err((*e).into()),
+                }
+            }
+            SegmentType::Spann => {
+                match SpannSegmentReader::from_segment(
+                    segment,
+                    &input.blockfile_provider,
+                    &input.hnsw_provider,
+                    input.dimension,
+                )
+                .await
+                {
+                    Ok(reader) => stats.spann = Some(reader.stats().await?),
+                    Err(SpannSegmentReaderError::UninitializedSegment) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            _ => {}
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chroma_log::test::{upsert_generator, LogGenerator, TEST_EMBEDDING_DIMENSION};
+    use chroma_segment::test::TestDistributedSegment;
+    use chroma_system::Operator;
+
+    use super::{SegmentStatsInput, SegmentStatsOperator};
+
+    #[tokio::test]
+    async fn test_segment_stats() {
+        let mut test_segment = TestDistributedSegment::new_with_dimension(TEST_EMBEDDING_DIMENSION);
+        test_segment
+            .compact_log(upsert_generator.generate_chunk(1..=100), 1)
+            .await;
+
+        let record_stats = SegmentStatsOperator::new()
+            .run(&SegmentStatsInput {
+                segment: test_segment.record_segment.clone(),
+                blockfile_provider: test_segment.blockfile_provider.clone(),
+                hnsw_provider: test_segment.hnsw_provider.clone(),
+                dimension: TEST_EMBEDDING_DIMENSION,
+            })
+            .await
+            .expect("SegmentStatsOperator should not fail");
+        assert_eq!(
+            record_stats.blockfiles.len(),
+            test_segment.record_segment.file_path.len()
+        );
+        assert!(record_stats
+            .blockfiles
+            .values()
+            .all(|stats| stats.block_count > 0));
+        assert!(record_stats.hnsw.is_none());
+
+        let vector_stats = SegmentStatsOperator::new()
+            .run(&SegmentStatsInput {
+                segment: test_segment.vector_segment.clone(),
+                blockfile_provider: test_segment.blockfile_provider.clone(),
+                hnsw_provider: test_segment.hnsw_provider.clone(),
+                dimension: TEST_EMBEDDING_DIMENSION,
+            })
+            .await
+            .expect("SegmentStatsOperator should not fail");
+        assert!(vector_stats.blockfiles.is_empty());
+        let hnsw_stats = vector_stats
+            .hnsw
+            .expect("Compacted vector segment should have hnsw stats");
+        assert_eq!(hnsw_stats.len, 100);
+        assert_eq!(hnsw_stats.dimensionality, TEST_EMBEDDING_DIMENSION as u32);
+    }
+}
@@ -46,6 +46,7 @@ use chroma_segment::types::MaterializeLogsResult;
 use chroma_sysdb::SysDb;
 use chroma_system::wrap;
 use chroma_system::ChannelError;
+use chroma_system::CommitGuard;
 use chroma_system::ComponentContext;
 use chroma_system::ComponentHandle;
 use chroma_system::Dispatcher;
@@ -137,6 +138,8 @@ pub struct CompactOrchestrator {
     segment_spans: HashMap<SegmentUuid, Span>,
     // Total number of records in the collection after the compaction
     total_records_last_compaction: u64,
+    // Keeps a cancelled compaction from registering, and a registering one from being cancelled
+    commit_guard: CommitGuard,
 }
 
 #[derive(Error, Debug)]
@@ -214,6 +217,23 @@ where
     }
 }
 
+impl CompactionError {
+    /// The name of the operator that failed, if the error comes from an operator
+    pub fn operator(&self) -> Option<&'static str> {
+        match self {
+            CompactionError::FetchLog(_) => Some("FetchLogOperator"),
+            CompactionError::Partition(_) => Some("PartitionOperator"),
+            CompactionError::MaterializeLogs(_) => Some("MaterializeLogOperator"),
+            CompactionError::ApplyLogToSegmentWriter(_) => Some("ApplyLogToSegmentWriterOperator"),
+            CompactionError::PrefetchSegment(_) => Some("PrefetchSegmentOperator"),
+            CompactionError::CommitSegmentWriter(_) => Some("CommitSegmentWriterOperator"),
+            CompactionError::FlushSegmentWriter(_) => Some("FlushSegmentWriterOperator"),
+            CompactionError::Register(_) => Some("RegisterOperator"),
+            _ => None,
+        }
+    }
+}
+
 impl ChromaError for CompactionError {
     fn code(&self) -> ErrorCodes {
         if matches!(self, CompactionError::Aborted) {
@@ -248,6 +268,9 @@ impl CompactOrchestrator {
         max_compaction_size: usize,
         max_partition_size: usize,
     ) -> Self {
+        // Everything this compaction writes belongs to the tenant of the collection
+        let blockfile_provider = blockfile_provider.for_tenant(&compaction_job.tenant_id);
+        let hnsw_index_provider = hnsw_index_provider.for_tenant(&compaction_job.tenant_id);
         CompactOrchestrator {
             id: Uuid::new_v4(),
             compaction_job,
@@ -269,6 +292,7 @@ impl CompactOrchestrator {
             flush_results: Vec::new(),
             segment_spans: HashMap::new(),
             total_records_last_compaction: 0,
+            commit_guard: CommitGuard::default(),
         }
     }
 
@@ -494,6 +518,13 @@ impl CompactOrchestrator {
     }
 
     async fn register(&mut self, log_position: i64, ctx: &ComponentContext<CompactOrchestrator>) {
+        if !self.commit_guard.try_commit() {
+            tracing::info!(
+                "Not registering compaction of collection {} because it was cancelled",
+                self.collection_id
+            );
+            return;
+        }
         self.state = ExecutionState::Register;
         let operator = RegisterOperator::new();
         let input = RegisterInput::new(
@@ -703,6 +734,8 @@ impl Orchestrator for CompactOrchestrator {
                 // offset is the one after the last compaction offset
                 start_log_offset_id: self.compaction_job.offset as u32,
                 maximum_fetch_count: Some(self.max_compaction_size as u32),
+                end_log_offset_id: None,
+                end_timestamp: None,
                 collection_uuid: self.collection_id,
             }),
             (),
@@ -710,6 +743,10 @@ impl Orchestrator for CompactOrchestrator {
         )]
     }
 
+    fn commit_guard(&self) -> Option<CommitGuard> {
+        Some(self.commit_guard.clone())
+    }
+
     fn set_result_channel(&mut self, sender: Sender<Result<CompactionResponse, CompactionError>>) {
         self.result_channel = Some(sender)
     }
@@ -8,6 +8,7 @@ use tokio::sync::oneshot::Sender;
 
 use crate::execution::operators::{
     knn::{KnnOperator, RecordDistance},
+    knn_brute_force::{KnnBruteForceError, KnnBruteForceInput, KnnBruteForceOutput},
     knn_hnsw::{KnnHnswError, KnnHnswInput, KnnHnswOutput},
     knn_log::{KnnLogError, KnnLogInput, KnnLogOutput},
     knn_merge::{KnnMergeError, KnnMergeInput, KnnMergeOperator, KnnMergeOutput},
@@ -19,7 +20,7 @@ use crate::execution::operators::{
     },
 };
 
-use super::knn_filter::{KnnError, KnnFilterOutput, KnnOutput, KnnResult};
+use super::knn_filter::{KnnError, KnnFilterOutput, KnnOutput, KnnResult, KnnSegmentStrategy};
 
 /// The `KnnOrchestrator` finds the nearest neighbor of a target embedding given the search domain.
 /// When used together with `KnnFilterOrchestrator`, they evaluate a `<collection>.query(...)` query
@@ -29,6 +30,8 @@ use super::knn_filter::{KnnError, KnnFilterOutput, KnnOutput, KnnResult};
 /// of the embedding together with a copy of the result from `KnnFilterOrchestrator`, run these
 /// orchestrators in parallel, and join them in the end.
 ///
+/// Depending on the `KnnSegmentStrategy` selected by the `KnnFilterOrchestrator`, the compacted
+/// records are searched either with the `KnnHnswOperator` or with the `KnnBruteForceOperator`.
 ///
 /// # Pipeline
 /// ```text
@@ -203,19 +206,38 @@ impl Orchestrator for KnnOrchestrator {
         tasks.push(knn_log_task);
 
         if let Some(hnsw_reader) = self.knn_filter_output.hnsw_reader.as_ref().cloned() {
-            let knn_segment_task = wrap(
-                Box::new(self.knn.clone()),
-                KnnHnswInput {
-                    hnsw_reader,
-                    compact_offset_ids: self
-                        .knn_filter_output
-                        .filter_output
-                        .compact_offset_ids
-                        .clone(),
-                    distance_function: self.knn_filter_output.distance_function.clone(),
-                },
-                ctx.receiver(),
-            );
+            let compact_offset_ids = self
+                .knn_filter_output
+                .filter_output
+                .compact_offset_ids
+                .clone();
+            let distance_function = self.knn_filter_output.distance_function.clone();
+            let knn_segment_task = match self.knn_filter_output.strategy {
+                KnnSegmentStrategy::BruteForce => wrap(
+                    Box::new(self.knn.clone()),
+                    KnnBruteForceInput {
+                        blockfile_provider: self.blockfile_provider.clone(),
+                        record_segment: self.knn_filter_output.record_segment.clone(),
+                        compact_offset_ids,
+                        distance_function,
+                    },
+                    ctx.receiver(),
+                ),
+                KnnSegmentStrategy::Hnsw {
+                    selectivity,
+                    max_ef_expansion,
+                } => wrap(
+                    Box::new(self.knn.clone()),
+                    KnnHnswInput {
+                        hnsw_reader,
+                        compact_offset_ids,
+                        distance_function,
+                        selectivity,
+                        max_ef_expansion,
+                    },
+                    ctx.receiver(),
+                ),
+            };
             tasks.push(knn_segment_task);
         }
 
@@ -255,6 +277,24 @@ impl Handler<TaskResult<KnnLogOutput, KnnLogError>> for KnnOrchestrator {
     }
 }
 
+#[async_trait]
+impl Handler<TaskResult<KnnBruteForceOutput, KnnBruteForceError>> for KnnOrchestrator {
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        message: TaskResult<KnnBruteForceOutput, KnnBruteForceError>,
+        ctx: &ComponentContext<Self>,
+    ) {
+        let output = match self.ok_or_terminate(message.into_inner(), ctx) {
+            Some(output) => output,
+            None => return,
+        };
+        self.knn_segment_distances = Some(output.record_distances);
+        self.try_start_knn_merge_operator(ctx).await;
+    }
+}
+
 #[async_trait]
 impl Handler<TaskResult<KnnHnswOutput, KnnHnswError>> for KnnOrchestrator {
     type Result = ();
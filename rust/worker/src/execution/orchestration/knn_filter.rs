@@ -10,13 +10,17 @@ use chroma_system::{
     wrap, ChannelError, ComponentContext, ComponentHandle, Dispatcher, Handler, Orchestrator,
     PanicError, TaskError, TaskMessage, TaskResult,
 };
-use chroma_types::{CollectionAndSegments, DistributedHnswParameters, Segment};
+use chroma_types::{
+    CollectionAndSegments, DistributedHnswParameters, Segment, SignedRoaringBitmap,
+};
 use thiserror::Error;
 use tokio::sync::oneshot::{error::RecvError, Sender};
 
+use crate::config::KnnQueryConfig;
 use crate::execution::operators::{
     fetch_log::{FetchLogError, FetchLogOperator, FetchLogOutput},
     filter::{FilterError, FilterInput, FilterOperator, FilterOutput},
+    knn_brute_force::KnnBruteForceError,
     knn_hnsw::KnnHnswError,
     knn_log::KnnLogError,
     knn_merge::KnnMergeError,
@@ -37,6 +41,8 @@ pub enum KnnError {
     Filter(#[from] FilterError),
     #[error("Error creating hnsw segment reader: {0}")]
     HnswReader(#[from] DistributedHNSWSegmentFromSegmentError),
+    #[error("Error running Knn Brute Force Operator: {0}")]
+    KnnBruteForce(#[from] KnnBruteForceError),
     #[error("Error running Knn Log Operator: {0}")]
     KnnLog(#[from] KnnLogError),
     #[error("Error running Knn Hnsw Operator: {0}")]
@@ -72,6 +78,7 @@ impl ChromaError for KnnError {
             KnnError::FetchLog(e) => e.code(),
             KnnError::Filter(e) => e.code(),
             KnnError::HnswReader(e) => e.code(),
+            KnnError::KnnBruteForce(e) => e.code(),
             KnnError::KnnLog(e) => e.code(),
             KnnError::KnnHnsw(e) => e.code(),
             KnnError::KnnMerge(_) => ErrorCodes::Internal,
@@ -102,12 +109,49 @@ where
     }
 }
 
+/// The strategy used to search the nearest neighbours in the compacted vector segment
+#[derive(Clone, Debug, PartialEq)]
+pub enum KnnSegmentStrategy {
+    /// Computes the exact distances of the candidate records read from the record segment.
+    /// This is also used regardless of the filter when the query asks for exact results
+    BruteForce,
+    /// Traverses the HNSW graph, expanding the search breadth by the inverse of the
+    /// estimated fraction of the index allowed by the filter, up to `max_ef_expansion`
+    Hnsw {
+        selectivity: f64,
+        max_ef_expansion: usize,
+    },
+}
+
+impl KnnSegmentStrategy {
+    pub fn select(
+        compact_offset_ids: &SignedRoaringBitmap,
+        index_size: usize,
+        config: &KnnQueryConfig,
+    ) -> Self {
+        let index_size = index_size as u64;
+        let candidate_count = match compact_offset_ids {
+            SignedRoaringBitmap::Include(rbm) => rbm.len().min(index_size),
+            SignedRoaringBitmap::Exclude(rbm) => index_size.saturating_sub(rbm.len()),
+        };
+        if candidate_count <= config.brute_force_threshold {
+            Self::BruteForce
+        } else {
+            Self::Hnsw {
+                selectivity: candidate_count as f64 / index_size as f64,
+                max_ef_expansion: config.max_ef_expansion,
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct KnnFilterOutput {
     pub logs: FetchLogOutput,
     pub distance_function: DistanceFunction,
     pub filter_output: FilterOutput,
     pub hnsw_reader: Option<Box<DistributedHNSWSegmentReader>>,
+    pub strategy: KnnSegmentStrategy,
     pub record_segment: Segment,
     pub vector_segment: Segment,
     pub dimension: usize,
@@ -118,6 +162,11 @@ type KnnFilterResult = Result<KnnFilterOutput, KnnError>;
 /// The `KnnFilterOrchestrator` chains a sequence of operators in sequence to evaluate
 /// the first half of a `<collection>.query(...)` query from the user
 ///
+/// Once the filter is evaluated, it selects how the compacted vector segment should be searched:
+/// if the filter leaves at most `brute_force_threshold` candidates in the segment, the exact distances
+/// are computed from the record segment; otherwise the HNSW graph is traversed with a search breadth
+/// expanded according to the selectivity of the filter.
+///
 /// # Pipeline
 /// ```text
 ///       ┌────────────┐
@@ -141,6 +190,13 @@ type KnnFilterResult = Result<KnnFilterOutput, KnnError>;
 ///    └─────────┬─────────┘
 ///              │
 ///              ▼
+///   ┌─────────────────────┐
+///   │                     │
+///   │  select strategy    │
+///   │                     │
+///   └──────────┬──────────┘
+///              │
+///              ▼
 ///     ┌──────────────────┐
 ///     │                  │
 ///     │  result_channel  │
@@ -154,6 +210,7 @@ pub struct KnnFilterOrchestrator {
     dispatcher: ComponentHandle<Dispatcher>,
     hnsw_provider: HnswIndexProvider,
     queue: usize,
+    knn_config: KnnQueryConfig,
 
     // Collection segments
     collection_and_segments: CollectionAndSegments,
@@ -172,11 +229,13 @@ pub struct KnnFilterOrchestrator {
 }
 
 impl KnnFilterOrchestrator {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         blockfile_provider: BlockfileProvider,
         dispatcher: ComponentHandle<Dispatcher>,
         hnsw_provider: HnswIndexProvider,
         queue: usize,
+        knn_config: KnnQueryConfig,
         collection_and_segments: CollectionAndSegments,
         fetch_log: FetchLogOperator,
         filter: FilterOperator,
@@ -186,6 +245,7 @@ impl KnnFilterOrchestrator {
             dispatcher,
             hnsw_provider,
             queue,
+            knn_config,
             collection_and_segments,
             fetch_log,
             fetched_logs: None,
@@ -302,6 +362,16 @@ impl Handler<TaskResult<FilterOutput, FilterError>> for KnnFilterOrchestrator {
                 return;
             }
         };
+        let strategy = KnnSegmentStrategy::select(
+            &output.compact_offset_ids,
+            hnsw_reader
+                .as_ref()
+                .map(|reader| reader.len())
+                .unwrap_or_default(),
+            &self.knn_config,
+        );
+        tracing::info!(?strategy, "Selected knn segment strategy");
+
         let output = KnnFilterOutput {
             logs: self
                 .fetched_logs
@@ -310,6 +380,7 @@ impl Handler<TaskResult<FilterOutput, FilterError>> for KnnFilterOrchestrator {
             distance_function: hnsw_configuration.space.into(),
             filter_output: output,
             hnsw_reader,
+            strategy,
             record_segment: self.collection_and_segments.record_segment.clone(),
             vector_segment: self.collection_and_segments.vector_segment.clone(),
             dimension: collection_dimension as usize,
@@ -320,3 +391,46 @@ impl Handler<TaskResult<FilterOutput, FilterError>> for KnnFilterOrchestrator {
 
 pub(super) type KnnOutput = KnnProjectionOutput;
 pub(super) type KnnResult = Result<KnnOutput, KnnError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roaring::RoaringBitmap;
+
+    #[test]
+    fn test_select_strategy() {
+        let config = KnnQueryConfig {
+            brute_force_threshold: 10,
+            max_ef_expansion: 4,
+        };
+
+        let selective = SignedRoaringBitmap::Include(RoaringBitmap::from_iter(0..10));
+        assert_eq!(
+            KnnSegmentStrategy::select(&selective, 1000, &config),
+            KnnSegmentStrategy::BruteForce
+        );
+
+        let filtered = SignedRoaringBitmap::Include(RoaringBitmap::from_iter(0..100));
+        assert_eq!(
+            KnnSegmentStrategy::select(&filtered, 1000, &config),
+            KnnSegmentStrategy::Hnsw {
+                selectivity: 0.1,
+                max_ef_expansion: 4,
+            }
+        );
+
+        let unfiltered = SignedRoaringBitmap::Exclude(RoaringBitmap::new());
+        assert_eq!(
+            KnnSegmentStrategy::select(&unfiltered, 1000, &config),
+            KnnSegmentStrategy::Hnsw {
+                selectivity: 1.0,
+                max_ef_expansion: 4,
+            }
+        );
+        // A small index is searched exactly even without a filter
+        assert_eq!(
+            KnnSegmentStrategy::select(&unfiltered, 10, &config),
+            KnnSegmentStrategy::BruteForce
+        );
+    }
+}
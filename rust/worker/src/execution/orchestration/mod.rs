@@ -1,8 +1,12 @@
 mod compact;
 mod count;
+mod reindex;
+mod segment_stats;
 mod spann_knn;
 pub(crate) use compact::*;
 pub(crate) use count::*;
+pub(crate) use reindex::*;
+pub(crate) use segment_stats::*;
 
 pub mod get;
 pub mod knn;
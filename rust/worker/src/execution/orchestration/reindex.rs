@@ -0,0 +1,279 @@
+use async_trait::async_trait;
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_index::hnsw_provider::HnswIndexProvider;
+use chroma_log::Log;
+use chroma_sysdb::SysDb;
+use chroma_system::{
+    wrap, ChannelError, ComponentContext, ComponentHandle, Dispatcher, Handler, Orchestrator,
+    PanicError, TaskError, TaskMessage, TaskResult,
+};
+use chroma_types::{
+    Collection, GetCollectionsError, GetSegmentsError, HnswParametersFromSegmentError, Segment,
+    SegmentType,
+};
+use thiserror::Error;
+use tokio::sync::oneshot::{error::RecvError, Sender};
+
+use crate::execution::operators::{
+    rebuild_vector_segment::{
+        RebuildVectorSegmentError, RebuildVectorSegmentInput, RebuildVectorSegmentOperator,
+        RebuildVectorSegmentOutput,
+    },
+    register::{RegisterError, RegisterInput, RegisterOperator, RegisterOutput},
+};
+
+#[derive(Error, Debug)]
+pub enum ReindexError {
+    #[error("Error sending message through channel: {0}")]
+    Channel(#[from] ChannelError),
+    #[error("Collection [{0}] not found")]
+    CollectionNotFound(String),
+    #[error("Collection is missing dimension")]
+    CollectionMissingDimension,
+    #[error("Error getting collection: {0}")]
+    GetCollection(#[from] GetCollectionsError),
+    #[error("Error getting segments: {0}")]
+    GetSegments(#[from] GetSegmentsError),
+    #[error("Invalid index parameters: {0}")]
+    InvalidParameters(#[from] HnswParametersFromSegmentError),
+    #[error("Segment of type {0:?} not found")]
+    MissingSegment(SegmentType),
+    #[error("Panic: {0}")]
+    Panic(#[from] PanicError),
+    #[error("Error running Rebuild Vector Segment Operator: {0}")]
+    RebuildVectorSegment(#[from] RebuildVectorSegmentError),
+    #[error("Error running Register Operator: {0}")]
+    Register(#[from] RegisterError),
+    #[error("Error receiving final result: {0}")]
+    Result(#[from] RecvError),
+    #[error("Reindexing into segment type {0} is not supported")]
+    UnsupportedSegmentType(String),
+    #[error("Operation aborted because resources exhausted")]
+    Aborted,
+}
+
+impl ChromaError for ReindexError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            ReindexError::Channel(e) => e.code(),
+            ReindexError::CollectionNotFound(_) => ErrorCodes::NotFound,
+            ReindexError::CollectionMissingDimension => ErrorCodes::FailedPrecondition,
+            ReindexError::GetCollection(e) => e.code(),
+            ReindexError::GetSegments(e) => e.code(),
+            ReindexError::InvalidParameters(e) => e.code(),
+            ReindexError::MissingSegment(_) => ErrorCodes::Internal,
+            ReindexError::Panic(_) => ErrorCodes::Aborted,
+            ReindexError::RebuildVectorSegment(e) => e.code(),
+            ReindexError::Register(e) => e.code(),
+            ReindexError::Result(_) => ErrorCodes::Internal,
+            ReindexError::UnsupportedSegmentType(_) => ErrorCodes::Unimplemented,
+            ReindexError::Aborted => ErrorCodes::ResourceExhausted,
+        }
+    }
+}
+
+impl<E> From<TaskError<E>> for ReindexError
+where
+    E: Into<ReindexError>,
+{
+    fn from(value: TaskError<E>) -> Self {
+        match value {
+            TaskError::Panic(e) => ReindexError::Panic(e),
+            TaskError::TaskFailed(e) => e.into(),
+            TaskError::Aborted => ReindexError::Aborted,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReindexResponse {
+    pub collection: Collection,
+    pub total_records: u64,
+}
+
+type ReindexResult = Result<ReindexResponse, ReindexError>;
+
+/// The `ReindexOrchestrator` rebuilds the vector segment of a collection from the
+/// embeddings in its record segment, using the index parameters in the metadata of
+/// `vector_segment`. The file paths of `vector_segment` are expected to be empty.
+///
+/// # Pipeline
+/// ```text
+/// ┌────────────┐
+/// │            │
+/// │  on_start  │
+/// │            │
+/// └──────┬─────┘
+///        │
+///        ▼
+/// ┌─────────────────────────────┐
+/// │                             │
+/// │ RebuildVectorSegmentOperator│
+/// │                             │
+/// └──────┬──────────────────────┘
+///        │
+///        ▼
+/// ┌──────────────────┐
+/// │                  │
+/// │ RegisterOperator │
+/// │                  │
+/// └──────┬───────────┘
+///        │
+///        ▼
+/// ┌───────────────────┐
+/// │                   │
+/// │ result_channel    │
+/// │                   │
+/// └───────────────────┘
+/// ```
+///
+/// The new index files and the new index parameters are swapped in together through a
+/// regular compaction flush at the current log position and collection version of the
+/// collection. If a compaction of the same collection is registered first, the version
+/// check in the sysdb rejects the flush and the reindex should be retried. The old index
+/// files are garbage collected together with the collection version that references them.
+#[derive(Debug)]
+pub struct ReindexOrchestrator {
+    // Orchestrator parameters
+    blockfile_provider: BlockfileProvider,
+    hnsw_provider: HnswIndexProvider,
+    dispatcher: ComponentHandle<Dispatcher>,
+    log: Log,
+    sysdb: SysDb,
+
+    // Collection and segments
+    collection: Collection,
+    record_segment: Segment,
+    vector_segment: Segment,
+
+    // Number of records in the rebuilt index
+    total_records: u64,
+
+    // Result channel
+    result_channel: Option<Sender<ReindexResult>>,
+}
+
+impl ReindexOrchestrator {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        blockfile_provider: BlockfileProvider,
+        hnsw_provider: HnswIndexProvider,
+        dispatcher: ComponentHandle<Dispatcher>,
+        log: Log,
+        sysdb: SysDb,
+        collection: Collection,
+        record_segment: Segment,
+        vector_segment: Segment,
+    ) -> Self {
+        Self {
+            blockfile_provider,
+            hnsw_provider,
+            dispatcher,
+            log,
+            sysdb,
+            collection,
+            record_segment,
+            vector_segment,
+            total_records: 0,
+            result_channel: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Orchestrator for ReindexOrchestrator {
+    type Output = ReindexResponse;
+    type Error = ReindexError;
+
+    fn dispatcher(&self) -> ComponentHandle<Dispatcher> {
+        self.dispatcher.clone()
+    }
+
+    fn initial_tasks(&self, ctx: &ComponentContext<Self>) -> Vec<TaskMessage> {
+        vec![wrap(
+            RebuildVectorSegmentOperator::new(),
+            RebuildVectorSegmentInput {
+                blockfile_provider: self.blockfile_provider.clone(),
+                hnsw_provider: self.hnsw_provider.clone(),
+                record_segment: self.record_segment.clone(),
+                vector_segment: self.vector_segment.clone(),
+                dimension: self.collection.dimension.unwrap_or_default() as usize,
+            },
+            ctx.receiver(),
+        )]
+    }
+
+    fn set_result_channel(&mut self, sender: Sender<ReindexResult>) {
+        self.result_channel = Some(sender)
+    }
+
+    fn take_result_channel(&mut self) -> Sender<ReindexResult> {
+        self.result_channel
+            .take()
+            .expect("The result channel should be set before take")
+    }
+}
+
+#[async_trait]
+impl Handler<TaskResult<RebuildVectorSegmentOutput, RebuildVectorSegmentError>>
+    for ReindexOrchestrator
+{
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        message: TaskResult<RebuildVectorSegmentOutput, RebuildVectorSegmentError>,
+        ctx: &ComponentContext<Self>,
+    ) {
+        let output = match self.ok_or_terminate(message.into_inner(), ctx) {
+            Some(output) => output,
+            None => return,
+        };
+        self.total_records = output.total_records;
+
+        let task = wrap(
+            RegisterOperator::new(),
+            RegisterInput::new(
+                self.collection.tenant.clone(),
+                self.collection.collection_id,
+                self.collection.log_position,
+                self.collection.version,
+                vec![output.flush_info].into(),
+                self.collection.total_records_post_compaction,
+                self.sysdb.clone(),
+                self.log.clone(),
+            ),
+            ctx.receiver(),
+        );
+        self.send(task, ctx).await;
+    }
+}
+
+#[async_trait]
+impl Handler<TaskResult<RegisterOutput, RegisterError>> for ReindexOrchestrator {
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        message: TaskResult<RegisterOutput, RegisterError>,
+        ctx: &ComponentContext<Self>,
+    ) {
+        if self.ok_or_terminate(message.into_inner(), ctx).is_none() {
+            return;
+        }
+
+        tracing::info!(
+            "Reindexed vector segment of collection {} with {} records",
+            self.collection.collection_id,
+            self.total_records
+        );
+        self.terminate_with_result(
+            Ok(ReindexResponse {
+                collection: self.collection.clone(),
+                total_records: self.total_records,
+            }),
+            ctx,
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_error::{ChromaError, ErrorCodes};
+use chroma_index::hnsw_provider::HnswIndexProvider;
+use chroma_system::{
+    wrap, ChannelError, ComponentContext, ComponentHandle, Dispatcher, Handler, Orchestrator,
+    PanicError, TaskError, TaskMessage, TaskResult,
+};
+use chroma_types::{CollectionAndSegments, SegmentStats};
+use thiserror::Error;
+use tokio::sync::oneshot::{error::RecvError, Sender};
+
+use crate::execution::operators::segment_stats::{
+    SegmentStatsError, SegmentStatsInput, SegmentStatsOperator, SegmentStatsOutput,
+};
+
+#[derive(Error, Debug)]
+pub enum SegmentStatsOrchestratorError {
+    #[error("Error sending message through channel: {0}")]
+    Channel(#[from] ChannelError),
+    #[error("Panic: {0}")]
+    Panic(#[from] PanicError),
+    #[error("Error receiving final result: {0}")]
+    Result(#[from] RecvError),
+    #[error("Error running Segment Stats Operator: {0}")]
+    SegmentStats(#[from] SegmentStatsError),
+    #[error("Operation aborted because resources exhausted")]
+    Aborted,
+}
+
+impl ChromaError for SegmentStatsOrchestratorError {
+    fn code(&self) -> ErrorCodes {
+        match self {
+            SegmentStatsOrchestratorError::Channel(e) => e.code(),
+            SegmentStatsOrchestratorError::Panic(_) => ErrorCodes::Aborted,
+            SegmentStatsOrchestratorError::Result(_) => ErrorCodes::Internal,
+            SegmentStatsOrchestratorError::SegmentStats(e) => e.code(),
+            SegmentStatsOrchestratorError::Aborted => ErrorCodes::ResourceExhausted,
+        }
+    }
+}
+
+impl<E> From<TaskError<E>> for SegmentStatsOrchestratorError
+where
+    E: Into<SegmentStatsOrchestratorError>,
+{
+    fn from(value: TaskError<E>) -> Self {
+        match value {
+            TaskError::Panic(e) => SegmentStatsOrchestratorError::Panic(e),
+            TaskError::TaskFailed(e) => e.into(),
+            TaskError::Aborted => SegmentStatsOrchestratorError::Aborted,
+        }
+    }
+}
+
+type SegmentStatsResult = Result<Vec<SegmentStats>, SegmentStatsOrchestratorError>;
+
+/// The `SegmentStatsOrchestrator` collects the statistics of the metadata, record and
+/// vector segments of a collection. Only the compacted data is inspected, the log is ignored.
+///
+/// # Pipeline
+/// ```text
+///                    ┌────────────┐
+///                    │            │
+///                    │  on_start  │
+///                    │            │
+///                    └──────┬─────┘
+///                           │
+///          ┌────────────────┼────────────────┐
+///          ▼                ▼                ▼
+/// ┌─────────────────┐┌─────────────────┐┌─────────────────┐
+/// │ SegmentStats    ││ SegmentStats    ││ SegmentStats    │
+/// │ (metadata)      ││ (record)        ││ (vector)        │
+/// └────────┬────────┘└────────┬────────┘└────────┬────────┘
+///          │                  │                  │
+///          └──────────────────┼──────────────────┘
+///                             ▼
+///                   ┌───────────────────┐
+///                   │                   │
+///                   │  result_channel   │
+///                   │                   │
+///                   └───────────────────┘
+/// ```
+#[derive(Debug)]
+pub struct SegmentStatsOrchestrator {
+    // Orchestrator parameters
+    blockfile_provider: BlockfileProvider,
+    hnsw_provider: HnswIndexProvider,
+    dispatcher: ComponentHandle<Dispatcher>,
+    queue: usize,
+
+    // Collection and segments
+    collection_and_segments: CollectionAndSegments,
+
+    // Statistics of the segments inspected so far
+    segment_stats: Vec<SegmentStats>,
+
+    // Result channel
+    result_channel: Option<Sender<SegmentStatsResult>>,
+}
+
+impl SegmentStatsOrchestrator {
+    pub(crate) fn new(
+        blockfile_provider: BlockfileProvider,
+        hnsw_provider: HnswIndexProvider,
+        dispatcher: ComponentHandle<Dispatcher>,
+        queue: usize,
+        collection_and_segments: CollectionAndSegments,
+    ) -> Self {
+        Self {
+            blockfile_provider,
+            hnsw_provider,
+            dispatcher,
+            queue,
+            collection_and_segments,
+            segment_stats: Vec::new(),
+            result_channel: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Orchestrator for SegmentStatsOrchestrator {
+    type Output = Vec<SegmentStats>;
+    type Error = SegmentStatsOrchestratorError;
+
+    fn dispatcher(&self) -> ComponentHandle<Dispatcher> {
+        self.dispatcher.clone()
+    }
+
+    fn initial_tasks(&self, ctx: &ComponentContext<Self>) -> Vec<TaskMessage> {
+        [
+            &self.collection_and_segments.metadata_segment,
+            &self.collection_and_segments.record_segment,
+            &self.collection_and_segments.vector_segment,
+        ]
+        .into_iter()
+        .map(|segment| {
+            wrap(
+                SegmentStatsOperator::new(),
+                SegmentStatsInput {
+                    segment: segment.clone(),
+                    blockfile_provider: self.blockfile_provider.clone(),
+                    hnsw_provider: self.hnsw_provider.clone(),
+                    dimension: self
+                        .collection_and_segments
+                        .collection
+                        .dimension
+                        .unwrap_or_default() as usize,
+                },
+                ctx.receiver(),
+            )
+        })
+        .collect()
+    }
+
+    fn queue_size(&self) -> usize {
+        self.queue
+    }
+
+    fn set_result_channel(&mut self, sender: Sender<SegmentStatsResult>) {
+        self.result_channel = Some(sender)
+    }
+
+    fn take_result_channel(&mut self) -> Sender<SegmentStatsResult> {
+        self.result_channel
+            .take()
+            .expect("The result channel should be set before take")
+    }
+}
+
+#[async_trait]
+impl Handler<TaskResult<SegmentStatsOutput, SegmentStatsError>> for SegmentStatsOrchestrator {
+    type Result = ();
+
+    async fn handle(
+        &mut self,
+        message: TaskResult<SegmentStatsOutput, SegmentStatsError>,
+        ctx: &ComponentContext<Self>,
+    ) {
+        let output = match self.ok_or_terminate(message.into_inner(), ctx) {
+            Some(output) => output,
+            None => return,
+        };
+        self.segment_stats.push(output);
+
+        if self.segment_stats.len() == 3 {
+            let segment_stats = std::mem::take(&mut self.segment_stats);
+            self.terminate_with_result(Ok(segment_stats), ctx);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod compactor;
+mod scrubber;
 mod server;
 mod utils;
 
@@ -10,6 +11,7 @@ use chroma_memberlist::memberlist_provider::{
 use clap::Parser;
 use compactor::compaction_client::CompactionClient;
 use compactor::compaction_server::CompactionServer;
+use scrubber::Scrubber;
 use tokio::select;
 use tokio::signal::unix::{signal, SignalKind};
 
@@ -124,6 +126,7 @@ pub async fn compaction_service_entrypoint() {
         };
     compaction_manager.set_dispatcher(dispatcher_handle.clone());
     compaction_manager.set_system(system.clone());
+    let job_tracker = compaction_manager.job_tracker();
 
     let mut compaction_manager_handle = system.start_component(compaction_manager);
     memberlist.subscribe(compaction_manager_handle.receiver());
@@ -132,6 +135,7 @@ pub async fn compaction_service_entrypoint() {
 
     let compaction_server = CompactionServer {
         manager: compaction_manager_handle.clone(),
+        job_tracker,
         port: config.my_port,
     };
 
@@ -171,3 +175,11 @@ pub async fn compaction_client_entrypoint() {
         eprintln!("{e}");
     }
 }
+
+pub async fn scrubber_entrypoint() {
+    let scrubber = Scrubber::parse();
+    if let Err(e) = scrubber.run().await {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,104 @@
+use chroma_blockstore::provider::BlockfileProvider;
+use chroma_config::{registry::Registry, Configurable};
+use chroma_error::ChromaError;
+use chroma_index::hnsw_provider::HnswIndexProvider;
+use chroma_segment::scrub::scrub_segment;
+use chroma_storage::Storage;
+use chroma_sysdb::SysDb;
+use chroma_types::{CollectionUuid, GetCollectionWithSegmentsError, ScrubReport};
+use clap::Parser;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::config::{QueryServiceConfig, RootConfig};
+
+/// Error for the scrubber
+#[derive(Debug, Error)]
+pub enum ScrubberError {
+    #[error("Unable to initialize from config: {0}")]
+    Config(Box<dyn ChromaError>),
+    #[error("Unable to get collection {0}: {1}")]
+    Collection(CollectionUuid, GetCollectionWithSegmentsError),
+    #[error("Found {0} corrupt files")]
+    Corruption(usize),
+}
+
+/// Tool to verify the persisted files of collections before queries hit them
+#[derive(Debug, Parser)]
+#[command(version, about, long_about = None)]
+pub struct Scrubber {
+    /// Path to the config of the query service. Defaults to the worker config lookup
+    #[arg(short, long)]
+    config: Option<String>,
+    /// Uuids of the collections to scrub
+    #[arg(short, long, required = true)]
+    id: Vec<Uuid>,
+}
+
+impl Scrubber {
+    fn load_config(&self) -> QueryServiceConfig {
+        match &self.config {
+            Some(path) => RootConfig::load_from_path(path).query_service,
+            None => RootConfig::load().query_service,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), ScrubberError> {
+        let config = self.load_config();
+        let registry = Registry::new();
+        let mut sysdb = SysDb::try_from_config(&config.sysdb, &registry)
+            .await
+            .map_err(ScrubberError::Config)?;
+        let storage = Storage::try_from_config(&config.storage, &registry)
+            .await
+            .map_err(ScrubberError::Config)?;
+        let blockfile_provider = BlockfileProvider::try_from_config(
+            &(config.blockfile_provider.clone(), storage.clone()),
+            &registry,
+        )
+        .await
+        .map_err(ScrubberError::Config)?;
+        let hnsw_provider =
+            HnswIndexProvider::try_from_config(&(config.hnsw_provider.clone(), storage), &registry)
+                .await
+                .map_err(ScrubberError::Config)?;
+
+        let mut corruptions = 0;
+        for id in &self.id {
+            let collection_id = CollectionUuid(*id);
+            let collection_and_segments =
+                sysdb
+                    .get_collection_with_segments(collection_id)
+                    .await
+                    .map_err(|e| ScrubberError::Collection(collection_id, e))?;
+            for segment in [
+                &collection_and_segments.metadata_segment,
+                &collection_and_segments.record_segment,
+                &collection_and_segments.vector_segment,
+            ] {
+                let report = scrub_segment(segment, &blockfile_provider, &hnsw_provider).await;
+                print_report(collection_id, &segment.id.to_string(), &report);
+                corruptions += report.corruptions.len();
+            }
+        }
+
+        if corruptions > 0 {
+            return Err(ScrubberError::Corruption(corruptions));
+        }
+        Ok(())
+    }
+}
+
+fn print_report(collection_id: CollectionUuid, segment_id: &str, report: &ScrubReport) {
+    println!(
+        "Collection {} segment {}: {} files checked, {} without checksum, {} corrupt",
+        collection_id,
+        segment_id,
+        report.files_checked,
+        report.files_without_checksum,
+        report.corruptions.len()
+    );
+    for corruption in &report.corruptions {
+        println!("  {}: {}", corruption.path, corruption.reason);
+    }
+}
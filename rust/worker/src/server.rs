@@ -13,10 +13,9 @@ use chroma_tracing::util::wrap_span_with_parent_context;
 use chroma_types::{
     chroma_proto::{
         self, query_executor_server::QueryExecutor, CountPlan, CountResult, GetPlan, GetResult,
-        KnnBatchResult, KnnPlan,
+        KnnBatchResult, KnnPlan, SegmentStatsPlan, SegmentStatsResult,
     },
-    operator::Scan,
-    CollectionAndSegments,
+    operator::{to_proto_stats_result, AsOf, Scan},
 };
 use futures::{stream, StreamExt, TryStreamExt};
 use tokio::signal::unix::{signal, SignalKind};
@@ -24,12 +23,14 @@ use tonic::{transport::Server, Request, Response, Status};
 use tracing::{trace_span, Instrument};
 
 use crate::{
-    config::QueryServiceConfig,
+    config::{KnnQueryConfig, QueryServiceConfig},
     execution::{
         operators::{fetch_log::FetchLogOperator, knn_projection::KnnProjectionOperator},
         orchestration::{
-            get::GetOrchestrator, knn::KnnOrchestrator, knn_filter::KnnFilterOrchestrator,
-            CountOrchestrator,
+            get::GetOrchestrator,
+            knn::KnnOrchestrator,
+            knn_filter::{KnnFilterOrchestrator, KnnSegmentStrategy},
+            CountOrchestrator, SegmentStatsOrchestrator,
         },
     },
     utils::convert::{from_proto_knn, to_proto_knn_batch_result},
@@ -46,6 +47,7 @@ pub struct WorkerServer {
     _sysdb: SysDb,
     hnsw_index_provider: HnswIndexProvider,
     blockfile_provider: BlockfileProvider,
+    knn_config: KnnQueryConfig,
     port: u16,
 }
 
@@ -75,6 +77,7 @@ impl Configurable<QueryServiceConfig> for WorkerServer {
             log,
             hnsw_index_provider,
             blockfile_provider,
+            knn_config: config.knn,
             port: config.my_port,
         })
     }
@@ -117,17 +120,45 @@ impl WorkerServer {
         self.system = Some(system);
     }
 
-    fn fetch_log(&self, collection_and_segments: &CollectionAndSegments) -> FetchLogOperator {
-        FetchLogOperator {
+    async fn fetch_log(&self, scan: &Scan) -> Result<FetchLogOperator, Status> {
+        let collection = &scan.collection_and_segments.collection;
+        // A timestamp reads at the offset of the last record written at or before it
+        let (as_of_offset, end_timestamp) = match scan.as_of {
+            Some(AsOf::LogOffset(offset)) => (Some(offset), None),
+            Some(AsOf::Timestamp(timestamp)) => {
+                let first_hidden = self
+                    .log
+                    .clone()
+                    .first_offset_since(collection.collection_id, timestamp.saturating_add(1))
+                    .await
+                    .map_err(|err| Status::new(err.code().into(), err.to_string()))?;
+                (first_hidden.map(|offset| offset - 1), Some(timestamp))
+            }
+            None => (None, None),
+        };
+        let end_log_offset_id = match as_of_offset {
+            // The records up to the log position are already in the segments and cannot be hidden
+            Some(offset) if offset < collection.log_position => {
+                return Err(Status::failed_precondition(format!(
+                    "Log offset {} has already been compacted, the collection is compacted up to log offset {}",
+                    offset, collection.log_position
+                )));
+            }
+            Some(offset) => Some(offset.min(u32::MAX as i64) as u32),
+            None => None,
+        };
+        Ok(FetchLogOperator {
             log_client: self.log.clone(),
             // TODO: Make this configurable
             batch_size: 100,
             // The collection log position is inclusive, and we want to start from the next log
             // Note that we query using the incoming log position this is critical for correctness
-            start_log_offset_id: collection_and_segments.collection.log_position as u32 + 1,
+            start_log_offset_id: collection.log_position as u32 + 1,
             maximum_fetch_count: None,
-            collection_uuid: collection_and_segments.collection.collection_id,
-        }
+            end_log_offset_id,
+            end_timestamp,
+            collection_uuid: collection.collection_id,
+        })
     }
 
     async fn orchestrate_count(
@@ -139,8 +170,9 @@ impl WorkerServer {
             .scan
             .ok_or(Status::invalid_argument("Invalid Scan Operator"))?;
 
-        let collection_and_segments = Scan::try_from(scan)?.collection_and_segments;
-        let fetch_log = self.fetch_log(&collection_and_segments);
+        let scan = Scan::try_from(scan)?;
+        let fetch_log = self.fetch_log(&scan).await?;
+        let collection_and_segments = scan.collection_and_segments;
 
         let count_orchestrator = CountOrchestrator::new(
             self.blockfile_provider.clone(),
@@ -159,14 +191,41 @@ impl WorkerServer {
         }
     }
 
+    async fn orchestrate_segment_stats(
+        &self,
+        stats: Request<SegmentStatsPlan>,
+    ) -> Result<Response<SegmentStatsResult>, Status> {
+        let scan = stats
+            .into_inner()
+            .scan
+            .ok_or(Status::invalid_argument("Invalid Scan Operator"))?;
+
+        let collection_and_segments = Scan::try_from(scan)?.collection_and_segments;
+
+        let segment_stats_orchestrator = SegmentStatsOrchestrator::new(
+            self.blockfile_provider.clone(),
+            self.hnsw_index_provider.clone(),
+            self.clone_dispatcher()?,
+            // TODO: Make this configurable
+            1000,
+            collection_and_segments,
+        );
+
+        match segment_stats_orchestrator.run(self.clone_system()?).await {
+            Ok(segment_stats) => Ok(Response::new(to_proto_stats_result(segment_stats))),
+            Err(err) => Err(Status::new(err.code().into(), err.to_string())),
+        }
+    }
+
     async fn orchestrate_get(&self, get: Request<GetPlan>) -> Result<Response<GetResult>, Status> {
         let get_inner = get.into_inner();
         let scan = get_inner
             .scan
             .ok_or(Status::invalid_argument("Invalid Scan Operator"))?;
 
-        let collection_and_segments = Scan::try_from(scan)?.collection_and_segments;
-        let fetch_log = self.fetch_log(&collection_and_segments);
+        let scan = Scan::try_from(scan)?;
+        let fetch_log = self.fetch_log(&scan).await?;
+        let collection_and_segments = scan.collection_and_segments;
 
         let filter = get_inner
             .filter
@@ -211,9 +270,9 @@ impl WorkerServer {
             .scan
             .ok_or(Status::invalid_argument("Invalid Scan Operator"))?;
 
-        let collection_and_segments = Scan::try_from(scan)?.collection_and_segments;
-
-        let fetch_log = self.fetch_log(&collection_and_segments);
+        let scan = Scan::try_from(scan)?;
+        let fetch_log = self.fetch_log(&scan).await?;
+        let collection_and_segments = scan.collection_and_segments;
 
         let filter = knn_inner
             .filter
@@ -252,17 +311,21 @@ impl WorkerServer {
             self.hnsw_index_provider.clone(),
             // TODO: Make this configurable
             1000,
+            self.knn_config,
             collection_and_segments,
             fetch_log,
             filter.try_into()?,
         );
 
-        let matching_records = match knn_filter_orchestrator.run(system.clone()).await {
+        let mut matching_records = match knn_filter_orchestrator.run(system.clone()).await {
             Ok(output) => output,
             Err(e) => {
                 return Err(Status::new(e.code().into(), e.to_string()));
             }
         };
+        if knn.brute_force {
+            matching_records.strategy = KnnSegmentStrategy::BruteForce;
+        }
 
         let knn_orchestrator_futures = from_proto_knn(knn)?
             .into_iter()
@@ -344,6 +407,22 @@ impl QueryExecutor for WorkerServer {
             .instrument(instrumented_span)
             .await
     }
+
+    async fn segment_stats(
+        &self,
+        stats: Request<SegmentStatsPlan>,
+    ) -> Result<Response<SegmentStatsResult>, Status> {
+        // Note: We cannot write a middleware that instruments every service rpc
+        // with a span because of https://github.com/hyperium/tonic/pull/1202.
+        let stats_span = trace_span!(
+            "SegmentStatsPlan",
+            stats = ?stats
+        );
+        let instrumented_span = wrap_span_with_parent_context(stats_span, stats.metadata());
+        self.orchestrate_segment_stats(stats)
+            .instrument(instrumented_span)
+            .await
+    }
 }
 
 #[cfg(debug_assertions)]
@@ -381,10 +460,12 @@ impl chroma_proto::debug_server::Debug for WorkerServer {
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::str::FromStr;
 
     use super::*;
     use chroma_index::test_hnsw_index_provider;
-    use chroma_log::in_memory_log::InMemoryLog;
+    use chroma_log::in_memory_log::{InMemoryLog, InternalLogRecord};
+    use chroma_log::test::{upsert_generator, LogGenerator};
     #[cfg(debug_assertions)]
     use chroma_proto::debug_client::DebugClient;
     use chroma_proto::query_executor_client::QueryExecutorClient;
@@ -392,11 +473,15 @@ mod tests {
     use chroma_sysdb::TestSysDb;
     use chroma_system::system;
     use chroma_system::DispatcherConfig;
+    use chroma_types::CollectionUuid;
     use uuid::Uuid;
 
     fn run_server() -> String {
+        run_server_with_log(InMemoryLog::new())
+    }
+
+    fn run_server_with_log(log: InMemoryLog) -> String {
         let sysdb = TestSysDb::new();
-        let log = InMemoryLog::new();
         let segments = TestDistributedSegment::default();
         let port = random_port::PortPicker::new().random(true).pick().unwrap();
 
@@ -407,6 +492,7 @@ mod tests {
             log: Log::InMemory(log),
             hnsw_index_provider: test_hnsw_index_provider(),
             blockfile_provider: segments.blockfile_provider,
+            knn_config: KnnQueryConfig::default(),
             port,
         };
 
@@ -469,6 +555,7 @@ mod tests {
                 metadata: None,
                 file_paths: HashMap::new(),
             }),
+            as_of: None,
         }
     }
 
@@ -513,6 +600,79 @@ mod tests {
         assert_eq!(response.unwrap_err().code(), tonic::Code::InvalidArgument);
     }
 
+    #[tokio::test]
+    async fn validate_count_plan_as_of() {
+        let mut executor = QueryExecutorClient::connect(run_server()).await.unwrap();
+        let mut scan_operator = scan();
+        if let Some(collection) = scan_operator.collection.as_mut() {
+            collection.log_position = 10;
+        }
+        scan_operator.as_of = Some(chroma_proto::scan_operator::AsOf::AsOfLogOffset(5));
+        let request = chroma_proto::CountPlan {
+            scan: Some(scan_operator),
+        };
+
+        // the log offset has already been compacted
+        let response = executor.count(request).await;
+        assert!(response.is_err());
+        assert_eq!(
+            response.unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_count_plan_as_of_timestamp() {
+        let mut scan_operator = scan();
+        let collection = scan_operator
+            .collection
+            .as_mut()
+            .expect("The collection should exist");
+        collection.log_position = 5;
+        let collection_id = CollectionUuid::from_str(&collection.id).unwrap();
+
+        // Record i is written at timestamp 10 * i
+        let mut log = InMemoryLog::new();
+        for record in upsert_generator.generate_vec(0..10) {
+            log.add_log(
+                collection_id,
+                InternalLogRecord {
+                    collection_id,
+                    log_offset: record.log_offset,
+                    log_ts: record.log_offset * 10,
+                    record,
+                },
+            );
+        }
+        let mut executor = QueryExecutorClient::connect(run_server_with_log(log))
+            .await
+            .unwrap();
+
+        // the records written after the timestamp include compacted ones
+        scan_operator.as_of = Some(chroma_proto::scan_operator::AsOf::AsOfTimestamp(25));
+        let response = executor
+            .count(chroma_proto::CountPlan {
+                scan: Some(scan_operator.clone()),
+            })
+            .await;
+        assert!(response.is_err());
+        assert_eq!(
+            response.unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+
+        // the records written after the timestamp have not been compacted yet
+        scan_operator.as_of = Some(chroma_proto::scan_operator::AsOf::AsOfTimestamp(55));
+        let response = executor
+            .count(chroma_proto::CountPlan {
+                scan: Some(scan_operator),
+            })
+            .await;
+        if let Err(status) = response {
+            assert_ne!(status.code(), tonic::Code::FailedPrecondition);
+        }
+    }
+
     #[tokio::test]
     async fn validate_get_plan() {
         let mut executor = QueryExecutorClient::connect(run_server()).await.unwrap();
@@ -588,6 +748,7 @@ mod tests {
             knn: Some(chroma_proto::KnnOperator {
                 embeddings: vec![],
                 fetch: 0,
+                brute_force: false,
             }),
             projection: Some(chroma_proto::KnnProjectionOperator {
                 projection: Some(chroma_proto::ProjectionOperator {
@@ -6,7 +6,7 @@ use chroma_types::{
 };
 
 use crate::{
-    compactor::OneOffCompactionMessage,
+    compactor::{CompactionJobRecord, CompactionJobState, OneOffCompactionMessage, ReindexMessage},
     execution::operators::{
         filter::FilterOperator,
         knn::KnnOperator,
@@ -176,3 +176,47 @@ impl TryFrom<chroma_proto::CompactionRequest> for OneOffCompactionMessage {
         })
     }
 }
+
+impl TryFrom<chroma_proto::ReindexRequest> for ReindexMessage {
+    type Error = ConversionError;
+
+    fn try_from(value: chroma_proto::ReindexRequest) -> Result<Self, ConversionError> {
+        Ok(Self {
+            collection_id: CollectionUuid::from_str(&value.collection_id)
+                .map_err(|_| ConversionError::DecodeError)?,
+            space: value.space,
+            m: value.m.map(|m| m as usize),
+            ef_construction: value.ef_construction.map(|ef| ef as usize),
+            ef_search: value.ef_search.map(|ef| ef as usize),
+        })
+    }
+}
+
+impl From<CompactionJobState> for chroma_proto::CompactionJobState {
+    fn from(value: CompactionJobState) -> Self {
+        match value {
+            CompactionJobState::Queued => chroma_proto::CompactionJobState::Queued,
+            CompactionJobState::Running => chroma_proto::CompactionJobState::Running,
+            CompactionJobState::Succeeded => chroma_proto::CompactionJobState::Succeeded,
+            CompactionJobState::Failed => chroma_proto::CompactionJobState::Failed,
+            CompactionJobState::Cancelled => chroma_proto::CompactionJobState::Cancelled,
+        }
+    }
+}
+
+impl From<CompactionJobRecord> for chroma_proto::CompactionJobInfo {
+    fn from(value: CompactionJobRecord) -> Self {
+        Self {
+            collection_id: value.collection_id.to_string(),
+            state: chroma_proto::CompactionJobState::from(value.state) as i32,
+            tenant_id: value.job.as_ref().map(|job| job.tenant_id.clone()),
+            log_offset: value.job.as_ref().map(|job| job.offset),
+            collection_version: value.job.as_ref().map(|job| job.collection_version),
+            queued_at_ms: value.queued_at_ms,
+            started_at_ms: value.started_at_ms,
+            finished_at_ms: value.finished_at_ms,
+            error: value.error,
+            failed_operator: value.failed_operator,
+        }
+    }
+}